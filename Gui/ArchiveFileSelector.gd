@@ -40,4 +40,6 @@ func _on_ArchiveFileSelector_confirmed():
 		ftype = "NGC"
 	elif $Grid/OptionButton.selected == 1:
 		ftype = "PS2"
+	elif $Grid/OptionButton.selected == 2:
+		ftype = "AUTO"
 	emit_signal("files_selected", $Grid/NGCLine.text, $Grid/DGCLine.text, ftype)
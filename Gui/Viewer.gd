@@ -14,6 +14,9 @@ const MENU_VIEW_ALPHA := 1
 const MENU_HELP_GUIDE := 0
 const MENU_HELP_ABOUT := 1
 
+# Names that the user has added, one on each line
+const USER_DICTIONARY := "user://names.txt"
+
 var archive: ChumArchive
 var should_3dview_reload := false
 
@@ -33,6 +36,8 @@ func _ready():
 	print(get_path())
 	node_view3d.set_active(false)
 	archive = ChumArchive.new()
+	if File.new().file_exists(USER_DICTIONARY):
+		archive.load_dictionary(ProjectSettings.globalize_path(USER_DICTIONARY))
 	node_menu_file.get_popup().connect(
 		"id_pressed", self, "_on_menu_file_select")
 	node_menu_export.get_popup().connect(
@@ -45,7 +50,10 @@ func _on_menu_file_select(id):
 		MENU_FILE_OPEN:
 			$ArchiveFileSelector.popup_centered()
 		MENU_FILE_EXIT:
-			$ExitDialogue.popup_centered()
+			if archive.is_dirty():
+				$ExitDialogue.popup_centered()
+			else:
+				get_tree().quit(0)
 		MENU_FILE_SAVE_AS:
 			$ArchiveFileSaver.popup_centered()
 
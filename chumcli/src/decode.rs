@@ -0,0 +1,276 @@
+use libchum::{archive::{ChumArchive, ChumFile}, binary::ChumBinary, format::TotemFormat, names::NameDictionary, reader::{self, bitmap, mesh, skin, sound, surface}, scene, structure, util};
+use std::collections::HashMap;
+use std::error::Error;
+use std::io::Cursor;
+use std::path::Path;
+
+/// The format that a file was decoded to when it was extracted.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DecodeFormat {
+    Png,
+    Wav,
+    Text,
+    Gltf,
+    Json,
+}
+
+impl DecodeFormat {
+    /// Get the extension that is added to the names of files decoded to this format
+    pub fn get_extension(&self) -> &'static str {
+        match self {
+            DecodeFormat::Png => "png",
+            DecodeFormat::Wav => "wav",
+            DecodeFormat::Text => "txt",
+            DecodeFormat::Gltf => "gltf",
+            DecodeFormat::Json => "json",
+        }
+    }
+}
+
+/// A file decoded to an editable format.
+pub struct Decoded {
+    pub format: DecodeFormat,
+    pub data: Vec<u8>,
+    /// True if encoding the decoded data gives back exactly the original data.
+    /// Otherwise, the original data should be kept along with the decoded data.
+    pub lossless: bool,
+}
+
+/// Get every name known to the archive or the dictionary.
+fn get_names(archive: &ChumArchive, dictionary: &NameDictionary) -> HashMap<i32, String> {
+    let mut names = dictionary.get_names().clone();
+    names.extend(archive.get_name_map().iter().map(|(id, name)| (*id, name.clone())));
+    names
+}
+
+fn decode_text(data: &[u8], fmt: TotemFormat) -> Option<Decoded> {
+    if data.len() < 4 {
+        return None;
+    }
+    let size = fmt.read_u32(&mut &data[..4]).ok()?;
+    let text = std::str::from_utf8(&data[4..]).ok()?;
+    if size as usize != text.len() {
+        return None;
+    }
+    Some(Decoded {
+        format: DecodeFormat::Text,
+        data: text.as_bytes().to_vec(),
+        lossless: true,
+    })
+}
+
+fn decode_structure(archive: &ChumArchive, file: &ChumFile, dictionary: &NameDictionary) -> Result<Decoded, Box<dyn Error>> {
+    let fmt = archive.get_format();
+    let data = reader::read_structure(file.get_type_id(), file.get_data(), fmt)?;
+    let value = structure::json::to_json(&data, |x| archive.resolve_name(x, dictionary));
+    // Some values, such as rotations, do not survive being structured
+    let lossless = match reader::write_structure(file.get_type_id(), &data, fmt) {
+        Ok(written) => written == file.get_data(),
+        Err(_) => false,
+    };
+    Ok(Decoded {
+        format: DecodeFormat::Json,
+        data: serde_json::to_vec_pretty(&value)?,
+        lossless,
+    })
+}
+
+fn decode_sound(data: &[u8], fmt: TotemFormat) -> Result<Decoded, Box<dyn Error>> {
+    let sound = sound::SoundGcn::read_from(&mut &data[..], fmt)?;
+    let spec = hound::WavSpec {
+        channels: 1,
+        sample_rate: sound.sample_rate,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+    let mut output = Cursor::new(Vec::new());
+    let mut writer = hound::WavWriter::new(&mut output, spec)?;
+    for sample in sound.gen_samples() {
+        writer.write_sample(sample)?;
+    }
+    writer.finalize()?;
+    Ok(Decoded {
+        format: DecodeFormat::Wav,
+        data: output.into_inner(),
+        lossless: false,
+    })
+}
+
+fn decode_scene(mut scene: scene::Scene, archive: &ChumArchive) -> Result<Decoded, Box<dyn Error>> {
+    scene.add_archive_materials(archive);
+    let mut data = Vec::new();
+    scene.write_gltf(&mut data)?;
+    Ok(Decoded {
+        format: DecodeFormat::Gltf,
+        data,
+        lossless: false,
+    })
+}
+
+fn create_mesh_scene(file: &ChumFile, fmt: TotemFormat) -> Result<scene::Scene, Box<dyn Error>> {
+    let mesh = mesh::Mesh::read_from(&mut file.get_data(), fmt)?;
+    let mut scene = scene::Scene::new_empty();
+    scene.meshes.insert(file.get_name_id().to_string(), mesh.create_scene_mesh());
+    scene.root.graphic = scene::NodeGraphic::Mesh {
+        mesh: file.get_name_id().to_string(),
+    };
+    Ok(scene)
+}
+
+fn create_skin_scene(archive: &ChumArchive, file: &ChumFile, dictionary: &NameDictionary) -> Result<scene::Scene, Box<dyn Error>> {
+    let fmt = archive.get_format();
+    let skin = skin::Skin::read_from(&mut file.get_data(), fmt)?;
+    let names = get_names(archive, dictionary);
+    let mut scene = scene::Scene::new_empty();
+    let mut skin_meshes = Vec::new();
+    for mesh_id in skin.meshes.iter() {
+        let meshfile = match archive.get_file_from_hash(*mesh_id) {
+            Some(meshfile) if meshfile.get_type_id() == "MESH" => meshfile,
+            _ => {
+                eprintln!("Warning: mesh {} of skin {} is not in the archive", mesh_id, file.get_name_id());
+                continue;
+            }
+        };
+        let mesh = mesh::Mesh::read_from(&mut meshfile.get_data(), fmt)?;
+        let mut trimesh = mesh.create_scene_mesh();
+        trimesh.skin = mesh.generate_mesh_skin(skin::SkinInfo {
+            names: &names,
+            skin: &skin,
+            skin_id: util::hash_name_i32(file.get_name_id()),
+            mesh_id: *mesh_id,
+        });
+        scene.meshes.insert(meshfile.get_name_id().to_string(), trimesh);
+        skin_meshes.push(meshfile.get_name_id().to_string());
+    }
+    let mut scene_skin = scene::Skin {
+        joints: skin.generate_scene_skin_joints(&names),
+    };
+    scene_skin.auto_set_joint_transforms(scene.meshes.values().map(|x| x.get_value_ref()));
+    scene.root.graphic = scene::NodeGraphic::Skin {
+        skin: scene_skin,
+        meshes: skin_meshes,
+    };
+    Ok(scene)
+}
+
+fn create_surface_scene(file: &ChumFile, fmt: TotemFormat) -> Result<scene::Scene, Box<dyn Error>> {
+    let surface = surface::SurfaceObject::read_data(file.get_data(), fmt)?;
+    let mut scene = scene::Scene::new_empty();
+    let mesh = surface.generate_simple_mesh(surface::SurfaceGenMode::BezierInterp(10));
+    scene.meshes.insert(file.get_name_id().to_string(), mesh);
+    scene.root.graphic = scene::NodeGraphic::Mesh {
+        mesh: file.get_name_id().to_string(),
+    };
+    Ok(scene)
+}
+
+/// Decode the given file to an editable format.
+/// Returns None if files of its type are not decoded.
+/// Skins and surfaces can not be encoded again, so they are packed from their
+/// original data as long as their decoded file is not changed.
+pub fn decode_file(archive: &ChumArchive, file: &ChumFile, dictionary: &NameDictionary) -> Result<Option<Decoded>, Box<dyn Error>> {
+    let fmt = archive.get_format();
+    let data = file.get_data();
+    Ok(Some(match file.get_type_id() {
+        "BITMAP" => {
+            let mut output = Vec::new();
+            bitmap::Bitmap::read_data(data, fmt)?.export_png(&mut output)?;
+            Decoded {
+                format: DecodeFormat::Png,
+                data: output,
+                lossless: false,
+            }
+        }
+        "SOUND" => decode_sound(data, fmt)?,
+        // Text that is not valid UTF-8 is kept as it is
+        "TXT" => return Ok(decode_text(data, fmt)),
+        "MESH" => decode_scene(create_mesh_scene(file, fmt)?, archive)?,
+        "SKIN" => decode_scene(create_skin_scene(archive, file, dictionary)?, archive)?,
+        "SURFACE" => decode_scene(create_surface_scene(file, fmt)?, archive)?,
+        other if reader::get_default_structure(other).is_some() => decode_structure(archive, file, dictionary)?,
+        _ => return Ok(None),
+    }))
+}
+
+fn encode_bitmap(decoded: &[u8], raw: &[u8], fmt: TotemFormat) -> Result<Vec<u8>, Box<dyn Error>> {
+    let base = bitmap::Bitmap::read_data(raw, fmt)?;
+    let (colors, width, height) = bitmap::import_bitmap(&mut Cursor::new(decoded), bitmap::image::ImageFormat::Png)?;
+    // Keep the format of the original bitmap
+    let mut data = bitmap::BitmapFormat::new_empty(base.get_data().get_format(), base.get_data().get_palette_format())
+        .ok_or("The original bitmap has an invalid format")?;
+    bitmap::compress_bitmap(&colors, &mut data, width, height)
+        .map_err(|e| format!("Could not compress bitmap: {:?}", e))?;
+    let mut output = Vec::new();
+    base.with_bitmap(data, width, height).write_to(&mut output, fmt)?;
+    Ok(output)
+}
+
+fn encode_sound(decoded: &[u8], raw: &[u8], fmt: TotemFormat) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut sound = sound::SoundGcn::read_from(&mut &raw[..], fmt)?;
+    let mut reader = hound::WavReader::new(Cursor::new(decoded))?;
+    let spec = reader.spec();
+    if spec.channels != 1 || spec.bits_per_sample != 16 || spec.sample_format != hound::SampleFormat::Int {
+        return Err("Sounds must be mono 16-bit WAV files".into());
+    }
+    let samples = reader.samples::<i16>().collect::<Result<Vec<i16>, _>>()?;
+    sound.import_samples(&samples);
+    if spec.sample_rate != sound.sample_rate {
+        let mut info = sound.get_struct();
+        info.sample_rate = spec.sample_rate;
+        sound.import_struct(&info);
+    }
+    let mut output = Vec::new();
+    sound.write_to(&mut output, fmt)?;
+    Ok(output)
+}
+
+fn encode_text(decoded: &[u8], fmt: TotemFormat) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut output = Vec::with_capacity(decoded.len() + 4);
+    fmt.write_u32(&mut output, decoded.len() as u32)?;
+    output.extend_from_slice(decoded);
+    Ok(output)
+}
+
+fn encode_mesh(path: &Path, raw: &[u8], fmt: TotemFormat) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut base = mesh::Mesh::read_from(&mut &raw[..], fmt)?;
+    let scene = scene::gltf::import_scene(path, scene::gltf::ImportHint::MESHES)?;
+    let trimesh = match scene.meshes.values().next() {
+        Some(trimesh) => trimesh,
+        None => return Err(format!("{} does not have a mesh", path.display()).into()),
+    };
+    base.import_scene_mesh(trimesh.get_value_ref());
+    let mut output = Vec::new();
+    base.write_to(&mut output, fmt)?;
+    Ok(output)
+}
+
+fn encode_structure(decoded: &[u8], type_id: &str, fmt: TotemFormat) -> Result<Vec<u8>, Box<dyn Error>> {
+    let value: serde_json::Value = serde_json::from_slice(decoded)?;
+    let template = reader::get_default_structure(type_id)
+        .ok_or_else(|| format!("Files of type {} can not be written from a structure", type_id))?;
+    let data = structure::json::from_json(&template, &value)?;
+    Ok(reader::write_structure(type_id, &data, fmt)?)
+}
+
+/// Encode a file of the given type from its decoded data, which was read from `path`.
+/// `raw` is the file's original data, which some formats are encoded on top of.
+/// Fails if files of the given type can not be encoded from the given format.
+pub fn encode_file(
+    format: DecodeFormat,
+    type_id: &str,
+    path: &Path,
+    decoded: &[u8],
+    raw: Option<&[u8]>,
+    fmt: TotemFormat,
+) -> Result<Vec<u8>, Box<dyn Error>> {
+    let require_raw = || raw.ok_or_else(|| format!("{} can not be encoded without the file's original data", path.display()));
+    match (format, type_id) {
+        (DecodeFormat::Png, "BITMAP") => encode_bitmap(decoded, require_raw()?, fmt),
+        (DecodeFormat::Wav, "SOUND") => encode_sound(decoded, require_raw()?, fmt),
+        (DecodeFormat::Text, "TXT") => encode_text(decoded, fmt),
+        (DecodeFormat::Gltf, "MESH") => encode_mesh(path, require_raw()?, fmt),
+        (DecodeFormat::Json, _) => encode_structure(decoded, type_id, fmt),
+        _ => Err(format!("{} files can not be encoded from {}; restore the extracted file to keep the original data", type_id, format.get_extension()).into()),
+    }
+}
@@ -1,5 +1,7 @@
-use libchum::{format::TotemFormat, archive::{ChumArchive, ChumFile, TotemHeader}, util};
+use crate::decode::{self, DecodeFormat};
+use libchum::{format::TotemFormat, archive::{ChumArchive, ChumFile, TotemHeader}, names::NameDictionary, util};
 use serde_json;
+use crc::crc32;
 use std::error::Error;
 use std::fs::{self, File};
 use std::io::{Read, Write};
@@ -33,14 +35,41 @@ pub struct JsonDataFile {
     pub id: String,
     pub type_id: String,
     pub subtype_id: String,
+    /// The file's raw data. Files that were decoded losslessly do not have raw data.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub decoded: Option<JsonDecodedFile>,
+}
+
+/// Represents how a file element was decoded.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct JsonDecodedFile {
+    pub format: DecodeFormat,
     pub file_name: String,
+    /// CRC32 of the decoded file as it was extracted, if the raw data was also kept.
+    /// Decoded files that have not changed are packed from their raw data.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checksum: Option<u32>,
+}
+
+/// Write data to the given path inside the output folder, creating folders as needed.
+fn write_file(output_folder: &Path, file_name: &str, data: &[u8]) -> Result<(), Box<dyn Error>> {
+    let fpath = output_folder.join(file_name.split('/').collect::<PathBuf>());
+    fs::create_dir_all(fpath.parent().unwrap())?;
+    let mut fh = File::create(fpath)?;
+    fh.write_all(data)?;
+    Ok(())
 }
 
 /// Extract the given archive into the given output folder.
+/// If `decode` is given, files are also decoded to editable formats,
+/// with references written using the given names.
 pub fn extract_archive(
     archive: &ChumArchive,
     output_folder: &Path,
     merge: bool,
+    decode: Option<&NameDictionary>,
 ) -> Result<(), Box<dyn Error>> {
     let json_path = output_folder.join("meta.json");
     // create folder
@@ -53,19 +82,39 @@ pub fn extract_archive(
     };
     // Iterate files
     for file in archive.get_files() {
-        // create data file
-        let fname = util::get_file_string(file.get_name_id(), util::hash_name_u32(file.get_name_id()));
-        let fpath = output_folder.join(fname.iter().collect::<PathBuf>());
-        fs::create_dir_all(fpath.parent().unwrap())?;
-        let mut fh = File::create(fpath)?;
-        fh.write_all(&file.get_data())?;
+        let fname = util::get_file_string(file.get_name_id(), util::hash_name_u32(file.get_name_id())).join("/");
+        let decoded = match decode.map(|names| decode::decode_file(archive, file, names)) {
+            Some(Ok(decoded)) => decoded,
+            Some(Err(e)) => {
+                eprintln!("Warning: could not decode {}: {}", file.get_name_id(), e);
+                None
+            }
+            None => None,
+        };
         // create entry in meta.json file
-        let jsonfile = JsonDataFile {
+        let mut jsonfile = JsonDataFile {
             id: file.get_name_id().to_string(),
             type_id: file.get_type_id().to_string(),
             subtype_id: file.get_subtype_id().to_string(),
-            file_name: fname.join("/"),
+            file_name: Some(fname.clone()),
+            decoded: None,
         };
+        if let Some(decoded) = decoded {
+            let decoded_name = format!("{}.{}", fname, decoded.format.get_extension());
+            write_file(output_folder, &decoded_name, &decoded.data)?;
+            jsonfile.decoded = Some(JsonDecodedFile {
+                format: decoded.format,
+                file_name: decoded_name,
+                checksum: if decoded.lossless { None } else { Some(crc32::checksum_ieee(&decoded.data)) },
+            });
+            if decoded.lossless {
+                jsonfile.file_name = None;
+            }
+        }
+        // create data file
+        if let Some(ref fname) = jsonfile.file_name {
+            write_file(output_folder, fname, file.get_data())?;
+        }
         json_data.files.push(jsonfile);
     }
     // sort files
@@ -91,7 +140,17 @@ pub fn extract_archive(
     Ok(())
 }
 
-/// Import an archive from the given path
+/// Read the file at the given path inside the input folder.
+fn read_file(input_folder: &Path, file_name: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+    let path: PathBuf = input_folder.join(file_name.split('/').collect::<PathBuf>());
+    let mut file_handle = File::open(path)?;
+    let mut data = Vec::new();
+    file_handle.read_to_end(&mut data)?;
+    Ok(data)
+}
+
+/// Import an archive from the given path.
+/// Decoded files are encoded again, unless they have not changed since they were extracted.
 pub fn import_archive(
     input_folder: &Path,
     fmt: TotemFormat,
@@ -102,10 +161,24 @@ pub fn import_archive(
     json_data.sort();
     let mut files = Vec::new();
     for file in json_data.files {
-        let path: PathBuf = input_folder.join(file.file_name.split('/').collect::<PathBuf>());
-        let mut file_handle = File::open(path)?;
-        let mut data = Vec::new();
-        file_handle.read_to_end(&mut data)?;
+        let raw = match file.file_name {
+            Some(ref file_name) => Some(read_file(input_folder, file_name)?),
+            None => None,
+        };
+        let data = match (file.decoded, raw) {
+            (Some(decoded), raw) => {
+                let path = input_folder.join(decoded.file_name.split('/').collect::<PathBuf>());
+                let data = read_file(input_folder, &decoded.file_name)?;
+                let checksum = crc32::checksum_ieee(&data);
+                match raw {
+                    Some(raw) if decoded.checksum == Some(checksum) => raw,
+                    raw => decode::encode_file(decoded.format, &file.type_id, &path, &data, raw.as_deref(), fmt)
+                        .map_err(|e| format!("Could not encode {}: {}", decoded.file_name, e))?,
+                }
+            }
+            (None, Some(raw)) => raw,
+            (None, None) => return Err(format!("{} has no data", file.id).into()),
+        };
         let chumfile = ChumFile::new(data, file.id, file.type_id, file.subtype_id);
         files.push(chumfile);
     }
@@ -115,3 +188,188 @@ pub fn import_archive(
         files,
     )?)
 }
+
+#[cfg(test)]
+mod test {
+    use crate::json::*;
+    use libchum::binary::ChumBinary;
+    use libchum::common::{self, Vector2, Vector3};
+    use libchum::reader::{bitmap, material, mesh, node, skin};
+    use libchum::scene;
+    use std::collections::HashMap;
+
+    fn make_mesh() -> mesh::Mesh {
+        let corner = |i| scene::MeshPoint {
+            vertex_id: i,
+            texcoord_id: i,
+            normal_id: 0,
+        };
+        let mut triangles = HashMap::new();
+        triangles.insert(
+            util::hash_name_i32("A.MATERIAL"),
+            vec![scene::MeshTriangle {
+                corners: [corner(0), corner(1), corner(2)],
+            }],
+        );
+        let mut mesh = mesh::Mesh {
+            item_type: common::ITEM_TYPE_MESH,
+            ..Default::default()
+        };
+        mesh.import_scene_mesh(&scene::Mesh {
+            vertices: vec![
+                Vector3::new(0.0, 0.0, 0.0),
+                Vector3::new(1.0, 0.0, 0.0),
+                Vector3::new(0.0, 1.0, 0.0),
+            ],
+            texcoords: vec![
+                Vector2::new(0.0, 0.0),
+                Vector2::new(1.0, 0.0),
+                Vector2::new(0.0, 1.0),
+            ],
+            normals: vec![Vector3::new(0.0, 0.0, 1.0)],
+            triangles,
+            skin: None,
+        });
+        mesh
+    }
+
+    fn make_bitmap() -> bitmap::Bitmap {
+        let colors = (0..16u8)
+            .map(|i| bitmap::Color {
+                r: i * 16,
+                g: 255 - i * 16,
+                b: i,
+                a: 255,
+            })
+            .collect();
+        bitmap::Bitmap::default().with_bitmap(bitmap::BitmapFormat::RGBA8888(colors), 4, 4)
+    }
+
+    /// Get the position, texture coordinate and normal of every corner of every triangle, sorted
+    fn get_corners(mesh: &mesh::Mesh) -> Vec<[u32; 8]> {
+        let mut corners: Vec<[u32; 8]> = mesh
+            .gen_triangles()
+            .iter()
+            .flat_map(|x| x.tris.iter())
+            .flat_map(|x| x.points.iter())
+            .map(|p| {
+                let (v, t, n) = (p.vertex, p.texcoord, p.normal);
+                [v.x, v.y, v.z, t.x, t.y, n.x, n.y, n.z].map(f32::to_bits)
+            })
+            .collect();
+        corners.sort();
+        corners
+    }
+
+    #[test]
+    fn decode_round_trip() {
+        let fmt = TotemFormat::NGC;
+        let mut archive = ChumArchive::new(TotemHeader::new(b"test"), fmt);
+        let mut add = |name: &str, type_id: &str, value: &dyn Fn(&mut Vec<u8>)| {
+            let mut data = Vec::new();
+            value(&mut data);
+            let file = ChumFile::new(data, name.to_owned(), type_id.to_owned(), name.to_owned());
+            archive.add_file(file).unwrap();
+        };
+        add("A.MESH", "MESH", &|data| make_mesh().write_to(data, fmt).unwrap());
+        add("A.BITMAP", "BITMAP", &|data| make_bitmap().write_to(data, fmt).unwrap());
+        add("A.MATERIAL", "MATERIAL", &|data| {
+            let material = material::Material {
+                texture: util::hash_name_i32("A.BITMAP"),
+                ..Default::default()
+            };
+            material.write_to(data, fmt).unwrap()
+        });
+        add("A.SKIN", "SKIN", &|data| {
+            let skin = skin::Skin {
+                meshes: vec![util::hash_name_i32("A.MESH")],
+                vertex_groups: vec![skin::VertexGroup {
+                    group_id: util::hash_name_i32("BONE"),
+                    sections: vec![skin::VertexGroupSection {
+                        mesh_index: 0,
+                        vertices: (0..3)
+                            .map(|i| skin::VertexGroupVertex {
+                                vertex_id: i,
+                                weight: 1.0,
+                            })
+                            .collect(),
+                        normals: Vec::new(),
+                    }],
+                }],
+                ..Default::default()
+            };
+            skin.write_to(data, fmt).unwrap()
+        });
+        add("A.SURFACE", "SURFACE", &|data| {
+            data.extend_from_slice(include_bytes!("../../libchum/res/golden/SURFACE.NGC"))
+        });
+        let node = node::Node {
+            local_rotation: common::Quaternion::new(0.1, 0.7, -0.3, 0.64),
+            ..Default::default()
+        };
+        add("A.NODE", "NODE", &|data| node.write_to(data, fmt).unwrap());
+
+        let dir = std::env::temp_dir().join(format!("chumcli-decode-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        extract_archive(&archive, &dir, false, Some(&NameDictionary::new())).unwrap();
+        // Pretend that every decoded file that can be encoded was edited,
+        // so that they are all encoded again
+        let meta_path = dir.join("meta.json");
+        let mut meta: JsonData = serde_json::from_reader(File::open(&meta_path).unwrap()).unwrap();
+        let mut formats = Vec::new();
+        for file in meta.files.iter_mut() {
+            if let Some(ref mut decoded) = file.decoded {
+                if file.type_id != "SKIN" && file.type_id != "SURFACE" {
+                    decoded.checksum = None;
+                }
+                formats.push((file.id.as_str(), decoded.format));
+            }
+        }
+        assert_eq!(
+            formats,
+            vec![
+                ("A.BITMAP", DecodeFormat::Png),
+                ("A.MATERIAL", DecodeFormat::Json),
+                ("A.MESH", DecodeFormat::Gltf),
+                ("A.NODE", DecodeFormat::Json),
+                ("A.SKIN", DecodeFormat::Gltf),
+                ("A.SURFACE", DecodeFormat::Gltf),
+            ]
+        );
+        serde_json::to_writer(File::create(&meta_path).unwrap(), &meta).unwrap();
+        // Edit a field of the node, which must not change its rotation
+        let node_path = dir.join(&meta.files[3].decoded.as_ref().unwrap().file_name);
+        let mut value: serde_json::Value = serde_json::from_reader(File::open(&node_path).unwrap()).unwrap();
+        value["resource_id"] = serde_json::Value::from(5);
+        serde_json::to_writer(File::create(&node_path).unwrap(), &value).unwrap();
+
+        let packed = import_archive(&dir, fmt).unwrap();
+        for name in ["A.BITMAP", "A.MATERIAL", "A.SKIN", "A.SURFACE"].iter() {
+            let data = packed.get_file_from_name(name).unwrap().get_data();
+            assert_eq!(data, archive.get_file_from_name(name).unwrap().get_data(), "{}", name);
+        }
+        let mut expected = Vec::new();
+        node::Node {
+            resource_id: 5,
+            ..node
+        }
+        .write_to(&mut expected, fmt)
+        .unwrap();
+        assert_eq!(packed.get_file_from_name("A.NODE").unwrap().get_data(), &expected[..]);
+        // Meshes are written with their points in a different order
+        let read_mesh = |archive: &ChumArchive| {
+            let data = archive.get_file_from_name("A.MESH").unwrap().get_data();
+            mesh::Mesh::read_from(&mut &data[..], fmt).unwrap()
+        };
+        let (original, encoded) = (read_mesh(&archive), read_mesh(&packed));
+        assert_eq!(get_corners(&encoded), get_corners(&original));
+        assert_eq!(encoded.materials, original.materials);
+
+        // Skins can not be packed once their decoded file was edited
+        let skin = meta.files.iter_mut().find(|x| x.id == "A.SKIN").unwrap();
+        skin.decoded.as_mut().unwrap().checksum = None;
+        serde_json::to_writer(File::create(&meta_path).unwrap(), &meta).unwrap();
+        assert!(import_archive(&dir, fmt).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
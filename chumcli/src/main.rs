@@ -1,54 +1,160 @@
 use std::cmp;
+use std::collections::BTreeSet;
 use std::error::Error;
-use std::fs::{self, File};
-use std::path::Path;
+use std::ffi::OsString;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufReader, Read, Write};
+use std::path::{Path, PathBuf};
 #[macro_use]
 extern crate clap;
 #[macro_use]
 extern crate serde_derive;
-use libchum::{format::TotemFormat, archive::{ChumArchive, TotemArchive, nametable::TotemNameTable}, util};
+use libchum::{format::TotemFormat, archive::{self, nametable::TotemNameTable, packer, scan, validate, ArchiveDiff, ChumArchive, ChumArchiveReader, ChumFile, ChunkPacker, ChunkSize, ConvertReport, NameOrder, ReferenceGraph, Severity, TotemArchiveIndex, subset}, names::{crack, NameCracker, NameDictionary, NameParts, Template}, reader, structure, util, workspace::Workspace};
 
+pub mod decode;
 pub mod json;
 
-fn load_archive_raw(
-    matches: &clap::ArgMatches,
-) -> Result<(TotemArchive, TotemNameTable), Box<dyn Error>> {
-    let namepath = Path::new(matches.value_of_os("NAMES").unwrap());
-    let datapath = Path::new(matches.value_of_os("DATA").unwrap());
-    let typeval = if matches.is_present("ngc") {
-        TotemFormat::NGC
+fn get_format(matches: &clap::ArgMatches) -> Option<TotemFormat> {
+    if matches.is_present("ngc") {
+        Some(TotemFormat::NGC)
     } else if matches.is_present("ps2") {
-        TotemFormat::PS2
+        Some(TotemFormat::PS2)
     } else {
-        panic!("No format given");
+        None
+    }
+}
+
+/// The files and format of an archive given on the command line.
+struct ArchivePaths {
+    names: PathBuf,
+    data: PathBuf,
+    format: TotemFormat,
+}
+
+/// Find the archive given by the argument `arg`, which is either the archive's
+/// path without extension, or the path to its NGC or DGC file.
+/// The format is detected from the DGC file unless --ngc or --ps2 is given.
+fn get_archive_paths(matches: &clap::ArgMatches, arg: &str) -> Result<ArchivePaths, Box<dyn Error>> {
+    let entry = scan::find_archive(Path::new(matches.value_of_os(arg).unwrap()))?;
+    let stem = entry.stem.display();
+    let names = entry.names_path.ok_or_else(|| format!("Could not find the NGC file for {}", stem))?;
+    let data = entry.data_path.ok_or_else(|| format!("Could not find the DGC file for {}", stem))?;
+    let format = match get_format(matches).or(entry.format) {
+        Some(format) => format,
+        None => return Err(format!("Could not detect the format of {}; use --ngc or --ps2", stem).into()),
     };
-    let mut namefile = File::open(namepath)?;
-    let mut datafile = File::open(datapath)?;
-    Ok((
-        TotemArchive::read_from(&mut datafile, typeval)?,
-        TotemNameTable::read_from(&mut namefile)?,
-    ))
+    Ok(ArchivePaths { names, data, format })
 }
 
-fn load_archive(matches: &clap::ArgMatches) -> Result<ChumArchive, Box<dyn Error>> {
-    let namepath = Path::new(matches.value_of_os("NAMES").unwrap());
-    let datapath = Path::new(matches.value_of_os("DATA").unwrap());
-    let typeval = if matches.is_present("ngc") {
-        TotemFormat::NGC
-    } else if matches.is_present("ps2") {
-        TotemFormat::PS2
-    } else {
-        panic!("No format given");
+/// Get the paths to write the archive given by the argument `arg` to.
+/// Existing files are overwritten; otherwise, the extensions used by `fmt` are added.
+fn get_output_paths(
+    matches: &clap::ArgMatches,
+    arg: &str,
+    fmt: TotemFormat,
+) -> Result<(PathBuf, PathBuf), Box<dyn Error>> {
+    let entry = scan::find_archive(Path::new(matches.value_of_os(arg).unwrap()))?;
+    let (names_ext, data_ext) = match fmt {
+        TotemFormat::NGC => ("NGC", "DGC"),
+        TotemFormat::PS2 => ("NPS", "DPS"),
     };
+    let stem = &entry.stem;
+    let namepath = entry.names_path.unwrap_or_else(|| scan::add_extension(stem, names_ext));
+    let datapath = entry.data_path.unwrap_or_else(|| scan::add_extension(stem, data_ext));
+    Ok((namepath, datapath))
+}
+
+fn load_archive_index(matches: &clap::ArgMatches) -> Result<TotemArchiveIndex, Box<dyn Error>> {
+    let paths = get_archive_paths(matches, "ARCHIVE")?;
+    let mut datafile = BufReader::new(File::open(paths.data)?);
+    Ok(TotemArchiveIndex::read_from(&mut datafile, paths.format)?)
+}
+
+fn load_archive_reader(
+    matches: &clap::ArgMatches,
+) -> Result<ChumArchiveReader<BufReader<File>>, Box<dyn Error>> {
+    let paths = get_archive_paths(matches, "ARCHIVE")?;
+    let mut namefile = File::open(paths.names)?;
+    let datafile = BufReader::new(File::open(paths.data)?);
+    Ok(ChumArchiveReader::open(&mut namefile, datafile, paths.format)?)
+}
+
+/// Load the built-in name dictionary along with any dictionaries given with --names.
+fn load_dictionary(matches: &clap::ArgMatches) -> Result<NameDictionary, Box<dyn Error>> {
+    let paths = matches.values_of_os("names").into_iter().flatten();
+    Ok(NameDictionary::with_user_dictionaries(paths)?)
+}
+
+/// Load an archive into memory so that it can be edited and written back in place.
+fn load_archive(matches: &clap::ArgMatches) -> Result<ChumArchive, Box<dyn Error>> {
+    let paths = get_archive_paths(matches, "ARCHIVE")?;
+    let mut namefile = File::open(paths.names)?;
+    let mut datafile = BufReader::new(File::open(paths.data)?);
+    let mut archive = ChumArchive::read_chum_archive(&mut namefile, &mut datafile, paths.format)?;
+    // Keep unchanged files where they are, so that edits produce minimal changes
+    archive.set_preserve_layout(true);
+    Ok(archive)
+}
+
+/// Write an archive loaded with `load_archive` back to the files it was read from.
+fn save_archive(matches: &clap::ArgMatches, archive: &ChumArchive) -> Result<(), Box<dyn Error>> {
+    let paths = get_archive_paths(matches, "ARCHIVE")?;
+    let mut ngc_data = Vec::new();
+    let mut dgc_data = Vec::new();
+    archive.write_chum_archive(&mut ngc_data, &mut dgc_data, NameOrder::Original)?;
+    // Only overwrite the archive once it has been written successfully
+    fs::write(paths.names, ngc_data)?;
+    fs::write(paths.data, dgc_data)?;
+    Ok(())
+}
+
+/// Load an archive whose file data is borrowed from a memory-mapped DGC file.
+/// The DGC file must not be written to while the returned archive is alive.
+fn load_archive_mapped(matches: &clap::ArgMatches, arg: &str) -> Result<ChumArchive, Box<dyn Error>> {
+    let paths = get_archive_paths(matches, arg)?;
+    read_archive_mapped(&paths.names, &paths.data, paths.format)
+}
+
+/// Load the archives given by the argument `arg` into a workspace, in the order they were given.
+/// Each archive's format is detected unless --ngc or --ps2 is given.
+fn load_shared_archives(matches: &clap::ArgMatches, arg: &str) -> Result<Workspace, Box<dyn Error>> {
+    let mut workspace = Workspace::new();
+    for value in matches.values_of_os(arg).into_iter().flatten() {
+        let entry = scan::find_archive(Path::new(value))?;
+        let stem = entry.stem.display();
+        let names = entry.names_path.as_ref().ok_or_else(|| format!("Could not find the NGC file for {}", stem))?;
+        let data = entry.data_path.as_ref().ok_or_else(|| format!("Could not find the DGC file for {}", stem))?;
+        let format = match get_format(matches).or(entry.format) {
+            Some(format) => format,
+            None => return Err(format!("Could not detect the format of {}; use --ngc or --ps2", stem).into()),
+        };
+        let archive = read_archive_mapped(names, data, format)?;
+        workspace.add_archive(stem.to_string(), archive);
+    }
+    Ok(workspace)
+}
+
+/// Read an archive from the given paths, memory-mapping the DGC file.
+fn read_archive_mapped(
+    namepath: &Path,
+    datapath: &Path,
+    typeval: TotemFormat,
+) -> Result<ChumArchive, Box<dyn Error>> {
     let mut namefile = File::open(namepath)?;
-    let mut datafile = File::open(datapath)?;
-    Ok(ChumArchive::read_chum_archive(&mut namefile, &mut datafile, typeval)?)
+    let datafile = File::open(datapath)?;
+    // The archive is only read from, and output never overwrites the input DGC.
+    let data = unsafe { archive::map_file(&datafile)? };
+    Ok(ChumArchive::read_chum_archive_shared(
+        &mut namefile,
+        data,
+        typeval,
+    )?)
 }
 
 /// Info command.
 /// Gets information about the given archive.
 fn cmd_info(matches: &clap::ArgMatches) -> Result<(), Box<dyn Error>> {
-    let (dgc, _ngc) = load_archive_raw(matches)?;
+    let dgc = load_archive_index(matches)?;
     let chunk_size = dgc.get_chunk_size();
     let mut max_file_size = 0usize;
     let mut min_file_size = usize::max_value();
@@ -56,7 +162,7 @@ fn cmd_info(matches: &clap::ArgMatches) -> Result<(), Box<dyn Error>> {
     let mut total_size = 0;
     for (chunk_i, chunk) in dgc.iter_chunks().enumerate() {
         let mut chunk_total_size = 0;
-        for file in chunk.iter_files() {
+        for file in dgc.iter_chunk_files(chunk) {
             let file_total_size = file.get_total_size();
             chunk_total_size += file_total_size;
             total_size += file_total_size;
@@ -93,7 +199,7 @@ fn cmd_info(matches: &clap::ArgMatches) -> Result<(), Box<dyn Error>> {
 /// List command.
 /// Lists all of the files in the given archive.
 fn cmd_list(matches: &clap::ArgMatches) -> Result<(), Box<dyn Error>> {
-    let archive = load_archive(matches)?;
+    let archive = load_archive_reader(matches)?;
     let mut maxnamelen = 4;
     let mut maxtypelen = 4;
     let mut maxsubtypelen = 7;
@@ -125,7 +231,7 @@ fn cmd_list(matches: &clap::ArgMatches) -> Result<(), Box<dyn Error>> {
 /// Extract command.
 /// Extracts the data from an archive into a folder and a json file.
 fn cmd_extract(matches: &clap::ArgMatches) -> Result<(), Box<dyn Error>> {
-    let archive = load_archive(matches)?;
+    let archive = load_archive_mapped(matches, "ARCHIVE")?;
     let output_path = Path::new(matches.value_of_os("OUTPUT").unwrap());
     fs::create_dir_all(&output_path)?;
     let merge = if matches.is_present("replace") {
@@ -135,82 +241,874 @@ fn cmd_extract(matches: &clap::ArgMatches) -> Result<(), Box<dyn Error>> {
     } else {
         panic!("Neither merge nor replace are present.");
     };
-    json::extract_archive(&archive, &output_path, merge)?;
+    let dictionary = load_dictionary(matches)?;
+    let decode = if matches.is_present("decode") { Some(&dictionary) } else { None };
+    json::extract_archive(&archive, &output_path, merge, decode)?;
     println!("Extraction successful");
     Ok(())
 }
 
+/// Check command.
+/// Validates the given archive, exiting with an error code if any errors are found.
+fn cmd_check(matches: &clap::ArgMatches) -> Result<(), Box<dyn Error>> {
+    let paths = get_archive_paths(matches, "ARCHIVE")?;
+    let ngc = TotemNameTable::read_from(&mut File::open(&paths.names)?)?;
+    let datafile = File::open(&paths.data)?;
+    // The DGC file is only read from.
+    let data = unsafe { archive::map_file(&datafile)? };
+    let mut diagnostics = validate::validate(&ngc, (*data).as_ref(), paths.format);
+    // References can only be checked if the archive can be read
+    if !diagnostics.iter().any(|x| x.is_error()) {
+        let archive = ChumArchive::read_chum_archive_shared(&mut File::open(&paths.names)?, data.clone(), paths.format)?;
+        let shared = load_shared_archives(matches, "shared")?;
+        diagnostics.append(&mut validate::validate_references(&archive, &shared));
+    }
+    let mut num_errors = 0;
+    for diagnostic in diagnostics.iter() {
+        match diagnostic.get_severity() {
+            Severity::Error => {
+                num_errors += 1;
+                println!("error: {}", diagnostic);
+            }
+            Severity::Warning => println!("warning: {}", diagnostic),
+        }
+    }
+    println!(
+        "{} errors, {} warnings",
+        num_errors,
+        diagnostics.len() - num_errors
+    );
+    if num_errors > 0 {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// Remove command.
+/// Removes files from an archive in place.
+fn cmd_rm(matches: &clap::ArgMatches) -> Result<(), Box<dyn Error>> {
+    let mut archive = load_archive(matches)?;
+    let gc = matches.is_present("gc");
+    for name in matches.values_of("FILES").unwrap() {
+        archive.remove_file(name, gc)?;
+        println!("Removed {}", name);
+    }
+    save_archive(matches, &archive)
+}
+
+/// Move command.
+/// Renames a file in an archive in place.
+fn cmd_mv(matches: &clap::ArgMatches) -> Result<(), Box<dyn Error>> {
+    let mut archive = load_archive(matches)?;
+    let source = matches.value_of("SOURCE").unwrap();
+    let dest = matches.value_of("DEST").unwrap();
+    archive.rename_file(source, dest, matches.is_present("gc"))?;
+    if matches.is_present("type_id") || matches.is_present("subtype_id") {
+        // Keep whichever of the type and subtype is not given
+        let file = archive.get_file_from_name(dest).unwrap();
+        let typeid = matches
+            .value_of("type_id")
+            .unwrap_or_else(|| file.get_type_id())
+            .to_string();
+        let subtypeid = matches
+            .value_of("subtype_id")
+            .unwrap_or_else(|| file.get_subtype_id())
+            .to_string();
+        archive.retype_file(dest, &typeid, &subtypeid, matches.is_present("gc"))?;
+    }
+    println!("Moved {} to {}", source, dest);
+    save_archive(matches, &archive)
+}
+
+/// Add command.
+/// Adds a file to an archive in place, or replaces an existing file's data.
+fn cmd_add(matches: &clap::ArgMatches) -> Result<(), Box<dyn Error>> {
+    let mut archive = load_archive(matches)?;
+    let input_path = Path::new(matches.value_of_os("INPUT").unwrap());
+    let name = matches.value_of("NAME").unwrap();
+    let mut data = Vec::new();
+    File::open(input_path)?.read_to_end(&mut data)?;
+    let typeid = matches.value_of("type_id");
+    let subtypeid = matches.value_of("subtype_id");
+    match archive.get_file_from_name(name) {
+        Some(file) if matches.is_present("replace") => {
+            let retype = typeid.is_some() || subtypeid.is_some();
+            // Keep whichever of the type and subtype is not given
+            let typeid = typeid.unwrap_or_else(|| file.get_type_id()).to_string();
+            let subtypeid = subtypeid.unwrap_or_else(|| file.get_subtype_id()).to_string();
+            archive.replace_file_data(name, data)?;
+            if retype {
+                archive.retype_file(name, &typeid, &subtypeid, true)?;
+            }
+            println!("Replaced {}", name);
+        }
+        _ => {
+            let typeid = match typeid {
+                Some(x) => x,
+                None => return Err("A type is required to add a new file".into()),
+            };
+            archive.add_file(ChumFile::new(
+                data,
+                name.to_string(),
+                typeid.to_string(),
+                subtypeid.unwrap_or(name).to_string(),
+            ))?;
+            println!("Added {}", name);
+        }
+    }
+    save_archive(matches, &archive)
+}
+
+/// Lists the fields that differ between two versions of the same file.
+fn diff_structure(old: &ChumArchive, new: &ChumArchive, name: &str) -> Result<(), Box<dyn Error>> {
+    let missing = || archive::ChumError::FileMissingError { name: name.into() };
+    let oldfile = old.get_file_from_name(name).ok_or_else(missing)?;
+    let newfile = new.get_file_from_name(name).ok_or_else(missing)?;
+    if oldfile.get_type_id() != newfile.get_type_id() {
+        println!("type         {} -> {}", oldfile.get_type_id(), newfile.get_type_id());
+        return Ok(());
+    }
+    let oldstruct = reader::read_structure(oldfile.get_type_id(), oldfile.get_data(), old.get_format())?;
+    let newstruct = reader::read_structure(newfile.get_type_id(), newfile.get_data(), new.get_format())?;
+    let changes = structure::diff::diff(&oldstruct, &newstruct);
+    for change in changes.iter() {
+        println!("{}", change);
+    }
+    println!("{} fields changed", changes.len());
+    Ok(())
+}
+
+/// Diff command.
+/// Lists the differences between two archives, optionally writing them to a patch file.
+fn cmd_diff(matches: &clap::ArgMatches) -> Result<(), Box<dyn Error>> {
+    let old = load_archive_mapped(matches, "OLD")?;
+    let new = load_archive_mapped(matches, "NEW")?;
+    if let Some(name) = matches.value_of("structural") {
+        return diff_structure(&old, &new, name);
+    }
+    let diff = ArchiveDiff::new(&old, &new);
+    for file in diff.get_added() {
+        println!("added        {}", file.get_name_id());
+    }
+    for file in diff.get_modified() {
+        println!("modified     {}", file.get_name_id());
+    }
+    for name in diff.get_removed() {
+        println!("removed      {}", name);
+    }
+    for name in diff.get_names_added() {
+        println!("name added   {}", name);
+    }
+    for name in diff.get_names_removed() {
+        println!("name removed {}", name);
+    }
+    println!(
+        "{} added, {} modified, {} removed",
+        diff.get_added().len(),
+        diff.get_modified().len(),
+        diff.get_removed().len()
+    );
+    if let Some(path) = matches.value_of_os("output") {
+        let mut patch_file = File::create(path)?;
+        diff.write_to(&mut patch_file)?;
+        println!("Patch written");
+    }
+    Ok(())
+}
+
+/// Patch command.
+/// Applies a patch file to an archive in place.
+fn cmd_patch(matches: &clap::ArgMatches) -> Result<(), Box<dyn Error>> {
+    let mut archive = load_archive(matches)?;
+    let patch_path = Path::new(matches.value_of_os("PATCH").unwrap());
+    let diff = ArchiveDiff::read_from(&mut BufReader::new(File::open(patch_path)?))?;
+    diff.apply(&mut archive)?;
+    save_archive(matches, &archive)?;
+    println!("Patching successful");
+    Ok(())
+}
+
+/// Convert command.
+/// Converts an archive to another format, listing the files that could not be converted.
+fn cmd_convert(matches: &clap::ArgMatches) -> Result<(), Box<dyn Error>> {
+    let archive = load_archive_mapped(matches, "ARCHIVE")?;
+    let target = match matches.value_of("to") {
+        Some("ps2") => TotemFormat::PS2,
+        _ => TotemFormat::NGC,
+    };
+    let (converted, report) = archive.convert_format(target);
+    check_conversion(&report, matches.is_present("allow_unconverted"))?;
+    let (namepath, datapath) = get_output_paths(matches, "OUTPUT", target)?;
+    let mut ngc_data = Vec::new();
+    let mut dgc_data = Vec::new();
+    converted.write_chum_archive(&mut ngc_data, &mut dgc_data, NameOrder::Original)?;
+    // The output may be the same as the input, which is memory-mapped
+    drop(archive);
+    fs::write(namepath, ngc_data)?;
+    fs::write(datapath, dgc_data)?;
+    println!(
+        "{} files converted, {} copied without changes",
+        report.converted.len(),
+        report.failed.len()
+    );
+    Ok(())
+}
+
+/// Print every file that could not be converted.
+/// Unless `allow_unconverted` is set, these are errors and nothing should be written.
+fn check_conversion(report: &ConvertReport, allow_unconverted: bool) -> Result<(), Box<dyn Error>> {
+    let severity = if allow_unconverted { "warning" } else { "error" };
+    for (name, err) in report.failed.iter() {
+        println!("{}: {} could not be converted: {}", severity, name, err);
+    }
+    if !allow_unconverted && !report.failed.is_empty() {
+        return Err(format!(
+            "{} files could not be converted; use --allow-unconverted to copy them without changes",
+            report.failed.len()
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// Scan command.
+/// Lists every archive in the given directory along with its format.
+fn cmd_scan(matches: &clap::ArgMatches) -> Result<(), Box<dyn Error>> {
+    let path = Path::new(matches.value_of_os("DIRECTORY").unwrap());
+    let catalog = scan::scan_directory(path)?;
+    println!("{:<7} ARCHIVE", "FORMAT");
+    for entry in catalog.iter() {
+        let format = match entry.format {
+            Some(TotemFormat::NGC) => "NGC",
+            Some(TotemFormat::PS2) => "PS2",
+            None => "?",
+        };
+        let missing = if entry.names_path.is_none() {
+            " (missing NGC)"
+        } else if entry.data_path.is_none() {
+            " (missing DGC)"
+        } else {
+            ""
+        };
+        println!("{:<7} {}{}", format, entry.stem.display(), missing);
+    }
+    println!(
+        "{} archives, {} incomplete",
+        catalog.len(),
+        catalog.iter().filter(|x| !x.is_complete()).count()
+    );
+    Ok(())
+}
+
+/// Read the lines of a text file, skipping blank lines.
+fn read_lines(path: &Path) -> Result<Vec<String>, Box<dyn Error>> {
+    Ok(fs::read_to_string(path)?
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
+        .map(|line| line.to_owned())
+        .collect())
+}
+
+/// Crack names command.
+/// Searches for the names of IDs that are referenced in an archive but are not in its name table,
+/// and adds the names that were found to a dictionary file.
+/// Get the IDs that the files of an archive refer to,
+/// including names that are not files, such as the names of a skin's bones.
+fn get_referenced_ids(archive: &ChumArchive) -> BTreeSet<i32> {
+    let graph = ReferenceGraph::new(archive);
+    let mut ids: BTreeSet<i32> = graph.get_references().iter().map(|x| x.to).collect();
+    for (id, _) in archive.get_files_hash() {
+        ids.extend(graph.get_names(*id));
+    }
+    ids
+}
+
+/// Append names to a dictionary file, so that comments and the order of existing names are kept
+fn append_names(path: &Path, names: &NameDictionary) -> Result<(), Box<dyn Error>> {
+    // Make sure that the first new name does not end up on the file's last line
+    let needs_newline = match fs::read(path) {
+        Ok(data) => !data.is_empty() && !data.ends_with(b"\n"),
+        Err(_) => false,
+    };
+    let mut dict_file = OpenOptions::new().create(true).append(true).open(path)?;
+    if needs_newline {
+        dict_file.write_all(b"\n")?;
+    }
+    names.write_to(&mut dict_file)?;
+    Ok(())
+}
+
+fn cmd_crack_names(matches: &clap::ArgMatches) -> Result<(), Box<dyn Error>> {
+    let archive = load_archive_mapped(matches, "ARCHIVE")?;
+    let dict_path = Path::new(matches.value_of_os("dictionary").unwrap());
+    let mut dictionary = load_dictionary(matches)?;
+    if dict_path.exists() {
+        dictionary.merge(NameDictionary::load(dict_path)?);
+    }
+    let mut targets = get_referenced_ids(&archive);
+    for id in matches.values_of("id").into_iter().flatten() {
+        targets.insert(id.parse::<i32>()?);
+    }
+    targets.retain(|id| archive.resolve_name(*id, &dictionary).is_none());
+    let mut cracker = NameCracker::new(targets);
+    if let Some(value) = matches.value_of("max_number") {
+        cracker.set_max_number(value.parse()?);
+    }
+    let num_targets = cracker.get_remaining().count();
+    println!("{} unresolved IDs", num_targets);
+    if cracker.is_done() {
+        return Ok(());
+    }
+
+    let known: Vec<&str> = archive
+        .get_names()
+        .chain(dictionary.get_names().values())
+        .map(|x| x.as_str())
+        .collect();
+    let parts = NameParts::from_names(&known);
+    let mut variables = parts.get_variables();
+    let mut words = Vec::new();
+    for path in matches.values_of_os("wordlist").into_iter().flatten() {
+        words.append(&mut read_lines(Path::new(path))?);
+    }
+    variables.insert("WORD".to_owned(), words.clone());
+    for var in matches.values_of("var").into_iter().flatten() {
+        let (name, path) = match var.find('=') {
+            Some(i) => (&var[..i], &var[i + 1..]),
+            None => return Err(format!("Expected NAME=FILE, got {}", var).into()),
+        };
+        variables.insert(name.to_owned(), read_lines(Path::new(path))?);
+    }
+    let mut templates = Vec::new();
+    for pattern in crack::DEFAULT_TEMPLATES.iter().copied().chain(matches.values_of("template").into_iter().flatten()) {
+        templates.push(Template::parse(pattern)?);
+    }
+
+    cracker.try_names(&words);
+    cracker.try_mirrored(words.iter().map(|x| x.as_str()).chain(known.iter().copied()));
+    cracker.try_recombined(&parts);
+    for template in templates.iter() {
+        cracker.try_template(template, &variables)?;
+    }
+
+    let found = cracker.get_found();
+    for (id, name) in found.iter() {
+        println!("found {:>11} {}", id, name);
+    }
+    println!(
+        "{} of {} names found, {} candidates tried",
+        found.len(),
+        num_targets,
+        cracker.get_tried()
+    );
+    if !found.is_empty() {
+        let mut new_names = NameDictionary::new();
+        for (id, name) in found.iter() {
+            new_names.insert(*id, name.clone());
+        }
+        append_names(dict_path, &new_names)?;
+        println!("Names written to {}", dict_path.display());
+    }
+    Ok(())
+}
+
+/// Get the ID of the file with the given name. The name may also be the file's ID.
+fn parse_file_id(archive: &ChumArchive, name: &str) -> Result<i32, Box<dyn Error>> {
+    if archive.get_file_from_name(name).is_some() {
+        return Ok(util::hash_name_i32(name));
+    }
+    match name.parse::<i32>() {
+        Ok(id) if archive.get_file_from_hash(id).is_some() => Ok(id),
+        _ => Err(format!("Could not find file {}", name).into()),
+    }
+}
+
+/// Get the ID of the file given by the argument `arg`, which is either its name or its ID.
+fn get_file_id(matches: &clap::ArgMatches, arg: &str, archive: &ChumArchive) -> Result<i32, Box<dyn Error>> {
+    parse_file_id(archive, matches.value_of(arg).unwrap())
+}
+
+/// Describe the given ID as its name and the type of the file that it refers to.
+fn describe_id(archive: &ChumArchive, dictionary: &NameDictionary, id: i32) -> String {
+    let name = match archive.resolve_name(id, dictionary) {
+        Some(name) => name.to_owned(),
+        None => format!("#{}", id),
+    };
+    match archive.get_file_from_hash(id) {
+        Some(file) => format!("{} ({})", name, file.get_type_id()),
+        None => format!("{} (not in archive)", name),
+    }
+}
+
+/// Print a warning if the structure of the given file could not be read.
+fn warn_unreadable(graph: &ReferenceGraph, archive: &ChumArchive, id: i32) {
+    for (_, err) in graph.get_unreadable().iter().filter(|(x, _)| *x == id) {
+        println!("warning: could not read {}: {}", archive.get_name_from_id(id).unwrap_or("?"), err);
+    }
+}
+
+/// Dependencies command.
+/// Lists the files that a file references.
+fn cmd_deps(matches: &clap::ArgMatches) -> Result<(), Box<dyn Error>> {
+    let archive = load_archive_mapped(matches, "ARCHIVE")?;
+    let dictionary = load_dictionary(matches)?;
+    let id = get_file_id(matches, "NAME", &archive)?;
+    let graph = ReferenceGraph::new(&archive);
+    warn_unreadable(&graph, &archive, id);
+    if matches.is_present("recursive") {
+        for other in graph.get_transitive_dependencies(&[id], |_| true) {
+            if other != id {
+                println!("{}", describe_id(&archive, &dictionary, other));
+            }
+        }
+    } else {
+        for reference in graph.get_dependencies(id) {
+            println!("{}: {}", reference.path, describe_id(&archive, &dictionary, reference.to));
+        }
+    }
+    Ok(())
+}
+
+/// Reverse dependencies command.
+/// Lists the files that reference a file.
+fn cmd_rdeps(matches: &clap::ArgMatches) -> Result<(), Box<dyn Error>> {
+    let archive = load_archive_mapped(matches, "ARCHIVE")?;
+    let dictionary = load_dictionary(matches)?;
+    let id = get_file_id(matches, "NAME", &archive)?;
+    let graph = ReferenceGraph::new(&archive);
+    if matches.is_present("recursive") {
+        for other in graph.get_transitive_dependents(&[id]) {
+            if other != id {
+                println!("{}", describe_id(&archive, &dictionary, other));
+            }
+        }
+    } else {
+        for reference in graph.get_dependents(id) {
+            println!("{}: {}", describe_id(&archive, &dictionary, reference.from), reference.path);
+        }
+    }
+    Ok(())
+}
+
+/// Orphans command.
+/// Lists the files that are not referenced by any other file.
+fn cmd_orphans(matches: &clap::ArgMatches) -> Result<(), Box<dyn Error>> {
+    let archive = load_archive_mapped(matches, "ARCHIVE")?;
+    let dictionary = load_dictionary(matches)?;
+    let graph = ReferenceGraph::new(&archive);
+    let orphans = graph.find_orphans();
+    for id in orphans.iter() {
+        println!("{}", describe_id(&archive, &dictionary, *id));
+    }
+    println!("{} orphaned files", orphans.len());
+    Ok(())
+}
+
+/// Subset command.
+/// Extracts files into a new archive, along with every file that they reference.
+fn cmd_subset(matches: &clap::ArgMatches) -> Result<(), Box<dyn Error>> {
+    let archive = load_archive_mapped(matches, "ARCHIVE")?;
+    let dictionary = load_dictionary(matches)?;
+    let mut roots = Vec::new();
+    for name in matches.values_of("ROOTS").unwrap() {
+        roots.push(parse_file_id(&archive, name)?);
+    }
+    let (subset, report) = if matches.is_present("parents") {
+        archive.extract_subset(&roots, |_| true)
+    } else {
+        archive.extract_subset(&roots, |x| !subset::is_parent_reference(x))
+    };
+    for id in report.files.iter() {
+        println!("{}", describe_id(&archive, &dictionary, *id));
+    }
+    for reference in report.external.iter() {
+        println!(
+            "not included: {} in {}: {}",
+            reference.path,
+            describe_id(&archive, &dictionary, reference.from),
+            describe_id(&archive, &dictionary, reference.to)
+        );
+    }
+    let (namepath, datapath) = get_output_paths(matches, "OUTPUT", archive.get_format())?;
+    let mut ngc_data = Vec::new();
+    let mut dgc_data = Vec::new();
+    subset.write_chum_archive(&mut ngc_data, &mut dgc_data, NameOrder::Original)?;
+    // The output may be the same as the input, which is memory-mapped
+    drop(subset);
+    drop(archive);
+    fs::write(namepath, ngc_data)?;
+    fs::write(datapath, dgc_data)?;
+    println!(
+        "{} files extracted, {} references not included",
+        report.files.len(),
+        report.external.len()
+    );
+    Ok(())
+}
+
+/// Export struct command.
+/// Writes the structure of a file as JSON.
+fn cmd_export_struct(matches: &clap::ArgMatches) -> Result<(), Box<dyn Error>> {
+    let archive = load_archive_mapped(matches, "ARCHIVE")?;
+    let dictionary = load_dictionary(matches)?;
+    let id = get_file_id(matches, "FILE", &archive)?;
+    let file = archive.get_file_from_hash(id).unwrap();
+    let data = reader::read_structure(file.get_type_id(), file.get_data(), archive.get_format())?;
+    let value = structure::json::to_json(&data, |x| archive.resolve_name(x, &dictionary));
+    match matches.value_of_os("OUTPUT") {
+        Some(path) => serde_json::to_writer_pretty(File::create(path)?, &value)?,
+        None => println!("{}", serde_json::to_string_pretty(&value)?),
+    }
+    Ok(())
+}
+
+/// Import struct command.
+/// Replaces a file's data in place with a structure read from JSON.
+fn cmd_import_struct(matches: &clap::ArgMatches) -> Result<(), Box<dyn Error>> {
+    let mut archive = load_archive(matches)?;
+    let id = get_file_id(matches, "FILE", &archive)?;
+    let input_path = Path::new(matches.value_of_os("INPUT").unwrap());
+    let value: serde_json::Value = serde_json::from_reader(BufReader::new(File::open(input_path)?))?;
+    let fmt = archive.get_format();
+    let file = archive.get_file_from_hash(id).unwrap();
+    let type_id = file.get_type_id().to_string();
+    let name = file.get_name_id().to_string();
+    let template = reader::get_default_structure(&type_id)
+        .ok_or_else(|| format!("Files of type {} can not be written from a structure", type_id))?;
+    let data = structure::json::from_json(&template, &value)?;
+    archive.replace_file_data(&name, reader::write_structure(&type_id, &data, fmt)?)?;
+    println!("Replaced {}", name);
+    save_archive(matches, &archive)
+}
+
+/// Parse a size in bytes, either in decimal or in hexadecimal with a 0x prefix.
+fn parse_size(value: &str) -> Result<usize, std::num::ParseIntError> {
+    if value.starts_with("0x") || value.starts_with("0X") {
+        usize::from_str_radix(&value[2..], 16)
+    } else {
+        value.parse()
+    }
+}
+
 /// Pack command.
 /// Pack the extracted .json and data folder back into archive files.
 fn cmd_pack(matches: &clap::ArgMatches) -> Result<(), Box<dyn Error>> {
     let input_path = Path::new(matches.value_of_os("INPUT").unwrap());
-    let namepath = Path::new(matches.value_of_os("NAMES").unwrap());
-    let datapath = Path::new(matches.value_of_os("DATA").unwrap());
-    let typeval = if matches.is_present("ngc") {
-        TotemFormat::NGC
-    } else if matches.is_present("ps2") {
-        TotemFormat::PS2
-    } else {
-        panic!("No format given");
+    let typeval = get_format(matches).unwrap();
+    let (namepath, datapath) = get_output_paths(matches, "ARCHIVE", typeval)?;
+    let chunk_size = match matches.value_of("chunk_size") {
+        Some(value) => ChunkSize::Fixed(parse_size(value)?),
+        None => ChunkSize::Auto,
+    };
+    let name_order = match matches.value_of("name_order") {
+        Some("id") => NameOrder::SortedById,
+        _ => NameOrder::SortedByName,
     };
     let archive = json::import_archive(&input_path, typeval)?;
+    let packer: Box<dyn ChunkPacker> = match matches.value_of("packer") {
+        // Keep files together with the files that they reference
+        Some("ordered") => {
+            let graph = ReferenceGraph::new(&archive);
+            Box::new(packer::OrderedPacker::with_dependencies(
+                graph.get_references().iter().map(|x| (x.from, x.to)),
+            ))
+        }
+        name => packer::packer_from_name(name.unwrap_or("greedy")).unwrap(),
+    };
     let mut ngc_file = File::create(namepath)?;
     let mut dgc_file = File::create(datapath)?;
-    archive.write_chum_archive(&mut ngc_file, &mut dgc_file)?;
+    let dgc = archive.write_chum_archive_packed(
+        &mut ngc_file,
+        &mut dgc_file,
+        name_order,
+        packer.as_ref(),
+        chunk_size,
+    )?;
     println!("Packing successful");
+    println!(
+        "{} chunks of {}B ({1:X}), {}B padding",
+        dgc.iter_chunks().count(),
+        dgc.get_chunk_size(),
+        dgc.get_total_padding()
+    );
     Ok(())
 }
 
+/// Subcommands that took an archive as separate NAMES and DATA arguments
+/// before archives could be given by their stem.
+const LEGACY_COMMANDS: &[&str] = &[
+    "info", "list", "extract", "check", "rm", "mv", "add", "diff", "patch", "pack",
+];
+
+/// Rewrite the old two-argument form of an archive, `ARCHIVE.NGC ARCHIVE.DGC`,
+/// into the single data file path that the subcommands now take.
+fn merge_archive_args(args: Vec<OsString>) -> Result<Vec<OsString>, Box<dyn Error>> {
+    let mut skip_value = false;
+    let command = args.iter().skip(1).position(|arg| {
+        let arg = arg.to_string_lossy();
+        if skip_value {
+            skip_value = false;
+            return false;
+        }
+        skip_value = arg == "--names";
+        !arg.starts_with('-')
+    });
+    let command = match command {
+        Some(i) if LEGACY_COMMANDS.contains(&&*args[i + 1].to_string_lossy()) => i + 1,
+        _ => return Ok(args),
+    };
+    let mut result: Vec<OsString> = args[..=command].to_vec();
+    let mut rest = args[command + 1..].iter().peekable();
+    while let Some(arg) = rest.next() {
+        let names = Path::new(arg);
+        let data = match rest.peek() {
+            Some(data) => Path::new(*data),
+            None => {
+                result.push(arg.clone());
+                break;
+            }
+        };
+        if scan::get_file_kind(names) != Some(scan::ArchiveFileKind::Names)
+            || scan::get_file_kind(data) != Some(scan::ArchiveFileKind::Data)
+        {
+            result.push(arg.clone());
+            continue;
+        }
+        if names.with_extension("") != data.with_extension("") {
+            return Err(format!(
+                "{} and {} are not the same archive; give the archive by its stem instead",
+                names.display(),
+                data.display()
+            )
+            .into());
+        }
+        result.push(data.as_os_str().to_owned());
+        rest.next();
+    }
+    Ok(result)
+}
+
 fn main() -> Result<(), Box<dyn Error>> {
     let mut app = clap_app!(chumcli =>
         (version: "0.1.0")
         (author: "Jocelyn \"Jellonator\" B. <jellonator00@gmail.com>")
         (about: "Edits Totem archive files")
         (name: "Chum World")
+        (after_help: "Archives may also be given as two paths, the name table followed by the data file, \
+            as in `chumcli list ARCHIVE.NGC ARCHIVE.DGC`.")
+        (@arg names: --names +global +takes_value +multiple number_of_values(1)
+            "A dictionary of names to use along with the built-in one")
         (@subcommand info =>
             (about: "Get information about the given archive")
-            (@arg NAMES: +required "The name table file to use")
-            (@arg DATA: +required "The data file to use")
-            (@group type +required =>
-                (@arg ngc: --ngc "Use Nintendo Gamecube format")
-                (@arg ps2: --ps2 "Use Playstation 2 format")
+            (@arg ARCHIVE: +required "The archive to use: its path without extension, or its name table or data file")
+            (@group type =>
+                (@arg ngc: --ngc "Use Nintendo Gamecube format (default: detect)")
+                (@arg ps2: --ps2 "Use Playstation 2 format (default: detect)")
             )
         )
         (@subcommand list =>
             (about: "Lists the contents of the given archive")
-            (@arg NAMES: +required "The name table file to use")
-            (@arg DATA: +required "The data file to use")
-            (@group type +required =>
-                (@arg ngc: --ngc "Use Nintendo Gamecube format")
-                (@arg ps2: --ps2 "Use Playstation 2 format")
+            (@arg ARCHIVE: +required "The archive to use: its path without extension, or its name table or data file")
+            (@group type =>
+                (@arg ngc: --ngc "Use Nintendo Gamecube format (default: detect)")
+                (@arg ps2: --ps2 "Use Playstation 2 format (default: detect)")
             )
         )
         (@subcommand extract =>
             (about: "Extracts the given archive to a folder")
-            (@arg NAMES: +required "The name table file to use")
-            (@arg DATA: +required "The data file to use")
-            (@group type +required =>
-                (@arg ngc: --ngc "Use Nintendo Gamecube format")
-                (@arg ps2: --ps2 "Use Playstation 2 format")
+            (@arg ARCHIVE: +required "The archive to use: its path without extension, or its name table or data file")
+            (@group type =>
+                (@arg ngc: --ngc "Use Nintendo Gamecube format (default: detect)")
+                (@arg ps2: --ps2 "Use Playstation 2 format (default: detect)")
             )
             (@arg OUTPUT: +required "The folder to extract to")
             (@group handler +required =>
                 (@arg merge: --merge "Merge with existing folder")
                 (@arg replace: --replace "Replace existing folder")
             )
+            (@arg decode: --decode "Also decode files to editable formats, such as PNG, WAV, glTF and JSON")
+        )
+        (@subcommand check =>
+            (about: "Checks the given archive for errors, including references to missing files")
+            (@arg ARCHIVE: +required "The archive to use: its path without extension, or its name table or data file")
+            (@group type =>
+                (@arg ngc: --ngc "Use Nintendo Gamecube format (default: detect)")
+                (@arg ps2: --ps2 "Use Playstation 2 format (default: detect)")
+            )
+            (@arg shared: --shared +takes_value +multiple number_of_values(1)
+                "An archive that references may point to, such as one of the game's global archives")
+        )
+        (@subcommand rm =>
+            (about: "Removes files from the given archive")
+            (@arg ARCHIVE: +required "The archive to use: its path without extension, or its name table or data file")
+            (@group type =>
+                (@arg ngc: --ngc "Use Nintendo Gamecube format (default: detect)")
+                (@arg ps2: --ps2 "Use Playstation 2 format (default: detect)")
+            )
+            (@arg FILES: +required +multiple "The names of the files to remove")
+            (@arg gc: --gc "Also remove names that are no longer used")
+        )
+        (@subcommand mv =>
+            (about: "Renames a file in the given archive")
+            (@arg ARCHIVE: +required "The archive to use: its path without extension, or its name table or data file")
+            (@group type =>
+                (@arg ngc: --ngc "Use Nintendo Gamecube format (default: detect)")
+                (@arg ps2: --ps2 "Use Playstation 2 format (default: detect)")
+            )
+            (@arg SOURCE: +required "The name of the file to rename")
+            (@arg DEST: +required "The new name of the file")
+            (@arg type_id: --type +takes_value "Also change the file's type")
+            (@arg subtype_id: --subtype +takes_value "Also change the file's subtype")
+            (@arg gc: --gc "Also remove names that are no longer used")
+        )
+        (@subcommand add =>
+            (about: "Adds a file to the given archive")
+            (@arg ARCHIVE: +required "The archive to use: its path without extension, or its name table or data file")
+            (@group type =>
+                (@arg ngc: --ngc "Use Nintendo Gamecube format (default: detect)")
+                (@arg ps2: --ps2 "Use Playstation 2 format (default: detect)")
+            )
+            (@arg INPUT: +required "The file to read data from")
+            (@arg NAME: +required "The name of the file in the archive")
+            (@arg type_id: --type +takes_value "The file's type; required for new files")
+            (@arg subtype_id: --subtype +takes_value
+                "The file's subtype (default: its name for new files, unchanged for replaced files)")
+            (@arg replace: --replace "Replace the file's data if it already exists")
+        )
+        (@subcommand diff =>
+            (about: "Lists the differences between two archives")
+            (@arg OLD: +required "The original archive")
+            (@arg NEW: +required "The modified archive")
+            (@group type =>
+                (@arg ngc: --ngc "Use Nintendo Gamecube format (default: detect)")
+                (@arg ps2: --ps2 "Use Playstation 2 format (default: detect)")
+            )
+            (@arg output: -o --output +takes_value conflicts_with[structural] "Write the differences to a patch file")
+            (@arg structural: -s --structural +takes_value "Compare the fields of the given file instead of whole archives")
+        )
+        (@subcommand patch =>
+            (about: "Applies a patch to the given archive")
+            (@arg ARCHIVE: +required "The archive to use: its path without extension, or its name table or data file")
+            (@group type =>
+                (@arg ngc: --ngc "Use Nintendo Gamecube format (default: detect)")
+                (@arg ps2: --ps2 "Use Playstation 2 format (default: detect)")
+            )
+            (@arg PATCH: +required "The patch file to apply")
         )
         (@subcommand pack =>
             (about: "Packs an folder into an archive")
-            (@arg NAMES: +required "The name table file to use")
-            (@arg DATA: +required "The data file to use")
+            (@arg ARCHIVE: +required "The archive to create: its path without extension, or its name table or data file")
             (@group type +required =>
                 (@arg ngc: --ngc "Use Nintendo Gamecube format")
                 (@arg ps2: --ps2 "Use Playstation 2 format")
             )
             (@arg INPUT: +required "The folder to read from")
+            (@arg packer: --packer +takes_value possible_values(&["greedy", "best-fit", "ordered"])
+                "How files are divided into chunks; ordered keeps files with the files they reference (default: greedy)")
+            (@arg chunk_size: --("chunk-size") +takes_value
+                "Use a fixed chunk size, a multiple of 0x800, failing if a file does not fit")
+            (@arg name_order: --("name-order") +takes_value possible_values(&["name", "id"])
+                "How names in the name table are sorted (default: name)")
+        )
+        (@subcommand convert =>
+            (about: "Converts an archive between Gamecube and Playstation 2 formats")
+            (@arg ARCHIVE: +required "The archive to convert: its path without extension, or its name table or data file")
+            (@arg OUTPUT: +required "The archive to create: its path without extension, or its name table or data file")
+            (@arg to: --to +takes_value +required possible_values(&["ngc", "ps2"]) "The format to convert to")
+            (@arg allow_unconverted: --("allow-unconverted") "Copy files that can not be converted without changes, instead of failing")
+            (@group type =>
+                (@arg ngc: --ngc "The archive uses Nintendo Gamecube format (default: detect)")
+                (@arg ps2: --ps2 "The archive uses Playstation 2 format (default: detect)")
+            )
+        )
+        (@subcommand scan =>
+            (about: "Lists the archives in a game directory")
+            (@arg DIRECTORY: +required "The directory to search")
+        )
+        (@subcommand crack_names =>
+            (name: "crack-names")
+            (about: "Searches for the names of IDs that are missing from an archive's name table")
+            (@arg ARCHIVE: +required "The archive to use: its path without extension, or its name table or data file")
+            (@group type =>
+                (@arg ngc: --ngc "Use Nintendo Gamecube format (default: detect)")
+                (@arg ps2: --ps2 "Use Playstation 2 format (default: detect)")
+            )
+            (@arg dictionary: -d --dictionary +takes_value +required
+                "The dictionary file to add found names to; it is created if it does not exist")
+            (@arg wordlist: -w --wordlist +takes_value +multiple number_of_values(1)
+                "A file with one candidate name per line; its names are also used for {WORD}")
+            (@arg template: -t --template +takes_value +multiple number_of_values(1)
+                "A pattern for candidate names, e.g. DB:>LEVELS>{LEVEL}>{NAME}.TWORLD")
+            (@arg var: --var +takes_value +multiple number_of_values(1)
+                "Values for a template placeholder, given as NAME=FILE with one value per line")
+            (@arg id: --id +takes_value +multiple +allow_hyphen_values number_of_values(1) "Also search for the name of the given ID")
+            (@arg max_number: --("max-number") +takes_value "The largest number used for {n} (default: 99)")
+        )
+        (@subcommand deps =>
+            (about: "Lists the files that a file references")
+            (@arg ARCHIVE: +required "The archive to use: its path without extension, or its name table or data file")
+            (@arg NAME: +required +allow_hyphen_values "The name or ID of the file")
+            (@group type =>
+                (@arg ngc: --ngc "Use Nintendo Gamecube format (default: detect)")
+                (@arg ps2: --ps2 "Use Playstation 2 format (default: detect)")
+            )
+            (@arg recursive: -r --recursive "Also list the files that those files reference")
+        )
+        (@subcommand rdeps =>
+            (about: "Lists the files that reference a file")
+            (@arg ARCHIVE: +required "The archive to use: its path without extension, or its name table or data file")
+            (@arg NAME: +required +allow_hyphen_values "The name or ID of the file")
+            (@group type =>
+                (@arg ngc: --ngc "Use Nintendo Gamecube format (default: detect)")
+                (@arg ps2: --ps2 "Use Playstation 2 format (default: detect)")
+            )
+            (@arg recursive: -r --recursive "Also list the files that reference those files")
+        )
+        (@subcommand orphans =>
+            (about: "Lists the files that are not referenced by any other file")
+            (@arg ARCHIVE: +required "The archive to use: its path without extension, or its name table or data file")
+            (@group type =>
+                (@arg ngc: --ngc "Use Nintendo Gamecube format (default: detect)")
+                (@arg ps2: --ps2 "Use Playstation 2 format (default: detect)")
+            )
+        )
+        (@subcommand subset =>
+            (about: "Extracts files into a new archive, along with every file that they reference")
+            (@arg ARCHIVE: +required "The archive to use: its path without extension, or its name table or data file")
+            (@arg OUTPUT: +required "The archive to create: its path without extension, or its name table or data file")
+            (@arg ROOTS: +required +multiple +allow_hyphen_values "The names or IDs of the files to extract")
+            (@group type =>
+                (@arg ngc: --ngc "Use Nintendo Gamecube format (default: detect)")
+                (@arg ps2: --ps2 "Use Playstation 2 format (default: detect)")
+            )
+            (@arg parents: --parents "Also follow references from nodes to their parents, which usually includes the whole scene")
+        )
+        (@subcommand export_struct =>
+            (name: "export-struct")
+            (about: "Writes the structure of a file as JSON, with references written as names")
+            (@arg ARCHIVE: +required "The archive to use: its path without extension, or its name table or data file")
+            (@arg FILE: +required +allow_hyphen_values "The name or ID of the file")
+            (@arg OUTPUT: "The JSON file to write (default: print to standard output)")
+            (@group type =>
+                (@arg ngc: --ngc "Use Nintendo Gamecube format (default: detect)")
+                (@arg ps2: --ps2 "Use Playstation 2 format (default: detect)")
+            )
+        )
+        (@subcommand import_struct =>
+            (name: "import-struct")
+            (about: "Replaces the data of a file in the given archive with a structure read from JSON")
+            (@arg ARCHIVE: +required "The archive to use: its path without extension, or its name table or data file")
+            (@arg FILE: +required +allow_hyphen_values "The name or ID of the file to replace")
+            (@arg INPUT: +required "The JSON file to read, as written by export-struct")
+            (@group type =>
+                (@arg ngc: --ngc "Use Nintendo Gamecube format (default: detect)")
+                (@arg ps2: --ps2 "Use Playstation 2 format (default: detect)")
+            )
         )
     );
-    let matches = app.clone().get_matches();
+    let args = merge_archive_args(std::env::args_os().collect())?;
+    let matches = app.clone().get_matches_from(args);
     if let Some(cmdlist) = matches.subcommand_matches("list") {
         cmd_list(cmdlist)?;
     } else if let Some(cmdlist) = matches.subcommand_matches("info") {
@@ -219,9 +1117,119 @@ fn main() -> Result<(), Box<dyn Error>> {
         cmd_extract(cmdlist)?;
     } else if let Some(cmdlist) = matches.subcommand_matches("pack") {
         cmd_pack(cmdlist)?;
+    } else if let Some(cmdlist) = matches.subcommand_matches("check") {
+        cmd_check(cmdlist)?;
+    } else if let Some(cmdlist) = matches.subcommand_matches("rm") {
+        cmd_rm(cmdlist)?;
+    } else if let Some(cmdlist) = matches.subcommand_matches("mv") {
+        cmd_mv(cmdlist)?;
+    } else if let Some(cmdlist) = matches.subcommand_matches("add") {
+        cmd_add(cmdlist)?;
+    } else if let Some(cmdlist) = matches.subcommand_matches("diff") {
+        cmd_diff(cmdlist)?;
+    } else if let Some(cmdlist) = matches.subcommand_matches("patch") {
+        cmd_patch(cmdlist)?;
+    } else if let Some(cmdlist) = matches.subcommand_matches("convert") {
+        cmd_convert(cmdlist)?;
+    } else if let Some(cmdlist) = matches.subcommand_matches("scan") {
+        cmd_scan(cmdlist)?;
+    } else if let Some(cmdlist) = matches.subcommand_matches("crack-names") {
+        cmd_crack_names(cmdlist)?;
+    } else if let Some(cmdlist) = matches.subcommand_matches("deps") {
+        cmd_deps(cmdlist)?;
+    } else if let Some(cmdlist) = matches.subcommand_matches("rdeps") {
+        cmd_rdeps(cmdlist)?;
+    } else if let Some(cmdlist) = matches.subcommand_matches("orphans") {
+        cmd_orphans(cmdlist)?;
+    } else if let Some(cmdlist) = matches.subcommand_matches("subset") {
+        cmd_subset(cmdlist)?;
+    } else if let Some(cmdlist) = matches.subcommand_matches("export-struct") {
+        cmd_export_struct(cmdlist)?;
+    } else if let Some(cmdlist) = matches.subcommand_matches("import-struct") {
+        cmd_import_struct(cmdlist)?;
     } else {
         app.print_long_help()?;
         println!();
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+
+    fn merge(args: &[&str]) -> Result<Vec<String>, Box<dyn Error>> {
+        let args = args.iter().map(OsString::from).collect();
+        Ok(merge_archive_args(args)?
+            .into_iter()
+            .map(|x| x.into_string().unwrap())
+            .collect())
+    }
+
+    #[test]
+    fn legacy_archive_args() {
+        assert_eq!(
+            merge(&["chumcli", "list", "A/LVL.NGC", "A/LVL.DGC"]).unwrap(),
+            vec!["chumcli", "list", "A/LVL.DGC"]
+        );
+        assert_eq!(
+            merge(&["chumcli", "--names", "x.ngc", "diff", "--ps2", "a.nps", "a.dps", "b.NPS", "b.DPS"]).unwrap(),
+            vec!["chumcli", "--names", "x.ngc", "diff", "--ps2", "a.dps", "b.DPS"]
+        );
+        assert_eq!(
+            merge(&["chumcli", "pack", "DIR", "OUT.NGC", "OUT.DGC", "--packer", "ordered"]).unwrap(),
+            vec!["chumcli", "pack", "DIR", "OUT.DGC", "--packer", "ordered"]
+        );
+        // Stems and single paths are left alone
+        let args = ["chumcli", "extract", "LVL.NGC", "OUT"];
+        assert_eq!(merge(&args).unwrap(), args);
+        let args = ["chumcli", "subset", "LVL.NGC", "LVL.DGC"];
+        assert_eq!(merge(&args).unwrap(), args);
+        assert!(merge(&["chumcli", "list", "A.NGC", "B.DGC"]).is_err());
+    }
+
+    #[test]
+    fn convert_rejects_unconverted() {
+        let mut archive = ChumArchive::new(archive::TotemHeader::new(b"test"), TotemFormat::NGC);
+        let file = ChumFile::new(vec![1, 2, 3], "README".to_owned(), "TXT".to_owned(), "README".to_owned());
+        archive.add_file(file).unwrap();
+        let (_, report) = archive.convert_format(TotemFormat::PS2);
+        assert_eq!(report.failed.len(), 1);
+        assert!(check_conversion(&report, false).is_err());
+        assert!(check_conversion(&report, true).is_ok());
+        let (_, report) = ChumArchive::new(archive::TotemHeader::new(b"test"), TotemFormat::NGC).convert_format(TotemFormat::PS2);
+        assert!(check_conversion(&report, false).is_ok());
+    }
+
+    #[test]
+    fn crack_names_targets() {
+        use libchum::binary::ChumBinary;
+        use libchum::reader::skin;
+        let skin = skin::Skin {
+            meshes: vec![util::hash_name_i32("MESH")],
+            vertex_groups: vec![skin::VertexGroup {
+                group_id: util::hash_name_i32("BONE"),
+                sections: Vec::new(),
+            }],
+            ..Default::default()
+        };
+        let mut data = Vec::new();
+        skin.write_to(&mut data, TotemFormat::NGC).unwrap();
+        let mut archive = ChumArchive::new(archive::TotemHeader::new(b"test"), TotemFormat::NGC);
+        archive.add_file(ChumFile::new(data, "SKIN".to_owned(), "SKIN".to_owned(), "SKIN".to_owned())).unwrap();
+        let targets = get_referenced_ids(&archive);
+        assert!(targets.contains(&util::hash_name_i32("MESH")));
+        assert!(targets.contains(&util::hash_name_i32("BONE")));
+
+        let path = std::env::temp_dir().join(format!("chumcli-names-{}.txt", std::process::id()));
+        fs::write(&path, "FIRST").unwrap();
+        let mut names = NameDictionary::new();
+        names.add_name("SECOND");
+        append_names(&path, &names).unwrap();
+        append_names(&path, &names).unwrap();
+        let text = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        let line = format!("{} \"SECOND\"", util::hash_name_i32("SECOND"));
+        assert_eq!(text, format!("FIRST\n{}\n{}\n", line, line));
+    }
+}
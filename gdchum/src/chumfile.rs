@@ -22,6 +22,7 @@ pub struct ChumFile {
     typestr: String,
     subtypestr: String,
     parent: Option<Instance<ChumArchive, Shared>>,
+    shared_archive: Option<usize>,
     format: libchum::format::TotemFormat,
 }
 
@@ -58,6 +59,7 @@ impl ChumFile {
             subtypestr: String::new(),
             format: libchum::format::TotemFormat::NGC,
             parent: None,
+            shared_archive: None,
         }
     }
 
@@ -154,6 +156,18 @@ impl ChumFile {
         self.parent = Some(parent);
     }
 
+    /// Set the index of the shared archive that this file comes from.
+    /// None if the file belongs to the parent archive itself.
+    pub fn set_shared_archive(&mut self, index: Option<usize>) {
+        self.shared_archive = index;
+    }
+
+    /// Returns true if this file comes from a read-only shared archive
+    #[export]
+    pub fn is_shared(&self, _owner: &Resource) -> bool {
+        self.shared_archive.is_some()
+    }
+
     /// Get this file's format
     pub fn get_format(&self) -> libchum::format::TotemFormat {
         self.format
@@ -164,7 +178,9 @@ impl ChumFile {
         let archive_instance = self.get_archive_instance();
         unsafe { archive_instance.assume_safe() }
             .map(|archive, _| {
-                let file = archive.archive.get_file_from_name(&self.namestr).unwrap();
+                let file = archive
+                    .get_archive_file(self.shared_archive, &self.namestr)
+                    .unwrap();
                 let f = Instance::<ByteData, Unique>::new();
                 f.map_mut(|script, _| {
                     script.set_data(file.get_data().to_vec());
@@ -180,7 +196,9 @@ impl ChumFile {
         let archive_instance = self.get_archive_instance();
         unsafe { archive_instance.assume_safe() }
             .map(|archive, _| {
-                let file = archive.archive.get_file_from_name(&self.namestr).unwrap();
+                let file = archive
+                    .get_archive_file(self.shared_archive, &self.namestr)
+                    .unwrap();
                 file.get_data().to_owned()
             })
             .unwrap()
@@ -193,8 +211,9 @@ impl ChumFile {
         let archive_instance = self.get_archive_instance();
         unsafe { archive_instance.assume_safe() }
             .map(|archive, _| {
-                let file: &archive::ChumFile =
-                    archive.archive.get_file_from_name(&self.namestr).unwrap();
+                let file: &archive::ChumFile = archive
+                    .get_archive_file(self.shared_archive, &self.namestr)
+                    .unwrap();
                 func(file.get_data())
             })
             .unwrap()
@@ -204,28 +223,29 @@ impl ChumFile {
     where
         F: Fn(&mut Vec<u8>) -> G,
     {
-        let archive_instance = self.get_archive_instance();
-        unsafe { archive_instance.assume_safe() }
-            .map_mut(|archive, _| {
-                let file: &mut archive::ChumFile = archive
-                    .archive
-                    .get_file_from_name_mut(&self.namestr)
-                    .unwrap();
-                func(file.get_data_mut())
-            })
-            .unwrap()
+        // Edit a copy so that the change is recorded for undo
+        let mut data = self.get_data_as_vec();
+        let value = func(&mut data);
+        self.replace_data_with_vec(data);
+        value
     }
 
     /// Replace this file's data with the given Vec<u8>
     pub fn replace_data_with_vec(&mut self, data: Vec<u8>) {
+        if self.shared_archive.is_some() {
+            display_err!(
+                "{} belongs to a shared archive and can not be edited",
+                self.namestr
+            );
+            return;
+        }
         let archive_instance = self.get_archive_instance();
         unsafe { archive_instance.assume_safe() }
             .map_mut(|archive, _| {
                 archive
                     .archive
-                    .get_file_from_name_mut(&self.namestr)
-                    .unwrap()
-                    .replace_data(data);
+                    .replace_file_data(&self.namestr, data)
+                    .unwrap();
                 godot_print!("WROTE TO ARCHIVE");
             })
             .unwrap();
@@ -686,7 +706,13 @@ impl ChumFile {
     #[export]
     pub fn import_structure(&mut self, _owner: &Resource, data: Dictionary) {
         use libchum::binary::ChumBinary;
-        let structure = util::dict_to_struct(&data);
+        let structure = match util::dict_to_struct(&data) {
+            Ok(structure) => structure,
+            Err(e) => {
+                display_err!("Could not import structure: {}", e);
+                return;
+            }
+        };
         match self.get_type_str() {
             "BITMAP" => {
                 let bitmap = match reader::bitmap::Bitmap::read_data(
@@ -698,7 +724,11 @@ impl ChumFile {
                         panic!("BITMAP file invalid: {}", err);
                     }
                 };
-                let bitmapstruct = reader::bitmap::BitmapStruct::destructure(&structure).unwrap();
+                let bitmapstruct: reader::bitmap::BitmapStruct = match util::destructure(&structure)
+                {
+                    Some(value) => value,
+                    None => return,
+                };
                 let bitmapdata = reader::bitmap::Bitmap::from_struct(&bitmapstruct).with_bitmap(
                     bitmap.get_data().clone(),
                     bitmap.get_width(),
@@ -709,56 +739,84 @@ impl ChumFile {
                 self.replace_data_with_vec(outdata);
             }
             "MATERIAL" => {
-                let materialdata = reader::material::Material::destructure(&structure).unwrap();
+                let materialdata: reader::material::Material = match util::destructure(&structure) {
+                    Some(value) => value,
+                    None => return,
+                };
                 let mut outdata = Vec::new();
                 materialdata.write_to(&mut outdata, self.format).unwrap();
                 self.replace_data_with_vec(outdata);
             }
             "COLLISIONVOL" => {
-                let voldata = reader::collisionvol::CollisionVol::destructure(&structure).unwrap();
+                let voldata: reader::collisionvol::CollisionVol =
+                    match util::destructure(&structure) {
+                        Some(value) => value,
+                        None => return,
+                    };
                 let mut outdata = Vec::new();
                 voldata.write_to(&mut outdata, self.format).unwrap();
                 self.replace_data_with_vec(outdata);
             }
             "WARP" => {
-                let warpdata = reader::warp::Warp::destructure(&structure).unwrap();
+                let warpdata: reader::warp::Warp = match util::destructure(&structure) {
+                    Some(value) => value,
+                    None => return,
+                };
                 let mut outdata = Vec::new();
                 warpdata.write_to(&mut outdata, self.format).unwrap();
                 self.replace_data_with_vec(outdata);
             }
             "ROTSHAPE" => {
-                let data = reader::rotshape::RotShape::destructure(&structure).unwrap();
+                let data: reader::rotshape::RotShape = match util::destructure(&structure) {
+                    Some(value) => value,
+                    None => return,
+                };
                 let mut outdata = Vec::new();
                 data.write_to(&mut outdata, self.format).unwrap();
                 self.replace_data_with_vec(outdata);
             }
             "OMNI" => {
-                let data = reader::omni::Omni::destructure(&structure).unwrap();
+                let data: reader::omni::Omni = match util::destructure(&structure) {
+                    Some(value) => value,
+                    None => return,
+                };
                 let mut outdata = Vec::new();
                 data.write_to(&mut outdata, self.format).unwrap();
                 self.replace_data_with_vec(outdata);
             }
             "LIGHT" => {
-                let data = reader::light::Light::destructure(&structure).unwrap();
+                let data: reader::light::Light = match util::destructure(&structure) {
+                    Some(value) => value,
+                    None => return,
+                };
                 let mut outdata = Vec::new();
                 data.write_to(&mut outdata, self.format).unwrap();
                 self.replace_data_with_vec(outdata);
             }
             "LOD" => {
-                let data = reader::lod::Lod::destructure(&structure).unwrap();
+                let data: reader::lod::Lod = match util::destructure(&structure) {
+                    Some(value) => value,
+                    None => return,
+                };
                 let mut outdata = Vec::new();
                 data.write_to(&mut outdata, self.format).unwrap();
                 self.replace_data_with_vec(outdata);
             }
             "MATERIALANIM" => {
-                let data =
-                    reader::materialanim::MaterialAnimation::destructure(&structure).unwrap();
+                let data: reader::materialanim::MaterialAnimation =
+                    match util::destructure(&structure) {
+                        Some(value) => value,
+                        None => return,
+                    };
                 let mut outdata = Vec::new();
                 data.write_to(&mut outdata, self.format).unwrap();
                 self.replace_data_with_vec(outdata);
             }
             "NODE" => {
-                let data = reader::node::Node::destructure(&structure).unwrap();
+                let data: reader::node::Node = match util::destructure(&structure) {
+                    Some(value) => value,
+                    None => return,
+                };
                 let mut outdata = Vec::new();
                 data.write_to(&mut outdata, self.format).unwrap();
                 self.replace_data_with_vec(outdata);
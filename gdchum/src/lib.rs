@@ -1,9 +1,10 @@
 use anyhow;
 use gdnative::api::Resource;
 use gdnative::prelude::*;
-use libchum::{self, archive};
+use libchum::{self, archive, names::NameDictionary, workspace::Workspace};
 use std::collections::HashMap;
 use std::fs::File;
+use std::path::Path;
 
 #[macro_use]
 pub mod macros;
@@ -11,7 +12,6 @@ pub mod macros;
 pub mod util;
 pub mod bytedata;
 pub mod chumfile;
-pub mod names;
 pub mod reader;
 pub mod scenedata;
 pub mod views;
@@ -21,6 +21,11 @@ pub mod views;
 pub struct ChumArchive {
     pub archive: archive::ChumArchive,
     pub files: HashMap<i32, Instance<chumfile::ChumFile, Shared>>,
+    /// Read-only archives that this archive's files may reference
+    pub workspace: Workspace,
+    pub shared_files: HashMap<i32, Instance<chumfile::ChumFile, Shared>>,
+    /// Names that are not in any archive's name table
+    pub dictionary: NameDictionary,
 }
 
 #[methods]
@@ -29,6 +34,9 @@ impl ChumArchive {
         ChumArchive {
             archive: archive::ChumArchive::default(),
             files: HashMap::new(),
+            workspace: Workspace::new(),
+            shared_files: HashMap::new(),
+            dictionary: NameDictionary::builtin(),
         }
     }
 
@@ -42,8 +50,15 @@ impl ChumArchive {
             Ok(x) => x,
             Err(_) => return GodotError::FileCantOpen as i64,
         };
-        match self.archive.write_chum_archive(&mut ngcfile, &mut dgcfile) {
-            Ok(_) => 0,
+        match self.archive.write_chum_archive(
+            &mut ngcfile,
+            &mut dgcfile,
+            archive::NameOrder::Original,
+        ) {
+            Ok(_) => {
+                self.archive.mark_saved();
+                0
+            }
             Err(_) => GodotError::FileCantWrite as i64,
         }
     }
@@ -56,57 +71,192 @@ impl ChumArchive {
         dgcpath: GodotString,
         fmt: GodotString,
     ) -> i64 {
-        let format = match fmt.to_string().as_ref() {
-            "PS2" => libchum::format::TotemFormat::PS2,
-            "NGC" => libchum::format::TotemFormat::NGC,
-            a => {
-                display_err!(
-                    "Error loading archive: {}, {}\nInvalid format input {}",
-                    ngcpath,
+        match read_archive(&ngcpath, &dgcpath, &fmt) {
+            Ok(mut x) => {
+                // Keep the original chunk layout so that saved archives diff cleanly
+                x.set_preserve_layout(true);
+                self.files.clear();
+                self.archive = x;
+                self.sync_files(owner);
+                0
+            }
+            Err(e) => e,
+        }
+    }
+
+    /// Load an archive whose files can be referenced by this archive's files,
+    /// e.g. a global archive used by a level.
+    /// Shared archives can not be edited; earlier shared archives take priority.
+    #[export]
+    fn add_shared_archive(
+        &mut self,
+        owner: TRef<Resource, Shared>,
+        ngcpath: GodotString,
+        dgcpath: GodotString,
+        fmt: GodotString,
+    ) -> i64 {
+        let shared = match read_archive(&ngcpath, &dgcpath, &fmt) {
+            Ok(x) => x,
+            Err(e) => return e,
+        };
+        let index = self.workspace.add_archive(dgcpath.to_string(), shared);
+        for shadowing in self.workspace.find_shadowed() {
+            if shadowing.shadowed.contains(&index) {
+                display_warn!(
+                    "{} in {} is hidden by the file in {}",
+                    self.maybe_get_name_from_hash_str(shadowing.id),
                     dgcpath,
-                    a
+                    self.workspace.get_label(shadowing.used).unwrap()
                 );
-                return GodotError::InvalidParameter as i64;
             }
-        };
-        let mut ngcfile = match File::open(ngcpath.to_string()) {
-            Ok(x) => x,
-            Err(e) => {
-                display_err!("Error loading archive: {}, {}\n{}", ngcpath, dgcpath, e);
-                return GodotError::FileBadPath as i64;
+        }
+        let archive = self.workspace.get_archive(index).unwrap();
+        let fmt = archive.get_format();
+        for (id, file) in archive.get_files_hash() {
+            if self.shared_files.contains_key(id) {
+                continue;
+            }
+            let f = Instance::<chumfile::ChumFile, Unique>::new();
+            f.map_mut(|script, _res| {
+                let instance = Instance::from_base(owner.claim()).unwrap();
+                script.read_from_chumfile(file, fmt, instance);
+                script.set_shared_archive(Some(index));
+            })
+            .unwrap();
+            self.shared_files.insert(*id, f.into_shared());
+        }
+        0
+    }
+
+    /// Unload all shared archives
+    #[export]
+    fn clear_shared_archives(&mut self, _owner: &Resource) {
+        self.shared_files.clear();
+        self.workspace = Workspace::new();
+    }
+
+    /// Add the names from the dictionary file at the given path.
+    /// Names in the file replace names with the same ID.
+    #[export]
+    fn load_dictionary(&mut self, _owner: &Resource, path: GodotString) -> i64 {
+        match NameDictionary::load(Path::new(&path.to_string())) {
+            Ok(dictionary) => {
+                self.dictionary.merge(dictionary);
+                0
             }
-        };
-        let mut dgcfile = match File::open(dgcpath.to_string()) {
-            Ok(x) => x,
             Err(e) => {
-                display_err!("Error loading archive: {}, {}\n{}", ngcpath, dgcpath, e);
-                return GodotError::FileBadPath as i64;
+                display_err!("Could not load name dictionary {}: {}", path, e);
+                GodotError::FileCantRead as i64
             }
-        };
-        match archive::ChumArchive::read_chum_archive(&mut ngcfile, &mut dgcfile, format) {
-            Ok(x) => {
-                self.files.clear();
-                for file in x.get_files() {
-                    let f = Instance::<chumfile::ChumFile, Unique>::new();
-                    let id = f
+        }
+    }
+
+    /// Get a file from this archive, or from the shared archive with the given index
+    pub fn get_archive_file(
+        &self,
+        shared: Option<usize>,
+        name: &str,
+    ) -> Option<&archive::ChumFile> {
+        match shared {
+            Some(index) => self.workspace.get_archive(index)?.get_file_from_name(name),
+            None => self.archive.get_file_from_name(name),
+        }
+    }
+
+    /// Update the file instances to match the archive's files.
+    /// Instances of files that still exist are kept, so that references to them stay valid.
+    fn sync_files(&mut self, owner: TRef<Resource, Shared>) {
+        let fmt = self.archive.get_format();
+        let archive = &self.archive;
+        self.files
+            .retain(|id, _| archive.get_file_from_hash(*id).is_some());
+        for (id, file) in self.archive.get_files_hash() {
+            match self.files.get(id) {
+                Some(f) => {
+                    unsafe { f.assume_safe() }
                         .map_mut(|script, _res| {
                             let instance = Instance::from_base(owner.claim()).unwrap();
-                            script.read_from_chumfile(file, x.get_format(), instance);
-                            script.get_hash_id_ownerless()
+                            script.read_from_chumfile(file, fmt, instance);
                         })
                         .unwrap();
-                    self.files.insert(id, f.into_shared());
                 }
-                self.archive = x;
-                0
-            }
-            Err(e) => {
-                display_err!("Error loading archive: {}, {}\n{}", ngcpath, dgcpath, e);
-                GodotError::FileCantOpen as i64
+                None => {
+                    let f = Instance::<chumfile::ChumFile, Unique>::new();
+                    f.map_mut(|script, _res| {
+                        let instance = Instance::from_base(owner.claim()).unwrap();
+                        script.read_from_chumfile(file, fmt, instance);
+                    })
+                    .unwrap();
+                    self.files.insert(*id, f.into_shared());
+                }
             }
         }
     }
 
+    /// Undo the last edit. Returns false if there was nothing to undo.
+    #[export]
+    fn undo(&mut self, owner: TRef<Resource, Shared>) -> bool {
+        let did_undo = self.archive.undo();
+        if did_undo {
+            self.sync_files(owner);
+        }
+        did_undo
+    }
+
+    /// Redo the last undone edit. Returns false if there was nothing to redo.
+    #[export]
+    fn redo(&mut self, owner: TRef<Resource, Shared>) -> bool {
+        let did_redo = self.archive.redo();
+        if did_redo {
+            self.sync_files(owner);
+        }
+        did_redo
+    }
+
+    #[export]
+    fn can_undo(&self, _owner: &Resource) -> bool {
+        self.archive.can_undo()
+    }
+
+    #[export]
+    fn can_redo(&self, _owner: &Resource) -> bool {
+        self.archive.can_redo()
+    }
+
+    #[export]
+    fn get_undo_description(&self, _owner: &Resource) -> GodotString {
+        GodotString::from_str(self.archive.get_undo_description().unwrap_or(""))
+    }
+
+    #[export]
+    fn get_redo_description(&self, _owner: &Resource) -> GodotString {
+        GodotString::from_str(self.archive.get_redo_description().unwrap_or(""))
+    }
+
+    /// Get an ID for the archive's current state, for use with `restore_savepoint`
+    #[export]
+    fn create_savepoint(&self, _owner: &Resource) -> i64 {
+        self.archive.create_savepoint().0 as i64
+    }
+
+    /// Return to the state of the given savepoint. Returns false if it no longer exists.
+    #[export]
+    fn restore_savepoint(&mut self, owner: TRef<Resource, Shared>, savepoint: i64) -> bool {
+        let restored = self
+            .archive
+            .restore_savepoint(archive::Savepoint(savepoint as u64));
+        if restored {
+            self.sync_files(owner);
+        }
+        restored
+    }
+
+    /// Returns true if the archive has unsaved changes
+    #[export]
+    fn is_dirty(&self, _owner: &Resource) -> bool {
+        self.archive.is_dirty()
+    }
+
     #[export]
     fn get_file_list(&self, _owner: &Resource) -> VariantArray<Unique> {
         let arr = VariantArray::<Unique>::new();
@@ -122,7 +272,10 @@ impl ChumArchive {
         _owner: &Resource,
         id: i32,
     ) -> Option<Instance<chumfile::ChumFile, Shared>> {
-        self.files.get(&id).map(|x| x.clone())
+        self.files
+            .get(&id)
+            .or_else(|| self.shared_files.get(&id))
+            .map(|x| x.clone())
     }
 
     #[export]
@@ -134,25 +287,25 @@ impl ChumArchive {
     pub fn maybe_get_name_from_hash(&self, _owner: &Resource, id: i32) -> GodotString {
         if let Some(name) = self.archive.get_name_from_id(id) {
             GodotString::from_str(&name)
+        } else if let Some(name) = self.workspace.get_name_from_id(id) {
+            GodotString::from_str(&name)
+        } else if let Some(name) = self.dictionary.get_name(id) {
+            GodotString::from_str(&name)
         } else {
-            if let Some(name) = names::NAMES.get(&id) {
-                GodotString::from_str(&name)
-            } else {
-                GodotString::from_str(&id.to_string())
-            }
+            GodotString::from_str(&id.to_string())
         }
     }
 
     pub fn maybe_get_name_from_hash_str(&self, id: i32) -> String {
         if let Some(name) = self.archive.get_name_from_id(id) {
             name.to_owned()
+        } else if let Some(name) = self.workspace.get_name_from_id(id) {
+            name.to_owned()
+        } else if let Some(name) = self.dictionary.get_name(id) {
+            name.to_owned()
         } else {
-            if let Some(name) = names::NAMES.get(&id) {
-                name.to_string()
-            } else {
-                // id.to_string()
-                format!("0x{:08X}", id)
-            }
+            // id.to_string()
+            format!("0x{:08X}", id)
         }
     }
 
@@ -190,6 +343,57 @@ impl ChumArchive {
     }
 }
 
+/// Read the archive with the given paths and format, displaying any errors.
+fn read_archive(
+    ngcpath: &GodotString,
+    dgcpath: &GodotString,
+    fmt: &GodotString,
+) -> Result<archive::ChumArchive, i64> {
+    let mut ngcfile = match File::open(ngcpath.to_string()) {
+        Ok(x) => x,
+        Err(e) => {
+            display_err!("Error loading archive: {}, {}\n{}", ngcpath, dgcpath, e);
+            return Err(GodotError::FileBadPath as i64);
+        }
+    };
+    let mut dgcfile = match File::open(dgcpath.to_string()) {
+        Ok(x) => x,
+        Err(e) => {
+            display_err!("Error loading archive: {}, {}\n{}", ngcpath, dgcpath, e);
+            return Err(GodotError::FileBadPath as i64);
+        }
+    };
+    let format = match fmt.to_string().as_ref() {
+        "PS2" => libchum::format::TotemFormat::PS2,
+        "NGC" => libchum::format::TotemFormat::NGC,
+        "" | "AUTO" => match libchum::format::TotemFormat::detect_from(&mut dgcfile) {
+            Ok(x) => x,
+            Err(e) => {
+                display_err!(
+                    "Error loading archive: {}, {}\nCould not detect format: {}",
+                    ngcpath,
+                    dgcpath,
+                    e
+                );
+                return Err(GodotError::FileUnrecognized as i64);
+            }
+        },
+        a => {
+            display_err!(
+                "Error loading archive: {}, {}\nInvalid format input {}",
+                ngcpath,
+                dgcpath,
+                a
+            );
+            return Err(GodotError::InvalidParameter as i64);
+        }
+    };
+    archive::ChumArchive::read_chum_archive(&mut ngcfile, &mut dgcfile, format).map_err(|e| {
+        display_err!("Error loading archive: {}, {}\n{}", ngcpath, dgcpath, e);
+        GodotError::FileCantOpen as i64
+    })
+}
+
 fn init(handle: InitHandle) {
     handle.add_class::<ChumArchive>();
     handle.add_class::<chumfile::ChumFile>();
@@ -1,277 +0,0 @@
-use phf::phf_map;
-
-pub static NAMES: phf::Map<i32, &str> = phf_map! {
-    -1276508687i32 => "NOSE",
-    201418859i32 => "R_EYE",
-    -741260920i32 => "L_EYE",
-    -1103883476i32 => "ABS",
-    -1855579352i32 => "HEAD",
-    -2119453848i32 => "TREE",
-    600182146i32 => "R_HIP",
-    -65657759i32 => "L_HIP",
-    1769695224i32 => "DYN1",
-    819195646i32 => "DYN11",
-    -1445281980i32 => "DYN12",
-    -555905070i32 => "DYN13",
-    1085872753i32 => "DYN14",
-    935209703i32 => "DYN15",
-    -1363707043i32 => "DYN16",
-    1846977505i32 => "DYN5",
-    -148901285i32 => "DYN6",
-    -2145860915i32 => "DYN7",
-    279437148i32 => "DYN8",
-    1738600394i32 => "DYN9",
-    1205132904i32 => "DYN10",
-    -260950462i32 => "DYN2",
-    -2022373676i32 => "DYN3",
-    420582263i32 => "DYN4",
-    1380634465i32 => "TAIL01",
-    -884760869i32 => "TAIL02",
-    1692361549i32 => "R_ELBOW",
-    2054430818i32 => "R_HAND",
-    202404103i32 => "CHEST",
-    -16068964i32 => "R_SHOULDER",
-    1135100809i32 => "L_HAND",
-    -965902393i32 => "L_SHOULDER",
-    1396606174i32 => "L_ELBOW",
-    377764839i32 => "NECK",
-    -143777300i32 => "R_KNEE",
-    -827178489i32 => "L_KNEE",
-    706655750i32 => "HIDE_01",
-    -1005529302i32 => "HIDE_03",
-    1517568649i32 => "HIDE_04",
-    -671426590i32 => "HIDE_13",
-    -1724901494i32 => "SOL",
-    762540575i32 => "HIDE_05",
-    -1267063899i32 => "HIDE_06",
-    -1015213261i32 => "HIDE_07",
-    1405226658i32 => "HIDE_08",
-    -1136349619i32 => "TAIL03",
-    1228553055i32 => "HAIR08",
-    -645585202i32 => "HAIR07",
-    -1367206312i32 => "HAIR06",
-    -1443900863i32 => "HAIR02",
-    -1165104679i32 => "ROCKET",
-    1290930908i32 => "LEFT",
-    1134475172i32 => "RIGHT",
-    162504774i32 => "FRONT",
-    -1641977065i32 => "JAW",
-    -770089962i32 => "L_EAR",
-    231306741i32 => "R_EAR",
-    -2122588271i32 => "NECK03",
-    -2031765068i32 => "R_EAR01",
-    535619598i32 => "R_EAR02",
-    -159584505i32 => "NECK02",
-    1869987517i32 => "NECK01",
-    -804553163i32 => "TORSO",
-    -1873618617i32 => "R_HEEL",
-    -1325113817i32 => "L_EAR01",
-    671944605i32 => "L_EAR02",
-    -1450263892i32 => "L_HEEL",
-    -1195555735i32 => "L_CHEEK",
-    -1711593378i32 => "PROP",
-    -1928279595i32 => "NECK_1",
-    522144306i32 => "NECK04",
-    1219817018i32 => "TOP_MOUTH",
-    1252153833i32 => "TAIL",
-    1504181106i32 => "L_COLLAR",
-    1771150887i32 => "MOUTH",
-    722555502i32 => "HAIR1",
-    -987811006i32 => "HAIR3",
-    -1546814933i32 => "L_KNUCKLES",
-    -950100441i32 => "L_THUMB",
-    870242984i32 => "L_TOES",
-    815192171i32 => "R_COLLAR",
-    -1889754118i32 => "R_CHEEK",
-    -1700082832i32 => "R_KNUCKLES",
-    -255886924i32 => "R_THUMB",
-    167950659i32 => "R_TOES",
-    1534830305i32 => "HAIR4",
-    746362487i32 => "HAIR5",
-    -728141364i32 => "R_FINGERS",
-    -1331959761i32 => "L_FINGERS",
-    -1895028409i32 => "R_FINGERS01",
-    -1545308241i32 => "R_HAND01",
-    369457405i32 => "R_FINGERS02",
-    -322380718i32 => "R_KNUCKLES01",
-    -891988810i32 => "L_HAND01",
-    1938605706i32 => "L_FINGERS01",
-    165798576i32 => "L_KNUCKLES01",
-    -2083899523i32 => "CONE_1",
-    1526626412i32 => "CONE_03",
-    771311866i32 => "CONE_02",
-    -505035195i32 => "R_BAT",
-    810031382i32 => "R_BAT01",
-    -1455339348i32 => "R_BAT02",
-    -1523001442i32 => "INFLATO_RAY1",
-    329752893i32 => "R_BATSWING05",
-    -1968246649i32 => "R_BATSWING06",
-    -1286329443i32 => "R_BATSWING1",
-    -39206895i32 => "R_BATSWING07",
-    1688244651i32 => "R_BATSWING04",
-    -87817208i32 => "R_BATSWING03",
-    -1916586850i32 => "R_BATSWING02",
-    334541898i32 => "INFLATO_BEAM_02",
-    1693943004i32 => "INFLATO_BEAM_03",
-    -1963334160i32 => "INFLATO_BEAM_01",
-    1406928140i32 => "L_HAND02",
-    -2024919541i32 => "HEADCHILD",
-    2048272076i32 => "R_SPEAR",
-    500823256i32 => "HAIR1_GOO",
-    1142535201i32 => "JAW_GOO",
-    -584359106i32 => "L_COLLAR_GOO",
-    -982699357i32 => "L_ELBOW_GOO",
-    1235470990i32 => "L_KNUCKLES_GOO",
-    939679196i32 => "R_COLLAR_GOO",
-    971890030i32 => "R_ELBOW_GOO",
-    518529057i32 => "R_FINGERS_GOO",
-    -1758990123i32 => "HEAD_GOO",
-    261213557i32 => "R_CHEEK_GOO",
-    -216980808i32 => "L_CHEEK_GOO",
-    -332151914i32 => "R_EYE_GOO",
-    -2010031499i32 => "L_EYE_GOO",
-    2041034018i32 => "BOTTOM",
-    976214176i32 => "L_ELBOW01",
-    279086673i32 => "JAW01",
-    1580024131i32 => "R_ELBOW01",
-    -1232037840i32 => "R_FINGERS01_GOO",
-    -1052705750i32 => "R_COLLAR01",
-    -131641999i32 => "L_COLLAR01",
-    269438074i32 => "R_HANDCHILD",
-    -75465788i32 => "R_CHEST_SHELL",
-    -1635906342i32 => "L_BACK_SHELL",
-    -1916785355i32 => "L_ARM_SHELL",
-    1277111285i32 => "R_AB_SHELL",
-    1900069624i32 => "R_ARM_SHELL",
-    -354788922i32 => "L_HAIR_SHELL",
-    1110032837i32 => "R_EYE_SHELL",
-    -1251880530i32 => "L_CHEST_SHELL",
-    20498377i32 => "R_HAND_SHELL",
-    2133088960i32 => "R_EYEBROW_SHELL",
-    -1096208888i32 => "L_EYE_SHELL",
-    -468584149i32 => "L_HAND_SHELL",
-    -4644300i32 => "R_THIGH",
-    -933706329i32 => "L_THIGH",
-    -1182822741i32 => "BROOM_BASE",
-    1585058688i32 => "BROOM_HANDLE",
-    -763104109i32 => "HAIR_1",
-    -666851571i32 => "TUMMY",
-    1712922909i32 => "R_HAND_CHILD",
-    -496823445i32 => "THUMB",
-    -1231416523i32 => "THUMB_2",
-    -1753213715i32 => "GUNS_01",
-    243877207i32 => "GUNS_02",
-    -1689471238i32 => "SHIELD_BONE",
-    -922767796i32 => "BUTT",
-    -907565876i32 => "GLASSES",
-    -757733829i32 => "MIDD",
-    -2004984512i32 => "TOP",
-    -711899067i32 => "DUMMY12",
-    -1148368494i32 => "DUMMY03",
-    636083249i32 => "DUMMY04",
-    1391389863i32 => "DUMMY05",
-    -874005219i32 => "DUMMY06",
-    -1126134389i32 => "DUMMY07",
-    744460314i32 => "DUMMY08",
-    1532534924i32 => "DUMMY09",
-    1285036543i32 => "DUMMY11",
-    -517506337i32 => "BOTTOM JAW",
-    -1280517435i32 => "PETAL 01",
-    768102246i32 => "PETAL 06",
-    715492223i32 => "PETAL 02",
-    -1567196973i32 => "DUMMY13",
-    1304000659i32 => "TOP JAW",
-    -1010422198i32 => "PETAL 04",
-    1570946025i32 => "PETAL 03",
-    -1262412068i32 => "PETAL 05",
-    -1510228790i32 => "DUMMY17",
-    893690203i32 => "DUMMY18",
-    1274404326i32 => "DUMMY15",
-    -755200932i32 => "DUMMY16",
-    1022553456i32 => "DUMMY14",
-    1000286569i32 => "DUMMY10",
-    -1032652965i32 => "HAIR7",
-    -1307049004i32 => "HAIR2",
-    -1250572339i32 => "HAIR6",
-    -1885051369i32 => "L_HIP01",
-    380450733i32 => "L_HIP02",
-    -2116350651i32 => "L_KNEE02",
-    2135848309i32 => "L_HEEL02",
-    1452297081i32 => "L_TOES02",
-    -812049725i32 => "L_TOES01",
-    416439551i32 => "L_KNEE01",
-    -431545137i32 => "L_HEEL01",
-    558682174i32 => "R_HIP02",
-    -1203404412i32 => "R_HIP01",
-    1910734822i32 => "R_KNEE01",
-    1139608369i32 => "R_KNEECHILD01",
-    -1888090154i32 => "R_HEEL01",
-    972678476i32 => "R_KNEECHILD",
-    377443948i32 => "R_HEEL02",
-    -387305892i32 => "R_KNEE02",
-    -622568821i32 => "R_KNEECHILD02",
-    1662953721i32 => "NECK_3",
-    337221743i32 => "NECK_2",
-    -773855862i32 => "R_ROCKETPART",
-    1810060854i32 => "R_PICKAXE",
-    616906292i32 => "HIDE_09",
-    -1498924582i32 => "R_TOES01",
-    -1118749749i32 => "CHESTRIGHTBOTTOM",
-    -1818178399i32 => "CHESTMIDBOTTOM",
-    -2043615904i32 => "ABSRIGHTBOTTOM",
-    1832573160i32 => "ABSMIDBOTTOM",
-    -46041517i32 => "BUTTRIGHTBOTTOM",
-    1875623831i32 => "BUTTMIDBOTTOM",
-    1824527263i32 => "CHESTRIGHTTOP",
-    -1273323987i32 => "CHESTMIDTOP",
-    584002567i32 => "ABSRIGHTTOP",
-    -1103406652i32 => "BUTTRIGHTTOP",
-    -1030036488i32 => "BUTTMIDTOP",
-    -1433513056i32 => "CHESTRIGHTMIDDLE",
-    -358736328i32 => "BUTTRIGHTMIDDLE",
-    -1846571765i32 => "ABSRIGHTMIDDLE",
-    690416051i32 => "TAIL_2",
-    1579276581i32 => "TAIL_3",
-    -1069200250i32 => "TAIL_4",
-    -1122284305i32 => "CHESTLEFTBOTTOM",
-    -1293602865i32 => "ABSLEFTBOTTOM",
-    1918743045i32 => "BUTTLEFTBOTTOM",
-    -1973542350i32 => "CHESTLEFTTOP",
-    -2115834160i32 => "ABSLEFTTOP",
-    2089911316i32 => "BUTTLEFTTOP",
-    -1429986172i32 => "CHESTLEFTMIDDLE",
-    1703074414i32 => "BUTTLEFTMIDDLE",
-    -1522907228i32 => "ABSLEFTMIDDLE",
-    192850261i32 => "ALT_NECK_2",
-    421721111i32 => "ALT_NECK",
-    1479322871i32 => "PRENECK",
-    -42320550i32 => "NECK_4",
-    -1808097872i32 => "RF_FOOT",
-    209575229i32 => "RR_FOOT",
-    -1545897437i32 => "LF_FOOT",
-    1000094382i32 => "LR_FOOT",
-    -718067009i32 => "REARPIVOT",
-    1109425421i32 => "RIGHTPIVOT",
-    -1563463985i32 => "LEFTPIVOT",
-    -221925700i32 => "FRONTPIVOT",
-    1434659006i32 => "DUMMY01",
-    1532701827i32 => "BACK",
-    -1863798006i32 => "L_KNUCKLES02",
-    -539990981i32 => "L_KNUCKLES01_GOO",
-    -475313686i32 => "TAIL_01",
-    2057443408i32 => "TAIL_02",
-    228927686i32 => "TAIL_03",
-    1858949135i32 => "R_CHEEK01",
-    181389804i32 => "L_CHEEK01",
-    864130771i32 => "FACE",
-    -199267326i32 => "ROCK_02",
-    -2095555436i32 => "ROCK_03",
-    1830174136i32 => "ROCK_01",
-    1015911871i32 => "HAIR_3",
-    -1561788388i32 => "HAIR_4",
-    1267377449i32 => "HAIR_2",
-    -401359373i32 => "F_POD",
-    496180019i32 => "B_POD",
-    1701277131i32 => "GUN_S",
-    146442528i32 => "GUN_F",
-};
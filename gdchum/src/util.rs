@@ -2,7 +2,8 @@ use gdnative::api::Engine;
 use gdnative::api::Resource;
 use gdnative::prelude::*;
 use libchum::common;
-use libchum::structure::{ArrayData, ChumStructVariant, ColorInfo, IntType};
+use libchum::error::DestructureError;
+use libchum::structure::{ArrayData, ChumStruct, ChumStructVariant, ColorInfo, IntType};
 
 #[derive(NativeClass)]
 #[inherit(Resource)]
@@ -173,31 +174,124 @@ pub fn godot_to_transform3d(tx: &Transform) -> common::Transform3D {
     )
 }
 
+/// Destructure a ChumStructVariant into a value of the given structure.
+/// Displays an error and returns None if it is invalid or does not match the structure.
+/// Values that are probably mistakes, such as numbers that are not finite, are displayed as warnings.
+pub fn destructure<T: ChumStruct>(structure: &ChumStructVariant) -> Option<T> {
+    let (warnings, errors): (Vec<_>, Vec<_>) = structure
+        .validate()
+        .into_iter()
+        .partition(|x| x.is_warning());
+    for e in warnings.iter() {
+        display_warn!("Suspicious value: {}", e);
+    }
+    if !errors.is_empty() {
+        for e in errors.iter() {
+            display_err!("Invalid value: {}", e);
+        }
+        return None;
+    }
+    match T::destructure(structure) {
+        Ok(value) => Some(value),
+        Err(e) => {
+            display_err!("Could not import structure: {}", e);
+            None
+        }
+    }
+}
+
+/// Convert a Godot Dictionary to a value of the given structure.
+/// Displays an error and returns None if the Dictionary does not match the structure.
+pub fn dict_to_value<T: ChumStruct>(dict: &Dictionary) -> Option<T> {
+    match dict_to_struct(dict) {
+        Ok(structure) => destructure(&structure),
+        Err(e) => {
+            display_err!("Could not import structure: {}", e);
+            None
+        }
+    }
+}
+
+/// Describe the type of a Variant, for error messages
+fn describe_variant(value: &Variant) -> &'static str {
+    match value.get_type() {
+        VariantType::Nil => "nil",
+        VariantType::Bool => "a boolean",
+        VariantType::I64 => "an integer",
+        VariantType::F64 => "a float",
+        VariantType::GodotString => "a string",
+        VariantType::Dictionary => "a dictionary",
+        VariantType::VariantArray => "an array",
+        _ => "another type",
+    }
+}
+
+fn join_member(path: &str, name: &str) -> String {
+    if path.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", path, name)
+    }
+}
+
+/// Get the entry `key` of the Dictionary for the value at `path`, converted with `convert`.
+/// Errors are reported at `path`, since the entries are not part of the structure.
+fn get_entry<T, F>(
+    dict: &Dictionary,
+    key: &str,
+    path: &str,
+    expected: &'static str,
+    convert: F,
+) -> Result<T, DestructureError>
+where
+    F: Fn(&Variant) -> Option<T>,
+{
+    if !dict.contains(key) {
+        return Err(DestructureError::MissingMember {
+            path: path.to_string(),
+        });
+    }
+    let value = dict.get(key);
+    convert(&value).ok_or_else(|| DestructureError::TypeMismatch {
+        path: path.to_string(),
+        expected,
+        found: describe_variant(&value),
+    })
+}
+
+fn get_names(dict: &Dictionary, path: &str) -> Result<Vec<String>, DestructureError> {
+    let names = get_entry(dict, "names", path, "a list of names", |x| {
+        x.try_to_string_array()
+    })?;
+    let mut value_names: Vec<String> = Vec::new();
+    for i in 0..names.len() {
+        value_names.push(names.get(i).to_string());
+    }
+    Ok(value_names)
+}
+
 /// Convert a Godot Dictionary to a ChumStructVariant
-pub fn dict_to_struct(dict: &Dictionary) -> ChumStructVariant {
-    let value_type = dict.get("type").try_to_string().unwrap();
-    match value_type.as_str() {
+pub fn dict_to_struct(dict: &Dictionary) -> Result<ChumStructVariant, DestructureError> {
+    dict_to_struct_inner(dict, "")
+}
+
+fn dict_to_struct_inner(
+    dict: &Dictionary,
+    path: &str,
+) -> Result<ChumStructVariant, DestructureError> {
+    let value_type = get_entry(dict, "type", path, "a string", |x| x.try_to_string())?;
+    Ok(match value_type.as_str() {
         "enum" => {
-            let value = dict.get("value").try_to_i64().unwrap();
-            let names = dict.get("names").try_to_string_array().unwrap();
-            let mut value_names: Vec<String> = Vec::new();
-            for i in 0..names.len() {
-                value_names.push(names.get(i).to_string());
-            }
-            ChumStructVariant::Integer(value, IntType::Enum(value_names))
+            let value = get_entry(dict, "value", path, "an integer", |x| x.try_to_i64())?;
+            ChumStructVariant::Integer(value, IntType::Enum(get_names(dict, path)?))
         }
         "flags" => {
-            let value = dict.get("value").try_to_i64().unwrap();
-            let names = dict.get("names").try_to_string_array().unwrap();
-            let mut value_names: Vec<String> = Vec::new();
-            for i in 0..names.len() {
-                value_names.push(names.get(i).to_string());
-            }
-            ChumStructVariant::Integer(value, IntType::Flags(value_names))
+            let value = get_entry(dict, "value", path, "an integer", |x| x.try_to_i64())?;
+            ChumStructVariant::Integer(value, IntType::Flags(get_names(dict, path)?))
         }
         "integer" => {
-            let value = dict.get("value").try_to_i64().unwrap();
-            let int_type = dict.get("integer").try_to_string().unwrap();
+            let value = get_entry(dict, "value", path, "an integer", |x| x.try_to_i64())?;
+            let int_type = get_entry(dict, "integer", path, "a string", |x| x.try_to_string())?;
             let t = match int_type.as_str() {
                 "I8" => IntType::I8,
                 "U8" => IntType::U8,
@@ -206,59 +300,83 @@ pub fn dict_to_struct(dict: &Dictionary) -> ChumStructVariant {
                 "I32" => IntType::I32,
                 "U32" => IntType::U32,
                 "custom" => {
-                    let vmin = dict.get("min").try_to_i64().unwrap();
-                    let vmax = dict.get("max").try_to_i64().unwrap();
+                    let vmin = get_entry(dict, "min", path, "an integer", |x| x.try_to_i64())?;
+                    let vmax = get_entry(dict, "max", path, "an integer", |x| x.try_to_i64())?;
                     IntType::Custom(vmin, vmax)
                 }
-                _ => panic!("Invalid integer type"),
+                other => {
+                    return Err(DestructureError::InvalidVariant {
+                        path: path.to_string(),
+                        expected: ["I8", "U8", "I16", "U16", "I32", "U32", "custom"]
+                            .iter()
+                            .map(|x| x.to_string())
+                            .collect(),
+                        value: other.to_string(),
+                    })
+                }
             };
             ChumStructVariant::Integer(value, t)
         }
         "float" => {
-            let value = dict.get("value").try_to_f64().unwrap() as f32;
-            ChumStructVariant::Float(value)
+            let value = get_entry(dict, "value", path, "a float", |x| x.try_to_f64())?;
+            ChumStructVariant::Float(value as f32)
         }
         "vec2" => {
-            let value = dict.get("value").try_to_vector2().unwrap();
+            let value = get_entry(dict, "value", path, "a Vector2", |x| x.try_to_vector2())?;
             ChumStructVariant::Vec2(common::Vector2::new(value.x, value.y))
         }
         "vec3" => {
-            let value = dict.get("value").try_to_vector3().unwrap();
+            let value = get_entry(dict, "value", path, "a Vector3", |x| x.try_to_vector3())?;
             ChumStructVariant::Vec3(common::Vector3::new(value.x, value.y, value.z))
         }
         "transform3d" => {
-            let value = dict.get("value").try_to_transform().unwrap();
-            let mat = godot_to_transform3d(&value);
-            ChumStructVariant::Transform3D(mat)
+            let value = get_entry(dict, "value", path, "a Transform", |x| x.try_to_transform())?;
+            ChumStructVariant::Transform3D(godot_to_transform3d(&value))
         }
         "transform2d" => {
-            let value = dict.get("value").try_to_transform2d().unwrap();
+            let value = get_entry(dict, "value", path, "a Transform2D", |x| {
+                x.try_to_transform2d()
+            })?;
             ChumStructVariant::Transform2D(value)
         }
         "color" => {
-            let value = dict.get("value").try_to_color().unwrap();
-            let alpha = dict.get("has_alpha").try_to_bool().unwrap();
+            let value = get_entry(dict, "value", path, "a Color", |x| x.try_to_color())?;
+            let alpha = get_entry(dict, "has_alpha", path, "a boolean", |x| x.try_to_bool())?;
             ChumStructVariant::Color(
                 common::ColorRGBA::new(value.r, value.g, value.b, value.a),
                 ColorInfo { has_alpha: alpha },
             )
         }
         "reference" => {
-            let value = dict.get("value").try_to_i64().unwrap() as i32;
-            let typename = dict.get("reference");
-            let reference = match typename.get_type() {
-                VariantType::Nil => None,
-                VariantType::GodotString => Some(typename.try_to_string().unwrap()),
-                _ => panic!(),
+            let value = get_entry(dict, "value", path, "an integer", |x| x.try_to_i64())?;
+            let reference = if dict.contains("reference") {
+                get_entry(dict, "reference", path, "a string or nil", |x| {
+                    match x.get_type() {
+                        VariantType::Nil => Some(None),
+                        _ => x.try_to_string().map(Some),
+                    }
+                })?
+            } else {
+                None
             };
-            ChumStructVariant::Reference(value, reference)
+            ChumStructVariant::Reference(value as i32, reference)
         }
         "array" => {
-            let array = dict.get("value").try_to_array().unwrap();
-            let can_resize = dict.get("can_resize").try_to_bool().unwrap();
+            let array = get_entry(dict, "value", path, "an array", |x| x.try_to_array())?;
+            let can_resize = get_entry(dict, "can_resize", path, "a boolean", |x| x.try_to_bool())?;
             let mut values = Vec::new();
             for i in 0..array.len() {
-                values.push(dict_to_struct(&array.get(i).try_to_dictionary().unwrap()));
+                let element_path = format!("{}[{}]", path, i);
+                let element = array.get(i);
+                let element_dict =
+                    element
+                        .try_to_dictionary()
+                        .ok_or_else(|| DestructureError::TypeMismatch {
+                            path: element_path.clone(),
+                            expected: "a dictionary",
+                            found: describe_variant(&element),
+                        })?;
+                values.push(dict_to_struct_inner(&element_dict, &element_path)?);
             }
             ChumStructVariant::Array(ArrayData {
                 data: values,
@@ -267,24 +385,31 @@ pub fn dict_to_struct(dict: &Dictionary) -> ChumStructVariant {
             })
         }
         "struct" => {
-            let values_dict = dict.get("value").try_to_dictionary().unwrap();
-            let values_order = dict.get("order").try_to_string_array().unwrap();
+            let values_dict = get_entry(dict, "value", path, "a dictionary", |x| {
+                x.try_to_dictionary()
+            })?;
+            let values_order = get_entry(dict, "order", path, "a list of names", |x| {
+                x.try_to_string_array()
+            })?;
             let mut values = Vec::new();
             for i in 0..values_order.len() {
                 let name = values_order.get(i).to_string();
-                let value_dict = values_dict
-                    .get(&Variant::from_str(&name))
-                    .try_to_dictionary()
-                    .unwrap();
-                let value = dict_to_struct(&value_dict);
+                let member_path = join_member(path, &name);
+                let value_dict =
+                    get_entry(&values_dict, &name, &member_path, "a dictionary", |x| {
+                        x.try_to_dictionary()
+                    })?;
+                let value = dict_to_struct_inner(&value_dict, &member_path)?;
                 values.push((name, value));
             }
             ChumStructVariant::Struct(values)
         }
         "option" => {
             let value = if dict.contains("value") {
-                let value_dict = dict.get("value").try_to_dictionary().unwrap();
-                Some(Box::new(dict_to_struct(&value_dict)))
+                let value_dict = get_entry(dict, "value", path, "a dictionary", |x| {
+                    x.try_to_dictionary()
+                })?;
+                Some(Box::new(dict_to_struct_inner(&value_dict, path)?))
             } else {
                 None
             };
@@ -293,15 +418,44 @@ pub fn dict_to_struct(dict: &Dictionary) -> ChumStructVariant {
                 default_value: || unimplemented!(),
             }
         }
-        "variant" => ChumStructVariant::Variant {
-            current: dict.get("current").try_to_string().unwrap(),
-            options: vec![],
-            value: Box::new(dict_to_struct(
-                &dict.get("value").try_to_dictionary().unwrap(),
-            )),
-        },
-        other => panic!("Invalid variant type {}", other),
-    }
+        "variant" => {
+            let current = get_entry(dict, "current", path, "a string", |x| x.try_to_string())?;
+            let value_dict = get_entry(dict, "value", path, "a dictionary", |x| {
+                x.try_to_dictionary()
+            })?;
+            let value = dict_to_struct_inner(&value_dict, &join_member(path, &current))?;
+            ChumStructVariant::Variant {
+                current,
+                options: vec![],
+                value: Box::new(value),
+            }
+        }
+        other => {
+            return Err(DestructureError::InvalidVariant {
+                path: path.to_string(),
+                expected: [
+                    "enum",
+                    "flags",
+                    "integer",
+                    "float",
+                    "vec2",
+                    "vec3",
+                    "transform3d",
+                    "transform2d",
+                    "color",
+                    "reference",
+                    "array",
+                    "struct",
+                    "option",
+                    "variant",
+                ]
+                .iter()
+                .map(|x| x.to_string())
+                .collect(),
+                value: other.to_string(),
+            })
+        }
+    })
 }
 
 /// Convert a ChumStructVariant to a format usable by Godot
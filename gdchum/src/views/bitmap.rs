@@ -73,10 +73,11 @@ impl BitmapView {
 
     #[export]
     pub fn import_structure(&mut self, owner: &Resource, data: Dictionary) {
-        use libchum::structure::ChumStruct;
-        let structure = util::dict_to_struct(&data);
-        self.inner
-            .import_struct(&bitmap::BitmapStruct::destructure(&structure).unwrap());
+        let structure: bitmap::BitmapStruct = match util::dict_to_value(&data) {
+            Some(value) => value,
+            None => return,
+        };
+        self.inner.import_struct(&structure);
         owner.emit_signal("modified", &[]);
     }
 }
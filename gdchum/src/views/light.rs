@@ -34,9 +34,10 @@ impl LightView {
 
     #[export]
     pub fn import_structure(&mut self, owner: &Resource, data: Dictionary) {
-        use libchum::structure::ChumStruct;
-        let structure = util::dict_to_struct(&data);
-        self.inner = light::Light::destructure(&structure).unwrap();
+        self.inner = match util::dict_to_value(&data) {
+            Some(value) => value,
+            None => return,
+        };
         owner.emit_signal("modified", &[]);
     }
 }
@@ -32,10 +32,11 @@ impl MeshView {
 
     #[export]
     pub fn import_structure(&mut self, owner: &Resource, data: Dictionary) {
-        use libchum::structure::ChumStruct;
-        let structure = util::dict_to_struct(&data);
-        self.inner
-            .import_struct(mesh::MeshStruct::destructure(&structure).unwrap());
+        let structure: mesh::MeshStruct = match util::dict_to_value(&data) {
+            Some(value) => value,
+            None => return,
+        };
+        self.inner.import_struct(structure);
         owner.emit_signal("modified", &[]);
     }
 
@@ -2,195 +2,195 @@
 //! Don't worry, I didn't type all of this out by hand, I'm not that crazy.
 //! I used a python script to generate most of this.
 
-chum_enum! {
-    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
-    pub enum AnimSymbol {
-        AnimDefault,
-        AnimWait,
-        AnimAnim1,
-        AnimAnim2,
-        AnimAnim3,
-        AnimAction,
-        AnimWait01,
-        AnimWait02,
-        AnimWait03,
-        AnimWait04,
-        AnimWait05,
-        AnimWalk,
-        AnimWalk01,
-        AnimWalk02,
-        AnimJog,
-        AnimRun,
-        AnimRun01,
-        AnimRun02,
-        AnimSkidIn,
-        AnimSkid,
-        AnimSkidOut,
-        AnimJump,
-        AnimJump01,
-        AnimJump02,
-        AnimJumpPwr,
-        AnimJumpToFall,
-        AnimJumpFall,
-        AnimJumpFall01,
-        AnimJumpFall02,
-        AnimJumpLand,
-        AnimHookHang,
-        AnimAttack,
-        AnimAttack01,
-        AnimAttack02,
-        AnimAttack03,
-        AnimAttack04,
-        AnimAttack05,
-        AnimAttack06,
-        AnimAttack07,
-        AnimAttack08,
-        AnimAttack09,
-        AnimJumpAttack,
-        AnimMovingAttack,
-        AnimPwrAttack,
-        AnimPwrAttack01,
-        AnimPwrAttack02,
-        AnimPwrAttack03,
-        AnimAttackJump,
-        AnimAttackJump01,
-        AnimAttackJump02,
-        AnimAttackJump03,
-        AnimAttackJumpKick,
-        AnimAttackJumpKick01,
-        AnimAttackJumpKick02,
-        AnimAttackJumpKick03,
-        AnimAttackJumpPunch,
-        AnimAttackJumpPunch01,
-        AnimAttackJumpPunch02,
-        AnimAttackJumpPunch03,
-        AnimChopWndup,
-        AnimDuckAttack,
-        AnimDuckIn,
-        AnimDuckIn01,
-        AnimDuckIn02,
-        AnimDuckHld,
-        AnimDuckOut,
-        AnimBellyBump,
-        AnimBellySlam,
-        AnimBellySlamLand,
-        AnimBellySlamFlip,
-        AnimGetHit,
-        AnimGetHit01,
-        AnimGetHit02,
-        AnimDefeated,
-        AnimDefeated01,
-        AnimDefeated02,
-        AnimLaugh,
-        AnimLaugh01,
-        AnimLaugh02,
-        AnimTalkStd,
-        AnimTalkStd01,
-        AnimTalkStd02,
-        AnimTalkStd03,
-        AnimTalkStd04,
-        AnimTalkStd05,
-        AnimTalkStd06,
-        AnimTalkStd07,
-        AnimTalkStd08,
-        AnimTalkStd09,
-        AnimTalkStd10,
-        AnimTalkStd11,
-        AnimTalkStd12,
-        AnimTalkStd13,
-        AnimTalkStd14,
-        AnimTalkStd15,
-        AnimTalkStd16,
-        AnimTalkStd17,
-        AnimTalkStd18,
-        AnimTalkStd19,
-        AnimTalkStd20,
-        AnimTalkStd21,
-        AnimTalkStd22,
-        AnimTalkStd23,
-        AnimTalkStd24,
-        AnimTalkStd25,
-        AnimTalkStd26,
-        AnimTalkStd27,
-        AnimTalkStd28,
-        AnimTalkStd29,
-        AnimGlide,
-        AnimGlideLand,
-        AnimRoll,
-        AnimSlide,
-        AnimSlide01,
-        AnimSlide02,
-        AnimPickup,
-        AnimPickup01,
-        AnimPickup02,
-        AnimCarry,
-        AnimJumpCarry,
-        AnimCarryFall,
-        AnimDrop,
-        AnimHold,
-        AnimThrow,
-        AnimThrow01,
-        AnimThrow02,
-        AnimJfCompress,
-        AnimJfPump,
-        AnimJfGlide,
-        AnimOpen,
-        AnimOpening,
-        AnimClosed,
-        AnimClosing,
-        AnimTucked,
-        AnimTuckOut,
-        AnimSpring,
-        AnimAlerted,
-        AnimAlerted01,
-        AnimAlerted02,
-        AnimAlertedIdle,
-        AnimAlertedIdle01,
-        AnimAlertedIdle02,
-        AnimAttackCharge,
-        AnimAttackCharge01,
-        AnimAttackCharge02,
-        AnimJumpMoving,
-        AnimJumpMoving01,
-        AnimJumpMoving02,
-        AnimFly,
-        AnimFly01,
-        AnimFly02,
-        AnimRunAttack,
-        AnimMisc,
-        AnimMisc01,
-        AnimMisc02,
-        AnimMisc03,
-        AnimMisc04,
-        AnimMisc05,
-        AnimMisc06,
-        AnimMisc07,
-        AnimMisc08,
-        AnimMisc09,
-        AnimMisc10,
-        AnimMisc11,
-        AnimMisc12,
-        AnimMisc13,
-        AnimMisc14,
-        AnimMisc15,
-        AnimMisc16,
-        AnimMisc17,
-        AnimMisc18,
-        AnimMisc19,
-        AnimMisc20,
-        AnimMisc21,
-        AnimMisc22,
-        AnimMisc23,
-        AnimMisc24,
-        AnimMisc25,
-        AnimMisc26,
-        AnimMisc27,
-        AnimMisc28,
-        AnimMisc29,
-        AnimJfMoveAll,
-        AnimTurn,
-        AnimTalkHold,
-        AnimTalkHold01,
-        AnimTalkHold02,
-    }
+use crate::structure::ChumEnum;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ChumEnum)]
+pub enum AnimSymbol {
+    AnimDefault,
+    AnimWait,
+    AnimAnim1,
+    AnimAnim2,
+    AnimAnim3,
+    AnimAction,
+    AnimWait01,
+    AnimWait02,
+    AnimWait03,
+    AnimWait04,
+    AnimWait05,
+    AnimWalk,
+    AnimWalk01,
+    AnimWalk02,
+    AnimJog,
+    AnimRun,
+    AnimRun01,
+    AnimRun02,
+    AnimSkidIn,
+    AnimSkid,
+    AnimSkidOut,
+    AnimJump,
+    AnimJump01,
+    AnimJump02,
+    AnimJumpPwr,
+    AnimJumpToFall,
+    AnimJumpFall,
+    AnimJumpFall01,
+    AnimJumpFall02,
+    AnimJumpLand,
+    AnimHookHang,
+    AnimAttack,
+    AnimAttack01,
+    AnimAttack02,
+    AnimAttack03,
+    AnimAttack04,
+    AnimAttack05,
+    AnimAttack06,
+    AnimAttack07,
+    AnimAttack08,
+    AnimAttack09,
+    AnimJumpAttack,
+    AnimMovingAttack,
+    AnimPwrAttack,
+    AnimPwrAttack01,
+    AnimPwrAttack02,
+    AnimPwrAttack03,
+    AnimAttackJump,
+    AnimAttackJump01,
+    AnimAttackJump02,
+    AnimAttackJump03,
+    AnimAttackJumpKick,
+    AnimAttackJumpKick01,
+    AnimAttackJumpKick02,
+    AnimAttackJumpKick03,
+    AnimAttackJumpPunch,
+    AnimAttackJumpPunch01,
+    AnimAttackJumpPunch02,
+    AnimAttackJumpPunch03,
+    AnimChopWndup,
+    AnimDuckAttack,
+    AnimDuckIn,
+    AnimDuckIn01,
+    AnimDuckIn02,
+    AnimDuckHld,
+    AnimDuckOut,
+    AnimBellyBump,
+    AnimBellySlam,
+    AnimBellySlamLand,
+    AnimBellySlamFlip,
+    AnimGetHit,
+    AnimGetHit01,
+    AnimGetHit02,
+    AnimDefeated,
+    AnimDefeated01,
+    AnimDefeated02,
+    AnimLaugh,
+    AnimLaugh01,
+    AnimLaugh02,
+    AnimTalkStd,
+    AnimTalkStd01,
+    AnimTalkStd02,
+    AnimTalkStd03,
+    AnimTalkStd04,
+    AnimTalkStd05,
+    AnimTalkStd06,
+    AnimTalkStd07,
+    AnimTalkStd08,
+    AnimTalkStd09,
+    AnimTalkStd10,
+    AnimTalkStd11,
+    AnimTalkStd12,
+    AnimTalkStd13,
+    AnimTalkStd14,
+    AnimTalkStd15,
+    AnimTalkStd16,
+    AnimTalkStd17,
+    AnimTalkStd18,
+    AnimTalkStd19,
+    AnimTalkStd20,
+    AnimTalkStd21,
+    AnimTalkStd22,
+    AnimTalkStd23,
+    AnimTalkStd24,
+    AnimTalkStd25,
+    AnimTalkStd26,
+    AnimTalkStd27,
+    AnimTalkStd28,
+    AnimTalkStd29,
+    AnimGlide,
+    AnimGlideLand,
+    AnimRoll,
+    AnimSlide,
+    AnimSlide01,
+    AnimSlide02,
+    AnimPickup,
+    AnimPickup01,
+    AnimPickup02,
+    AnimCarry,
+    AnimJumpCarry,
+    AnimCarryFall,
+    AnimDrop,
+    AnimHold,
+    AnimThrow,
+    AnimThrow01,
+    AnimThrow02,
+    AnimJfCompress,
+    AnimJfPump,
+    AnimJfGlide,
+    AnimOpen,
+    AnimOpening,
+    AnimClosed,
+    AnimClosing,
+    AnimTucked,
+    AnimTuckOut,
+    AnimSpring,
+    AnimAlerted,
+    AnimAlerted01,
+    AnimAlerted02,
+    AnimAlertedIdle,
+    AnimAlertedIdle01,
+    AnimAlertedIdle02,
+    AnimAttackCharge,
+    AnimAttackCharge01,
+    AnimAttackCharge02,
+    AnimJumpMoving,
+    AnimJumpMoving01,
+    AnimJumpMoving02,
+    AnimFly,
+    AnimFly01,
+    AnimFly02,
+    AnimRunAttack,
+    AnimMisc,
+    AnimMisc01,
+    AnimMisc02,
+    AnimMisc03,
+    AnimMisc04,
+    AnimMisc05,
+    AnimMisc06,
+    AnimMisc07,
+    AnimMisc08,
+    AnimMisc09,
+    AnimMisc10,
+    AnimMisc11,
+    AnimMisc12,
+    AnimMisc13,
+    AnimMisc14,
+    AnimMisc15,
+    AnimMisc16,
+    AnimMisc17,
+    AnimMisc18,
+    AnimMisc19,
+    AnimMisc20,
+    AnimMisc21,
+    AnimMisc22,
+    AnimMisc23,
+    AnimMisc24,
+    AnimMisc25,
+    AnimMisc26,
+    AnimMisc27,
+    AnimMisc28,
+    AnimMisc29,
+    AnimJfMoveAll,
+    AnimTurn,
+    AnimTalkHold,
+    AnimTalkHold01,
+    AnimTalkHold02,
 }
@@ -0,0 +1,235 @@
+use crate::archive::{journal, ChumArchive, ChumFile};
+use crate::binary::ChumBinary;
+use crate::error;
+use crate::format::TotemFormat;
+use crate::reader;
+use std::collections::HashMap;
+use std::io;
+use thiserror::Error;
+
+/// Reason why a file could not be converted to another format.
+#[derive(Debug, Error)]
+pub enum ConvertError {
+    #[error("Files of type {0} can not be converted")]
+    UnsupportedType(String),
+    #[error("Files of type {0} contain data that is specific to one platform")]
+    PlatformSpecific(String),
+    #[error(transparent)]
+    Unpack(#[from] error::StructUnpackError),
+    #[error("{0} bytes at the end of the file were not read")]
+    TrailingData(usize),
+    #[error("Text is {found} bytes long, but its length is given as {expected}")]
+    TextLength { expected: usize, found: usize },
+    #[error("File does not stay the same when written back in its original format")]
+    RoundTripMismatch,
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+/// The result of converting an archive to another format.
+#[derive(Debug, Default)]
+pub struct ConvertReport {
+    /// Names of files whose data was converted
+    pub converted: Vec<String>,
+    /// Names of files that were copied without changes, along with the reason why
+    pub failed: Vec<(String, ConvertError)>,
+}
+
+/// Convert data that is read and written with a ChumBinary implementation.
+/// The data must be read completely, and must be written back exactly the same,
+/// so that nothing the reader does not understand is lost.
+fn convert_binary<T: ChumBinary>(
+    data: &[u8],
+    from: TotemFormat,
+    to: TotemFormat,
+) -> Result<Vec<u8>, ConvertError> {
+    let mut reader = data;
+    let value = T::read_from(&mut reader, from)?;
+    if !reader.is_empty() {
+        return Err(ConvertError::TrailingData(reader.len()));
+    }
+    let mut original = Vec::with_capacity(data.len());
+    value.write_to(&mut original, from)?;
+    if original != data {
+        return Err(ConvertError::RoundTripMismatch);
+    }
+    let mut converted = Vec::with_capacity(data.len());
+    value.write_to(&mut converted, to)?;
+    Ok(converted)
+}
+
+/// Convert text, which starts with its length in bytes
+fn convert_text(data: &[u8], from: TotemFormat, to: TotemFormat) -> Result<Vec<u8>, ConvertError> {
+    let mut reader = data;
+    let size = from.read_u32(&mut reader)? as usize;
+    if size != reader.len() {
+        return Err(ConvertError::TextLength {
+            expected: size,
+            found: reader.len(),
+        });
+    }
+    let mut converted = Vec::with_capacity(data.len());
+    to.write_u32(&mut converted, size as u32)?;
+    converted.extend_from_slice(reader);
+    Ok(converted)
+}
+
+/// Convert the data of a file with the given type from one format to another.
+pub fn convert_file_data(
+    type_id: &str,
+    data: &[u8],
+    from: TotemFormat,
+    to: TotemFormat,
+) -> Result<Vec<u8>, ConvertError> {
+    use reader::*;
+    if from == to {
+        return Ok(data.to_vec());
+    }
+    match type_id {
+        "CAMERA" => convert_binary::<camera::Camera>(data, from, to),
+        "COLLISIONVOL" => convert_binary::<collisionvol::CollisionVol>(data, from, to),
+        "GAMEOBJ" => convert_binary::<gameobj::GameObj>(data, from, to),
+        "HFOG" => convert_binary::<hfog::HFog>(data, from, to),
+        "LIGHT" => convert_binary::<light::Light>(data, from, to),
+        "LOD" => convert_binary::<lod::Lod>(data, from, to),
+        "MATERIAL" => convert_binary::<material::Material>(data, from, to),
+        "MATERIALANIM" => convert_binary::<materialanim::MaterialAnimation>(data, from, to),
+        "MATERIALOBJ" => convert_binary::<materialobj::MaterialObj>(data, from, to),
+        "MESH" => convert_binary::<mesh::Mesh>(data, from, to),
+        "NODE" => convert_binary::<node::Node>(data, from, to),
+        "OMNI" => convert_binary::<omni::Omni>(data, from, to),
+        "ROTSHAPE" => convert_binary::<rotshape::RotShape>(data, from, to),
+        "SKIN" => convert_binary::<skin::Skin>(data, from, to),
+        "SPLINE" => convert_binary::<spline::Spline>(data, from, to),
+        "WARP" => convert_binary::<warp::Warp>(data, from, to),
+        "TXT" => convert_text(data, from, to),
+        // Bitmaps are stored in the Gamecube's tiled layout,
+        // and sounds are Gamecube DSP ADPCM audio.
+        "BITMAP" | "SOUND" => Err(ConvertError::PlatformSpecific(type_id.to_owned())),
+        _ => Err(ConvertError::UnsupportedType(type_id.to_owned())),
+    }
+}
+
+impl ChumArchive {
+    /// Create a copy of this archive in another format, converting every file's data.
+    /// Files that can not be converted are copied without changes, and are listed
+    /// in the returned report.
+    pub fn convert_format(&self, to: TotemFormat) -> (ChumArchive, ConvertReport) {
+        let mut report = ConvertReport::default();
+        let mut files = HashMap::with_capacity(self.files.len());
+        for (id, file) in self.files.iter() {
+            let data = match convert_file_data(file.get_type_id(), file.get_data(), self.format, to)
+            {
+                Ok(data) => {
+                    report.converted.push(file.get_name_id().to_owned());
+                    data
+                }
+                Err(err) => {
+                    report.failed.push((file.get_name_id().to_owned(), err));
+                    file.get_data().to_vec()
+                }
+            };
+            let converted = ChumFile::new(
+                data,
+                file.get_name_id().to_owned(),
+                file.get_type_id().to_owned(),
+                file.get_subtype_id().to_owned(),
+            );
+            files.insert(*id, converted);
+        }
+        report.converted.sort();
+        report.failed.sort_by(|a, b| a.0.cmp(&b.0));
+        let archive = ChumArchive {
+            header: self.header.clone(),
+            names: self.names.clone(),
+            name_order: self.name_order.clone(),
+            files,
+            format: to,
+            chunk_size: self.chunk_size,
+            chunk_padding: Vec::new(),
+            preserve_layout: false,
+            journal: journal::EditJournal::default(),
+        };
+        (archive, report)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::archive::convert::*;
+    use crate::archive::TotemHeader;
+    use crate::reader::material::Material;
+
+    #[test]
+    fn convert_material() {
+        let material = Material {
+            texture: 0x1234,
+            rotation: 2.5,
+            ..Default::default()
+        };
+        let mut ngc_data = Vec::new();
+        material.write_to(&mut ngc_data, TotemFormat::NGC).unwrap();
+
+        let mut archive = ChumArchive::new(TotemHeader::new(b"test"), TotemFormat::NGC);
+        let file = |data: Vec<u8>, name: &str, type_id: &str| {
+            ChumFile::new(data, name.to_owned(), type_id.to_owned(), name.to_owned())
+        };
+        archive
+            .add_file(file(ngc_data.clone(), "MAT", "MATERIAL"))
+            .unwrap();
+        archive
+            .add_file(file(vec![0, 0, 0, 2, b'h', b'i'], "README", "TXT"))
+            .unwrap();
+        archive
+            .add_file(file(vec![0, 0, 0, 9, b'h', b'i'], "SHORT", "TXT"))
+            .unwrap();
+        archive
+            .add_file(file(vec![1, 2, 3], "ANIM", "ANIMATION"))
+            .unwrap();
+        // Too short to be a material
+        archive
+            .add_file(file(vec![0; 10], "BROKEN", "MATERIAL"))
+            .unwrap();
+
+        let (ps2, report) = archive.convert_format(TotemFormat::PS2);
+        assert_eq!(
+            report.converted,
+            vec!["MAT".to_owned(), "README".to_owned()]
+        );
+        let failed: Vec<&str> = report.failed.iter().map(|x| x.0.as_str()).collect();
+        assert_eq!(failed, vec!["ANIM", "BROKEN", "SHORT"]);
+        assert!(matches!(
+            report.failed[0].1,
+            ConvertError::UnsupportedType(_)
+        ));
+        assert!(matches!(
+            report.failed[2].1,
+            ConvertError::TextLength {
+                expected: 9,
+                found: 2
+            }
+        ));
+        assert_eq!(ps2.get_format(), TotemFormat::PS2);
+        assert_eq!(
+            ps2.get_file_from_name("ANIM").unwrap().get_data(),
+            &[1, 2, 3]
+        );
+        // The length of text is written in the new format's byte order
+        assert_eq!(
+            ps2.get_file_from_name("README").unwrap().get_data(),
+            &[2, 0, 0, 0, b'h', b'i']
+        );
+
+        let ps2_data = ps2.get_file_from_name("MAT").unwrap().get_data();
+        assert_ne!(ps2_data, ngc_data.as_slice());
+        let converted = Material::read_from(&mut &ps2_data[..], TotemFormat::PS2).unwrap();
+        assert_eq!(converted.texture, 0x1234);
+        assert_eq!(converted.rotation, 2.5);
+
+        let (ngc, _) = ps2.convert_format(TotemFormat::NGC);
+        assert_eq!(
+            ngc.get_file_from_name("MAT").unwrap().get_data(),
+            ngc_data.as_slice()
+        );
+    }
+}
@@ -1,5 +1,5 @@
+use crate::archive::packer;
 use crate::format::TotemFormat;
-use std::cmp;
 use std::io::{self, Read, Write};
 use std::mem;
 
@@ -7,11 +7,12 @@ use std::mem;
 /// Format:
 /// legal notice [u8; 0x100]
 /// chunk size   u32         (implied)
-/// junk padding [u8; 0x6FC] (ignored)
+/// junk padding [u8; 0x6FC] (kept as-is so that archives round-trip)
 /// data         [u8; chunk size * N] (N is any whole number)
 #[derive(Clone)]
 pub struct TotemHeader {
     legal_notice: [u8; 0x100],
+    junk: Vec<u8>,
 }
 
 impl TotemHeader {
@@ -25,6 +26,7 @@ impl TotemHeader {
         }
         TotemHeader {
             legal_notice: headerdata,
+            junk: vec![0; 0x6FC],
         }
     }
 
@@ -32,6 +34,11 @@ impl TotemHeader {
     pub fn get_legal_notice(&self) -> &[u8; 0x100] {
         return &self.legal_notice;
     }
+
+    /// Get the junk padding that follows the chunk size.
+    pub fn get_junk(&self) -> &[u8] {
+        &self.junk
+    }
 }
 
 /// .DGC file element
@@ -112,8 +119,10 @@ impl TotemFile {
 /// Format:
 /// num files u32 (implied)
 /// data      [u8; chunk size] (inherited from header)
+/// padding   [u8] (fills the rest of the chunk, kept as-is so that archives round-trip)
 pub struct TotemChunk {
     data: Vec<TotemFile>,
+    padding: Vec<u8>,
 }
 
 impl TotemChunk {
@@ -124,7 +133,32 @@ impl TotemChunk {
 
     /// Create a new DgcChunk
     pub fn new() -> TotemChunk {
-        TotemChunk { data: Vec::new() }
+        TotemChunk {
+            data: Vec::new(),
+            padding: Vec::new(),
+        }
+    }
+
+    /// Create a new DgcChunk from the given files
+    pub(crate) fn from_files(files: Vec<TotemFile>) -> TotemChunk {
+        TotemChunk {
+            data: files,
+            padding: Vec::new(),
+        }
+    }
+
+    /// Create a new DgcChunk from the given files and the padding that followed them
+    pub(crate) fn from_files_padded(files: Vec<TotemFile>, padding: Vec<u8>) -> TotemChunk {
+        TotemChunk {
+            data: files,
+            padding,
+        }
+    }
+
+    /// Get the padding that followed this chunk's files when it was read.
+    /// Empty for chunks that were not read from an archive.
+    pub fn get_padding(&self) -> &[u8] {
+        &self.padding
     }
 
     /// Add a file to this chunk
@@ -132,6 +166,11 @@ impl TotemChunk {
         self.data.push(file);
     }
 
+    /// Take all files from this chunk
+    pub fn take_files(self) -> Vec<TotemFile> {
+        self.data
+    }
+
     /// Get the total size of this chunk, including the contents of each file
     /// stored in this chunk, the header data of each file stored in this
     /// chunk, and the header of the chunk itself.
@@ -147,7 +186,8 @@ impl TotemChunk {
 
     /// Write this chunk to the given writer. Also expects a chunk size argument, that describes
     /// exactly how many bytes this chunk should write. If the chunk is too small to fill this
-    /// size, then the chunk will zero-pad the rest.
+    /// size, then the rest is filled with the chunk's original padding if it still has exactly
+    /// the right size, and zero-padded otherwise.
     /// Returns the number of bytes that were written in total to the writer.
     fn write_to<W: Write>(
         &self,
@@ -161,7 +201,11 @@ impl TotemChunk {
             file.write_to(writer, fmt)?;
         }
         let required_padding = chunk_size - self.get_total_size();
-        io::copy(&mut io::repeat(0u8).take(required_padding as u64), writer)?;
+        if self.padding.len() == required_padding {
+            writer.write_all(&self.padding)?;
+        } else {
+            io::copy(&mut io::repeat(0u8).take(required_padding as u64), writer)?;
+        }
         Ok(self.get_total_size() + required_padding)
     }
 }
@@ -203,7 +247,22 @@ impl TotemArchive {
         TotemArchive {
             header,
             data: vec![],
-            chunk_size: calculate_chunk_size(chunk_size),
+            chunk_size: packer::calculate_chunk_size(chunk_size),
+            format: fmt,
+        }
+    }
+
+    /// Create a DgcArchive from chunks that have already been laid out.
+    pub(crate) fn from_chunks(
+        header: TotemHeader,
+        chunks: Vec<TotemChunk>,
+        chunk_size: usize,
+        fmt: TotemFormat,
+    ) -> TotemArchive {
+        TotemArchive {
+            header,
+            data: chunks,
+            chunk_size,
             format: fmt,
         }
     }
@@ -219,6 +278,46 @@ impl TotemArchive {
         dgc
     }
 
+    /// Create a new DgcArchive that keeps the given chunk layout and chunk size.
+    /// Each file in `unplaced` is put into the first chunk it fits in, or in a new chunk at the
+    /// end of the archive. Empty chunks are dropped. If a chunk overflows or a file is too big
+    /// to fit in any chunk, then the layout can not be kept, and all files are repacked instead.
+    pub fn new_from_layout(
+        header: TotemHeader,
+        chunks: Vec<TotemChunk>,
+        unplaced: Vec<TotemFile>,
+        chunk_size: usize,
+        fmt: TotemFormat,
+    ) -> TotemArchive {
+        let chunks_fit = chunks
+            .iter()
+            .all(|chunk| chunk.get_total_size() <= chunk_size);
+        let files_fit = unplaced
+            .iter()
+            .all(|file| file.get_total_size() + 4 <= chunk_size);
+        if !chunks_fit || !files_fit {
+            let mut files: Vec<TotemFile> = chunks
+                .into_iter()
+                .flat_map(|chunk| chunk.data.into_iter())
+                .collect();
+            files.extend(unplaced);
+            return TotemArchive::new_from_files(header, files, fmt);
+        }
+        let mut dgc = TotemArchive {
+            header,
+            data: chunks
+                .into_iter()
+                .filter(|chunk| chunk.get_num_files() > 0)
+                .collect(),
+            chunk_size,
+            format: fmt,
+        };
+        for file in unplaced {
+            dgc.add_file(file);
+        }
+        dgc
+    }
+
     /// Iterate over all files in this archive.
     pub fn iter_files(&self) -> impl Iterator<Item = &TotemFile> {
         self.data.iter().flat_map(|chunk| chunk.data.iter())
@@ -229,6 +328,11 @@ impl TotemArchive {
         self.data.iter()
     }
 
+    /// Take all chunks from this archive
+    pub fn take_chunks(self) -> Vec<TotemChunk> {
+        self.data
+    }
+
     /// Take all files from this archive
     pub fn take_files(self) -> Vec<TotemFile> {
         self.data
@@ -238,38 +342,48 @@ impl TotemArchive {
     }
 
     /// Set the files so that the given files all fit
-    fn set_files(&mut self, mut files: Vec<TotemFile>) {
-        // Clear current chunks
-        self.data = Vec::new();
-        // Sort files by size. Not the most efficient,
-        // but this is the way that DGC files tend to store files.
-        files.sort_unstable_by(|a, b| b.data.len().cmp(&a.data.len()));
-        // Calculate chunk size
-        self.chunk_size = if let Some(f) = files.get(0) {
-            calculate_chunk_size(f.get_total_size() + 4)
-        } else {
-            calculate_chunk_size(0)
-        };
-        // Pack files.
-        // On each iteration, while there is at least one file:
-        //     Create a new chunk
-        //     Fit as many files as possible in the chunk
-        while files.len() > 0 {
-            // Create a new chunk
-            let mut chunk = TotemChunk::new();
-            // Iterate files
-            let mut i = 0;
-            // Add file if it fits, otherwise skip
-            while i < files.len() {
-                if files[i].get_total_size() + chunk.get_total_size() <= self.chunk_size {
-                    chunk.add_file(files.remove(i));
-                } else {
-                    i += 1;
-                }
-            }
-            // Push chunk
-            self.data.push(chunk);
-        }
+    fn set_files(&mut self, files: Vec<TotemFile>) {
+        // The chunk size is automatic, so this can not fail
+        self.set_files_packed(files, &packer::GreedyPacker, packer::ChunkSize::Auto)
+            .unwrap();
+    }
+
+    /// Set the files using the given packing strategy and chunk size
+    fn set_files_packed(
+        &mut self,
+        files: Vec<TotemFile>,
+        packer: &dyn packer::ChunkPacker,
+        chunk_size: packer::ChunkSize,
+    ) -> Result<(), packer::PackError> {
+        self.chunk_size = chunk_size.resolve(&files)?;
+        self.data = packer
+            .pack(files, self.chunk_size)
+            .into_iter()
+            .map(TotemChunk::from_files)
+            .collect();
+        Ok(())
+    }
+
+    /// Create a new DgcArchive with the given files, packed into chunks using the given
+    /// strategy. Fails if `chunk_size` is fixed and a file does not fit into a chunk.
+    pub fn new_from_files_packed(
+        header: TotemHeader,
+        files: Vec<TotemFile>,
+        fmt: TotemFormat,
+        packer: &dyn packer::ChunkPacker,
+        chunk_size: packer::ChunkSize,
+    ) -> Result<TotemArchive, packer::PackError> {
+        let mut dgc = TotemArchive::new(header, 0, fmt);
+        dgc.set_files_packed(files, packer, chunk_size)?;
+        Ok(dgc)
+    }
+
+    /// Get the total number of padding bytes in this archive's chunks
+    pub fn get_total_padding(&self) -> usize {
+        self.data
+            .iter()
+            .map(|chunk| self.chunk_size - chunk.get_total_size())
+            .sum()
     }
 
     /// Add a file to this archive. Will be automatically put into a chunk. This function may
@@ -304,7 +418,7 @@ impl TotemArchive {
     pub fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
         writer.write_all(&self.header.legal_notice)?;
         self.format.write_u32(writer, self.chunk_size as u32)?;
-        io::copy(&mut io::repeat(0u8).take(0x6FC), writer)?;
+        writer.write_all(&self.header.junk)?;
         for chunk in &self.data {
             chunk.write_to(writer, self.chunk_size, self.format)?;
         }
@@ -312,44 +426,42 @@ impl TotemArchive {
     }
 
     /// Create an archive from a reader.
+    /// A truncated final chunk is read as-is; use `validate::validate_dgc` to report it.
     pub fn read_from<R: Read>(file: &mut R, fmt: TotemFormat) -> io::Result<TotemArchive> {
-        let mut legal_notice: [u8; 0x100] = [0; 0x100];
-        file.read_exact(&mut legal_notice)?;
-        let size = fmt.read_u32(file)?;
-        io::copy(&mut file.take(0x6FC), &mut io::sink())?;
+        let (header, size) = read_header(file, fmt)?;
         let mut fdata = Vec::new();
         let mut chunks = Vec::new();
         file.read_to_end(&mut fdata)?;
-        if fdata.len() % (size as usize) > 0 {
-            eprintln!(
-                "Warning: stream size {} is not divisible by chunk size {}!",
-                fdata.len(),
-                size
-            );
-        }
-        for chunk in fdata.chunks(size as usize) {
+        for chunk in fdata.chunks(size) {
             chunks.push(load_chunk(chunk, fmt)?);
         }
         Ok(TotemArchive {
-            header: TotemHeader {
-                legal_notice: legal_notice,
-            },
+            header,
             data: chunks,
-            chunk_size: size as usize,
+            chunk_size: size,
             format: fmt,
         })
     }
 }
 
-/// Calculate the size that a chunk would have to be in order to store a file of the given size.
-fn calculate_chunk_size(max_size: usize) -> usize {
-    // Each chunk's size is a multiple of 0x800 bytes
-    const CHUNK_MULT: usize = 0x800;
-    if max_size == 0 {
-        // avoid subtract with overflow error
-        return CHUNK_MULT;
+/// Read the header of a .DGC archive.
+/// Returns the header and the archive's chunk size.
+pub(crate) fn read_header<R: Read>(
+    file: &mut R,
+    fmt: TotemFormat,
+) -> io::Result<(TotemHeader, usize)> {
+    let mut legal_notice: [u8; 0x100] = [0; 0x100];
+    file.read_exact(&mut legal_notice)?;
+    let size = fmt.read_u32(file)?;
+    let mut junk = vec![0; 0x6FC];
+    file.read_exact(&mut junk)?;
+    if size == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "DGC chunk size is zero",
+        ));
     }
-    cmp::max(1, 1 + ((max_size - 1) / CHUNK_MULT)) * CHUNK_MULT
+    Ok((TotemHeader { legal_notice, junk }, size as usize))
 }
 
 /// Load a chunk from the given chunk data.
@@ -362,6 +474,12 @@ fn load_chunk(mut data: &[u8], fmt: TotemFormat) -> io::Result<TotemChunk> {
         let id_type = fmt.read_i32(&mut data)?;
         let id1 = fmt.read_i32(&mut data)?;
         let id2 = fmt.read_i32(&mut data)?;
+        if file_size < 16 || file_size as usize - 16 > data.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("File {} has invalid size {}", id1, file_size),
+            ));
+        }
         let mut contents: Vec<u8> = vec![0; file_size as usize - 16];
         data.read_exact(&mut contents)?;
         files.push(TotemFile {
@@ -371,5 +489,8 @@ fn load_chunk(mut data: &[u8], fmt: TotemFormat) -> io::Result<TotemChunk> {
             subtype_id: id2,
         });
     }
-    Ok(TotemChunk { data: files })
+    Ok(TotemChunk {
+        data: files,
+        padding: data.to_vec(),
+    })
 }
@@ -0,0 +1,436 @@
+use crate::archive::{ChumArchive, ChumError, ChumFile};
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use crc::crc32::{self, Hasher32};
+use std::collections::HashSet;
+use std::io::{self, Read, Write};
+use thiserror::Error;
+
+/// Identifies a patch file
+pub const PATCH_MAGIC: &[u8; 8] = b"CHUMDIFF";
+/// Version of the patch format written by `ArchiveDiff::write_to`
+pub const PATCH_VERSION: u32 = 1;
+
+#[derive(Error, Debug)]
+/// Error type for reading and applying patches
+pub enum PatchError {
+    #[error("Not a patch file")]
+    InvalidMagic,
+    #[error("Unsupported patch version {0}")]
+    UnsupportedVersion(u32),
+    #[error("Patch contains a string that is not valid UTF-8")]
+    InvalidString,
+    /// The archive is not the one the patch was made from
+    #[error(
+        "Patch does not apply to this archive (expected hash {expected:08X}, found {found:08X})"
+    )]
+    BaseMismatch { expected: u32, found: u32 },
+    /// The archive did not turn out as expected after applying the patch
+    #[error("Patched archive is incorrect (expected hash {expected:08X}, found {found:08X})")]
+    ResultMismatch { expected: u32, found: u32 },
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+/// Hash the contents of the given archive.
+/// Only files and names are hashed; chunk layout, name order and the header are ignored.
+pub fn hash_archive(archive: &ChumArchive) -> u32 {
+    let mut digest = crc32::Digest::new(crc32::IEEE);
+    let mut write_bytes = |data: &[u8]| {
+        digest.write(&(data.len() as u32).to_le_bytes());
+        digest.write(data);
+    };
+    let mut files: Vec<&ChumFile> = archive.get_files().collect();
+    files.sort_by(|a, b| a.get_name_id().cmp(b.get_name_id()));
+    for file in files {
+        write_bytes(file.get_name_id().as_bytes());
+        write_bytes(file.get_type_id().as_bytes());
+        write_bytes(file.get_subtype_id().as_bytes());
+        write_bytes(file.get_data());
+    }
+    let mut names: Vec<&String> = archive.get_names().collect();
+    names.sort();
+    for name in names {
+        write_bytes(name.as_bytes());
+    }
+    digest.sum32()
+}
+
+/// A file that was added or modified.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FileDiff {
+    name_id: String,
+    type_id: String,
+    subtype_id: String,
+    data: Option<Vec<u8>>,
+}
+
+impl FileDiff {
+    /// Get the file's name
+    pub fn get_name_id(&self) -> &str {
+        &self.name_id
+    }
+
+    /// Get the file's new type
+    pub fn get_type_id(&self) -> &str {
+        &self.type_id
+    }
+
+    /// Get the file's new subtype
+    pub fn get_subtype_id(&self) -> &str {
+        &self.subtype_id
+    }
+
+    /// Get the file's new data.
+    /// Returns None if the file was modified, but its data did not change.
+    pub fn get_data(&self) -> Option<&[u8]> {
+        self.data.as_ref().map(|x| x.as_slice())
+    }
+}
+
+/// Differences between two archives, which can be applied to the first archive to get the
+/// second.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ArchiveDiff {
+    base_hash: u32,
+    result_hash: u32,
+    added: Vec<FileDiff>,
+    modified: Vec<FileDiff>,
+    removed: Vec<String>,
+    names_added: Vec<String>,
+    names_removed: Vec<String>,
+}
+
+impl ArchiveDiff {
+    /// Find the differences between the two archives.
+    pub fn new(old: &ChumArchive, new: &ChumArchive) -> ArchiveDiff {
+        let mut added = Vec::new();
+        let mut modified = Vec::new();
+        let mut removed = Vec::new();
+        for file in new.get_files() {
+            match old.get_file_from_name(file.get_name_id()) {
+                None => added.push(FileDiff {
+                    name_id: file.get_name_id().to_string(),
+                    type_id: file.get_type_id().to_string(),
+                    subtype_id: file.get_subtype_id().to_string(),
+                    data: Some(file.get_data().to_vec()),
+                }),
+                Some(old_file) => {
+                    let data_changed = old_file.get_data() != file.get_data();
+                    if data_changed
+                        || old_file.get_type_id() != file.get_type_id()
+                        || old_file.get_subtype_id() != file.get_subtype_id()
+                    {
+                        modified.push(FileDiff {
+                            name_id: file.get_name_id().to_string(),
+                            type_id: file.get_type_id().to_string(),
+                            subtype_id: file.get_subtype_id().to_string(),
+                            data: if data_changed {
+                                Some(file.get_data().to_vec())
+                            } else {
+                                None
+                            },
+                        });
+                    }
+                }
+            }
+        }
+        for file in old.get_files() {
+            if new.get_file_from_name(file.get_name_id()).is_none() {
+                removed.push(file.get_name_id().to_string());
+            }
+        }
+        let old_names: HashSet<&String> = old.get_names().collect();
+        let new_names: HashSet<&String> = new.get_names().collect();
+        let mut names_added: Vec<String> = new_names
+            .difference(&old_names)
+            .map(|x| x.to_string())
+            .collect();
+        let mut names_removed: Vec<String> = old_names
+            .difference(&new_names)
+            .map(|x| x.to_string())
+            .collect();
+        added.sort_by(|a, b| a.name_id.cmp(&b.name_id));
+        modified.sort_by(|a, b| a.name_id.cmp(&b.name_id));
+        removed.sort();
+        names_added.sort();
+        names_removed.sort();
+        ArchiveDiff {
+            base_hash: hash_archive(old),
+            result_hash: hash_archive(new),
+            added,
+            modified,
+            removed,
+            names_added,
+            names_removed,
+        }
+    }
+
+    /// Returns true if there are no differences
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.modified.is_empty()
+            && self.removed.is_empty()
+            && self.names_added.is_empty()
+            && self.names_removed.is_empty()
+    }
+
+    /// Get the hash of the archive this diff applies to
+    pub fn get_base_hash(&self) -> u32 {
+        self.base_hash
+    }
+
+    /// Get the hash of the archive this diff results in
+    pub fn get_result_hash(&self) -> u32 {
+        self.result_hash
+    }
+
+    /// Get the files that were added
+    pub fn get_added(&self) -> &[FileDiff] {
+        &self.added
+    }
+
+    /// Get the files that were modified
+    pub fn get_modified(&self) -> &[FileDiff] {
+        &self.modified
+    }
+
+    /// Get the names of files that were removed
+    pub fn get_removed(&self) -> &[String] {
+        &self.removed
+    }
+
+    /// Get the names that were added to the name table
+    pub fn get_names_added(&self) -> &[String] {
+        &self.names_added
+    }
+
+    /// Get the names that were removed from the name table
+    pub fn get_names_removed(&self) -> &[String] {
+        &self.names_removed
+    }
+
+    /// Apply this diff to the given archive.
+    /// Fails without changing the archive if it is not the archive this diff was made from.
+    pub fn apply(&self, archive: &mut ChumArchive) -> Result<(), ChumError> {
+        let found = hash_archive(archive);
+        if found != self.base_hash {
+            return Err(PatchError::BaseMismatch {
+                expected: self.base_hash,
+                found,
+            }
+            .into());
+        }
+        let savepoint = archive.create_savepoint();
+        let result = self.apply_unchecked(archive).and_then(|_| {
+            let found = hash_archive(archive);
+            if found != self.result_hash {
+                Err(PatchError::ResultMismatch {
+                    expected: self.result_hash,
+                    found,
+                }
+                .into())
+            } else {
+                Ok(())
+            }
+        });
+        if result.is_err() {
+            archive.restore_savepoint(savepoint);
+        }
+        result
+    }
+
+    /// Apply this diff without checking hashes.
+    fn apply_unchecked(&self, archive: &mut ChumArchive) -> Result<(), ChumError> {
+        for name in self.removed.iter() {
+            archive.remove_file(name, false)?;
+        }
+        for file in self.added.iter() {
+            archive.add_file(ChumFile::new(
+                file.data.clone().unwrap_or_default(),
+                file.name_id.clone(),
+                file.type_id.clone(),
+                file.subtype_id.clone(),
+            ))?;
+        }
+        for file in self.modified.iter() {
+            if let Some(ref data) = file.data {
+                archive.replace_file_data(&file.name_id, data.clone())?;
+            }
+            let old = archive.get_file_from_name(&file.name_id).unwrap();
+            if old.get_type_id() != file.type_id || old.get_subtype_id() != file.subtype_id {
+                archive.retype_file(&file.name_id, &file.type_id, &file.subtype_id, false)?;
+            }
+        }
+        for name in self.names_added.iter() {
+            archive.add_name(name)?;
+        }
+        for name in self.names_removed.iter() {
+            archive.remove_name(name);
+        }
+        Ok(())
+    }
+
+    /// Write this diff as a patch file.
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(PATCH_MAGIC)?;
+        writer.write_u32::<LittleEndian>(PATCH_VERSION)?;
+        writer.write_u32::<LittleEndian>(self.base_hash)?;
+        writer.write_u32::<LittleEndian>(self.result_hash)?;
+        for list in [&self.removed, &self.names_added, &self.names_removed].iter() {
+            writer.write_u32::<LittleEndian>(list.len() as u32)?;
+            for name in list.iter() {
+                write_bytes(writer, name.as_bytes())?;
+            }
+        }
+        for list in [&self.added, &self.modified].iter() {
+            writer.write_u32::<LittleEndian>(list.len() as u32)?;
+            for file in list.iter() {
+                write_bytes(writer, file.name_id.as_bytes())?;
+                write_bytes(writer, file.type_id.as_bytes())?;
+                write_bytes(writer, file.subtype_id.as_bytes())?;
+                match file.data {
+                    Some(ref data) => {
+                        writer.write_u8(1)?;
+                        write_bytes(writer, data)?;
+                    }
+                    None => writer.write_u8(0)?,
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Read a patch file.
+    pub fn read_from<R: Read>(reader: &mut R) -> Result<ArchiveDiff, PatchError> {
+        let mut magic = [0; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != PATCH_MAGIC {
+            return Err(PatchError::InvalidMagic);
+        }
+        let version = reader.read_u32::<LittleEndian>()?;
+        if version != PATCH_VERSION {
+            return Err(PatchError::UnsupportedVersion(version));
+        }
+        let base_hash = reader.read_u32::<LittleEndian>()?;
+        let result_hash = reader.read_u32::<LittleEndian>()?;
+        let mut string_lists = Vec::new();
+        for _ in 0..3 {
+            let num = reader.read_u32::<LittleEndian>()?;
+            let mut list = Vec::new();
+            for _ in 0..num {
+                list.push(read_string(reader)?);
+            }
+            string_lists.push(list);
+        }
+        let mut file_lists = Vec::new();
+        for _ in 0..2 {
+            let num = reader.read_u32::<LittleEndian>()?;
+            let mut list = Vec::new();
+            for _ in 0..num {
+                let name_id = read_string(reader)?;
+                let type_id = read_string(reader)?;
+                let subtype_id = read_string(reader)?;
+                let data = match reader.read_u8()? {
+                    0 => None,
+                    _ => Some(read_bytes(reader)?),
+                };
+                list.push(FileDiff {
+                    name_id,
+                    type_id,
+                    subtype_id,
+                    data,
+                });
+            }
+            file_lists.push(list);
+        }
+        let modified = file_lists.pop().unwrap();
+        let added = file_lists.pop().unwrap();
+        let names_removed = string_lists.pop().unwrap();
+        let names_added = string_lists.pop().unwrap();
+        let removed = string_lists.pop().unwrap();
+        Ok(ArchiveDiff {
+            base_hash,
+            result_hash,
+            added,
+            modified,
+            removed,
+            names_added,
+            names_removed,
+        })
+    }
+}
+
+/// Write a length-prefixed byte string
+fn write_bytes<W: Write>(writer: &mut W, data: &[u8]) -> io::Result<()> {
+    writer.write_u32::<LittleEndian>(data.len() as u32)?;
+    writer.write_all(data)
+}
+
+/// Read a length-prefixed byte string
+fn read_bytes<R: Read>(reader: &mut R) -> io::Result<Vec<u8>> {
+    let len = reader.read_u32::<LittleEndian>()? as u64;
+    let mut data = Vec::new();
+    // Avoid allocating a huge buffer for a corrupt length
+    reader.take(len).read_to_end(&mut data)?;
+    if data.len() as u64 != len {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(data)
+}
+
+/// Read a length-prefixed UTF-8 string
+fn read_string<R: Read>(reader: &mut R) -> Result<String, PatchError> {
+    String::from_utf8(read_bytes(reader)?).map_err(|_| PatchError::InvalidString)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::archive::diff::*;
+    use crate::archive::fixture::ArchiveBuilder;
+    use crate::format::TotemFormat;
+
+    #[test]
+    fn diff_round_trip() {
+        let mut old = ArchiveBuilder::new(TotemFormat::NGC)
+            .add("KEEP", "TXT", b"same".to_vec())
+            .add("CHANGE", "TXT", b"old".to_vec())
+            .add("RETYPE", "TXT", b"same".to_vec())
+            .add("REMOVE", "BITMAP", b"gone".to_vec())
+            .name("UNUSED")
+            .build();
+        let new = ArchiveBuilder::new(TotemFormat::NGC)
+            .add("KEEP", "TXT", b"same".to_vec())
+            .add("CHANGE", "TXT", b"new".to_vec())
+            .add("RETYPE", "USERDEFINE", b"same".to_vec())
+            .add("ADD", "TXT", b"added".to_vec())
+            .name("EXTRA")
+            .build();
+        let diff = ArchiveDiff::new(&old, &new);
+        assert_eq!(diff.get_removed(), &["REMOVE".to_string()]);
+        assert_eq!(diff.get_added().len(), 1);
+        assert_eq!(diff.get_modified().len(), 2);
+        assert_eq!(diff.get_modified()[1].get_data(), None);
+        assert_eq!(
+            diff.get_names_removed(),
+            &[
+                "BITMAP".to_string(),
+                "REMOVE".to_string(),
+                "UNUSED".to_string()
+            ]
+        );
+
+        let mut patch = Vec::new();
+        diff.write_to(&mut patch).unwrap();
+        let read = ArchiveDiff::read_from(&mut patch.as_slice()).unwrap();
+        assert_eq!(read, diff);
+
+        read.apply(&mut old).unwrap();
+        assert_eq!(hash_archive(&old), hash_archive(&new));
+        assert!(ArchiveDiff::new(&old, &new).is_empty());
+        // The patch can not be applied twice
+        match read.apply(&mut old) {
+            Err(ChumError::Patch(PatchError::BaseMismatch { .. })) => {}
+            _ => panic!("Expected a base mismatch"),
+        }
+    }
+}
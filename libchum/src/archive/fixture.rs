@@ -0,0 +1,47 @@
+//! Archives built from a list of files, for use in tests.
+
+use crate::archive::{ChumArchive, ChumFile, TotemHeader};
+use crate::binary::ChumBinary;
+use crate::format::TotemFormat;
+
+/// Builds an archive one file at a time.
+/// Every file's subtype is its name, as it is for most files in the game's archives.
+pub struct ArchiveBuilder {
+    archive: ChumArchive,
+}
+
+impl ArchiveBuilder {
+    /// Create a builder for an empty archive with the given format
+    pub fn new(fmt: TotemFormat) -> ArchiveBuilder {
+        ArchiveBuilder {
+            archive: ChumArchive::new(TotemHeader::new(b"test"), fmt),
+        }
+    }
+
+    /// Add a file with the given data
+    pub fn add(mut self, name: &str, type_id: &str, data: Vec<u8>) -> ArchiveBuilder {
+        let file = ChumFile::new(data, name.to_owned(), type_id.to_owned(), name.to_owned());
+        self.archive.add_file(file).unwrap();
+        self
+    }
+
+    /// Add a file whose data is the given value, written in the archive's format
+    pub fn add_binary<T: ChumBinary>(self, name: &str, type_id: &str, value: &T) -> ArchiveBuilder {
+        let mut data = Vec::new();
+        value
+            .write_to(&mut data, self.archive.get_format())
+            .unwrap();
+        self.add(name, type_id, data)
+    }
+
+    /// Add a name that no file has, such as the name of a bone
+    pub fn name(mut self, name: &str) -> ArchiveBuilder {
+        self.archive.add_name(name).unwrap();
+        self
+    }
+
+    /// Get the built archive
+    pub fn build(self) -> ChumArchive {
+        self.archive
+    }
+}
@@ -0,0 +1,325 @@
+use crate::archive::{ChumArchive, ChumFile};
+use crate::binary::ChumBinary;
+use crate::error;
+use crate::format::TotemFormat;
+use crate::reader::{self, skin, spline, surface, ReadStructureError};
+use std::collections::{BTreeSet, HashMap};
+
+/// A reference from one file to another.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FileReference {
+    /// ID of the file that contains the reference
+    pub from: i32,
+    /// ID that is referenced; it may not be a file in the archive
+    pub to: i32,
+    /// Path to the reference within the file's structure, e.g. `skin_ids[2]`
+    pub path: String,
+    /// The type of file that the reference should point to
+    pub type_id: Option<String>,
+}
+
+type References = Vec<(String, i32, Option<String>)>;
+
+/// Read every reference in the given file, along with the IDs of names that it uses
+/// without referring to files.
+/// Skins, splines and surfaces do not have a structure, so their references are read directly.
+fn read_references(
+    file: &ChumFile,
+    fmt: TotemFormat,
+) -> Result<(References, Vec<i32>), ReadStructureError> {
+    let mut references = Vec::new();
+    let mut names = Vec::new();
+    match file.get_type_id() {
+        "SKIN" => {
+            let skin = skin::Skin::read_from(&mut file.get_data(), fmt)?;
+            // Vertex groups refer to bones by name, not to files
+            names.extend(skin.vertex_groups.iter().map(|x| x.group_id));
+            for (i, id) in skin.meshes.iter().enumerate() {
+                references.push((format!("meshes[{}]", i), *id, Some("MESH".to_string())));
+            }
+            if let Some(ref anims) = skin.anims {
+                for (i, entry) in anims.entries.iter().enumerate() {
+                    references.push((
+                        format!("anims.entries[{}].anim_id", i),
+                        entry.anim_id,
+                        Some("ANIMATION".to_string()),
+                    ));
+                }
+            }
+        }
+        "SPLINE" => {
+            // Splines have no references, but are still checked for being readable
+            spline::Spline::read_from(&mut file.get_data(), fmt)?;
+        }
+        "SURFACE" => {
+            let surface = surface::SurfaceObject::read_data(file.get_data(), fmt)
+                .map_err(error::UnpackError::from)?;
+            for (i, surf) in surface.surfaces.iter().enumerate() {
+                references.push((
+                    format!("surfaces[{}].material_id", i),
+                    surf.material_id,
+                    Some("MATERIAL".to_string()),
+                ));
+            }
+        }
+        type_id => {
+            let structure = reader::read_structure(type_id, file.get_data(), fmt)?;
+            references.extend(
+                structure
+                    .get_references()
+                    .into_iter()
+                    .map(|x| (x.path, x.id, x.type_id.map(|t| t.to_string()))),
+            );
+        }
+    }
+    Ok((references, names))
+}
+
+/// The references between every file of an archive.
+/// References are found by reading each file's structure;
+/// files whose structure can not be read have no references.
+pub struct ReferenceGraph {
+    references: Vec<FileReference>,
+    outgoing: HashMap<i32, Vec<usize>>,
+    incoming: HashMap<i32, Vec<usize>>,
+    files: BTreeSet<i32>,
+    names: HashMap<i32, Vec<i32>>,
+    unreadable: Vec<(i32, ReadStructureError)>,
+}
+
+impl ReferenceGraph {
+    /// Build the reference graph of the given archive.
+    /// References to ID 0 are treated as empty, and are skipped.
+    pub fn new(archive: &ChumArchive) -> ReferenceGraph {
+        let mut graph = ReferenceGraph {
+            references: Vec::new(),
+            outgoing: HashMap::new(),
+            incoming: HashMap::new(),
+            files: BTreeSet::new(),
+            names: HashMap::new(),
+            unreadable: Vec::new(),
+        };
+        let mut files: Vec<(&i32, &ChumFile)> = archive.get_files_hash().collect();
+        files.sort_by_key(|(id, _)| **id);
+        for (id, file) in files {
+            graph.files.insert(*id);
+            let (references, names) = match read_references(file, archive.get_format()) {
+                Ok(result) => result,
+                Err(ReadStructureError::UnsupportedType(_)) => continue,
+                Err(e) => {
+                    graph.unreadable.push((*id, e));
+                    continue;
+                }
+            };
+            if !names.is_empty() {
+                graph.names.insert(*id, names);
+            }
+            for (path, to, type_id) in references {
+                if to == 0 {
+                    continue;
+                }
+                let index = graph.references.len();
+                graph.outgoing.entry(*id).or_default().push(index);
+                graph.incoming.entry(to).or_default().push(index);
+                graph.references.push(FileReference {
+                    from: *id,
+                    to,
+                    path,
+                    type_id,
+                });
+            }
+        }
+        graph
+    }
+
+    /// Get every reference in the archive, sorted by the ID of the file that contains it
+    pub fn get_references(&self) -> &[FileReference] {
+        &self.references
+    }
+
+    /// Get the files whose structure could not be read, along with the reason why
+    pub fn get_unreadable(&self) -> &[(i32, ReadStructureError)] {
+        &self.unreadable
+    }
+
+    /// Get the references that the given file contains
+    pub fn get_dependencies(&self, id: i32) -> impl Iterator<Item = &FileReference> {
+        self.outgoing
+            .get(&id)
+            .into_iter()
+            .flatten()
+            .map(move |i| &self.references[*i])
+    }
+
+    /// Get the IDs of names that the given file uses without referring to files,
+    /// such as the names of a skin's bones
+    pub fn get_names(&self, id: i32) -> &[i32] {
+        self.names.get(&id).map(|x| x.as_slice()).unwrap_or(&[])
+    }
+
+    /// Get the references to the given ID from other files
+    pub fn get_dependents(&self, id: i32) -> impl Iterator<Item = &FileReference> {
+        self.incoming
+            .get(&id)
+            .into_iter()
+            .flatten()
+            .map(move |i| &self.references[*i])
+    }
+
+    /// Get every ID that the given files depend on, directly or indirectly,
+    /// including the given files themselves.
+    /// Only references for which `follow` returns true are followed.
+    pub fn get_transitive_dependencies<F>(&self, roots: &[i32], follow: F) -> BTreeSet<i32>
+    where
+        F: Fn(&FileReference) -> bool,
+    {
+        self.walk(roots, |id| self.get_dependencies(id), |x| x.to, follow)
+    }
+
+    /// Get every file that depends on the given IDs, directly or indirectly,
+    /// including the given IDs themselves.
+    pub fn get_transitive_dependents(&self, roots: &[i32]) -> BTreeSet<i32> {
+        self.walk(roots, |id| self.get_dependents(id), |x| x.from, |_| true)
+    }
+
+    fn walk<'a, E, I, N, F>(&'a self, roots: &[i32], edges: E, next: N, follow: F) -> BTreeSet<i32>
+    where
+        E: Fn(i32) -> I,
+        I: Iterator<Item = &'a FileReference>,
+        N: Fn(&FileReference) -> i32,
+        F: Fn(&FileReference) -> bool,
+    {
+        let mut visited: BTreeSet<i32> = roots.iter().copied().collect();
+        let mut stack: Vec<i32> = roots.to_vec();
+        while let Some(id) = stack.pop() {
+            for reference in edges(id) {
+                let other = next(reference);
+                if follow(reference) && visited.insert(other) {
+                    stack.push(other);
+                }
+            }
+        }
+        visited
+    }
+
+    /// Get every file that is not referenced by any other file, sorted by ID.
+    /// Files at the root of a scene, and files that the game loads by name,
+    /// are expected to be orphans.
+    pub fn find_orphans(&self) -> Vec<i32> {
+        self.files
+            .iter()
+            .copied()
+            .filter(|id| self.get_dependents(*id).all(|x| x.from == *id))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::archive::fixture::ArchiveBuilder;
+    use crate::archive::graph::*;
+    use crate::reader::material::Material;
+    use crate::reader::warp::Warp;
+    use crate::util;
+
+    #[test]
+    fn reference_graph() {
+        let material = Material {
+            texture: util::hash_name_i32("TEX.BITMAP"),
+            ..Default::default()
+        };
+        let warp = Warp {
+            material_ids: [util::hash_name_i32("A.MATERIAL"); 6],
+            ..Default::default()
+        };
+        let archive = ArchiveBuilder::new(TotemFormat::NGC)
+            .add_binary("A.MATERIAL", "MATERIAL", &material)
+            .add_binary("B.WARP", "WARP", &warp)
+            .add("BROKEN.MATERIAL", "MATERIAL", vec![0; 4])
+            .add("README.TXT", "TXT", vec![0; 4])
+            .build();
+
+        let graph = ReferenceGraph::new(&archive);
+        let a = util::hash_name_i32("A.MATERIAL");
+        let b = util::hash_name_i32("B.WARP");
+        let tex = util::hash_name_i32("TEX.BITMAP");
+        let deps: Vec<&FileReference> = graph.get_dependencies(a).collect();
+        assert_eq!(deps.len(), 1);
+        assert_eq!(deps[0].to, tex);
+        assert_eq!(deps[0].path, "texture");
+        assert_eq!(deps[0].type_id.as_deref(), Some("BITMAP"));
+        let rdeps: Vec<&str> = graph.get_dependents(a).map(|x| x.path.as_str()).collect();
+        assert_eq!(rdeps.len(), 6);
+        assert_eq!(rdeps[5], "material_ids[5]");
+        assert_eq!(
+            graph.get_transitive_dependencies(&[b], |_| true),
+            vec![a, b, tex].into_iter().collect()
+        );
+        assert_eq!(
+            graph.get_transitive_dependents(&[tex]),
+            vec![a, b, tex].into_iter().collect()
+        );
+        assert_eq!(graph.get_unreadable().len(), 1);
+        assert_eq!(
+            graph.get_unreadable()[0].0,
+            util::hash_name_i32("BROKEN.MATERIAL")
+        );
+        let mut orphans = vec![
+            b,
+            util::hash_name_i32("BROKEN.MATERIAL"),
+            util::hash_name_i32("README.TXT"),
+        ];
+        orphans.sort();
+        assert_eq!(graph.find_orphans(), orphans);
+    }
+
+    #[test]
+    fn direct_references() {
+        let skin = skin::Skin {
+            meshes: vec![util::hash_name_i32("A.MESH")],
+            vertex_groups: vec![skin::VertexGroup {
+                group_id: util::hash_name_i32("BONE"),
+                sections: Vec::new(),
+            }],
+            ..Default::default()
+        };
+        // A surface with no vertices, curves or normals, and a single surface
+        let mut data = Vec::new();
+        crate::common::THeader::default()
+            .write_to(&mut data, TotemFormat::NGC)
+            .unwrap();
+        data.extend_from_slice(&[0; 16]);
+        data.extend_from_slice(&1u32.to_be_bytes());
+        data.extend_from_slice(&[0; 32 + 48 + 8 + 8 + 4 + 36]);
+        data.extend_from_slice(&util::hash_name_i32("A.MATERIAL").to_be_bytes());
+        data.extend_from_slice(&[0; 8]);
+        let archive = ArchiveBuilder::new(TotemFormat::NGC)
+            .add_binary("A.SKIN", "SKIN", &skin)
+            .add("A.SURFACE", "SURFACE", data)
+            .add("BROKEN.SPLINE", "SPLINE", vec![0; 4])
+            .build();
+
+        let graph = ReferenceGraph::new(&archive);
+        let skin_deps: Vec<(&str, Option<&str>)> = graph
+            .get_dependencies(util::hash_name_i32("A.SKIN"))
+            .map(|x| (x.path.as_str(), x.type_id.as_deref()))
+            .collect();
+        assert_eq!(skin_deps, vec![("meshes[0]", Some("MESH"))]);
+        assert_eq!(
+            graph.get_names(util::hash_name_i32("A.SKIN")),
+            &[util::hash_name_i32("BONE")]
+        );
+        let surface_deps: Vec<&FileReference> = graph
+            .get_dependencies(util::hash_name_i32("A.SURFACE"))
+            .collect();
+        assert_eq!(surface_deps.len(), 1);
+        assert_eq!(surface_deps[0].to, util::hash_name_i32("A.MATERIAL"));
+        assert_eq!(surface_deps[0].path, "surfaces[0].material_id");
+        assert_eq!(surface_deps[0].type_id.as_deref(), Some("MATERIAL"));
+        assert_eq!(graph.get_unreadable().len(), 1);
+        assert_eq!(
+            graph.get_unreadable()[0].0,
+            util::hash_name_i32("BROKEN.SPLINE")
+        );
+    }
+}
@@ -0,0 +1,283 @@
+use crate::archive::data::{self, TotemArchive, TotemChunk, TotemFile, TotemHeader};
+use crate::format::TotemFormat;
+use std::collections::HashMap;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::ops::Range;
+
+/// Offset of the first chunk in a .DGC archive; everything before it is header data.
+pub const DATA_OFFSET: u64 = 0x800;
+
+/// Location of a single file in a .DGC archive.
+/// The file's data is not loaded; only the information stored in its header.
+#[derive(Clone, Debug)]
+pub struct TotemFileEntry {
+    chunk: usize,
+    offset: u64,
+    size: usize,
+    type_id: i32,
+    name_id: i32,
+    subtype_id: i32,
+}
+
+impl TotemFileEntry {
+    /// Get the index of the chunk that contains this file
+    pub fn get_chunk(&self) -> usize {
+        self.chunk
+    }
+
+    /// Get the offset of this file's data from the start of the stream
+    pub fn get_offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Get the type id of this file
+    pub fn get_type_id(&self) -> i32 {
+        self.type_id
+    }
+
+    /// Get the name id of this file
+    pub fn get_name_id(&self) -> i32 {
+        self.name_id
+    }
+
+    /// Get the subtype id of this file
+    pub fn get_subtype_id(&self) -> i32 {
+        self.subtype_id
+    }
+
+    /// Get the total size of this file, including its header information.
+    pub fn get_total_size(&self) -> usize {
+        self.size + 16
+    }
+
+    /// Get the size of this file's data
+    pub fn get_file_size(&self) -> usize {
+        self.size
+    }
+}
+
+/// Location of a single chunk in a .DGC archive.
+#[derive(Clone, Debug)]
+pub struct TotemChunkEntry {
+    offset: u64,
+    files: Range<usize>,
+    padding: Range<u64>,
+}
+
+impl TotemChunkEntry {
+    /// Get the offset of this chunk from the start of the stream
+    pub fn get_offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Get the number of files stored within this chunk.
+    pub fn get_num_files(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Get the range of the stream that holds the padding after this chunk's files
+    pub fn get_padding_range(&self) -> Range<u64> {
+        self.padding.clone()
+    }
+}
+
+/// Index of a .DGC archive.
+/// Only the archive's header and the headers of each chunk and file are read,
+/// so building an index is cheap even for very large archives. File data can
+/// then be read on demand from the same stream.
+pub struct TotemArchiveIndex {
+    header: TotemHeader,
+    chunks: Vec<TotemChunkEntry>,
+    files: Vec<TotemFileEntry>,
+    names: HashMap<i32, usize>,
+    chunk_size: usize,
+    format: TotemFormat,
+}
+
+impl TotemArchiveIndex {
+    /// Get the format
+    pub fn get_format(&self) -> TotemFormat {
+        self.format
+    }
+
+    /// Get the header
+    pub fn get_header(&self) -> &TotemHeader {
+        &self.header
+    }
+
+    /// Get the chunk size
+    pub fn get_chunk_size(&self) -> usize {
+        self.chunk_size
+    }
+
+    /// Get the number of files in this archive
+    pub fn get_num_files(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Iterate over all files in this archive.
+    pub fn iter_files(&self) -> impl Iterator<Item = &TotemFileEntry> {
+        self.files.iter()
+    }
+
+    /// Iterate over all chunks in this archive.
+    pub fn iter_chunks(&self) -> impl Iterator<Item = &TotemChunkEntry> {
+        self.chunks.iter()
+    }
+
+    /// Iterate over all files in the given chunk.
+    pub fn iter_chunk_files(
+        &self,
+        chunk: &TotemChunkEntry,
+    ) -> impl Iterator<Item = &TotemFileEntry> {
+        self.files[chunk.files.clone()].iter()
+    }
+
+    /// Get a file entry from its name ID
+    pub fn get_file_from_hash(&self, name_id: i32) -> Option<&TotemFileEntry> {
+        self.names.get(&name_id).map(|i| &self.files[*i])
+    }
+
+    /// Read the data of the given file from the stream this index was built from.
+    pub fn read_file_data<R: Read + Seek>(
+        &self,
+        reader: &mut R,
+        entry: &TotemFileEntry,
+    ) -> io::Result<Vec<u8>> {
+        reader.seek(SeekFrom::Start(entry.offset))?;
+        let mut data = vec![0; entry.size];
+        reader.read_exact(&mut data)?;
+        Ok(data)
+    }
+
+    /// Read the given file from the stream this index was built from.
+    pub fn read_file<R: Read + Seek>(
+        &self,
+        reader: &mut R,
+        entry: &TotemFileEntry,
+    ) -> io::Result<TotemFile> {
+        Ok(TotemFile::new(
+            self.read_file_data(reader, entry)?,
+            entry.type_id,
+            entry.name_id,
+            entry.subtype_id,
+        ))
+    }
+
+    /// Load every file in this index, keeping their chunk assignment.
+    pub fn read_archive<R: Read + Seek>(&self, reader: &mut R) -> io::Result<TotemArchive> {
+        let mut chunks = Vec::with_capacity(self.chunks.len());
+        for chunk in self.chunks.iter() {
+            let mut files = Vec::with_capacity(chunk.files.len());
+            for entry in self.iter_chunk_files(chunk) {
+                files.push(self.read_file(reader, entry)?);
+            }
+            reader.seek(SeekFrom::Start(chunk.padding.start))?;
+            let mut padding = vec![0; (chunk.padding.end - chunk.padding.start) as usize];
+            reader.read_exact(&mut padding)?;
+            chunks.push(TotemChunk::from_files_padded(files, padding));
+        }
+        Ok(TotemArchive::from_chunks(
+            self.header.clone(),
+            chunks,
+            self.chunk_size,
+            self.format,
+        ))
+    }
+
+    /// Index an archive from a reader.
+    /// Only header data is read; file data is skipped over.
+    pub fn read_from<R: Read + Seek>(
+        reader: &mut R,
+        fmt: TotemFormat,
+    ) -> io::Result<TotemArchiveIndex> {
+        reader.seek(SeekFrom::Start(0))?;
+        let (header, chunk_size) = data::read_header(reader, fmt)?;
+        let stream_size = reader.seek(SeekFrom::End(0))?;
+        let mut chunks = Vec::new();
+        let mut files = Vec::new();
+        let mut names = HashMap::new();
+        let mut chunk_offset = DATA_OFFSET;
+        while chunk_offset < stream_size {
+            let chunk_end = (chunk_offset + chunk_size as u64).min(stream_size);
+            reader.seek(SeekFrom::Start(chunk_offset))?;
+            let num_files = fmt.read_u32(reader)?;
+            let first_file = files.len();
+            let mut offset = chunk_offset + 4;
+            for _ in 0..num_files {
+                let file_size = fmt.read_u32(reader)? as u64;
+                let type_id = fmt.read_i32(reader)?;
+                let name_id = fmt.read_i32(reader)?;
+                let subtype_id = fmt.read_i32(reader)?;
+                if file_size < 16 || offset + file_size > chunk_end {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!(
+                            "File {} in chunk {} has invalid size {}",
+                            name_id,
+                            chunks.len(),
+                            file_size
+                        ),
+                    ));
+                }
+                names.insert(name_id, files.len());
+                files.push(TotemFileEntry {
+                    chunk: chunks.len(),
+                    offset: offset + 16,
+                    size: file_size as usize - 16,
+                    type_id,
+                    name_id,
+                    subtype_id,
+                });
+                offset += file_size;
+                reader.seek(SeekFrom::Start(offset))?;
+            }
+            chunks.push(TotemChunkEntry {
+                offset: chunk_offset,
+                files: first_file..files.len(),
+                padding: offset..chunk_end,
+            });
+            chunk_offset += chunk_size as u64;
+        }
+        Ok(TotemArchiveIndex {
+            header,
+            chunks,
+            files,
+            names,
+            chunk_size,
+            format: fmt,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::archive::data::TotemArchive;
+    use crate::archive::index::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn index_matches_archive() {
+        let files = vec![
+            TotemFile::new(vec![1; 3000], 1, 10, 100),
+            TotemFile::new(vec![2; 100], 2, 20, 200),
+            TotemFile::new(vec![3; 1500], 3, 30, 300),
+        ];
+        let dgc = TotemArchive::new_from_files(TotemHeader::new(b"test"), files, TotemFormat::NGC);
+        let mut data = Vec::new();
+        dgc.write_to(&mut data).unwrap();
+        let mut cursor = Cursor::new(data);
+        let index = TotemArchiveIndex::read_from(&mut cursor, TotemFormat::NGC).unwrap();
+        assert_eq!(index.get_chunk_size(), dgc.get_chunk_size());
+        assert_eq!(index.iter_chunks().count(), dgc.iter_chunks().count());
+        for (entry, file) in index.iter_files().zip(dgc.iter_files()) {
+            assert_eq!(entry.get_name_id(), file.get_name_id());
+            assert_eq!(entry.get_file_size(), file.get_file_size());
+            let loaded = index.read_file_data(&mut cursor, entry).unwrap();
+            assert_eq!(loaded.as_slice(), file.get_data());
+        }
+        let entry = index.get_file_from_hash(20).unwrap();
+        assert_eq!(entry.get_type_id(), 2);
+        assert_eq!(entry.get_subtype_id(), 200);
+    }
+}
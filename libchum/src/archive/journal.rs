@@ -0,0 +1,104 @@
+use crate::archive::ChumFile;
+
+/// A single change to a ChumArchive's files or names.
+/// Applying a change swaps the stored value with the archive's current value,
+/// so applying the same change again reverts it.
+pub(crate) enum Change {
+    /// The file stored under the given hash; None if there is no file
+    File { id: i32, file: Option<ChumFile> },
+    /// The name stored under the given hash; None if there is no name
+    Name { id: i32, name: Option<String> },
+    /// The type and subtype of the file stored under the given hash
+    Ids {
+        id: i32,
+        type_id: String,
+        subtype_id: String,
+    },
+    /// Move the file stored under `from` to `to`, giving it the name `name_id`
+    Move { from: i32, to: i32, name_id: String },
+}
+
+/// A group of changes made by a single edit.
+pub(crate) struct Edit {
+    pub id: u64,
+    pub description: String,
+    pub changes: Vec<Change>,
+}
+
+/// Identifies a state of an archive that can be returned to with
+/// `ChumArchive::restore_savepoint`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Savepoint(pub u64);
+
+/// Undo and redo history for a ChumArchive.
+pub(crate) struct EditJournal {
+    pub undo: Vec<Edit>,
+    pub redo: Vec<Edit>,
+    pending: Vec<Change>,
+    base: u64,  // id of the state before the first edit in `undo`
+    saved: u64, // id of the state that was last saved
+    next_id: u64,
+}
+
+impl Default for EditJournal {
+    fn default() -> EditJournal {
+        EditJournal {
+            undo: Vec::new(),
+            redo: Vec::new(),
+            pending: Vec::new(),
+            base: 0,
+            saved: 0,
+            next_id: 1,
+        }
+    }
+}
+
+impl EditJournal {
+    /// Record a change for the edit that is currently being made.
+    pub fn record(&mut self, change: Change) {
+        self.pending.push(change);
+    }
+
+    /// Finish the edit that is currently being made.
+    /// Does nothing if no changes were recorded.
+    pub fn commit(&mut self, description: String) {
+        if self.pending.is_empty() {
+            return;
+        }
+        self.undo.push(Edit {
+            id: self.next_id,
+            description,
+            changes: std::mem::replace(&mut self.pending, Vec::new()),
+        });
+        self.next_id += 1;
+        self.redo.clear();
+    }
+
+    /// Get the id of the current state
+    pub fn current(&self) -> u64 {
+        self.undo.last().map(|edit| edit.id).unwrap_or(self.base)
+    }
+
+    /// Returns true if the state with the given id can be reached by undoing edits
+    pub fn is_undo_state(&self, id: u64) -> bool {
+        id == self.base || self.undo.iter().any(|edit| edit.id == id)
+    }
+
+    /// Mark the current state as saved
+    pub fn mark_saved(&mut self) {
+        self.saved = self.current();
+    }
+
+    /// Returns true if the current state is not the saved state
+    pub fn is_dirty(&self) -> bool {
+        self.current() != self.saved
+    }
+
+    /// Forget all history; the current state becomes the new base.
+    pub fn clear(&mut self) {
+        let current = self.current();
+        self.undo.clear();
+        self.redo.clear();
+        self.base = current;
+    }
+}
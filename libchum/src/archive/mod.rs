@@ -1,12 +1,39 @@
+pub mod convert;
 pub mod data;
+pub mod diff;
+#[cfg(test)]
+pub(crate) mod fixture;
+pub mod graph;
+pub mod index;
+pub mod journal;
 pub mod nametable;
+pub mod packer;
+pub mod scan;
+pub mod subset;
+pub mod validate;
 
+pub use convert::{ConvertError, ConvertReport};
 pub use data::{TotemArchive, TotemFile, TotemHeader};
+pub use diff::ArchiveDiff;
+pub use graph::{FileReference, ReferenceGraph};
+pub use index::{TotemArchiveIndex, TotemFileEntry};
+pub use journal::Savepoint;
+pub use nametable::NameOrder;
+pub use packer::{ChunkPacker, ChunkSize};
+pub use scan::CatalogEntry;
+pub use subset::SubsetReport;
+pub use validate::{Diagnostic, Severity};
 
 use crate::format;
+use crate::names::NameDictionary;
 use crate::util;
+use memmap2::Mmap;
+use std::cmp;
 use std::collections::{HashMap, HashSet};
-use std::io::{self, Read, Write};
+use std::fs::File;
+use std::io::{self, Read, Seek, Write};
+use std::ops::Range;
+use std::sync::Arc;
 use thiserror::Error;
 
 /// Complete Chum archive.
@@ -14,8 +41,13 @@ use thiserror::Error;
 pub struct ChumArchive {
     header: data::TotemHeader,
     names: HashMap<i32, String>, // a separate HashMap used to check for name collisions
+    name_order: Vec<i32>,        // order of names in the NGC archive this was read from
     files: HashMap<i32, ChumFile>,
     format: format::TotemFormat,
+    chunk_size: Option<usize>, // chunk size of the DGC archive this was read from
+    chunk_padding: Vec<ChumFileData>, // non-zero padding of each chunk that was read
+    preserve_layout: bool,
+    journal: journal::EditJournal,
 }
 
 impl Default for ChumArchive {
@@ -23,39 +55,127 @@ impl Default for ChumArchive {
         ChumArchive {
             header: data::TotemHeader::new(b"Legally distinct legal notice."),
             names: HashMap::new(),
+            name_order: Vec::new(),
             files: HashMap::new(),
             format: format::TotemFormat::NGC,
+            chunk_size: None,
+            chunk_padding: Vec::new(),
+            preserve_layout: false,
+            journal: journal::EditJournal::default(),
         }
     }
 }
 
+/// Shared, read-only data that files in a ChumArchive can borrow from.
+/// Usually a memory-mapped DGC archive; see `map_file`.
+pub type SharedData = Arc<dyn AsRef<[u8]> + Send + Sync>;
+
+/// Memory-map the given file so that it can be used as SharedData.
+///
+/// # Safety
+/// The file must not be modified or truncated while the mapping is alive,
+/// including by any ChumArchive whose files borrow from it. Call
+/// `ChumArchive::detach` before overwriting the file that an archive was
+/// loaded from.
+pub unsafe fn map_file(file: &File) -> io::Result<SharedData> {
+    Ok(Arc::new(Mmap::map(file)?))
+}
+
+/// Storage for a ChumFile's data.
+#[derive(Clone)]
+enum ChumFileData {
+    /// The file owns its data
+    Owned(Vec<u8>),
+    /// The file's data is a slice of some shared data
+    Borrowed(SharedData, Range<usize>),
+}
+
+impl ChumFileData {
+    fn as_slice(&self) -> &[u8] {
+        match self {
+            ChumFileData::Owned(ref data) => data,
+            ChumFileData::Borrowed(ref source, ref range) => &(**source).as_ref()[range.clone()],
+        }
+    }
+}
+
+/// Location of a file in the DGC archive that it was read from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct FileLocation {
+    chunk: usize,
+    index: usize,
+}
+
 /// A ChumFile that is returned by the Chum Archive
+#[derive(Clone)]
 pub struct ChumFile {
-    data: Vec<u8>,
+    data: ChumFileData,
     type_id: String,
     name_id: String,
     subtype_id: String,
+    location: Option<FileLocation>,
+    modified: bool,
 }
 
 impl ChumFile {
     /// Create a new ChumFile
     pub fn new(data: Vec<u8>, nameid: String, typeid: String, subtypeid: String) -> ChumFile {
         ChumFile {
-            data,
+            data: ChumFileData::Owned(data),
+            type_id: typeid,
+            name_id: nameid,
+            subtype_id: subtypeid,
+            location: None,
+            modified: false,
+        }
+    }
+
+    /// Create a new ChumFile that borrows its data from the given range of `source`.
+    /// Panics if the range is out of bounds.
+    pub fn new_borrowed(
+        source: SharedData,
+        range: Range<usize>,
+        nameid: String,
+        typeid: String,
+        subtypeid: String,
+    ) -> ChumFile {
+        assert!(range.start <= range.end && range.end <= (*source).as_ref().len());
+        ChumFile {
+            data: ChumFileData::Borrowed(source, range),
             type_id: typeid,
             name_id: nameid,
             subtype_id: subtypeid,
+            location: None,
+            modified: false,
         }
     }
 
     /// Get the file's data
     pub fn get_data(&self) -> &[u8] {
-        &self.data
+        self.data.as_slice()
+    }
+
+    /// Take the file's data
+    pub fn take_data(self) -> Vec<u8> {
+        match self.data {
+            ChumFileData::Owned(data) => data,
+            ChumFileData::Borrowed(source, range) => (*source).as_ref()[range].to_vec(),
+        }
     }
 
-    /// Get the file's data as a mutable vector
-    pub fn get_data_mut(&mut self) -> &mut Vec<u8> {
-        &mut self.data
+    /// Returns true if this file's data is borrowed from shared data
+    pub fn is_borrowed(&self) -> bool {
+        match self.data {
+            ChumFileData::Owned(_) => false,
+            ChumFileData::Borrowed(_, _) => true,
+        }
+    }
+
+    /// Copy borrowed data so that this file owns its data
+    pub fn detach(&mut self) {
+        if let ChumFileData::Borrowed(ref source, ref range) = self.data {
+            self.data = ChumFileData::Owned((**source).as_ref()[range.clone()].to_vec());
+        }
     }
 
     /// Get the file's name
@@ -73,9 +193,19 @@ impl ChumFile {
         &self.subtype_id
     }
 
-    /// Replace this file's data
-    pub fn replace_data(&mut self, data: Vec<u8>) {
-        self.data = data;
+    /// Returns true if this file's data may have changed since it was read
+    pub fn is_modified(&self) -> bool {
+        self.modified
+    }
+
+    /// Convert this file into a TotemFile
+    fn to_totem_file(&self) -> data::TotemFile {
+        data::TotemFile::new(
+            self.get_data().to_vec(),
+            util::hash_name_i32(self.get_type_id()),
+            util::hash_name_i32(self.get_name_id()),
+            util::hash_name_i32(self.get_subtype_id()),
+        )
     }
 }
 
@@ -85,6 +215,9 @@ pub enum ChumError {
     /// A name does not exist in the name table for this file
     #[error("Could not find ID {id} in NGC archive")]
     NameMissingError { id: i32 },
+    /// There is no file with the given name
+    #[error("Could not find file {name}")]
+    FileMissingError { name: String },
     /// Two strings share the same CRC32 hash
     #[error("Names {existing_name} and {new_name} have colliding ID {id}")]
     NameCollisionError {
@@ -93,6 +226,12 @@ pub enum ChumError {
         new_name: String,
     },
     #[error(transparent)]
+    Pack(#[from] packer::PackError),
+    #[error(transparent)]
+    Patch(#[from] diff::PatchError),
+    #[error(transparent)]
+    NameTableError(#[from] nametable::NameTableError),
+    #[error(transparent)]
     Io(#[from] io::Error),
     #[error(transparent)]
     ParseIntError(#[from] std::num::ParseIntError),
@@ -105,7 +244,12 @@ impl ChumArchive {
             header,
             files: HashMap::new(),
             names: HashMap::new(),
+            name_order: Vec::new(),
             format: fmt,
+            chunk_size: None,
+            chunk_padding: Vec::new(),
+            preserve_layout: false,
+            journal: journal::EditJournal::default(),
         }
     }
 
@@ -171,11 +315,16 @@ impl ChumArchive {
         let hashsubtype = self.check_can_add_id(file.get_subtype_id())?;
         let typestr = file.get_type_id().to_string();
         let subtypestr = file.get_subtype_id().to_string();
+        let hash = util::hash_name_i32(file.get_name_id());
+        let description = format!("Add {}", file.get_name_id());
         // Add name if it doesn't already exist
         if let Some(i) = hashname {
-            self.names.insert(i, file.get_name_id().into());
+            self.set_name(i, Some(file.get_name_id().into()));
             // Add file
-            self.files.insert(i, file);
+            self.set_file(i, Some(file));
+        } else if !self.files.contains_key(&hash) {
+            // Name exists, but is not used by another file
+            self.set_file(hash, Some(file));
         } else {
             // Name must not already exist
             return Err(ChumError::NameCollisionError {
@@ -186,14 +335,313 @@ impl ChumArchive {
         }
         // add type/subtype
         if let Some(i) = hashtype {
-            self.names.insert(i, typestr);
+            self.set_name(i, Some(typestr));
+        }
+        if let Some(i) = hashsubtype {
+            self.set_name(i, Some(subtypestr));
+        }
+        self.journal.commit(description);
+        Ok(())
+    }
+
+    /// Get the hash of the file with the given name.
+    /// Returns an error if there is no such file.
+    fn find_file(&self, name: &str) -> Result<i32, ChumError> {
+        let hash = util::hash_name_i32(name);
+        match self.names.get(&hash) {
+            Some(x) if x == name && self.files.contains_key(&hash) => Ok(hash),
+            _ => Err(ChumError::FileMissingError { name: name.into() }),
+        }
+    }
+
+    /// Remove the given names if no file uses them anymore.
+    fn remove_names_if_unused(&mut self, candidates: &[String]) {
+        for name in candidates {
+            let in_use = self.files.values().any(|file| {
+                file.get_name_id() == name
+                    || file.get_type_id() == name
+                    || file.get_subtype_id() == name
+            });
+            if !in_use {
+                self.set_name(util::hash_name_i32(name), None);
+            }
+        }
+    }
+
+    /// Remove the file with the given name, returning it.
+    /// If `gc_names` is true, names that the file used and that are no longer used by any
+    /// other file are removed as well.
+    pub fn remove_file(&mut self, name: &str, gc_names: bool) -> Result<ChumFile, ChumError> {
+        let hash = self.find_file(name)?;
+        let file = self.files.remove(&hash).unwrap();
+        if gc_names {
+            self.remove_names_if_unused(&[
+                file.name_id.clone(),
+                file.type_id.clone(),
+                file.subtype_id.clone(),
+            ]);
+        }
+        // The journal keeps its own copy of the removed file
+        self.journal.record(journal::Change::File {
+            id: hash,
+            file: Some(file.clone()),
+        });
+        self.journal.commit(format!("Remove {}", name));
+        Ok(file)
+    }
+
+    /// Rename the file with the given name.
+    /// Returns an error if the new name collides with an existing name or file.
+    /// If `gc_names` is true, the old name is removed if it is no longer used.
+    pub fn rename_file(
+        &mut self,
+        name: &str,
+        new_name: &str,
+        gc_names: bool,
+    ) -> Result<(), ChumError> {
+        let hash = self.find_file(name)?;
+        if name == new_name {
+            return Ok(());
+        }
+        let new_hash = util::hash_name_i32(new_name);
+        let add_name = self.check_can_add_id(new_name)?;
+        if let Some(existing) = self.files.get(&new_hash) {
+            return Err(ChumError::NameCollisionError {
+                id: new_hash,
+                existing_name: existing.get_name_id().into(),
+                new_name: new_name.into(),
+            });
+        }
+        if add_name.is_some() {
+            self.set_name(new_hash, Some(new_name.into()));
+        }
+        self.make_change(journal::Change::Move {
+            from: hash,
+            to: new_hash,
+            name_id: new_name.into(),
+        });
+        if gc_names {
+            self.remove_names_if_unused(&[name.into()]);
+        }
+        self.journal
+            .commit(format!("Rename {} to {}", name, new_name));
+        Ok(())
+    }
+
+    /// Change the type and subtype of the file with the given name.
+    /// Returns an error if either name collides with an existing name.
+    /// If `gc_names` is true, the old type and subtype are removed if they are no longer used.
+    pub fn retype_file(
+        &mut self,
+        name: &str,
+        type_id: &str,
+        subtype_id: &str,
+        gc_names: bool,
+    ) -> Result<(), ChumError> {
+        let hash = self.find_file(name)?;
+        let hashtype = self.check_can_add_id(type_id)?;
+        let hashsubtype = self.check_can_add_id(subtype_id)?;
+        if let Some(i) = hashtype {
+            self.set_name(i, Some(type_id.into()));
         }
         if let Some(i) = hashsubtype {
-            self.names.insert(i, subtypestr);
+            self.set_name(i, Some(subtype_id.into()));
+        }
+        let mut change = journal::Change::Ids {
+            id: hash,
+            type_id: type_id.into(),
+            subtype_id: subtype_id.into(),
+        };
+        self.apply_change(&mut change);
+        if let journal::Change::Ids {
+            ref type_id,
+            ref subtype_id,
+            ..
+        } = change
+        {
+            if gc_names {
+                self.remove_names_if_unused(&[type_id.clone(), subtype_id.clone()]);
+            }
+        }
+        self.journal.record(change);
+        self.journal.commit(format!("Change type of {}", name));
+        Ok(())
+    }
+
+    /// Remove all names that are not used by any file.
+    /// Returns the number of names that were removed.
+    pub fn remove_unused_names(&mut self) -> usize {
+        let unused: Vec<String> = self
+            .find_unused_names()
+            .into_iter()
+            .map(|x| x.to_string())
+            .collect();
+        for name in unused.iter() {
+            self.set_name(util::hash_name_i32(name), None);
         }
+        self.journal.commit("Remove unused names".to_string());
+        unused.len()
+    }
+
+    /// Replace the data of the file with the given name.
+    /// The change is recorded, so that it can be undone.
+    pub fn replace_file_data(&mut self, name: &str, data: Vec<u8>) -> Result<(), ChumError> {
+        let hash = self.find_file(name)?;
+        let old = &self.files[&hash];
+        let file = ChumFile {
+            data: ChumFileData::Owned(data),
+            type_id: old.type_id.clone(),
+            name_id: old.name_id.clone(),
+            subtype_id: old.subtype_id.clone(),
+            location: old.location,
+            modified: true,
+        };
+        self.set_file(hash, Some(file));
+        self.journal.commit(format!("Modify {}", name));
         Ok(())
     }
 
+    /// Apply a change, leaving the change in a state that reverts it.
+    fn apply_change(&mut self, change: &mut journal::Change) {
+        match change {
+            journal::Change::File { id, file } => {
+                let old = match file.take() {
+                    Some(file) => self.files.insert(*id, file),
+                    None => self.files.remove(id),
+                };
+                *file = old;
+            }
+            journal::Change::Name { id, name } => {
+                let old = match name.take() {
+                    Some(name) => self.names.insert(*id, name),
+                    None => self.names.remove(id),
+                };
+                *name = old;
+            }
+            journal::Change::Ids {
+                id,
+                type_id,
+                subtype_id,
+            } => {
+                let file = self.files.get_mut(id).unwrap();
+                std::mem::swap(&mut file.type_id, type_id);
+                std::mem::swap(&mut file.subtype_id, subtype_id);
+            }
+            journal::Change::Move { from, to, name_id } => {
+                let mut file = self.files.remove(from).unwrap();
+                std::mem::swap(&mut file.name_id, name_id);
+                self.files.insert(*to, file);
+                std::mem::swap(from, to);
+            }
+        }
+    }
+
+    /// Apply a change and record it as part of the current edit.
+    fn make_change(&mut self, mut change: journal::Change) {
+        self.apply_change(&mut change);
+        self.journal.record(change);
+    }
+
+    /// Set or remove the file with the given hash, recording the change.
+    fn set_file(&mut self, id: i32, file: Option<ChumFile>) {
+        self.make_change(journal::Change::File { id, file });
+    }
+
+    /// Set or remove the name with the given hash, recording the change.
+    fn set_name(&mut self, id: i32, name: Option<String>) {
+        self.make_change(journal::Change::Name { id, name });
+    }
+
+    /// Undo the last edit.
+    /// Returns false if there is nothing to undo.
+    pub fn undo(&mut self) -> bool {
+        match self.journal.undo.pop() {
+            Some(mut edit) => {
+                for change in edit.changes.iter_mut().rev() {
+                    self.apply_change(change);
+                }
+                self.journal.redo.push(edit);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Redo the last edit that was undone.
+    /// Returns false if there is nothing to redo.
+    pub fn redo(&mut self) -> bool {
+        match self.journal.redo.pop() {
+            Some(mut edit) => {
+                for change in edit.changes.iter_mut() {
+                    self.apply_change(change);
+                }
+                self.journal.undo.push(edit);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns true if there is an edit to undo
+    pub fn can_undo(&self) -> bool {
+        !self.journal.undo.is_empty()
+    }
+
+    /// Returns true if there is an edit to redo
+    pub fn can_redo(&self) -> bool {
+        !self.journal.redo.is_empty()
+    }
+
+    /// Get a description of the edit that would be undone
+    pub fn get_undo_description(&self) -> Option<&str> {
+        self.journal.undo.last().map(|x| x.description.as_str())
+    }
+
+    /// Get a description of the edit that would be redone
+    pub fn get_redo_description(&self) -> Option<&str> {
+        self.journal.redo.last().map(|x| x.description.as_str())
+    }
+
+    /// Get a savepoint for the archive's current state
+    pub fn create_savepoint(&self) -> journal::Savepoint {
+        journal::Savepoint(self.journal.current())
+    }
+
+    /// Undo or redo edits until the archive is in the state of the given savepoint.
+    /// Returns false, without changing anything, if the savepoint is no longer in the history.
+    pub fn restore_savepoint(&mut self, savepoint: journal::Savepoint) -> bool {
+        let id = savepoint.0;
+        if self.journal.is_undo_state(id) {
+            while self.journal.current() != id {
+                self.undo();
+            }
+            true
+        } else if self.journal.redo.iter().any(|edit| edit.id == id) {
+            while self.journal.current() != id {
+                self.redo();
+            }
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Returns true if the archive has been edited since it was read or last marked as saved
+    pub fn is_dirty(&self) -> bool {
+        self.journal.is_dirty()
+    }
+
+    /// Mark the archive's current state as saved
+    pub fn mark_saved(&mut self) {
+        self.journal.mark_saved();
+    }
+
+    /// Forget all undo and redo history.
+    /// Savepoints other than the current state can no longer be restored.
+    pub fn clear_history(&mut self) {
+        self.journal.clear();
+    }
+
     /// Get this archive's Dgc header
     pub fn get_header(&self) -> &data::TotemHeader {
         &self.header
@@ -238,44 +686,133 @@ impl ChumArchive {
         }
     }
 
-    /// Get a mutable file from its hash
-    pub fn get_file_from_hash_mut(&mut self, hash: i32) -> Option<&mut ChumFile> {
-        self.files.get_mut(&hash)
+    /// Split this ChumArchive into an NgcArchive and a DgcArchive
+    pub fn split_archives(&self) -> (nametable::TotemNameTable, data::TotemArchive) {
+        let dgc = match self.chunk_size {
+            Some(chunk_size) if self.preserve_layout => self.split_layout(chunk_size),
+            _ => data::TotemArchive::new_from_files(
+                self.header.clone(),
+                self.files
+                    .values()
+                    .map(|file| file.to_totem_file())
+                    .collect(),
+                self.format,
+            ),
+        };
+        (self.split_names(), dgc)
     }
 
-    /// Get a mutable file from its name
-    pub fn get_file_from_name_mut(&mut self, name: &str) -> Option<&mut ChumFile> {
-        let hash = util::hash_name_i32(&name);
-        if let Some(x) = self.names.get(&hash) {
-            if x != name {
-                None
-            } else {
-                self.files.get_mut(&hash)
-            }
-        } else {
-            None
-        }
+    /// Split this ChumArchive into an NgcArchive and a DgcArchive, dividing files into chunks
+    /// with the given packing strategy. Files are given to the packer in the order they were
+    /// read in, followed by any added files sorted by name.
+    pub fn split_archives_packed(
+        &self,
+        packer: &dyn packer::ChunkPacker,
+        chunk_size: packer::ChunkSize,
+    ) -> Result<(nametable::TotemNameTable, data::TotemArchive), ChumError> {
+        let mut files: Vec<&ChumFile> = self.files.values().collect();
+        files.sort_by(|a, b| match (a.location, b.location) {
+            (Some(x), Some(y)) => x.cmp(&y),
+            (Some(_), None) => cmp::Ordering::Less,
+            (None, Some(_)) => cmp::Ordering::Greater,
+            (None, None) => a.get_name_id().cmp(b.get_name_id()),
+        });
+        let dgc = data::TotemArchive::new_from_files_packed(
+            self.header.clone(),
+            files.into_iter().map(|file| file.to_totem_file()).collect(),
+            self.format,
+            packer,
+            chunk_size,
+        )?;
+        Ok((self.split_names(), dgc))
     }
 
-    /// Split this ChumArchive into an NgcArchive and a DgcArchive
-    pub fn split_archives(&self) -> (nametable::TotemNameTable, data::TotemArchive) {
-        let dgc = data::TotemArchive::new_from_files(
+    /// Create an NgcArchive that remembers the order names were read in.
+    fn split_names(&self) -> nametable::TotemNameTable {
+        nametable::TotemNameTable::new_ordered(self.names.clone(), self.name_order.clone())
+    }
+
+    /// Create a DgcArchive that keeps the chunk layout that files were read with.
+    /// Files that no longer fit in their chunk and files that were added later are re-placed.
+    fn split_layout(&self, chunk_size: usize) -> data::TotemArchive {
+        let mut located: Vec<&ChumFile> = self
+            .files
+            .values()
+            .filter(|file| file.location.is_some())
+            .collect();
+        located.sort_by_key(|file| file.location);
+        let mut unplaced: Vec<&ChumFile> = self
+            .files
+            .values()
+            .filter(|file| file.location.is_none())
+            .collect();
+        let mut chunks: Vec<Vec<&ChumFile>> = Vec::new();
+        let mut chunk_ids = Vec::new();
+        let mut last_chunk = None;
+        for file in located {
+            let chunk = file.location.map(|x| x.chunk);
+            if chunk != last_chunk {
+                chunks.push(Vec::new());
+                chunk_ids.extend(chunk);
+                last_chunk = chunk;
+            }
+            chunks.last_mut().unwrap().push(file);
+        }
+        // Evict modified files from chunks that they no longer fit in, last file first
+        for chunk in chunks.iter_mut() {
+            let mut size = chunk
+                .iter()
+                .fold(4, |acc, file| acc + file.get_data().len() + 16);
+            while size > chunk_size {
+                let i = match chunk.iter().rposition(|file| file.modified) {
+                    Some(i) => i,
+                    None => chunk.len() - 1,
+                };
+                let file = chunk.remove(i);
+                size -= file.get_data().len() + 16;
+                unplaced.push(file);
+            }
+        }
+        // Place files deterministically
+        unplaced.sort_by(|a, b| a.get_name_id().cmp(b.get_name_id()));
+        data::TotemArchive::new_from_layout(
             self.header.clone(),
-            self.files
-                .values()
-                .map(|file| {
-                    data::TotemFile::new(
-                        file.data.clone(),
-                        util::hash_name_i32(file.get_type_id()),
-                        util::hash_name_i32(file.get_name_id()),
-                        util::hash_name_i32(file.get_subtype_id()),
+            chunks
+                .into_iter()
+                .zip(chunk_ids)
+                .map(|(chunk, chunk_id)| {
+                    // Padding is only written if the chunk's contents still have the same size
+                    let padding = self
+                        .chunk_padding
+                        .get(chunk_id)
+                        .map(|x| x.as_slice().to_vec())
+                        .unwrap_or_default();
+                    data::TotemChunk::from_files_padded(
+                        chunk.into_iter().map(|file| file.to_totem_file()).collect(),
+                        padding,
                     )
                 })
                 .collect(),
+            unplaced
+                .into_iter()
+                .map(|file| file.to_totem_file())
+                .collect(),
+            chunk_size,
             self.format,
-        );
-        let ngc = nametable::TotemNameTable::new(self.names.clone());
-        (ngc, dgc)
+        )
+    }
+
+    /// Set whether this archive should keep the chunk layout it was read with when written.
+    /// When enabled, an archive that is loaded and saved without modification is written
+    /// exactly as it was read; only modified or added files that no longer fit are re-placed.
+    /// Has no effect on archives that were not read from a DGC archive.
+    pub fn set_preserve_layout(&mut self, value: bool) {
+        self.preserve_layout = value;
+    }
+
+    /// Returns true if this archive keeps its original chunk layout when written.
+    pub fn get_preserve_layout(&self) -> bool {
+        self.preserve_layout
     }
 
     /// Find unused names
@@ -295,47 +832,59 @@ impl ChumArchive {
         dgc: data::TotemArchive,
     ) -> Result<ChumArchive, ChumError> {
         // Check NGC data for matching names
-        for file in dgc.iter_files() {
-            if !ngc.get_names().contains_key(&file.get_type_id()) {
-                return Err(ChumError::NameMissingError {
-                    id: file.get_type_id(),
-                });
-            }
-            if !ngc.get_names().contains_key(&file.get_name_id()) {
-                return Err(ChumError::NameMissingError {
-                    id: file.get_name_id(),
-                });
-            }
-            if !ngc.get_names().contains_key(&file.get_subtype_id()) {
-                return Err(ChumError::NameMissingError {
-                    id: file.get_subtype_id(),
-                });
+        check_names(
+            &ngc,
+            dgc.iter_files().map(|file| {
+                [
+                    file.get_type_id(),
+                    file.get_name_id(),
+                    file.get_subtype_id(),
+                ]
+            }),
+        )?;
+        let fmt = dgc.get_format();
+        let chunk_size = dgc.get_chunk_size();
+        let dgc_header = dgc.get_header().clone();
+        let (names, name_order) = ngc.take_names_ordered();
+        let mut files = HashMap::new();
+        let mut chunk_padding = Vec::new();
+        for (chunk_i, chunk) in dgc.take_chunks().into_iter().enumerate() {
+            chunk_padding.push(ChumFileData::Owned(if is_zeroed(chunk.get_padding()) {
+                Vec::new()
+            } else {
+                chunk.get_padding().to_vec()
+            }));
+            for (file_i, file) in chunk.take_files().into_iter().enumerate() {
+                let type_id = file.get_type_id();
+                let name_id = file.get_name_id();
+                let subtype_id = file.get_subtype_id();
+                files.insert(
+                    name_id,
+                    ChumFile {
+                        data: ChumFileData::Owned(file.take_data()),
+                        type_id: names[&type_id].clone(),
+                        name_id: names[&name_id].clone(),
+                        subtype_id: names[&subtype_id].clone(),
+                        location: Some(FileLocation {
+                            chunk: chunk_i,
+                            index: file_i,
+                        }),
+                        modified: false,
+                    },
+                );
             }
         }
-        let fmt = dgc.get_format();
         // Return archive
         Ok(ChumArchive {
-            header: dgc.get_header().clone(),
-            files: dgc
-                .take_files()
-                .into_iter()
-                .map(|file| {
-                    let type_id = file.get_type_id();
-                    let name_id = file.get_name_id();
-                    let subtype_id = file.get_subtype_id();
-                    (
-                        name_id,
-                        ChumFile {
-                            data: file.take_data(),
-                            type_id: ngc.get_names()[&type_id].clone(),
-                            name_id: ngc.get_names()[&name_id].clone(),
-                            subtype_id: ngc.get_names()[&subtype_id].clone(),
-                        },
-                    )
-                })
-                .collect(),
-            names: ngc.take_names(),
+            header: dgc_header,
+            files,
+            names,
+            name_order,
             format: fmt,
+            chunk_size: Some(chunk_size),
+            chunk_padding,
+            preserve_layout: false,
+            journal: journal::EditJournal::default(),
         })
     }
 
@@ -352,18 +901,114 @@ impl ChumArchive {
         ChumArchive::merge_archives(ngc, dgc)
     }
 
-    /// Write the chum archive to two writers
+    /// Read the chum archive from an NGC reader and shared DGC data.
+    /// Files borrow their data from `dgc_data` instead of copying it, so this is
+    /// very fast for memory-mapped archives (see `map_file`).
+    pub fn read_chum_archive_shared<R: Read>(
+        ngc_reader: &mut R,
+        dgc_data: SharedData,
+        format: format::TotemFormat,
+    ) -> Result<ChumArchive, ChumError> {
+        let ngc = nametable::TotemNameTable::read_from(ngc_reader)?;
+        let index = {
+            let mut cursor = io::Cursor::new((*dgc_data).as_ref());
+            index::TotemArchiveIndex::read_from(&mut cursor, format)?
+        };
+        check_names(
+            &ngc,
+            index.iter_files().map(|file| {
+                [
+                    file.get_type_id(),
+                    file.get_name_id(),
+                    file.get_subtype_id(),
+                ]
+            }),
+        )?;
+        let (names, name_order) = ngc.take_names_ordered();
+        let mut files = HashMap::new();
+        let mut chunk_padding = Vec::new();
+        for (chunk_i, chunk) in index.iter_chunks().enumerate() {
+            let padding = chunk.get_padding_range();
+            let padding = padding.start as usize..padding.end as usize;
+            chunk_padding.push(if is_zeroed(&(*dgc_data).as_ref()[padding.clone()]) {
+                ChumFileData::Owned(Vec::new())
+            } else {
+                ChumFileData::Borrowed(dgc_data.clone(), padding)
+            });
+            for (file_i, entry) in index.iter_chunk_files(chunk).enumerate() {
+                let start = entry.get_offset() as usize;
+                files.insert(
+                    entry.get_name_id(),
+                    ChumFile {
+                        data: ChumFileData::Borrowed(
+                            dgc_data.clone(),
+                            start..start + entry.get_file_size(),
+                        ),
+                        type_id: names[&entry.get_type_id()].clone(),
+                        name_id: names[&entry.get_name_id()].clone(),
+                        subtype_id: names[&entry.get_subtype_id()].clone(),
+                        location: Some(FileLocation {
+                            chunk: chunk_i,
+                            index: file_i,
+                        }),
+                        modified: false,
+                    },
+                );
+            }
+        }
+        Ok(ChumArchive {
+            header: index.get_header().clone(),
+            files,
+            names,
+            name_order,
+            format,
+            chunk_size: Some(index.get_chunk_size()),
+            chunk_padding,
+            preserve_layout: false,
+            journal: journal::EditJournal::default(),
+        })
+    }
+
+    /// Copy all borrowed file data so that this archive no longer depends on
+    /// the shared data it was read from.
+    pub fn detach(&mut self) {
+        for file in self.files.values_mut() {
+            file.detach();
+        }
+        for padding in self.chunk_padding.iter_mut() {
+            *padding = ChumFileData::Owned(padding.as_slice().to_vec());
+        }
+    }
+
+    /// Write the chum archive to two writers, with names in the given order
     pub fn write_chum_archive<W: Write, V: Write>(
         &self,
         ngc_writer: &mut W,
         dgc_writer: &mut V,
+        name_order: nametable::NameOrder,
     ) -> Result<(), ChumError> {
         let (ngc, dgc) = self.split_archives();
-        ngc.write_to(ngc_writer)?;
+        ngc.write_to_ordered(ngc_writer, name_order)?;
         dgc.write_to(dgc_writer)?;
         Ok(())
     }
 
+    /// Write the chum archive to two writers, dividing files into chunks with the given
+    /// packing strategy and with names in the given order. Returns the DGC archive that was written.
+    pub fn write_chum_archive_packed<W: Write, V: Write>(
+        &self,
+        ngc_writer: &mut W,
+        dgc_writer: &mut V,
+        name_order: nametable::NameOrder,
+        packer: &dyn packer::ChunkPacker,
+        chunk_size: packer::ChunkSize,
+    ) -> Result<data::TotemArchive, ChumError> {
+        let (ngc, dgc) = self.split_archives_packed(packer, chunk_size)?;
+        ngc.write_to_ordered(ngc_writer, name_order)?;
+        dgc.write_to(dgc_writer)?;
+        Ok(dgc)
+    }
+
     /// Get the format for this archive
     pub fn get_format(&self) -> format::TotemFormat {
         self.format
@@ -374,18 +1019,44 @@ impl ChumArchive {
         self.names.get(&id).map(|x| x.as_str())
     }
 
+    /// Get a string from the given ID, looking in the given dictionary
+    /// if it is not in this archive's name table.
+    pub fn resolve_name<'a>(&'a self, id: i32, dictionary: &'a NameDictionary) -> Option<&'a str> {
+        self.get_name_from_id(id)
+            .or_else(|| dictionary.get_name(id))
+    }
+
     /// Add the given name to this archive's name database.
     /// Returns an error if adding the name would result in a hash collision.
     pub fn add_name(&mut self, name: &str) -> Result<bool, ChumError> {
         match self.check_can_add_id(name)? {
             None => Ok(false),
             Some(id) => {
-                self.names.insert(id, name.to_owned());
+                self.set_name(id, Some(name.to_owned()));
+                self.journal.commit(format!("Add name {}", name));
                 Ok(true)
             }
         }
     }
 
+    /// Remove the given name from this archive's name database.
+    /// Names that are used by a file are not removed.
+    /// Returns true if the name was removed.
+    pub fn remove_name(&mut self, name: &str) -> bool {
+        let id = util::hash_name_i32(name);
+        let in_use = self.files.values().any(|file| {
+            file.get_name_id() == name
+                || file.get_type_id() == name
+                || file.get_subtype_id() == name
+        });
+        if in_use || self.names.get(&id).map(|x| x.as_str()) != Some(name) {
+            return false;
+        }
+        self.set_name(id, None);
+        self.journal.commit(format!("Remove name {}", name));
+        true
+    }
+
     /// Get an iterator of IDs
     pub fn get_ids(&self) -> impl Iterator<Item = &i32> {
         self.names.keys()
@@ -401,3 +1072,451 @@ impl ChumArchive {
         &self.names
     }
 }
+
+/// Returns true if every byte in `data` is zero
+fn is_zeroed(data: &[u8]) -> bool {
+    data.iter().all(|x| *x == 0)
+}
+
+/// Check that every ID of every file exists in the given name table.
+fn check_names<I>(ngc: &nametable::TotemNameTable, ids: I) -> Result<(), ChumError>
+where
+    I: Iterator<Item = [i32; 3]>,
+{
+    for file_ids in ids {
+        for id in file_ids.iter() {
+            if !ngc.get_names().contains_key(id) {
+                return Err(ChumError::NameMissingError { id: *id });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Information about a file in a ChumArchiveReader.
+/// The file's data has not been loaded yet.
+pub struct ChumFileInfo<'a> {
+    entry: &'a index::TotemFileEntry,
+    type_id: &'a str,
+    name_id: &'a str,
+    subtype_id: &'a str,
+}
+
+impl<'a> ChumFileInfo<'a> {
+    /// Get the file's name
+    pub fn get_name_id(&self) -> &'a str {
+        self.name_id
+    }
+
+    /// Get the file's type
+    pub fn get_type_id(&self) -> &'a str {
+        self.type_id
+    }
+
+    /// Get the file's subtype
+    pub fn get_subtype_id(&self) -> &'a str {
+        self.subtype_id
+    }
+
+    /// Get the location of this file in the DGC archive
+    pub fn get_entry(&self) -> &'a index::TotemFileEntry {
+        self.entry
+    }
+}
+
+/// A Chum archive whose file data is read on demand.
+/// Opening an archive only reads the NGC name table and the headers of the DGC
+/// archive, so files can be listed without loading the entire archive into memory.
+pub struct ChumArchiveReader<R> {
+    reader: R,
+    index: index::TotemArchiveIndex,
+    names: nametable::TotemNameTable,
+}
+
+impl<R: Read + Seek> ChumArchiveReader<R> {
+    /// Open a chum archive from an NGC reader and a seekable DGC reader
+    pub fn open<N: Read>(
+        ngc_reader: &mut N,
+        mut dgc_reader: R,
+        format: format::TotemFormat,
+    ) -> Result<ChumArchiveReader<R>, ChumError> {
+        let names = nametable::TotemNameTable::read_from(ngc_reader)?;
+        let index = index::TotemArchiveIndex::read_from(&mut dgc_reader, format)?;
+        check_names(
+            &names,
+            index.iter_files().map(|file| {
+                [
+                    file.get_type_id(),
+                    file.get_name_id(),
+                    file.get_subtype_id(),
+                ]
+            }),
+        )?;
+        Ok(ChumArchiveReader {
+            reader: dgc_reader,
+            index,
+            names,
+        })
+    }
+
+    /// Get the index of the underlying DGC archive
+    pub fn get_index(&self) -> &index::TotemArchiveIndex {
+        &self.index
+    }
+
+    /// Get the format for this archive
+    pub fn get_format(&self) -> format::TotemFormat {
+        self.index.get_format()
+    }
+
+    /// Get this archive's Dgc header
+    pub fn get_header(&self) -> &data::TotemHeader {
+        self.index.get_header()
+    }
+
+    /// Get a string from the given ID
+    pub fn get_name_from_id(&self, id: i32) -> Option<&str> {
+        self.names.get_names().get(&id).map(|x| x.as_str())
+    }
+
+    fn make_info<'a>(&'a self, entry: &'a index::TotemFileEntry) -> ChumFileInfo<'a> {
+        let names = self.names.get_names();
+        ChumFileInfo {
+            entry,
+            type_id: &names[&entry.get_type_id()],
+            name_id: &names[&entry.get_name_id()],
+            subtype_id: &names[&entry.get_subtype_id()],
+        }
+    }
+
+    /// Get information about all files in this archive
+    pub fn get_files(&self) -> impl Iterator<Item = ChumFileInfo<'_>> {
+        self.index
+            .iter_files()
+            .map(move |entry| self.make_info(entry))
+    }
+
+    /// Get information about a file from its hash
+    pub fn get_file_info_from_hash(&self, hash: i32) -> Option<ChumFileInfo<'_>> {
+        self.index
+            .get_file_from_hash(hash)
+            .map(|entry| self.make_info(entry))
+    }
+
+    /// Read a file from its hash
+    pub fn read_file_from_hash(&mut self, hash: i32) -> Result<Option<ChumFile>, ChumError> {
+        let entry = match self.index.get_file_from_hash(hash) {
+            Some(entry) => entry,
+            None => return Ok(None),
+        };
+        let data = self.index.read_file_data(&mut self.reader, entry)?;
+        let names = self.names.get_names();
+        Ok(Some(ChumFile::new(
+            data,
+            names[&entry.get_name_id()].clone(),
+            names[&entry.get_type_id()].clone(),
+            names[&entry.get_subtype_id()].clone(),
+        )))
+    }
+
+    /// Read a file from its name
+    pub fn read_file_from_name(&mut self, name: &str) -> Result<Option<ChumFile>, ChumError> {
+        let hash = util::hash_name_i32(name);
+        match self.names.get_names().get(&hash) {
+            Some(x) if x == name => self.read_file_from_hash(hash),
+            _ => Ok(None),
+        }
+    }
+
+    /// Load every file into a ChumArchive
+    pub fn read_archive(mut self) -> Result<ChumArchive, ChumError> {
+        let dgc = self.index.read_archive(&mut self.reader)?;
+        ChumArchive::merge_archives(self.names, dgc)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::archive::*;
+    use crate::format::TotemFormat;
+
+    fn make_archive() -> (Vec<u8>, Vec<u8>) {
+        let names = ["SMALL", "LARGE", "MEDIUM", "TYPE"];
+        let ngc = nametable::TotemNameTable::new(
+            names
+                .iter()
+                .map(|name| (util::hash_name_i32(name), name.to_string()))
+                .collect(),
+        );
+        let file = |name: &str, size: usize| {
+            let id = util::hash_name_i32(name);
+            TotemFile::new(vec![size as u8; size], util::hash_name_i32("TYPE"), id, id)
+        };
+        // Files are deliberately not sorted by size, and the chunk size is larger than needed
+        let chunks = vec![
+            data::TotemChunk::from_files(vec![file("SMALL", 10), file("LARGE", 2000)]),
+            data::TotemChunk::from_files(vec![file("MEDIUM", 1000)]),
+        ];
+        let dgc =
+            TotemArchive::from_chunks(TotemHeader::new(b"test"), chunks, 0x1000, TotemFormat::PS2);
+        let mut ngc_data = Vec::new();
+        let mut dgc_data = Vec::new();
+        ngc.write_to(&mut ngc_data).unwrap();
+        dgc.write_to(&mut dgc_data).unwrap();
+        (ngc_data, dgc_data)
+    }
+
+    #[test]
+    fn preserve_layout_round_trip() {
+        let (ngc_data, dgc_data) = make_archive();
+        let mut archive = ChumArchive::read_chum_archive(
+            &mut ngc_data.as_slice(),
+            &mut dgc_data.as_slice(),
+            TotemFormat::PS2,
+        )
+        .unwrap();
+        archive.set_preserve_layout(true);
+        let mut ngc_out = Vec::new();
+        let mut dgc_out = Vec::new();
+        archive
+            .write_chum_archive(&mut ngc_out, &mut dgc_out, NameOrder::Original)
+            .unwrap();
+        assert_eq!(dgc_out, dgc_data);
+        // A file that grows too big for its chunk is moved, but other files stay in place
+        archive.replace_file_data("SMALL", vec![0; 2500]).unwrap();
+        let (_ngc, dgc) = archive.split_archives();
+        assert_eq!(dgc.get_chunk_size(), 0x1000);
+        let chunks: Vec<Vec<i32>> = dgc
+            .iter_chunks()
+            .map(|chunk| chunk.iter_files().map(|f| f.get_name_id()).collect())
+            .collect();
+        assert_eq!(
+            chunks,
+            vec![
+                vec![util::hash_name_i32("LARGE")],
+                vec![util::hash_name_i32("MEDIUM"), util::hash_name_i32("SMALL")],
+            ]
+        );
+    }
+
+    #[test]
+    fn preserve_padding_round_trip() {
+        let (ngc_data, mut dgc_data) = make_archive();
+        // Dirty the padding at the end of both chunks
+        let padding_start = [0x800 + 4 + 26 + 2016, 0x1800 + 4 + 1016];
+        for (i, start) in padding_start.iter().enumerate() {
+            dgc_data[*start] = 0xAA;
+            dgc_data[0x800 + 0x1000 * (i + 1) - 1] = 0x55;
+        }
+        let write = |archive: &ChumArchive| {
+            let mut ngc_out = Vec::new();
+            let mut dgc_out = Vec::new();
+            archive
+                .write_chum_archive(&mut ngc_out, &mut dgc_out, NameOrder::Original)
+                .unwrap();
+            dgc_out
+        };
+        let mut archive = ChumArchive::read_chum_archive(
+            &mut ngc_data.as_slice(),
+            &mut dgc_data.as_slice(),
+            TotemFormat::PS2,
+        )
+        .unwrap();
+        archive.set_preserve_layout(true);
+        assert_eq!(write(&archive), dgc_data);
+        let mut shared = ChumArchive::read_chum_archive_shared(
+            &mut ngc_data.as_slice(),
+            Arc::new(dgc_data.clone()),
+            TotemFormat::PS2,
+        )
+        .unwrap();
+        shared.set_preserve_layout(true);
+        assert_eq!(write(&shared), dgc_data);
+        let mut lazy = ChumArchiveReader::open(
+            &mut ngc_data.as_slice(),
+            io::Cursor::new(&dgc_data),
+            TotemFormat::PS2,
+        )
+        .unwrap()
+        .read_archive()
+        .unwrap();
+        lazy.set_preserve_layout(true);
+        assert_eq!(write(&lazy), dgc_data);
+        // A chunk whose contents change size is zero-padded; other chunks keep their padding
+        archive.replace_file_data("MEDIUM", vec![1; 1010]).unwrap();
+        let dgc_out = write(&archive);
+        assert_eq!(dgc_out[..0x1800], dgc_data[..0x1800]);
+        assert!(dgc_out[0x1800 + 4 + 1026..].iter().all(|x| *x == 0));
+    }
+
+    #[test]
+    fn edit_files() {
+        let (ngc_data, dgc_data) = make_archive();
+        let mut archive = ChumArchive::read_chum_archive(
+            &mut ngc_data.as_slice(),
+            &mut dgc_data.as_slice(),
+            TotemFormat::PS2,
+        )
+        .unwrap();
+        // Renaming onto an existing file fails
+        match archive.rename_file("SMALL", "LARGE", true) {
+            Err(ChumError::NameCollisionError {
+                existing_name,
+                new_name,
+                ..
+            }) => {
+                assert_eq!(existing_name, "LARGE");
+                assert_eq!(new_name, "LARGE");
+            }
+            _ => panic!("Expected a name collision error"),
+        }
+        assert!(archive.rename_file("MISSING", "OTHER", true).is_err());
+        archive.rename_file("SMALL", "TINY", true).unwrap();
+        assert!(archive.get_file_from_name("SMALL").is_none());
+        assert_eq!(
+            archive.get_file_from_name("TINY").unwrap().get_data().len(),
+            10
+        );
+        // The old name is still used as the file's subtype
+        assert!(archive
+            .get_name_from_id(util::hash_name_i32("SMALL"))
+            .is_some());
+        archive
+            .retype_file("TINY", "OTHERTYPE", "TINY", true)
+            .unwrap();
+        assert_eq!(archive.get_name_from_id(util::hash_name_i32("SMALL")), None);
+        // Old type stays while other files still use it
+        assert!(archive
+            .get_name_from_id(util::hash_name_i32("TYPE"))
+            .is_some());
+        let file = archive.remove_file("TINY", true).unwrap();
+        assert_eq!(file.get_type_id(), "OTHERTYPE");
+        assert_eq!(
+            archive.get_name_from_id(util::hash_name_i32("OTHERTYPE")),
+            None
+        );
+        assert_eq!(archive.get_name_from_id(util::hash_name_i32("TINY")), None);
+        assert_eq!(archive.get_files().count(), 2);
+        // Removing without collecting names keeps them around
+        archive.remove_file("LARGE", false).unwrap();
+        assert_eq!(archive.find_unused_names().len(), 1);
+        assert_eq!(archive.remove_unused_names(), 1);
+    }
+
+    #[test]
+    fn undo_redo() {
+        let (ngc_data, dgc_data) = make_archive();
+        let mut archive = ChumArchive::read_chum_archive(
+            &mut ngc_data.as_slice(),
+            &mut dgc_data.as_slice(),
+            TotemFormat::PS2,
+        )
+        .unwrap();
+        assert!(!archive.is_dirty());
+        let start = archive.create_savepoint();
+        archive.replace_file_data("SMALL", vec![1, 2, 3]).unwrap();
+        archive.rename_file("SMALL", "TINY", true).unwrap();
+        let renamed = archive.create_savepoint();
+        archive.remove_file("MEDIUM", true).unwrap();
+        archive
+            .add_file(ChumFile::new(
+                vec![4],
+                "NEW".to_string(),
+                "NEWTYPE".to_string(),
+                "NEW".to_string(),
+            ))
+            .unwrap();
+        assert!(archive.is_dirty());
+        assert_eq!(archive.get_undo_description(), Some("Add NEW"));
+        // Undo the add and remove
+        assert!(archive.restore_savepoint(renamed));
+        assert!(archive.get_file_from_name("NEW").is_none());
+        assert_eq!(
+            archive.get_name_from_id(util::hash_name_i32("NEWTYPE")),
+            None
+        );
+        assert_eq!(
+            archive
+                .get_file_from_name("MEDIUM")
+                .unwrap()
+                .get_data()
+                .len(),
+            1000
+        );
+        assert_eq!(
+            archive.get_file_from_name("TINY").unwrap().get_data(),
+            &[1, 2, 3]
+        );
+        // Back to the start
+        assert!(archive.restore_savepoint(start));
+        assert!(!archive.is_dirty());
+        assert!(!archive.can_undo());
+        assert!(archive.get_file_from_name("TINY").is_none());
+        assert_eq!(
+            archive
+                .get_file_from_name("SMALL")
+                .unwrap()
+                .get_data()
+                .len(),
+            10
+        );
+        assert!(!archive.get_file_from_name("SMALL").unwrap().is_modified());
+        // Redo everything
+        while archive.redo() {}
+        assert!(archive.get_file_from_name("NEW").is_some());
+        assert!(archive.get_file_from_name("MEDIUM").is_none());
+        archive.mark_saved();
+        assert!(!archive.is_dirty());
+        // A new edit after undoing discards the redo history
+        archive.undo();
+        assert!(archive.is_dirty());
+        archive.add_name("NAME").unwrap();
+        assert!(!archive.can_redo());
+        assert!(archive.is_dirty());
+        // Savepoints that were undone and then overwritten can not be restored
+        assert!(archive.restore_savepoint(start));
+        archive.add_name("OTHER").unwrap();
+        assert!(!archive.restore_savepoint(renamed));
+        assert!(archive.restore_savepoint(start));
+    }
+
+    #[test]
+    fn shared_data_matches_owned() {
+        let (ngc_data, dgc_data) = make_archive();
+        let path = std::env::temp_dir().join(format!("libchum-mmap-{}.DGC", std::process::id()));
+        std::fs::write(&path, &dgc_data).unwrap();
+        let mapped = unsafe { map_file(&File::open(&path).unwrap()).unwrap() };
+        let shared = ChumArchive::read_chum_archive_shared(
+            &mut ngc_data.as_slice(),
+            mapped,
+            TotemFormat::PS2,
+        )
+        .unwrap();
+        let mut reader = ChumArchiveReader::open(
+            &mut ngc_data.as_slice(),
+            io::Cursor::new(&dgc_data),
+            TotemFormat::PS2,
+        )
+        .unwrap();
+        assert_eq!(shared.get_files().count(), 3);
+        for file in shared.get_files() {
+            assert!(file.is_borrowed());
+            let owned = reader
+                .read_file_from_name(file.get_name_id())
+                .unwrap()
+                .unwrap();
+            assert_eq!(file.get_data(), owned.get_data());
+            assert_eq!(file.get_type_id(), owned.get_type_id());
+            assert_eq!(file.get_subtype_id(), owned.get_subtype_id());
+        }
+        // Detaching copies the data, so the mapped file can be removed
+        let mut shared = shared;
+        shared.detach();
+        std::fs::remove_file(&path).unwrap();
+        assert!(shared.get_files().all(|file| !file.is_borrowed()));
+        assert_eq!(
+            shared.get_file_from_name("MEDIUM").unwrap().get_data(),
+            &[232u8; 1000][..]
+        );
+    }
+}
@@ -1,6 +1,53 @@
 use crate::archive::ChumError;
-use std::collections::HashMap;
-use std::io::{self, BufRead, BufReader, Read, Write};
+use crate::util;
+use std::collections::{HashMap, HashSet};
+use std::io::{self, Read, Write};
+use thiserror::Error;
+
+/// The kind of problem found on a line of a name table.
+#[derive(Error, Clone, Debug, PartialEq, Eq)]
+pub enum NameTableErrorKind {
+    #[error("line is not valid UTF-8")]
+    InvalidUtf8,
+    #[error("invalid ID {0:?}")]
+    InvalidId(String),
+    #[error("expected whitespace after ID")]
+    MissingSeparator,
+    #[error("expected '\"' to start the name")]
+    MissingQuote,
+    #[error("name is missing its closing '\"'")]
+    UnterminatedName,
+    #[error("unexpected {0:?} after the name")]
+    TrailingCharacters(char),
+}
+
+/// An error found while parsing a name table.
+/// Lines and columns both start at 1; columns are counted in characters.
+#[derive(Error, Clone, Debug, PartialEq, Eq)]
+#[error("NGC line {line}, column {column}: {kind}")]
+pub struct NameTableError {
+    pub line: usize,
+    pub column: usize,
+    pub kind: NameTableErrorKind,
+}
+
+/// The order in which a name table writes its names.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NameOrder {
+    /// Names are written in the order they were read in,
+    /// followed by any new names sorted by name
+    Original,
+    /// All names are sorted by name
+    SortedByName,
+    /// All names are sorted by ID
+    SortedById,
+}
+
+impl Default for NameOrder {
+    fn default() -> NameOrder {
+        NameOrder::Original
+    }
+}
 
 /// .NGC archive
 /// Contains multiple NGC elements
@@ -8,14 +55,34 @@ use std::io::{self, BufRead, BufReader, Read, Write};
 /// <ID> "FILENAME"
 /// The ID matches the type_id in DGC data
 /// Note that ID is just the crc32::checksum_ieee hash of FILENAME
+///
+/// Within FILENAME, quotes and line breaks are escaped with a backslash
+/// (`\"`, `\n`, `\r`, and `\0`). Any other backslash is read as-is. FILENAME ends at the
+/// last quote on the line. If unescaping gives a name whose hash is not ID, then the
+/// name was not escaped (e.g. `C:\new`), and it is read as-is.
 pub struct TotemNameTable {
     names: HashMap<i32, String>,
+    order: Vec<i32>, // order that IDs were read in
 }
 
 impl TotemNameTable {
     /// Create a new Archive file.
     pub fn new(names: HashMap<i32, String>) -> TotemNameTable {
-        TotemNameTable { names }
+        TotemNameTable {
+            names,
+            order: Vec::new(),
+        }
+    }
+
+    /// Create a new Archive file that remembers the order of the given IDs.
+    /// IDs in `order` that are not in `names` are ignored.
+    pub fn new_ordered(names: HashMap<i32, String>, order: Vec<i32>) -> TotemNameTable {
+        TotemNameTable { names, order }
+    }
+
+    /// Get the IDs in the order that they were read in
+    pub fn get_order(&self) -> &[i32] {
+        &self.order
     }
 
     // Get the hashmap of names
@@ -28,34 +95,326 @@ impl TotemNameTable {
         self.names
     }
 
-    /// Write the archive to the given Writer.
+    /// Claim the names from this archive, along with the order they were read in
+    pub fn take_names_ordered(self) -> (HashMap<i32, String>, Vec<i32>) {
+        (self.names, self.order)
+    }
+
+    /// Get every ID in this archive, in the given order.
+    pub fn get_ordered_ids(&self, order: NameOrder) -> Vec<i32> {
+        let mut ids: Vec<i32> = match order {
+            NameOrder::Original => self
+                .order
+                .iter()
+                .copied()
+                .filter(|id| self.names.contains_key(id))
+                .collect(),
+            _ => Vec::new(),
+        };
+        let known: HashSet<i32> = ids.iter().copied().collect();
+        let mut rest: Vec<i32> = self
+            .names
+            .keys()
+            .copied()
+            .filter(|id| !known.contains(id))
+            .collect();
+        match order {
+            NameOrder::SortedById => rest.sort(),
+            _ => rest.sort_by(|a, b| (&self.names[a], a).cmp(&(&self.names[b], b))),
+        }
+        ids.append(&mut rest);
+        ids
+    }
+
+    /// Write the archive to the given Writer, keeping the original order of names.
     pub fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
-        for (id, name) in &self.names {
-            writeln!(writer, "{} \"{}\"", id, name)?;
+        self.write_to_ordered(writer, NameOrder::Original)
+    }
+
+    /// Write the archive to the given Writer, with names in the given order.
+    pub fn write_to_ordered<W: Write>(&self, writer: &mut W, order: NameOrder) -> io::Result<()> {
+        for id in self.get_ordered_ids(order) {
+            writeln!(writer, "{} \"{}\"", id, escape_name(&self.names[&id]))?;
         }
         Ok(())
     }
 
-    /// Read names from the given Reader
+    /// Read names from the given Reader.
+    /// Fails on the first malformed line.
     pub fn read_from<R: Read>(reader: &mut R) -> Result<TotemNameTable, ChumError> {
-        let file = BufReader::new(reader);
+        let (names, order) = parse_names(reader, None)?;
+        Ok(TotemNameTable { names, order })
+    }
 
-        let mut elements = HashMap::new();
+    /// Read names from the given Reader, skipping malformed lines.
+    /// Returns the names that could be read, along with an error for each skipped line.
+    pub fn read_from_lenient<R: Read>(
+        reader: &mut R,
+    ) -> Result<(TotemNameTable, Vec<NameTableError>), ChumError> {
+        let mut warnings = Vec::new();
+        let (names, order) = parse_names(reader, Some(&mut warnings))?;
+        Ok((TotemNameTable { names, order }, warnings))
+    }
+}
 
-        for line in file.lines() {
-            let line = line?;
-            if line.len() == 0 || line.starts_with('\0') {
-                break;
+/// Escape a name so that it can be written between quotes.
+/// Only quotes and the control characters that would break a line are escaped;
+/// backslashes are written as-is, so that names like `C:\PATH` are unchanged.
+pub(crate) fn escape_name(name: &str) -> String {
+    let mut escaped = String::with_capacity(name.len());
+    for c in name.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\0' => escaped.push_str("\\0"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Parse the names in the given reader.
+/// Returns the names, and the order that IDs first appeared in.
+/// If `warnings` is given, malformed lines are skipped and added to it.
+fn parse_names<R: Read>(
+    reader: &mut R,
+    mut warnings: Option<&mut Vec<NameTableError>>,
+) -> Result<(HashMap<i32, String>, Vec<i32>), ChumError> {
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data)?;
+    // Name tables may be padded with NUL bytes
+    while data.last() == Some(&0) {
+        data.pop();
+    }
+    let mut names = HashMap::new();
+    let mut order = Vec::new();
+    for (line_i, line) in data.split(|x| *x == b'\n').enumerate() {
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        if line.starts_with(b"\0") {
+            break;
+        }
+        let result = match std::str::from_utf8(line) {
+            Ok(line) => parse_line(line),
+            Err(e) => Err((
+                String::from_utf8_lossy(&line[..e.valid_up_to()])
+                    .chars()
+                    .count()
+                    + 1,
+                NameTableErrorKind::InvalidUtf8,
+            )),
+        };
+        match result {
+            Ok(Some((id, raw))) => {
+                // Legacy name tables do not escape anything, so a backslash in a name like
+                // `C:\new` is not an escape. The ID is the name's hash, so it tells which
+                // reading is right.
+                let mut name = unescape_name(&raw);
+                if name != raw && util::hash_name_i32(&name) != id {
+                    name = raw;
+                }
+                if names.insert(id, name).is_none() {
+                    order.push(id);
+                }
+            }
+            Ok(None) => {}
+            Err((column, kind)) => {
+                let error = NameTableError {
+                    line: line_i + 1,
+                    column,
+                    kind,
+                };
+                match warnings {
+                    Some(ref mut warnings) => warnings.push(error),
+                    None => return Err(error.into()),
+                }
+            }
+        }
+    }
+    Ok((names, order))
+}
+
+/// Parse a single line of a name table.
+/// Returns the ID and the name as it was written, before `unescape_name`.
+/// Returns None for blank lines, or the column and kind of error for malformed lines.
+pub(crate) fn parse_line(line: &str) -> Result<Option<(i32, String)>, (usize, NameTableErrorKind)> {
+    let chars: Vec<char> = line.chars().collect();
+    let mut i = 0;
+    while i < chars.len() && chars[i].is_whitespace() {
+        i += 1;
+    }
+    if i == chars.len() {
+        return Ok(None);
+    }
+    // ID
+    let id_start = i;
+    while i < chars.len() && !chars[i].is_whitespace() && chars[i] != '"' {
+        i += 1;
+    }
+    let id_str: String = chars[id_start..i].iter().collect();
+    let id = id_str
+        .parse()
+        .map_err(|_| (id_start + 1, NameTableErrorKind::InvalidId(id_str)))?;
+    // Separator
+    if i == chars.len() || !chars[i].is_whitespace() {
+        return Err((i + 1, NameTableErrorKind::MissingSeparator));
+    }
+    while i < chars.len() && chars[i].is_whitespace() {
+        i += 1;
+    }
+    // Name
+    if i == chars.len() || chars[i] != '"' {
+        return Err((i + 1, NameTableErrorKind::MissingQuote));
+    }
+    let name_start = i + 1;
+    // The name ends at the last quote, which may only be followed by whitespace
+    let name_end = match chars[name_start..].iter().rposition(|c| *c == '"') {
+        Some(end) => name_start + end,
+        None => return Err((chars.len() + 1, NameTableErrorKind::UnterminatedName)),
+    };
+    if let Some(end) = chars[name_end + 1..]
+        .iter()
+        .position(|c| !c.is_whitespace())
+    {
+        let column = name_end + 1 + end;
+        return Err((
+            column + 1,
+            NameTableErrorKind::TrailingCharacters(chars[column]),
+        ));
+    }
+    Ok(Some((id, chars[name_start..name_end].iter().collect())))
+}
+
+/// Undo `escape_name`. Backslashes that do not start an escape are kept.
+pub(crate) fn unescape_name(raw: &str) -> String {
+    let raw: Vec<char> = raw.chars().collect();
+    let mut name = String::with_capacity(raw.len());
+    let mut i = 0;
+    while i < raw.len() {
+        let escaped = match (raw[i], raw.get(i + 1)) {
+            ('\\', Some('"')) => '"',
+            ('\\', Some('n')) => '\n',
+            ('\\', Some('r')) => '\r',
+            ('\\', Some('0')) => '\0',
+            (c, _) => {
+                name.push(c);
+                i += 1;
+                continue;
             }
-            let pos = line.find(char::is_whitespace).unwrap();
-            let id_str = &line[0..pos];
-            let file_str = &line[pos + 1..];
-            let filelen = file_str.len();
-            let id = id_str.parse()?;
-            let filename = file_str[1..filelen - 1].to_string();
-            elements.insert(id, filename);
+        };
+        name.push(escaped);
+        i += 2;
+    }
+    name
+}
+
+#[cfg(test)]
+mod test {
+    use crate::archive::nametable::*;
+
+    #[test]
+    fn names_round_trip() {
+        let names: HashMap<i32, String> = [
+            "DB:>PLAIN>NAME",
+            "with \"quotes\"",
+            "line\nbreak\r\nand\0nul",
+            "back\\slash\\",
+            "back\\\"slash",
+            "",
+        ]
+        .iter()
+        .map(|name| (util::hash_name_i32(name), name.to_string()))
+        .collect();
+        let mut data = Vec::new();
+        TotemNameTable::new(names.clone())
+            .write_to(&mut data)
+            .unwrap();
+        let ngc = TotemNameTable::read_from(&mut data.as_slice()).unwrap();
+        assert_eq!(ngc.get_names(), &names);
+    }
+
+    #[test]
+    fn legacy_backslashes() {
+        // Written by the game's tools, which do not escape anything
+        let paths = [
+            "C:\\Totem\\new\\rock.tga",
+            "C:\\Totem\\0\\",
+            "\\\\server\\share\\",
+        ];
+        let mut data = Vec::new();
+        for path in paths.iter() {
+            writeln!(data, "{} \"{}\"", util::hash_name_i32(path), path).unwrap();
+        }
+        let ngc = TotemNameTable::read_from(&mut data.as_slice()).unwrap();
+        for path in paths.iter() {
+            assert_eq!(ngc.get_names()[&util::hash_name_i32(path)], *path);
         }
+        // Saving an unmodified name table does not change it
+        let mut out = Vec::new();
+        ngc.write_to(&mut out).unwrap();
+        assert_eq!(out, data);
+    }
+
+    #[test]
+    fn names_keep_order() {
+        let data = b"30 \"C\"\n10 \"A\"\n20 \"B\"\n";
+        let ngc = TotemNameTable::read_from(&mut &data[..]).unwrap();
+        let mut out = Vec::new();
+        ngc.write_to(&mut out).unwrap();
+        assert_eq!(out.as_slice(), &data[..]);
 
-        Ok(TotemNameTable { names: elements })
+        let (mut names, order) = ngc.take_names_ordered();
+        names.remove(&10);
+        names.insert(5, "E".to_string());
+        names.insert(40, "D".to_string());
+        let ngc = TotemNameTable::new_ordered(names, order);
+        assert_eq!(
+            ngc.get_ordered_ids(NameOrder::Original),
+            vec![30, 20, 40, 5]
+        );
+        assert_eq!(
+            ngc.get_ordered_ids(NameOrder::SortedByName),
+            vec![20, 30, 40, 5]
+        );
+        assert_eq!(
+            ngc.get_ordered_ids(NameOrder::SortedById),
+            vec![5, 20, 30, 40]
+        );
+    }
+
+    #[test]
+    fn names_malformed() {
+        let data = b"1 \"ONE\"\r\n\r\n2 \"TWO\"\r\n3 THREE\r\n4 \"FOUR\r\nfive \"FIVE\"\r\n6 \"C:\\PATH\"\0\0\0";
+        match TotemNameTable::read_from(&mut &data[..]) {
+            Err(ChumError::NameTableError(error)) => assert_eq!(
+                error,
+                NameTableError {
+                    line: 4,
+                    column: 3,
+                    kind: NameTableErrorKind::MissingQuote,
+                }
+            ),
+            _ => panic!("Expected a name table error"),
+        }
+        let (ngc, warnings) = TotemNameTable::read_from_lenient(&mut &data[..]).unwrap();
+        let kinds: Vec<_> = warnings.into_iter().map(|x| (x.line, x.kind)).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (4, NameTableErrorKind::MissingQuote),
+                (5, NameTableErrorKind::UnterminatedName),
+                (6, NameTableErrorKind::InvalidId("five".to_string())),
+            ]
+        );
+        let mut names: Vec<_> = ngc.take_names().into_iter().collect();
+        names.sort();
+        assert_eq!(
+            names,
+            vec![
+                (1, "ONE".to_string()),
+                (2, "TWO".to_string()),
+                (6, "C:\\PATH".to_string()),
+            ]
+        );
     }
 }
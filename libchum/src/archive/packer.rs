@@ -0,0 +1,382 @@
+use crate::archive::data::TotemFile;
+use std::collections::HashMap;
+use thiserror::Error;
+
+/// Each chunk's size is a multiple of 0x800 bytes
+pub const CHUNK_MULT: usize = 0x800;
+
+/// Calculate the size that a chunk would have to be in order to store a file of the given size.
+pub fn calculate_chunk_size(max_size: usize) -> usize {
+    if max_size == 0 {
+        // avoid subtract with overflow error
+        return CHUNK_MULT;
+    }
+    std::cmp::max(1, 1 + ((max_size - 1) / CHUNK_MULT)) * CHUNK_MULT
+}
+
+#[derive(Error, Debug)]
+/// Error type for packing files into chunks
+pub enum PackError {
+    /// A file does not fit into a chunk of the requested size
+    #[error("File {name_id} of size {size} does not fit into chunks of size {chunk_size}")]
+    FileTooLarge {
+        name_id: i32,
+        size: usize,
+        chunk_size: usize,
+    },
+    /// A fixed chunk size is zero or not a multiple of 0x800
+    #[error(
+        "Chunk size {chunk_size:X} is not a non-zero multiple of {:X}",
+        CHUNK_MULT
+    )]
+    InvalidChunkSize { chunk_size: usize },
+}
+
+/// How the chunk size of a packed archive is determined.
+#[derive(Clone, Copy, Debug)]
+pub enum ChunkSize {
+    /// Use the smallest chunk size that fits the largest file
+    Auto,
+    /// Use exactly this chunk size, failing if a file does not fit.
+    /// Must be a non-zero multiple of 0x800.
+    Fixed(usize),
+}
+
+impl ChunkSize {
+    /// Determine the chunk size for the given files
+    pub fn resolve(&self, files: &[TotemFile]) -> Result<usize, PackError> {
+        match *self {
+            ChunkSize::Auto => Ok(calculate_chunk_size(
+                files
+                    .iter()
+                    .map(|f| f.get_total_size() + 4)
+                    .max()
+                    .unwrap_or(0),
+            )),
+            ChunkSize::Fixed(chunk_size) => {
+                if chunk_size == 0 || chunk_size % CHUNK_MULT != 0 {
+                    return Err(PackError::InvalidChunkSize { chunk_size });
+                }
+                for file in files {
+                    if file.get_total_size() + 4 > chunk_size {
+                        return Err(PackError::FileTooLarge {
+                            name_id: file.get_name_id(),
+                            size: file.get_total_size(),
+                            chunk_size,
+                        });
+                    }
+                }
+                Ok(chunk_size)
+            }
+        }
+    }
+}
+
+/// A strategy for dividing files into chunks.
+pub trait ChunkPacker {
+    /// Divide the given files into chunks of the given size.
+    /// Every file is guaranteed to fit into an empty chunk.
+    fn pack(&self, files: Vec<TotemFile>, chunk_size: usize) -> Vec<Vec<TotemFile>>;
+}
+
+/// Get the size that the given chunk takes up
+fn chunk_total_size(chunk: &[TotemFile]) -> usize {
+    chunk.iter().fold(4, |acc, f| acc + f.get_total_size())
+}
+
+/// Sorts files by size, then fills each chunk with as many files as possible.
+/// Not the most efficient, but this is the way that DGC files tend to store files.
+pub struct GreedyPacker;
+
+impl ChunkPacker for GreedyPacker {
+    fn pack(&self, mut files: Vec<TotemFile>, chunk_size: usize) -> Vec<Vec<TotemFile>> {
+        files.sort_by(|a, b| b.get_file_size().cmp(&a.get_file_size()));
+        let mut chunks = Vec::new();
+        // Pack files.
+        // On each iteration, while there is at least one file:
+        //     Create a new chunk
+        //     Fit as many files as possible in the chunk
+        while files.len() > 0 {
+            let mut chunk = Vec::new();
+            let mut size = 4;
+            let mut i = 0;
+            // Add file if it fits, otherwise skip
+            while i < files.len() {
+                if files[i].get_total_size() + size <= chunk_size {
+                    size += files[i].get_total_size();
+                    chunk.push(files.remove(i));
+                } else {
+                    i += 1;
+                }
+            }
+            chunks.push(chunk);
+        }
+        chunks
+    }
+}
+
+/// Sorts files by size, then puts each file into the chunk with the least space left that it
+/// still fits in. This usually results in less padding than `GreedyPacker`.
+pub struct BestFitPacker;
+
+impl ChunkPacker for BestFitPacker {
+    fn pack(&self, mut files: Vec<TotemFile>, chunk_size: usize) -> Vec<Vec<TotemFile>> {
+        files.sort_by(|a, b| b.get_file_size().cmp(&a.get_file_size()));
+        let mut chunks: Vec<Vec<TotemFile>> = Vec::new();
+        let mut sizes: Vec<usize> = Vec::new();
+        for file in files {
+            let best = sizes
+                .iter()
+                .enumerate()
+                .filter(|(_, size)| **size + file.get_total_size() <= chunk_size)
+                .max_by_key(|(_, size)| **size)
+                .map(|(i, _)| i);
+            match best {
+                Some(i) => {
+                    sizes[i] += file.get_total_size();
+                    chunks[i].push(file);
+                }
+                None => {
+                    sizes.push(4 + file.get_total_size());
+                    chunks.push(vec![file]);
+                }
+            }
+        }
+        chunks
+    }
+}
+
+/// Keeps files in the order they are given, grouped with the files they depend on
+/// (e.g. a NODE with the MESH and MATERIALs it uses).
+/// Each file is followed by its dependencies that have not been placed yet. If this group fits
+/// into the current chunk, or into an empty chunk, then it is kept together in one chunk;
+/// otherwise it is spread over neighboring chunks.
+/// Without dependencies, files are packed in the order they are given.
+#[derive(Default)]
+pub struct OrderedPacker {
+    dependencies: HashMap<i32, Vec<i32>>,
+}
+
+impl OrderedPacker {
+    /// Create a packer that keeps files in the order they are given
+    pub fn new() -> OrderedPacker {
+        OrderedPacker::default()
+    }
+
+    /// Create a packer that groups files with their dependencies.
+    /// Each pair is the name ID of a file and the ID of a file it depends on,
+    /// e.g. from `ReferenceGraph::get_references`.
+    pub fn with_dependencies<I>(dependencies: I) -> OrderedPacker
+    where
+        I: IntoIterator<Item = (i32, i32)>,
+    {
+        let mut packer = OrderedPacker::default();
+        for (from, to) in dependencies {
+            packer.dependencies.entry(from).or_default().push(to);
+        }
+        packer
+    }
+
+    /// Get the given file followed by its unplaced dependencies, depth first.
+    /// Every file in the group is marked as placed.
+    fn take_group(
+        &self,
+        first: usize,
+        files: &[TotemFile],
+        positions: &HashMap<i32, usize>,
+        placed: &mut [bool],
+    ) -> Vec<usize> {
+        let mut group = Vec::new();
+        let mut stack = vec![first];
+        placed[first] = true;
+        while let Some(i) = stack.pop() {
+            group.push(i);
+            let dependencies = self.dependencies.get(&files[i].get_name_id());
+            // Reversed so that dependencies are visited in the order they are listed
+            for id in dependencies.into_iter().flatten().rev() {
+                if let Some(&j) = positions.get(id) {
+                    if !placed[j] {
+                        placed[j] = true;
+                        stack.push(j);
+                    }
+                }
+            }
+        }
+        group
+    }
+}
+
+impl ChunkPacker for OrderedPacker {
+    fn pack(&self, files: Vec<TotemFile>, chunk_size: usize) -> Vec<Vec<TotemFile>> {
+        let positions: HashMap<i32, usize> = files
+            .iter()
+            .enumerate()
+            .map(|(i, file)| (file.get_name_id(), i))
+            .collect();
+        let mut placed = vec![false; files.len()];
+        let mut order = Vec::with_capacity(files.len());
+        for i in 0..files.len() {
+            if !placed[i] {
+                order.push(self.take_group(i, &files, &positions, &mut placed));
+            }
+        }
+        let mut files: Vec<Option<TotemFile>> = files.into_iter().map(Some).collect();
+        let mut chunks: Vec<Vec<TotemFile>> = Vec::new();
+        for group in order {
+            let group_size: usize = group
+                .iter()
+                .map(|i| files[*i].as_ref().unwrap().get_total_size())
+                .sum();
+            let fits_current = match chunks.last() {
+                Some(chunk) => chunk_total_size(chunk) + group_size <= chunk_size,
+                None => false,
+            };
+            if !fits_current && group_size + 4 <= chunk_size {
+                chunks.push(Vec::new());
+            }
+            for i in group {
+                let file = files[i].take().unwrap();
+                match chunks.last_mut() {
+                    Some(chunk)
+                        if chunk_total_size(chunk) + file.get_total_size() <= chunk_size =>
+                    {
+                        chunk.push(file);
+                    }
+                    _ => chunks.push(vec![file]),
+                }
+            }
+        }
+        chunks
+    }
+}
+
+/// Get a packer from its name.
+/// Valid names are "greedy", "best-fit", and "ordered".
+/// The "ordered" packer has no dependencies; use `OrderedPacker::with_dependencies` to group files.
+pub fn packer_from_name(name: &str) -> Option<Box<dyn ChunkPacker>> {
+    match name {
+        "greedy" => Some(Box::new(GreedyPacker)),
+        "best-fit" => Some(Box::new(BestFitPacker)),
+        "ordered" => Some(Box::new(OrderedPacker::new())),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::archive::data::{TotemArchive, TotemHeader};
+    use crate::archive::packer::*;
+    use crate::format::TotemFormat;
+
+    fn make_files() -> Vec<TotemFile> {
+        files_with_sizes(&[700, 3000, 100, 1500, 900, 20, 2000, 600])
+    }
+
+    /// Create files with the given sizes, with name IDs starting at 1
+    fn files_with_sizes(sizes: &[usize]) -> Vec<TotemFile> {
+        sizes
+            .iter()
+            .enumerate()
+            .map(|(i, size)| TotemFile::new(vec![i as u8; *size], 1, i as i32 + 1, 0))
+            .collect()
+    }
+
+    /// Pack, write and read back an archive, returning the name IDs in each chunk
+    fn round_trip(
+        packer: &dyn ChunkPacker,
+        chunk_size: ChunkSize,
+        fmt: TotemFormat,
+    ) -> Result<Vec<Vec<i32>>, PackError> {
+        let files = make_files();
+        let dgc = TotemArchive::new_from_files_packed(
+            TotemHeader::new(b"test"),
+            make_files(),
+            fmt,
+            packer,
+            chunk_size,
+        )?;
+        let mut data = Vec::new();
+        dgc.write_to(&mut data).unwrap();
+        assert_eq!(
+            data.len(),
+            0x800 + dgc.get_chunk_size() * dgc.iter_chunks().count()
+        );
+        let read = TotemArchive::read_from(&mut data.as_slice(), fmt).unwrap();
+        assert_eq!(read.get_chunk_size(), dgc.get_chunk_size());
+        let mut found = 0;
+        for file in files.iter() {
+            let other = read
+                .iter_files()
+                .find(|x| x.get_name_id() == file.get_name_id())
+                .unwrap();
+            assert_eq!(other.get_data(), file.get_data());
+            found += 1;
+        }
+        assert_eq!(found, read.iter_files().count());
+        Ok(read
+            .iter_chunks()
+            .map(|chunk| chunk.iter_files().map(|f| f.get_name_id()).collect())
+            .collect())
+    }
+
+    #[test]
+    fn packers_round_trip() {
+        for fmt in [TotemFormat::NGC, TotemFormat::PS2].iter() {
+            let greedy = round_trip(&GreedyPacker, ChunkSize::Auto, *fmt).unwrap();
+            assert_eq!(greedy, vec![vec![2, 5, 3, 6], vec![7, 4], vec![1, 8]]);
+            let best_fit = round_trip(&BestFitPacker, ChunkSize::Fixed(0x1000), *fmt).unwrap();
+            assert_eq!(best_fit, vec![vec![2, 5, 3, 6], vec![7, 4], vec![1, 8]]);
+            let ordered = round_trip(&OrderedPacker::new(), ChunkSize::Auto, *fmt).unwrap();
+            assert_eq!(ordered, vec![vec![1, 2, 3], vec![4, 5, 6], vec![7, 8]]);
+        }
+
+        // Greedy packing can not fit 500 and 300 next to 3100 once 700 is there,
+        // so it needs a fourth chunk, while best fit puts 700 in with 1800 and 1500
+        let pack = |packer: &dyn ChunkPacker| {
+            TotemArchive::new_from_files_packed(
+                TotemHeader::new(b"test"),
+                files_with_sizes(&[3100, 700, 1500, 3800, 1800, 500, 300]),
+                TotemFormat::NGC,
+                packer,
+                ChunkSize::Fixed(0x1000),
+            )
+            .unwrap()
+        };
+        let greedy = pack(&GreedyPacker);
+        assert_eq!(greedy.iter_chunks().count(), 4);
+        assert_eq!(greedy.get_total_padding(), 4556);
+        let best_fit = pack(&BestFitPacker);
+        assert_eq!(best_fit.iter_chunks().count(), 3);
+        assert_eq!(best_fit.get_total_padding(), 464);
+    }
+
+    #[test]
+    fn ordered_packer_groups_dependencies() {
+        // 1 depends on 7 and 3, and 7 depends on 6: they fit together in one chunk
+        let packer = OrderedPacker::with_dependencies(vec![(1, 7), (1, 3), (7, 6), (3, 1)]);
+        let chunks = round_trip(&packer, ChunkSize::Auto, TotemFormat::NGC).unwrap();
+        assert_eq!(chunks, vec![vec![1, 7, 6, 3], vec![2], vec![4, 5, 8]]);
+        // A group that is too big for one chunk is spread over neighboring chunks
+        let packer = OrderedPacker::with_dependencies(vec![(1, 7), (1, 4), (7, 6)]);
+        let chunks = round_trip(&packer, ChunkSize::Auto, TotemFormat::NGC).unwrap();
+        assert_eq!(chunks, vec![vec![1, 7, 6], vec![4], vec![2, 3, 5], vec![8]]);
+    }
+
+    #[test]
+    fn fixed_chunk_size() {
+        for chunk_size in [0, 0x1001, 0x400].iter() {
+            match round_trip(
+                &GreedyPacker,
+                ChunkSize::Fixed(*chunk_size),
+                TotemFormat::NGC,
+            ) {
+                Err(PackError::InvalidChunkSize { chunk_size: x }) => assert_eq!(x, *chunk_size),
+                _ => panic!("Expected an invalid chunk size error"),
+            }
+        }
+        match round_trip(&GreedyPacker, ChunkSize::Fixed(0x800), TotemFormat::NGC) {
+            Err(PackError::FileTooLarge { name_id, .. }) => assert_eq!(name_id, 2),
+            _ => panic!("Expected a file too large error"),
+        }
+    }
+}
@@ -0,0 +1,213 @@
+use crate::format::{DetectError, TotemFormat};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// An archive found while scanning a directory.
+/// One of the paths may be missing if the archive is incomplete.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CatalogEntry {
+    /// Path of the archive without its extension
+    pub stem: PathBuf,
+    /// Path to the name table (.NGC or .NPS)
+    pub names_path: Option<PathBuf>,
+    /// Path to the data file (.DGC or .DPS)
+    pub data_path: Option<PathBuf>,
+    /// Format of the archive; None if it could not be determined
+    pub format: Option<TotemFormat>,
+}
+
+impl CatalogEntry {
+    /// Returns true if both of the archive's files were found
+    pub fn is_complete(&self) -> bool {
+        self.names_path.is_some() && self.data_path.is_some()
+    }
+}
+
+/// Detect the format of the .DGC file at the given path.
+/// Returns None if the format could not be detected.
+pub fn detect_file_format(path: &Path) -> io::Result<Option<TotemFormat>> {
+    match TotemFormat::detect_from(&mut File::open(path)?) {
+        Ok(format) => Ok(Some(format)),
+        Err(DetectError::Io(e)) => Err(e),
+        Err(_) => Ok(None),
+    }
+}
+
+/// One of the two files that make up an archive.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArchiveFileKind {
+    /// The name table (.NGC or .NPS)
+    Names,
+    /// The data file (.DGC or .DPS)
+    Data,
+}
+
+/// Which file of an archive the given path is, from its extension.
+/// Gamecube archives use .NGC/.DGC, and Playstation 2 archives use .NPS/.DPS.
+pub fn get_file_kind(path: &Path) -> Option<ArchiveFileKind> {
+    let ext = path.extension()?.to_str()?;
+    if ext.eq_ignore_ascii_case("ngc") || ext.eq_ignore_ascii_case("nps") {
+        Some(ArchiveFileKind::Names)
+    } else if ext.eq_ignore_ascii_case("dgc") || ext.eq_ignore_ascii_case("dps") {
+        Some(ArchiveFileKind::Data)
+    } else {
+        None
+    }
+}
+
+fn scan_into(path: &Path, entries: &mut BTreeMap<PathBuf, CatalogEntry>) -> io::Result<()> {
+    for dir_entry in fs::read_dir(path)? {
+        let dir_entry = dir_entry?;
+        let file_path = dir_entry.path();
+        if dir_entry.file_type()?.is_dir() {
+            scan_into(&file_path, entries)?;
+            continue;
+        }
+        let kind = match get_file_kind(&file_path) {
+            Some(x) => x,
+            None => continue,
+        };
+        let stem = file_path.with_extension("");
+        let entry = entries.entry(stem.clone()).or_insert_with(|| CatalogEntry {
+            stem,
+            names_path: None,
+            data_path: None,
+            format: None,
+        });
+        match kind {
+            ArchiveFileKind::Data => entry.data_path = Some(file_path),
+            ArchiveFileKind::Names => entry.names_path = Some(file_path),
+        }
+    }
+    Ok(())
+}
+
+/// Find every archive in the given directory and its subdirectories.
+/// Name table and data files are paired by their path without extension,
+/// and the format of each archive is detected from its .DGC file.
+/// Entries are sorted by path.
+pub fn scan_directory(path: &Path) -> io::Result<Vec<CatalogEntry>> {
+    let mut entries = BTreeMap::new();
+    scan_into(path, &mut entries)?;
+    let mut catalog: Vec<CatalogEntry> = entries.into_iter().map(|(_, x)| x).collect();
+    for entry in catalog.iter_mut() {
+        if let Some(ref data_path) = entry.data_path {
+            entry.format = detect_file_format(data_path)?;
+        }
+    }
+    Ok(catalog)
+}
+
+/// Add an extension to the given archive path.
+/// Unlike `Path::with_extension`, this keeps any dots that are already in the path.
+pub fn add_extension(stem: &Path, ext: &str) -> PathBuf {
+    let mut name = stem.as_os_str().to_owned();
+    name.push(".");
+    name.push(ext);
+    PathBuf::from(name)
+}
+
+/// Find the archive for the given path, which is either the path to
+/// the archive without its extension, or the path to its name table or data file.
+/// Extensions are matched regardless of case.
+pub fn find_archive(path: &Path) -> io::Result<CatalogEntry> {
+    let stem = match get_file_kind(path) {
+        Some(_) => path.with_extension(""),
+        None => path.to_owned(),
+    };
+    let find = |extensions: [&str; 4]| {
+        extensions
+            .iter()
+            .map(|ext| add_extension(&stem, ext))
+            .find(|path| path.is_file())
+    };
+    let names_path = find(["NGC", "ngc", "NPS", "nps"]);
+    let data_path = find(["DGC", "dgc", "DPS", "dps"]);
+    let format = match data_path {
+        Some(ref data_path) => detect_file_format(data_path)?,
+        None => None,
+    };
+    Ok(CatalogEntry {
+        stem,
+        names_path,
+        data_path,
+        format,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::archive::data::{TotemArchive, TotemFile, TotemHeader};
+    use crate::archive::scan::*;
+
+    /// Create a directory with an archive of each format, and some incomplete archives
+    fn make_directory() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("libchum-scan-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("LEVELS/LVL.1")).unwrap();
+        for (stem, fmt) in [
+            ("GLOBAL", TotemFormat::NGC),
+            ("LEVELS/LVL.1/LVL", TotemFormat::PS2),
+        ]
+        .iter()
+        {
+            let files = vec![TotemFile::new(vec![0; 0x900], 1, 2, 3)];
+            let dgc = TotemArchive::new_from_files(TotemHeader::new(b"test"), files, *fmt);
+            let (names_ext, data_ext) = match fmt {
+                TotemFormat::NGC => ("NGC", "DGC"),
+                TotemFormat::PS2 => ("nps", "dps"),
+            };
+            let mut data = Vec::new();
+            dgc.write_to(&mut data).unwrap();
+            fs::write(add_extension(&dir.join(stem), data_ext), data).unwrap();
+            fs::write(add_extension(&dir.join(stem), names_ext), b"2 \"NAME\"\n").unwrap();
+        }
+        fs::write(dir.join("LEVELS/ONLY.NGC"), b"").unwrap();
+        fs::write(dir.join("LEVELS/README.TXT"), b"").unwrap();
+        dir
+    }
+
+    #[test]
+    fn scan_and_find() {
+        let dir = make_directory();
+        let catalog = scan_directory(&dir).unwrap();
+        let stems: Vec<PathBuf> = catalog.iter().map(|x| x.stem.clone()).collect();
+        assert_eq!(
+            stems,
+            vec![
+                dir.join("GLOBAL"),
+                dir.join("LEVELS/LVL.1/LVL"),
+                dir.join("LEVELS/ONLY"),
+            ]
+        );
+        assert_eq!(catalog[0].format, Some(TotemFormat::NGC));
+        assert_eq!(catalog[1].format, Some(TotemFormat::PS2));
+        assert_eq!(
+            catalog[1].names_path,
+            Some(dir.join("LEVELS/LVL.1/LVL.nps"))
+        );
+        assert!(catalog[0].is_complete() && catalog[1].is_complete());
+        assert!(!catalog[2].is_complete());
+        assert_eq!(catalog[2].format, None);
+
+        // An archive can be found from its stem, or from either of its files
+        for path in [
+            "LEVELS/LVL.1/LVL",
+            "LEVELS/LVL.1/LVL.nps",
+            "LEVELS/LVL.1/LVL.DPS",
+        ]
+        .iter()
+        {
+            assert_eq!(find_archive(&dir.join(path)).unwrap(), catalog[1]);
+        }
+        assert_eq!(find_archive(&dir.join("GLOBAL.dgc")).unwrap(), catalog[0]);
+        assert_eq!(find_archive(&dir.join("LEVELS/ONLY")).unwrap(), catalog[2]);
+        let missing = find_archive(&dir.join("MISSING.NGC")).unwrap();
+        assert_eq!(missing.stem, dir.join("MISSING"));
+        assert_eq!(missing.names_path, None);
+        assert_eq!(missing.data_path, None);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,158 @@
+use crate::archive::graph::{FileReference, ReferenceGraph};
+use crate::archive::{journal, ChumArchive};
+use crate::util;
+use std::collections::{HashMap, HashSet};
+
+/// Returns true if the given reference points from a node to its parent.
+/// Following these references would pull in the rest of the scene that the node is in.
+pub fn is_parent_reference(reference: &FileReference) -> bool {
+    reference.path == "node_parent_id"
+}
+
+/// The result of extracting a subset of an archive.
+#[derive(Clone, Debug, Default)]
+pub struct SubsetReport {
+    /// IDs of the files that were extracted, sorted by ID
+    pub files: Vec<i32>,
+    /// References from the extracted files to files that were not extracted,
+    /// either because they are not in the archive or because they were not followed
+    pub external: Vec<FileReference>,
+}
+
+impl ChumArchive {
+    /// Create a new archive with the given files, along with every file that they
+    /// reference directly or indirectly. Only references for which `follow` returns true
+    /// are followed. The new archive's name table only has the names that its files use.
+    pub fn extract_subset<F>(&self, roots: &[i32], follow: F) -> (ChumArchive, SubsetReport)
+    where
+        F: Fn(&FileReference) -> bool,
+    {
+        let graph = ReferenceGraph::new(self);
+        let closure = graph.get_transitive_dependencies(roots, follow);
+        let mut report = SubsetReport::default();
+        let mut used_ids = HashSet::new();
+        let mut files = HashMap::new();
+        for id in closure.iter() {
+            let file = match self.files.get(id) {
+                Some(file) => file,
+                None => continue,
+            };
+            report.files.push(*id);
+            used_ids.insert(*id);
+            used_ids.insert(util::hash_name_i32(file.get_type_id()));
+            used_ids.insert(util::hash_name_i32(file.get_subtype_id()));
+            // Keep names such as bone names, even if they are not files
+            used_ids.extend(graph.get_names(*id));
+            for reference in graph.get_dependencies(*id) {
+                used_ids.insert(reference.to);
+                let is_file = self.files.contains_key(&reference.to);
+                if (is_file && !closure.contains(&reference.to))
+                    || (!is_file && reference.type_id.is_some())
+                {
+                    report.external.push(reference.clone());
+                }
+            }
+            files.insert(*id, file.clone());
+        }
+        let names = self
+            .names
+            .iter()
+            .filter(|(id, _)| used_ids.contains(id))
+            .map(|(id, name)| (*id, name.clone()))
+            .collect();
+        let name_order = self
+            .name_order
+            .iter()
+            .copied()
+            .filter(|id| used_ids.contains(id))
+            .collect();
+        let archive = ChumArchive {
+            header: self.header.clone(),
+            names,
+            name_order,
+            files,
+            format: self.format,
+            chunk_size: self.chunk_size,
+            chunk_padding: Vec::new(),
+            preserve_layout: false,
+            journal: journal::EditJournal::default(),
+        };
+        (archive, report)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::archive::fixture::ArchiveBuilder;
+    use crate::archive::subset::*;
+    use crate::format::TotemFormat;
+    use crate::reader::material::Material;
+    use crate::reader::node::Node;
+    use crate::reader::skin::{Skin, VertexGroup};
+
+    #[test]
+    fn extract_subset() {
+        let node = Node {
+            node_parent_id: util::hash_name_i32("ROOT"),
+            resource_id: util::hash_name_i32("MAT"),
+            ..Default::default()
+        };
+        let skin = Skin {
+            vertex_groups: vec![VertexGroup {
+                group_id: util::hash_name_i32("BONE"),
+                sections: Vec::new(),
+            }],
+            ..Default::default()
+        };
+        let skin_node = Node {
+            resource_id: util::hash_name_i32("SKIN"),
+            ..Default::default()
+        };
+        let material = Material {
+            texture: util::hash_name_i32("TEX"),
+            texture_reflection: util::hash_name_i32("MISSING"),
+            ..Default::default()
+        };
+        let archive = ArchiveBuilder::new(TotemFormat::NGC)
+            .add_binary("CHILD", "NODE", &node)
+            .add_binary("MAT", "MATERIAL", &material)
+            .add("TEX", "BITMAP", vec![0; 4])
+            .add("ROOT", "NODE", vec![0; 4])
+            .add("OTHER", "BITMAP", vec![0; 4])
+            .add_binary("SKINNED", "NODE", &skin_node)
+            .add_binary("SKIN", "SKIN", &skin)
+            .name("BONE")
+            .build();
+
+        let child = util::hash_name_i32("CHILD");
+        let (subset, report) = archive.extract_subset(&[child], |x| !is_parent_reference(x));
+        let mut names: Vec<&str> = subset.get_files_hash().map(|x| x.1.get_name_id()).collect();
+        names.sort();
+        assert_eq!(names, vec!["CHILD", "MAT", "TEX"]);
+        assert_eq!(report.files.len(), 3);
+        let mut external: Vec<&str> = report.external.iter().map(|x| x.path.as_str()).collect();
+        external.sort();
+        assert_eq!(external, vec!["node_parent_id", "texture_reflection"]);
+        // The parent's name is kept, since the node still refers to it
+        assert_eq!(
+            subset.get_name_from_id(util::hash_name_i32("ROOT")),
+            Some("ROOT")
+        );
+        assert_eq!(subset.get_name_from_id(util::hash_name_i32("OTHER")), None);
+        assert_eq!(subset.get_name_from_id(util::hash_name_i32("BONE")), None);
+        assert!(subset
+            .get_name_from_id(util::hash_name_i32("NODE"))
+            .is_some());
+
+        let (subset, _) = archive.extract_subset(&[child], |_| true);
+        assert!(subset.get_file_from_name("ROOT").is_some());
+
+        // Bones are referred to by name
+        let (subset, report) = archive.extract_subset(&[util::hash_name_i32("SKINNED")], |_| true);
+        assert_eq!(report.files.len(), 2);
+        assert_eq!(
+            subset.get_name_from_id(util::hash_name_i32("BONE")),
+            Some("BONE")
+        );
+    }
+}
@@ -0,0 +1,421 @@
+use crate::archive::graph::ReferenceGraph;
+use crate::archive::index::DATA_OFFSET;
+use crate::archive::nametable::TotemNameTable;
+use crate::archive::packer::CHUNK_MULT;
+use crate::archive::ChumArchive;
+use crate::format::TotemFormat;
+use crate::workspace::Workspace;
+use std::collections::{HashMap, HashSet};
+use thiserror::Error;
+
+/// How serious a diagnostic is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Severity {
+    /// The archive can be read, but was probably not written by the game's tools
+    Warning,
+    /// The archive is broken, and will likely not load in game
+    Error,
+}
+
+/// A single problem found while validating an archive.
+/// Chunks and files are referred to by their index; `index` is the
+/// position of a file within its chunk.
+#[derive(Error, Clone, Debug, PartialEq, Eq)]
+pub enum Diagnostic {
+    /// The stream is too short to contain a .DGC header
+    #[error("Archive is {size} bytes long, which is too short for its header")]
+    HeaderTruncated { size: usize },
+    /// The header's chunk size is zero
+    #[error("Header chunk size is zero")]
+    ZeroChunkSize,
+    /// The header's chunk size is not a multiple of 0x800
+    #[error(
+        "Header chunk size {chunk_size:X} is not a multiple of {:X}",
+        CHUNK_MULT
+    )]
+    UnalignedChunkSize { chunk_size: usize },
+    /// The stream ends before the end of a chunk
+    #[error("Chunk {chunk} is truncated: {size} of {chunk_size} bytes present")]
+    TruncatedChunk {
+        chunk: usize,
+        size: usize,
+        chunk_size: usize,
+    },
+    /// A file's size is smaller than its own header
+    #[error("File {index} in chunk {chunk} has size {size}, which is smaller than its header")]
+    FileTooSmall {
+        chunk: usize,
+        index: usize,
+        size: usize,
+    },
+    /// A file (or its header) does not fit in the space left in its chunk
+    #[error("File {index} in chunk {chunk} needs {size} bytes, but only {available} are left in the chunk")]
+    FileOverrun {
+        chunk: usize,
+        index: usize,
+        size: usize,
+        available: usize,
+    },
+    /// The padding at the end of a chunk contains data
+    #[error("Chunk {chunk} has {count} non-zero padding bytes, starting at offset {offset:X}")]
+    NonZeroPadding {
+        chunk: usize,
+        offset: usize,
+        count: usize,
+    },
+    /// Two files share the same name ID
+    #[error("File {index} in chunk {chunk} has name ID {name_id}, which was already used in chunk {first_chunk}")]
+    DuplicateName {
+        name_id: i32,
+        chunk: usize,
+        index: usize,
+        first_chunk: usize,
+    },
+    /// An ID used by a file does not exist in the name table.
+    /// Only the first file using each ID is reported.
+    #[error("File {index} in chunk {chunk} uses ID {id}, which is missing from the NGC archive")]
+    NameMissing { id: i32, chunk: usize, index: usize },
+    /// A reference points to an ID that is not a file in this archive,
+    /// and no shared archives were given to look it up in
+    #[error(
+        "{file} references ID {id} in {path}, which is not in this archive (expected a {expected} file); it may be in a shared archive"
+    )]
+    UnresolvedReference {
+        file: String,
+        path: String,
+        id: i32,
+        expected: String,
+    },
+    /// A reference points to an ID that is not a file in this archive or any shared archive
+    #[error(
+        "{file} references ID {id} in {path}, but there is no file with that ID (expected a {expected} file)"
+    )]
+    DanglingReference {
+        file: String,
+        path: String,
+        id: i32,
+        expected: String,
+    },
+    /// A reference points to a file of the wrong type
+    #[error("{file} references {target} in {path}, which is a {found} file instead of a {expected} file")]
+    MistypedReference {
+        file: String,
+        path: String,
+        target: String,
+        expected: String,
+        found: String,
+    },
+}
+
+impl Diagnostic {
+    /// Get the severity of this diagnostic
+    pub fn get_severity(&self) -> Severity {
+        match self {
+            Diagnostic::UnalignedChunkSize { .. }
+            | Diagnostic::NonZeroPadding { .. }
+            | Diagnostic::UnresolvedReference { .. } => Severity::Warning,
+            _ => Severity::Error,
+        }
+    }
+
+    /// Returns true if this diagnostic is an error
+    pub fn is_error(&self) -> bool {
+        self.get_severity() == Severity::Error
+    }
+}
+
+/// IDs of a file found while scanning, along with its position.
+struct ScannedFile {
+    chunk: usize,
+    index: usize,
+    ids: [i32; 3],
+}
+
+/// Walk over the chunks in the given .DGC data, reporting structural problems.
+fn scan_dgc(data: &[u8], fmt: TotemFormat, diagnostics: &mut Vec<Diagnostic>) -> Vec<ScannedFile> {
+    let mut files = Vec::new();
+    if data.len() < DATA_OFFSET as usize {
+        diagnostics.push(Diagnostic::HeaderTruncated { size: data.len() });
+        return files;
+    }
+    let chunk_size = fmt.read_u32(&mut &data[0x100..0x104]).unwrap() as usize;
+    if chunk_size == 0 {
+        diagnostics.push(Diagnostic::ZeroChunkSize);
+        return files;
+    }
+    if chunk_size % CHUNK_MULT != 0 {
+        diagnostics.push(Diagnostic::UnalignedChunkSize { chunk_size });
+    }
+    let mut first_chunks = HashMap::new();
+    for (chunk_i, chunk) in data[DATA_OFFSET as usize..].chunks(chunk_size).enumerate() {
+        if chunk.len() < chunk_size {
+            diagnostics.push(Diagnostic::TruncatedChunk {
+                chunk: chunk_i,
+                size: chunk.len(),
+                chunk_size,
+            });
+        }
+        if chunk.len() < 4 {
+            continue;
+        }
+        let num_files = fmt.read_u32(&mut &chunk[0..4]).unwrap() as usize;
+        let mut offset = 4;
+        let mut intact = true;
+        for file_i in 0..num_files {
+            let available = chunk.len() - offset;
+            if available < 16 {
+                diagnostics.push(Diagnostic::FileOverrun {
+                    chunk: chunk_i,
+                    index: file_i,
+                    size: 16,
+                    available,
+                });
+                intact = false;
+                break;
+            }
+            let mut header = &chunk[offset..offset + 16];
+            let size = fmt.read_u32(&mut header).unwrap() as usize;
+            let type_id = fmt.read_i32(&mut header).unwrap();
+            let name_id = fmt.read_i32(&mut header).unwrap();
+            let subtype_id = fmt.read_i32(&mut header).unwrap();
+            if size < 16 {
+                diagnostics.push(Diagnostic::FileTooSmall {
+                    chunk: chunk_i,
+                    index: file_i,
+                    size,
+                });
+                intact = false;
+                break;
+            }
+            if size > available {
+                diagnostics.push(Diagnostic::FileOverrun {
+                    chunk: chunk_i,
+                    index: file_i,
+                    size,
+                    available,
+                });
+                intact = false;
+                break;
+            }
+            if let Some(first_chunk) = first_chunks.insert(name_id, chunk_i) {
+                first_chunks.insert(name_id, first_chunk);
+                diagnostics.push(Diagnostic::DuplicateName {
+                    name_id,
+                    chunk: chunk_i,
+                    index: file_i,
+                    first_chunk,
+                });
+            }
+            files.push(ScannedFile {
+                chunk: chunk_i,
+                index: file_i,
+                ids: [type_id, name_id, subtype_id],
+            });
+            offset += size;
+        }
+        // Padding can not be located if the files could not be read
+        if intact {
+            let padding = &chunk[offset..];
+            let count = padding.iter().filter(|x| **x != 0).count();
+            if count > 0 {
+                diagnostics.push(Diagnostic::NonZeroPadding {
+                    chunk: chunk_i,
+                    offset: offset + padding.iter().position(|x| *x != 0).unwrap(),
+                    count,
+                });
+            }
+        }
+    }
+    files
+}
+
+/// Check the raw data of a .DGC archive for structural problems.
+pub fn validate_dgc(data: &[u8], fmt: TotemFormat) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    scan_dgc(data, fmt, &mut diagnostics);
+    diagnostics
+}
+
+/// Check the raw data of a .DGC archive for structural problems,
+/// as well as check that every ID it uses exists in the given name table.
+pub fn validate(ngc: &TotemNameTable, dgc: &[u8], fmt: TotemFormat) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let files = scan_dgc(dgc, fmt, &mut diagnostics);
+    let mut reported = HashSet::new();
+    for file in files {
+        for id in file.ids.iter() {
+            if !ngc.get_names().contains_key(id) && reported.insert(*id) {
+                diagnostics.push(Diagnostic::NameMissing {
+                    id: *id,
+                    chunk: file.chunk,
+                    index: file.index,
+                });
+            }
+        }
+    }
+    diagnostics
+}
+
+/// Check that every reference in the given archive points to a file of the type it expects.
+/// References are looked up in the archive first, then in `shared`,
+/// which should contain the archives that the game loads along with this one.
+/// If `shared` is empty, references to files that are not in the archive are only warnings.
+/// References without a type, such as the names of bones, are not checked.
+pub fn validate_references(archive: &ChumArchive, shared: &Workspace) -> Vec<Diagnostic> {
+    let graph = ReferenceGraph::new(archive);
+    let get_name = |id: i32| match archive.get_name_from_id(id) {
+        Some(name) => name.to_string(),
+        None => id.to_string(),
+    };
+    let mut diagnostics = Vec::new();
+    for reference in graph.get_references() {
+        let expected = match reference.type_id {
+            Some(ref expected) => expected,
+            None => continue,
+        };
+        let target = archive
+            .get_file_from_hash(reference.to)
+            .or_else(|| shared.resolve(reference.to).map(|x| x.file));
+        match target {
+            None if shared.is_empty() => diagnostics.push(Diagnostic::UnresolvedReference {
+                file: get_name(reference.from),
+                path: reference.path.clone(),
+                id: reference.to,
+                expected: expected.clone(),
+            }),
+            None => diagnostics.push(Diagnostic::DanglingReference {
+                file: get_name(reference.from),
+                path: reference.path.clone(),
+                id: reference.to,
+                expected: expected.clone(),
+            }),
+            Some(file) if file.get_type_id() != expected => {
+                diagnostics.push(Diagnostic::MistypedReference {
+                    file: get_name(reference.from),
+                    path: reference.path.clone(),
+                    target: file.get_name_id().to_string(),
+                    expected: expected.clone(),
+                    found: file.get_type_id().to_string(),
+                })
+            }
+            Some(_) => {}
+        }
+    }
+    diagnostics
+}
+
+#[cfg(test)]
+mod test {
+    use crate::archive::data::{TotemArchive, TotemFile, TotemHeader};
+    use crate::archive::fixture::ArchiveBuilder;
+    use crate::archive::validate::*;
+    use crate::reader::material::Material;
+    use crate::util;
+
+    #[test]
+    fn validate_finds_problems() {
+        let files = vec![
+            TotemFile::new(vec![1; 100], 1, 10, 100),
+            TotemFile::new(vec![2; 100], 1, 20, 100),
+        ];
+        let dgc = TotemArchive::new_from_files(TotemHeader::new(b"test"), files, TotemFormat::PS2);
+        let mut data = Vec::new();
+        dgc.write_to(&mut data).unwrap();
+        let mut names = HashMap::new();
+        for id in [1, 10, 20, 100].iter() {
+            names.insert(*id, String::new());
+        }
+        let ngc = TotemNameTable::new(names);
+        assert_eq!(validate(&ngc, &data, TotemFormat::PS2), vec![]);
+
+        // Give the second file the first file's name, dirty the padding,
+        // and cut off the end of the chunk.
+        let second = 0x800 + 4 + 116;
+        data[second + 8] = 10;
+        data[0x900] = 0xFF;
+        data.truncate(0x800 + 0x400);
+        assert_eq!(
+            validate(&ngc, &data, TotemFormat::PS2),
+            vec![
+                Diagnostic::TruncatedChunk {
+                    chunk: 0,
+                    size: 0x400,
+                    chunk_size: 0x800,
+                },
+                Diagnostic::DuplicateName {
+                    name_id: 10,
+                    chunk: 0,
+                    index: 1,
+                    first_chunk: 0,
+                },
+                Diagnostic::NonZeroPadding {
+                    chunk: 0,
+                    offset: 0x100,
+                    count: 1,
+                },
+            ]
+        );
+
+        // A file smaller than its own header
+        data[second] = 4;
+        data.truncate(0x800 + 0x200);
+        let diagnostics = validate_dgc(&data, TotemFormat::PS2);
+        assert!(diagnostics.contains(&Diagnostic::FileTooSmall {
+            chunk: 0,
+            index: 1,
+            size: 4,
+        }));
+    }
+
+    #[test]
+    fn validate_finds_bad_references() {
+        let material = |texture: &str| Material {
+            texture: util::hash_name_i32(texture),
+            ..Default::default()
+        };
+        let archive = ArchiveBuilder::new(TotemFormat::NGC)
+            .add("TEX", "BITMAP", vec![0; 4])
+            .add_binary("GOOD", "MATERIAL", &material("TEX"))
+            .add_binary("SHARED", "MATERIAL", &material("GLOBAL"))
+            .add_binary("DANGLING", "MATERIAL", &material("MISSING"))
+            .add_binary("MISTYPED", "MATERIAL", &material("GOOD"))
+            .build();
+        let global = ArchiveBuilder::new(TotemFormat::NGC)
+            .add("GLOBAL", "BITMAP", vec![0; 4])
+            .build();
+
+        // Without shared archives, missing files may be in an archive that was not given
+        let mut shared = Workspace::new();
+        let diagnostics = validate_references(&archive, &shared);
+        assert_eq!(diagnostics.len(), 3);
+        let unresolved = diagnostics
+            .iter()
+            .filter(|x| matches!(x, Diagnostic::UnresolvedReference { .. }))
+            .count();
+        assert_eq!(unresolved, 2);
+        assert_eq!(diagnostics.iter().filter(|x| x.is_error()).count(), 1);
+        shared.add_archive("global", global);
+
+        let mut diagnostics = validate_references(&archive, &shared);
+        diagnostics.sort_by_key(|x| x.to_string());
+        assert_eq!(
+            diagnostics,
+            vec![
+                Diagnostic::DanglingReference {
+                    file: "DANGLING".to_string(),
+                    path: "texture".to_string(),
+                    id: util::hash_name_i32("MISSING"),
+                    expected: "BITMAP".to_string(),
+                },
+                Diagnostic::MistypedReference {
+                    file: "MISTYPED".to_string(),
+                    path: "texture".to_string(),
+                    target: "GOOD".to_string(),
+                    expected: "BITMAP".to_string(),
+                    found: "MATERIAL".to_string(),
+                },
+            ]
+        );
+        assert!(diagnostics.iter().all(|x| x.is_error()));
+    }
+}
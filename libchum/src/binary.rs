@@ -1,9 +1,185 @@
-// use crate::common;
-use crate::error;
+use crate::common;
+use crate::error::{self, StructUnpackError, StructUnpackResult, UnpackError};
 use crate::format::TotemFormat;
+use std::convert::{TryFrom, TryInto};
 use std::io::{self, Read, Write};
 
+pub use libchum_derive::ChumBinary;
+
 pub trait ChumBinary: Sized {
     fn read_from(file: &mut dyn Read, fmt: TotemFormat) -> error::StructUnpackResult<Self>;
     fn write_to(&self, writer: &mut dyn Write, fmt: TotemFormat) -> io::Result<()>;
 }
+
+macro_rules! impl_binary_primitive {
+    ($type:ty, $read:ident, $write:ident) => {
+        impl ChumBinary for $type {
+            fn read_from(file: &mut dyn Read, fmt: TotemFormat) -> StructUnpackResult<Self> {
+                fmt.$read(file).map_err(StructUnpackError::new)
+            }
+            fn write_to(&self, writer: &mut dyn Write, fmt: TotemFormat) -> io::Result<()> {
+                fmt.$write(writer, *self)
+            }
+        }
+    };
+}
+
+macro_rules! impl_binary_common {
+    ($type:ty, $read:path, $write:path) => {
+        impl ChumBinary for $type {
+            fn read_from(file: &mut dyn Read, fmt: TotemFormat) -> StructUnpackResult<Self> {
+                $read(file, fmt).map_err(StructUnpackError::new)
+            }
+            fn write_to(&self, writer: &mut dyn Write, fmt: TotemFormat) -> io::Result<()> {
+                $write(self, writer, fmt)
+            }
+        }
+    };
+}
+
+impl_binary_primitive!(u8, read_u8, write_u8);
+impl_binary_primitive!(i8, read_i8, write_i8);
+impl_binary_primitive!(u16, read_u16, write_u16);
+impl_binary_primitive!(i16, read_i16, write_i16);
+impl_binary_primitive!(u32, read_u32, write_u32);
+impl_binary_primitive!(i32, read_i32, write_i32);
+impl_binary_primitive!(f32, read_f32, write_f32);
+impl_binary_common!(
+    common::Transform3D,
+    common::read_transform3d,
+    common::write_transform3d
+);
+impl_binary_common!(
+    common::Transform2D,
+    common::read_transform2d,
+    common::write_transform2d
+);
+impl_binary_common!(common::Vector2, common::read_vec2, common::write_vec2);
+impl_binary_common!(common::Vector3, common::read_vec3, common::write_vec3);
+impl_binary_common!(
+    common::ColorRGBA,
+    common::read_color_rgba,
+    common::write_color_rgba
+);
+impl_binary_common!(common::Quaternion, common::read_quat, common::write_quat);
+
+impl<T: ChumBinary, const N: usize> ChumBinary for [T; N] {
+    fn read_from(file: &mut dyn Read, fmt: TotemFormat) -> StructUnpackResult<Self> {
+        let mut data = Vec::with_capacity(N);
+        for i in 0..N {
+            data.push(T::read_from(file, fmt).map_err(|e| e.in_index(i))?);
+        }
+        // The length is always N, so this never fails
+        Ok(data.try_into().ok().unwrap())
+    }
+    fn write_to(&self, writer: &mut dyn Write, fmt: TotemFormat) -> io::Result<()> {
+        for value in self.iter() {
+            value.write_to(writer, fmt)?;
+        }
+        Ok(())
+    }
+}
+
+/// A u8 that is 1 if there is a value, followed by the value
+impl<T: ChumBinary> ChumBinary for Option<T> {
+    fn read_from(file: &mut dyn Read, fmt: TotemFormat) -> StructUnpackResult<Self> {
+        match u8::read_from(file, fmt)? {
+            0 => Ok(None),
+            1 => Ok(Some(T::read_from(file, fmt)?)),
+            o => Err(StructUnpackError::new(UnpackError::InvalidEnumeration {
+                enum_name: "Optional".to_owned(),
+                value: o as i64,
+            })),
+        }
+    }
+    fn write_to(&self, writer: &mut dyn Write, fmt: TotemFormat) -> io::Result<()> {
+        match self {
+            Some(ref value) => {
+                fmt.write_u8(writer, 1)?;
+                value.write_to(writer, fmt)
+            }
+            None => fmt.write_u8(writer, 0),
+        }
+    }
+}
+
+/// Read a value that is not kept, e.g. padding.
+/// `value` is only used for its type; it is what gets written in its place.
+pub fn read_ignored<T: ChumBinary>(
+    _value: &T,
+    file: &mut dyn Read,
+    fmt: TotemFormat,
+) -> StructUnpackResult<()> {
+    T::read_from(file, fmt).map(|_| ())
+}
+
+/// Read a length of type `L`, followed by that many values
+pub fn read_vec<L, T>(file: &mut dyn Read, fmt: TotemFormat) -> StructUnpackResult<Vec<T>>
+where
+    L: ChumBinary + Into<u64>,
+    T: ChumBinary,
+{
+    let len: u64 = L::read_from(file, fmt)?.into();
+    let mut data = Vec::with_capacity((len as usize).min(common::SAFE_CAPACITY_BIG));
+    for i in 0..len as usize {
+        data.push(T::read_from(file, fmt).map_err(|e| e.in_index(i))?);
+    }
+    Ok(data)
+}
+
+/// Write the length of `values` as type `L`, followed by the values
+pub fn write_vec<L, T>(values: &[T], writer: &mut dyn Write, fmt: TotemFormat) -> io::Result<()>
+where
+    L: ChumBinary + TryFrom<usize>,
+    T: ChumBinary,
+{
+    let len = L::try_from(values.len()).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} elements is too many to write", values.len()),
+        )
+    })?;
+    len.write_to(writer, fmt)?;
+    for value in values.iter() {
+        value.write_to(writer, fmt)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::binary::*;
+    use crate::reader::node::Node;
+
+    #[test]
+    fn read_errors() {
+        let fmt = TotemFormat::NGC;
+        let mut data = Vec::new();
+        Node::default().write_to(&mut data, fmt).unwrap();
+        assert!(Node::read_from(&mut data.as_slice(), fmt).is_ok());
+
+        let error = Node::read_from(&mut &data[..10], fmt).err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "Could not read into Node::node_unk_ids[1]: failed to fill whole buffer"
+        );
+
+        // node_data's tag comes after three references
+        data[20..24].copy_from_slice(&5i32.to_be_bytes());
+        match Node::read_from(&mut data.as_slice(), fmt) {
+            Err(StructUnpackError {
+                structpath,
+                error:
+                    UnpackError::InvalidEnumeration {
+                        enum_name,
+                        value: 5,
+                    },
+                ..
+            }) => {
+                assert_eq!(structpath, "node_data");
+                assert_eq!(enum_name, "NodeDataUnion");
+            }
+            _ => panic!("Expected an invalid enumeration error"),
+        }
+    }
+}
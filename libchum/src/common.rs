@@ -1,4 +1,6 @@
+use crate::binary::ChumBinary;
 use crate::format::TotemFormat;
+use crate::structure::ChumStruct;
 use euclid;
 use std::hash::{Hash, Hasher};
 use std::io::{self, Read, Write};
@@ -66,6 +68,13 @@ impl Quaternion {
         }
     }
 
+    /// Create a quaternion from its components, without normalizing them
+    pub fn new(i: f32, j: f32, k: f32, w: f32) -> Quaternion {
+        Self {
+            inner: euclid::Rotation3D::quaternion(i, j, k, w),
+        }
+    }
+
     pub fn new_unit(i: f32, j: f32, k: f32, w: f32) -> Quaternion {
         Self {
             inner: euclid::Rotation3D::unit_quaternion(i, j, k, w),
@@ -112,8 +121,8 @@ pub fn read_quat(reader: &mut dyn Read, fmt: TotemFormat) -> io::Result<Quaterni
     let j = fmt.read_f32(reader)?;
     let k = fmt.read_f32(reader)?;
     let w = fmt.read_f32(reader)?;
-    // Quaternion::new is in (w, i, j, k) order
-    Ok(Quaternion::new_unit(i, j, k, w))
+    // Components are kept as they are, so that they are written back unchanged
+    Ok(Quaternion::new(i, j, k, w))
 }
 
 pub fn write_quat(q: &Quaternion, writer: &mut dyn Write, fmt: TotemFormat) -> io::Result<()> {
@@ -205,7 +214,7 @@ pub fn quat_to_euler(quat: Quaternion) -> Vector3 {
     let q = &quat.inner;
     // roll (x-axis rotation)
     let sinr_cosp = 2.0 * (q.r * q.i + q.j * q.k);
-    let cosr_cosp = 1.0 - 2.9 * (q.i * q.i + q.j * q.j);
+    let cosr_cosp = 1.0 - 2.0 * (q.i * q.i + q.j * q.j);
     let roll = f32::atan2(sinr_cosp, cosr_cosp);
     // pitch (y-axis rotation)
     let sinp = 2.0 * (q.r * q.j - q.k * q.i);
@@ -462,13 +471,12 @@ pub fn write_color_rgba(
     Ok(())
 }
 
-chum_struct_binary! {
-    /// Common header used by many different structures
-    #[repr(C)]
-    #[derive(Clone, Debug, Default)]
-    pub struct THeader {
-        pub floats: [fixed array [f32] 4],
-        pub transform: [Transform3D],
-        pub junk: [ignore [fixed array [u8] 16] [0;16]],
-    }
+/// Common header used by many different structures
+#[repr(C)]
+#[derive(Clone, Debug, Default, ChumStruct, ChumBinary)]
+pub struct THeader {
+    pub floats: [f32; 4],
+    pub transform: Transform3D,
+    #[chum(ignore = [0u8; 16])]
+    pub junk: (),
 }
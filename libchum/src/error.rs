@@ -13,14 +13,99 @@ pub enum UnpackError {
     Io(#[from] std::io::Error),
 }
 
-/// Error that can occur while destructuring
+/// Error that can occur while destructuring.
+/// `path` is the path to the value that could not be destructured, e.g. `nodes[3].transform`;
+/// it is empty for the structure itself.
 #[derive(Debug, thiserror::Error)]
 pub enum DestructureError {
-    #[error("Invalid value variant {value}; expected one of {expected:?}")]
+    #[error("{}: expected {expected}, found {found}", display_path(.path))]
+    TypeMismatch {
+        path: String,
+        expected: &'static str,
+        found: &'static str,
+    },
+    #[error("{}: {value} is out of range; expected {min} to {max}", display_path(.path))]
+    OutOfRange {
+        path: String,
+        value: i64,
+        min: i64,
+        max: i64,
+    },
+    #[error("{}: invalid value {value} for enumeration {enum_name}", display_path(.path))]
+    InvalidEnumeration {
+        path: String,
+        enum_name: String,
+        value: i64,
+    },
+    #[error("{}: invalid value variant {value}; expected one of {expected:?}", display_path(.path))]
     InvalidVariant {
+        path: String,
         expected: Vec<String>,
         value: String,
     },
+    #[error("{}: missing member", display_path(.path))]
+    MissingMember { path: String },
+    #[error("{}: expected {expected} elements, found {found}", display_path(.path))]
+    WrongLength {
+        path: String,
+        expected: usize,
+        found: usize,
+    },
+    #[error("{}: transform can not be inverted", display_path(.path))]
+    NotInvertible { path: String },
+}
+
+fn display_path(path: &str) -> &str {
+    if path.is_empty() {
+        "(root)"
+    } else {
+        path
+    }
+}
+
+impl DestructureError {
+    /// Get the path to the value that could not be destructured
+    pub fn get_path(&self) -> &str {
+        use DestructureError::*;
+        match self {
+            TypeMismatch { ref path, .. }
+            | OutOfRange { ref path, .. }
+            | InvalidEnumeration { ref path, .. }
+            | InvalidVariant { ref path, .. }
+            | MissingMember { ref path }
+            | WrongLength { ref path, .. }
+            | NotInvertible { ref path } => path,
+        }
+    }
+
+    fn prepend(mut self, prefix: String) -> DestructureError {
+        use DestructureError::*;
+        let path = match self {
+            TypeMismatch { ref mut path, .. }
+            | OutOfRange { ref mut path, .. }
+            | InvalidEnumeration { ref mut path, .. }
+            | InvalidVariant { ref mut path, .. }
+            | MissingMember { ref mut path }
+            | WrongLength { ref mut path, .. }
+            | NotInvertible { ref mut path } => path,
+        };
+        *path = if path.is_empty() || path.starts_with('[') {
+            format!("{}{}", prefix, path)
+        } else {
+            format!("{}.{}", prefix, path)
+        };
+        self
+    }
+
+    /// Prepend a struct member to this error's path
+    pub fn prepend_member(self, name: &str) -> DestructureError {
+        self.prepend(name.to_owned())
+    }
+
+    /// Prepend an array index to this error's path
+    pub fn prepend_index(self, index: usize) -> DestructureError {
+        self.prepend(format!("[{}]", index))
+    }
 }
 
 /// Error that occurs when failing to read a structure
@@ -32,6 +117,39 @@ pub struct StructUnpackError {
 }
 
 impl StructUnpackError {
+    /// Create an error with an empty struct name and path, which the caller fills in
+    pub fn new(error: impl Into<UnpackError>) -> StructUnpackError {
+        StructUnpackError {
+            structname: String::new(),
+            structpath: String::new(),
+            error: error.into(),
+        }
+    }
+
+    fn prepend_path(mut self, prefix: &str) -> StructUnpackError {
+        if prefix.is_empty() {
+            return self;
+        }
+        self.structpath = if self.structpath.is_empty() || self.structpath.starts_with('[') {
+            format!("{}{}", prefix, self.structpath)
+        } else {
+            format!("{}.{}", prefix, self.structpath)
+        };
+        self
+    }
+
+    /// Set the struct that this error occurred in, and prepend the field to its path
+    pub fn in_field(self, structname: &str, field: &str) -> StructUnpackError {
+        let mut error = self.prepend_path(field);
+        error.structname = structname.to_owned();
+        error
+    }
+
+    /// Prepend an array index to this error's path
+    pub fn in_index(self, index: usize) -> StructUnpackError {
+        self.prepend_path(&format!("[{}]", index))
+    }
+
     pub fn prepend(self, s: &str) -> StructUnpackError {
         StructUnpackError {
             structname: self.structname,
@@ -54,11 +172,19 @@ impl StructUnpackError {
 
 impl fmt::Display for StructUnpackError {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            fmt,
-            "Could not read into {}::{}: {}",
-            self.structname, self.structpath, self.error
-        )
+        if self.structpath.is_empty() {
+            write!(
+                fmt,
+                "Could not read into {}: {}",
+                self.structname, self.error
+            )
+        } else {
+            write!(
+                fmt,
+                "Could not read into {}::{}: {}",
+                self.structname, self.structpath, self.error
+            )
+        }
     }
 }
 
@@ -1,16 +1,86 @@
 use byteorder::{BigEndian, LittleEndian, ReadBytesExt, WriteBytesExt};
-use std::io::{self, Read, Write};
+use std::io::{self, Read, Seek, SeekFrom, Write};
 
 /// The format of the Totem archive.
 /// There is one main difference between the Gamecube builds and Playstation 2 builds:
 /// The Gamecube is big-endian, while the Playstation 2 is little-endian.
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum TotemFormat {
     NGC, // Gamecube
     PS2, // Playstation2
 }
 
+/// Error that can occur while detecting the format of an archive.
+#[derive(Debug, thiserror::Error)]
+pub enum DetectError {
+    #[error("Data is not a valid archive in either format")]
+    Unrecognized,
+    #[error("Data is a valid archive in both formats")]
+    Ambiguous,
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
 impl TotemFormat {
+    /// Check if the start of a .DGC file makes sense when read in this format.
+    fn is_plausible(&self, data: &[u8], size: Option<u64>) -> bool {
+        let read_u32 = |offset: usize| self.read_u32(&mut &data[offset..offset + 4]).unwrap();
+        if data.len() < 0x104 {
+            return false;
+        }
+        // Chunk sizes are always a non-zero multiple of 0x800,
+        // and the data after the header is made up of whole chunks.
+        let chunk_size = read_u32(0x100) as u64;
+        if chunk_size == 0 || chunk_size % 0x800 != 0 {
+            return false;
+        }
+        if let Some(size) = size {
+            if size < 0x800 || (size - 0x800) % chunk_size != 0 {
+                return false;
+            }
+        }
+        // The first chunk's files must fit inside of it.
+        if data.len() >= 0x804 {
+            let num_files = read_u32(0x800) as u64;
+            if 4 + num_files * 16 > chunk_size {
+                return false;
+            }
+            if num_files > 0 && data.len() >= 0x808 {
+                let file_size = read_u32(0x804) as u64;
+                if file_size < 16 || file_size > chunk_size - 4 {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+
+    /// Detect the format of a .DGC file from its header and the start of its first chunk.
+    /// `data` should contain at least the first 0x808 bytes of the file,
+    /// and `size` is the size of the whole file, if it is known.
+    pub fn detect(data: &[u8], size: Option<u64>) -> Result<TotemFormat, DetectError> {
+        match (
+            TotemFormat::NGC.is_plausible(data, size),
+            TotemFormat::PS2.is_plausible(data, size),
+        ) {
+            (true, false) => Ok(TotemFormat::NGC),
+            (false, true) => Ok(TotemFormat::PS2),
+            (true, true) => Err(DetectError::Ambiguous),
+            (false, false) => Err(DetectError::Unrecognized),
+        }
+    }
+
+    /// Detect the format of the .DGC file in the given reader.
+    /// The reader is returned to the start of the file afterwards.
+    pub fn detect_from<R: Read + Seek>(reader: &mut R) -> Result<TotemFormat, DetectError> {
+        let size = reader.seek(SeekFrom::End(0))?;
+        reader.seek(SeekFrom::Start(0))?;
+        let mut data = Vec::with_capacity(0x808);
+        reader.by_ref().take(0x808).read_to_end(&mut data)?;
+        reader.seek(SeekFrom::Start(0))?;
+        TotemFormat::detect(&data, Some(size))
+    }
+
     // writing functions
     pub fn write_u32(&self, writer: &mut dyn Write, value: u32) -> io::Result<()> {
         match self {
@@ -180,3 +250,62 @@ impl TotemFormat {
         reader.read_i8_into(dst)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::format::*;
+
+    fn make_header(chunk_size: [u8; 4], num_files: [u8; 4], file_size: [u8; 4]) -> Vec<u8> {
+        let mut data = vec![0u8; 0x808];
+        data[0x100..0x104].copy_from_slice(&chunk_size);
+        data[0x800..0x804].copy_from_slice(&num_files);
+        data[0x804..0x808].copy_from_slice(&file_size);
+        data
+    }
+
+    #[test]
+    fn detect_format() {
+        let data = make_header(
+            0x10000u32.to_be_bytes(),
+            2u32.to_be_bytes(),
+            100u32.to_be_bytes(),
+        );
+        assert_eq!(
+            TotemFormat::detect(&data, Some(0x20800)).unwrap(),
+            TotemFormat::NGC
+        );
+        let data = make_header(
+            0x10000u32.to_le_bytes(),
+            2u32.to_le_bytes(),
+            100u32.to_le_bytes(),
+        );
+        assert_eq!(TotemFormat::detect(&data, None).unwrap(), TotemFormat::PS2);
+        let mut file = data.clone();
+        file.resize(0x10800, 0);
+        assert_eq!(
+            TotemFormat::detect_from(&mut io::Cursor::new(file)).unwrap(),
+            TotemFormat::PS2
+        );
+
+        // 0x1800 big-endian is 0x180000 little-endian; only the file count tells them apart
+        let data = make_header(
+            0x1800u32.to_be_bytes(),
+            0x100u32.to_be_bytes(),
+            16u32.to_be_bytes(),
+        );
+        assert_eq!(TotemFormat::detect(&data, None).unwrap(), TotemFormat::NGC);
+        let data = make_header(0x1800u32.to_be_bytes(), [0, 0, 0, 0], [0, 0, 0, 0]);
+        assert!(matches!(
+            TotemFormat::detect(&data, None),
+            Err(DetectError::Ambiguous)
+        ));
+        assert_eq!(
+            TotemFormat::detect(&data, Some(0x800 + 0x1800)).unwrap(),
+            TotemFormat::NGC
+        );
+        assert!(matches!(
+            TotemFormat::detect(&data[..0x100], None),
+            Err(DetectError::Unrecognized)
+        ));
+    }
+}
@@ -1,3 +1,6 @@
+// Lets the derive macros refer to `::libchum` from inside of this crate
+extern crate self as libchum;
+
 #[macro_use]
 pub mod macros;
 pub mod animsymbol;
@@ -6,9 +9,11 @@ pub mod binary;
 pub mod common;
 pub mod error;
 pub mod format;
+pub mod names;
 pub mod reader;
 pub mod scene;
 pub mod structure;
 pub mod util;
+pub mod workspace;
 
 pub use gltf;
@@ -0,0 +1,393 @@
+//! Recover names from their hashes by trying many candidate names.
+//! Candidates come from wordlists, templates such as
+//! `DB:>LEVELS>{LEVEL}>{NAME}.TWORLD`, and the parts of names that are already known.
+
+use crate::util;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::str::FromStr;
+use thiserror::Error;
+
+/// Templates that are worth trying for any archive.
+pub const DEFAULT_TEMPLATES: &[&str] = &["DYN{n}", "{NAME}{n}"];
+
+/// An error in a template, or in the values given for its placeholders.
+#[derive(Error, Clone, Debug, PartialEq, Eq)]
+pub enum TemplateError {
+    #[error("Placeholder starting at column {0} is not closed")]
+    UnterminatedPlaceholder(usize),
+    #[error("Placeholder at column {0} has no name")]
+    EmptyPlaceholder(usize),
+    #[error("No values were given for placeholder {{{0}}}")]
+    UnknownPlaceholder(String),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum TemplatePart {
+    Text(String),
+    Placeholder(String),
+    Number,
+}
+
+/// A pattern for candidate names.
+/// `{NAME}` is replaced by each value of the variable NAME,
+/// and `{n}` is replaced by each number up to the cracker's maximum number.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Template {
+    parts: Vec<TemplatePart>,
+}
+
+impl Template {
+    /// Parse a template from a pattern.
+    pub fn parse(pattern: &str) -> Result<Template, TemplateError> {
+        let mut parts = Vec::new();
+        let mut rest = pattern;
+        while let Some(start) = rest.find('{') {
+            let column = pattern.len() - rest.len() + start + 1;
+            if start > 0 {
+                parts.push(TemplatePart::Text(rest[..start].to_string()));
+            }
+            let end = rest[start..]
+                .find('}')
+                .ok_or(TemplateError::UnterminatedPlaceholder(column))?
+                + start;
+            let name = &rest[start + 1..end];
+            parts.push(match name {
+                "" => return Err(TemplateError::EmptyPlaceholder(column)),
+                "n" => TemplatePart::Number,
+                _ => TemplatePart::Placeholder(name.to_string()),
+            });
+            rest = &rest[end + 1..];
+        }
+        if !rest.is_empty() {
+            parts.push(TemplatePart::Text(rest.to_string()));
+        }
+        Ok(Template { parts })
+    }
+
+    /// Get the names of every placeholder in this template, except for `{n}`
+    pub fn get_placeholders(&self) -> impl Iterator<Item = &str> {
+        self.parts.iter().filter_map(|part| match part {
+            TemplatePart::Placeholder(ref name) => Some(name.as_str()),
+            _ => None,
+        })
+    }
+}
+
+impl FromStr for Template {
+    type Err = TemplateError;
+
+    fn from_str(pattern: &str) -> Result<Template, TemplateError> {
+        Template::parse(pattern)
+    }
+}
+
+/// The parts of known names, used to build new candidates.
+#[derive(Clone, Debug, Default)]
+pub struct NameParts {
+    /// Every directory of every name, including the trailing `>`, e.g. `DB:>LEVELS>`
+    pub dirs: BTreeSet<String>,
+    /// The last component of every name without its extension, e.g. `ROCK`
+    pub names: BTreeSet<String>,
+    /// Every component of every name without extensions, e.g. `DB:`, `LEVELS` and `ROCK`
+    pub components: BTreeSet<String>,
+    /// Every extension without its dot, e.g. `TWORLD`
+    pub extensions: BTreeSet<String>,
+}
+
+impl NameParts {
+    /// Create an empty set of parts
+    pub fn new() -> NameParts {
+        NameParts::default()
+    }
+
+    /// Split the given names into parts
+    pub fn from_names<I, S>(names: I) -> NameParts
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut parts = NameParts::new();
+        for name in names {
+            parts.add_name(name.as_ref());
+        }
+        parts
+    }
+
+    /// Split a name such as `DB:>LEVELS>BB>ROCK.TWORLD` into parts
+    pub fn add_name(&mut self, name: &str) {
+        let mut components: Vec<&str> = name.split('>').collect();
+        let file = components.pop().unwrap_or("");
+        let stem = match file.rfind('.') {
+            Some(i) if i > 0 => {
+                self.extensions.insert(file[i + 1..].to_string());
+                &file[..i]
+            }
+            _ => file,
+        };
+        let mut dir = String::new();
+        for component in components {
+            dir.push_str(component);
+            dir.push('>');
+            self.dirs.insert(dir.clone());
+            self.components.insert(component.to_string());
+        }
+        if !stem.is_empty() {
+            self.names.insert(stem.to_string());
+            self.components.insert(stem.to_string());
+        }
+    }
+
+    /// Get the parts as template variables: DIR, NAME, COMPONENT and EXT.
+    pub fn get_variables(&self) -> HashMap<String, Vec<String>> {
+        let mut variables = HashMap::new();
+        let mut add = |name: &str, values: &BTreeSet<String>| {
+            variables.insert(name.to_string(), values.iter().cloned().collect());
+        };
+        add("DIR", &self.dirs);
+        add("NAME", &self.names);
+        add("COMPONENT", &self.components);
+        add("EXT", &self.extensions);
+        variables
+    }
+}
+
+/// Swap the `L_` or `R_` prefix of the last component of a name,
+/// e.g. `L_HIP` becomes `R_HIP`.
+pub fn mirror_name(name: &str) -> Option<String> {
+    let start = name.rfind('>').map(|i| i + 1).unwrap_or(0);
+    let (dir, file) = name.split_at(start);
+    if let Some(rest) = file.strip_prefix("L_") {
+        return Some(format!("{}R_{}", dir, rest));
+    }
+    file.strip_prefix("R_")
+        .map(|rest| format!("{}L_{}", dir, rest))
+}
+
+/// Finds the names of hashed IDs by trying candidate names.
+pub struct NameCracker {
+    targets: HashSet<i32>,
+    found: BTreeMap<i32, String>,
+    max_number: u32,
+    tried: u64,
+}
+
+impl NameCracker {
+    /// Create a cracker that searches for the names of the given IDs.
+    /// ID 0 is the hash of the empty string, and is never searched for.
+    pub fn new<I: IntoIterator<Item = i32>>(targets: I) -> NameCracker {
+        NameCracker {
+            targets: targets.into_iter().filter(|x| *x != 0).collect(),
+            found: BTreeMap::new(),
+            max_number: 99,
+            tried: 0,
+        }
+    }
+
+    /// Set the largest number that `{n}` is replaced by in templates
+    pub fn set_max_number(&mut self, max_number: u32) {
+        self.max_number = max_number;
+    }
+
+    /// Get the IDs whose names have not been found yet
+    pub fn get_remaining(&self) -> impl Iterator<Item = i32> + '_ {
+        self.targets
+            .iter()
+            .copied()
+            .filter(move |id| !self.found.contains_key(id))
+    }
+
+    /// Returns true if every name has been found
+    pub fn is_done(&self) -> bool {
+        self.found.len() == self.targets.len()
+    }
+
+    /// Get the names that were found, sorted by ID
+    pub fn get_found(&self) -> &BTreeMap<i32, String> {
+        &self.found
+    }
+
+    /// Claim the names that were found
+    pub fn take_found(self) -> BTreeMap<i32, String> {
+        self.found
+    }
+
+    /// Get the number of candidates that have been tried
+    pub fn get_tried(&self) -> u64 {
+        self.tried
+    }
+
+    /// Try a single candidate name.
+    /// Returns true if it is the name of an ID that had not been found yet.
+    pub fn try_name(&mut self, name: &str) -> bool {
+        self.tried += 1;
+        let id = util::hash_name_i32(name);
+        if self.targets.contains(&id) && !self.found.contains_key(&id) {
+            self.found.insert(id, name.to_string());
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Try every name in the given list, e.g. the lines of a wordlist.
+    /// Returns the number of names found.
+    pub fn try_names<I, S>(&mut self, names: I) -> usize
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        names
+            .into_iter()
+            .filter(|name| self.try_name(name.as_ref()))
+            .count()
+    }
+
+    /// Try the left and right versions of the given names, as used for bones.
+    /// Names that start with `L_` or `R_` are mirrored,
+    /// and other names are tried with both prefixes.
+    /// Returns the number of names found.
+    pub fn try_mirrored<I, S>(&mut self, names: I) -> usize
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut count = 0;
+        for name in names {
+            let name = name.as_ref();
+            match mirror_name(name) {
+                Some(mirrored) => count += self.try_name(&mirrored) as usize,
+                None => {
+                    count += self.try_name(&format!("L_{}", name)) as usize;
+                    count += self.try_name(&format!("R_{}", name)) as usize;
+                }
+            }
+        }
+        count
+    }
+
+    /// Try every name that the given template can produce.
+    /// Every placeholder must have values in `variables`.
+    /// Returns the number of names found.
+    pub fn try_template(
+        &mut self,
+        template: &Template,
+        variables: &HashMap<String, Vec<String>>,
+    ) -> Result<usize, TemplateError> {
+        let numbers: Vec<String> = (0..=self.max_number).map(|x| x.to_string()).collect();
+        let mut choices: Vec<&[String]> = Vec::with_capacity(template.parts.len());
+        for part in template.parts.iter() {
+            choices.push(match part {
+                TemplatePart::Text(ref text) => std::slice::from_ref(text),
+                TemplatePart::Number => &numbers,
+                TemplatePart::Placeholder(ref name) => variables
+                    .get(name)
+                    .ok_or_else(|| TemplateError::UnknownPlaceholder(name.clone()))?,
+            });
+        }
+        Ok(self.try_product(&choices))
+    }
+
+    /// Try every combination of a directory, name and extension from the given parts,
+    /// along with every combination of a directory and name without an extension.
+    /// Returns the number of names found.
+    pub fn try_recombined(&mut self, parts: &NameParts) -> usize {
+        let dirs: Vec<String> = parts.dirs.iter().cloned().collect();
+        let names: Vec<String> = parts.names.iter().cloned().collect();
+        let mut extensions: Vec<String> =
+            parts.extensions.iter().map(|x| format!(".{}", x)).collect();
+        extensions.push(String::new());
+        self.try_product(&[&dirs, &names, &extensions])
+    }
+
+    /// Try the concatenation of every combination of one value from each choice.
+    fn try_product(&mut self, choices: &[&[String]]) -> usize {
+        if choices.iter().any(|x| x.is_empty()) {
+            return 0;
+        }
+        let mut count = 0;
+        let mut indices = vec![0; choices.len()];
+        let mut name = String::new();
+        loop {
+            name.clear();
+            for (choice, i) in choices.iter().zip(indices.iter()) {
+                name.push_str(&choice[*i]);
+            }
+            if self.try_name(&name) {
+                count += 1;
+                if self.is_done() {
+                    return count;
+                }
+            }
+            // Advance to the next combination, with the last choice changing fastest
+            let mut pos = choices.len();
+            loop {
+                if pos == 0 {
+                    return count;
+                }
+                pos -= 1;
+                indices[pos] += 1;
+                if indices[pos] < choices[pos].len() {
+                    break;
+                }
+                indices[pos] = 0;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::names::crack::*;
+
+    #[test]
+    fn crack_names() {
+        let targets = [
+            "DB:>LEVELS>CC>ROCK.TWORLD",
+            "DYN12",
+            "R_HIP",
+            "DB:>LEVELS>BB>TREE.BITMAP",
+            "NOT_A_KNOWN_NAME",
+        ];
+        let mut cracker = NameCracker::new(targets.iter().map(|x| util::hash_name_i32(x)));
+        let known = [
+            "DB:>LEVELS>AA>ROCK.TWORLD",
+            "DB:>LEVELS>BB>TREE.MATERIAL",
+            "DB:>LEVELS>AA>SKY.BITMAP",
+            "L_HIP",
+        ];
+        let parts = NameParts::from_names(known.iter());
+        assert!(parts.dirs.contains("DB:>LEVELS>BB>"));
+        assert!(parts.components.contains("LEVELS"));
+        assert_eq!(parts.extensions.len(), 3);
+
+        assert_eq!(cracker.try_names(known.iter()), 0);
+        assert_eq!(cracker.try_mirrored(known.iter()), 1);
+        assert_eq!(cracker.try_recombined(&parts), 1);
+
+        let mut variables = parts.get_variables();
+        let template = Template::parse("DB:>LEVELS>{LEVEL}>{NAME}.TWORLD").unwrap();
+        assert_eq!(
+            cracker.try_template(&template, &variables),
+            Err(TemplateError::UnknownPlaceholder("LEVEL".to_string()))
+        );
+        variables.insert(
+            "LEVEL".to_string(),
+            vec!["BB".to_string(), "CC".to_string()],
+        );
+        assert_eq!(cracker.try_template(&template, &variables), Ok(1));
+        let template = Template::parse("DYN{n}").unwrap();
+        assert_eq!(cracker.try_template(&template, &variables), Ok(1));
+        assert_eq!(
+            Template::parse("DB:>{LEVEL"),
+            Err(TemplateError::UnterminatedPlaceholder(5))
+        );
+
+        let found: Vec<&str> = cracker.get_found().values().map(|x| x.as_str()).collect();
+        assert_eq!(found.len(), 4);
+        assert!(found.contains(&"DYN12"));
+        assert!(found.contains(&"DB:>LEVELS>BB>TREE.BITMAP"));
+        let remaining: Vec<i32> = cracker.get_remaining().collect();
+        assert_eq!(remaining, vec![util::hash_name_i32("NOT_A_KNOWN_NAME")]);
+        assert!(!cracker.is_done());
+    }
+}
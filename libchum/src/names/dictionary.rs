@@ -0,0 +1,187 @@
+//! Names that are used by the game but are not stored in any archive's name table,
+//! such as the names of bones and dynamic nodes.
+//!
+//! Dictionaries are text files with one name on each line. A line is either
+//! an ID followed by its quoted name, as in an NGC file, or just a name,
+//! in which case its ID is the name's hash. Lines that start with `#` are ignored.
+
+use crate::archive::nametable::{self, NameTableError};
+use crate::archive::ChumError;
+use crate::util;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::Path;
+
+const BUILTIN_NAMES: &str = include_str!("../../res/names.txt");
+
+/// A set of names that can be looked up by their ID.
+#[derive(Clone, Debug, Default)]
+pub struct NameDictionary {
+    names: HashMap<i32, String>,
+}
+
+impl NameDictionary {
+    /// Create a new, empty dictionary
+    pub fn new() -> NameDictionary {
+        NameDictionary::default()
+    }
+
+    /// Get the dictionary of names that is included with libchum
+    pub fn builtin() -> NameDictionary {
+        NameDictionary::parse(BUILTIN_NAMES).expect("The built-in name dictionary is invalid")
+    }
+
+    /// Get the built-in dictionary merged with the user dictionaries at the given paths.
+    /// Names in later dictionaries replace names with the same ID in earlier ones.
+    pub fn with_user_dictionaries<I, P>(paths: I) -> Result<NameDictionary, ChumError>
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<Path>,
+    {
+        let mut dictionary = NameDictionary::builtin();
+        for path in paths {
+            dictionary.merge(NameDictionary::load(path.as_ref())?);
+        }
+        Ok(dictionary)
+    }
+
+    /// Read a dictionary from the given Reader
+    pub fn read_from<R: Read>(reader: &mut R) -> Result<NameDictionary, ChumError> {
+        let mut text = String::new();
+        reader.read_to_string(&mut text)?;
+        Ok(NameDictionary::parse(&text)?)
+    }
+
+    /// Read the dictionary file at the given path
+    pub fn load(path: &Path) -> Result<NameDictionary, ChumError> {
+        NameDictionary::read_from(&mut File::open(path)?)
+    }
+
+    fn parse(text: &str) -> Result<NameDictionary, NameTableError> {
+        let mut names = HashMap::new();
+        for (line_i, line) in text.lines().enumerate() {
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+            if !trimmed.contains('"') {
+                names.insert(util::hash_name_i32(trimmed), trimmed.to_string());
+                continue;
+            }
+            match nametable::parse_line(line) {
+                Ok(Some((id, name))) => {
+                    names.insert(id, nametable::unescape_name(&name));
+                }
+                Ok(None) => {}
+                Err((column, kind)) => {
+                    return Err(NameTableError {
+                        line: line_i + 1,
+                        column,
+                        kind,
+                    })
+                }
+            }
+        }
+        Ok(NameDictionary { names })
+    }
+
+    /// Write this dictionary to the given Writer, sorted by name.
+    /// Every name is written along with its ID.
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let mut names: Vec<(&i32, &String)> = self.names.iter().collect();
+        names.sort_by(|a, b| (a.1, a.0).cmp(&(b.1, b.0)));
+        for (id, name) in names {
+            writeln!(writer, "{} \"{}\"", id, nametable::escape_name(name))?;
+        }
+        Ok(())
+    }
+
+    /// Add every name from another dictionary to this one.
+    /// Names from `other` replace names with the same ID.
+    pub fn merge(&mut self, other: NameDictionary) {
+        self.names.extend(other.names);
+    }
+
+    /// Add a name with the given ID, returning the name it replaced
+    pub fn insert(&mut self, id: i32, name: String) -> Option<String> {
+        self.names.insert(id, name)
+    }
+
+    /// Add a name, returning its ID
+    pub fn add_name(&mut self, name: &str) -> i32 {
+        let id = util::hash_name_i32(name);
+        self.names.insert(id, name.to_string());
+        id
+    }
+
+    /// Get the name with the given ID
+    pub fn get_name(&self, id: i32) -> Option<&str> {
+        self.names.get(&id).map(|x| x.as_str())
+    }
+
+    /// Returns true if this dictionary has a name for the given ID
+    pub fn contains_id(&self, id: i32) -> bool {
+        self.names.contains_key(&id)
+    }
+
+    /// Get the number of names in this dictionary
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    /// Returns true if this dictionary has no names
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// Get this dictionary's name map
+    pub fn get_names(&self) -> &HashMap<i32, String> {
+        &self.names
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::archive::nametable::NameTableErrorKind;
+    use crate::names::dictionary::*;
+
+    #[test]
+    fn dictionary_parse() {
+        let builtin = NameDictionary::builtin();
+        assert_eq!(
+            builtin.get_name(util::hash_name_i32("R_HIP")),
+            Some("R_HIP")
+        );
+        // Some built-in IDs do not match the hash of their name
+        assert_eq!(builtin.get_name(-1276508687), Some("NOSE"));
+
+        let text = "# comment\n\nL_FOOT\n  -5 \"odd \\\"name\\\"\"\r\nDB:>LEVELS>BB\n";
+        let mut dictionary = NameDictionary::read_from(&mut text.as_bytes()).unwrap();
+        assert_eq!(dictionary.len(), 3);
+        assert_eq!(
+            dictionary.get_name(util::hash_name_i32("L_FOOT")),
+            Some("L_FOOT")
+        );
+        assert_eq!(dictionary.get_name(-5), Some("odd \"name\""));
+        assert!(dictionary.contains_id(util::hash_name_i32("DB:>LEVELS>BB")));
+
+        let mut data = Vec::new();
+        dictionary.write_to(&mut data).unwrap();
+        let read = NameDictionary::read_from(&mut data.as_slice()).unwrap();
+        assert_eq!(read.get_names(), dictionary.get_names());
+
+        let mut other = NameDictionary::new();
+        other.insert(-5, "REPLACED".to_string());
+        dictionary.merge(other);
+        assert_eq!(dictionary.get_name(-5), Some("REPLACED"));
+
+        match NameDictionary::read_from(&mut &b"NAME\n12 \"BROKEN\n"[..]) {
+            Err(ChumError::NameTableError(error)) => {
+                assert_eq!(error.line, 2);
+                assert_eq!(error.kind, NameTableErrorKind::UnterminatedName);
+            }
+            _ => panic!("Expected a name table error"),
+        }
+    }
+}
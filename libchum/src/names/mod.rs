@@ -0,0 +1,10 @@
+//! Names that IDs are hashed from.
+//! Most IDs can be looked up in an archive's name table,
+//! but references may use names that are not stored in any archive.
+//! Those names are kept in dictionaries, and can be searched for with a `NameCracker`.
+
+pub mod crack;
+pub mod dictionary;
+
+pub use crack::{NameCracker, NameParts, Template, TemplateError};
+pub use dictionary::NameDictionary;
@@ -3,6 +3,7 @@
 
 use crate::error;
 use crate::format::TotemFormat;
+use crate::structure::{ChumEnum, ChumStruct};
 use crate::util;
 pub use image;
 use imagequant;
@@ -155,16 +156,14 @@ impl Color {
 }
 
 // The alpha level.
-chum_enum! {
-    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
-    pub enum AlphaLevel {
-        // alpha is always 255
-        Opaque,
-        // alpha is always either 0 or 255
-        Bit,
-        // alpha can be any value
-        Blend,
-    }
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ChumEnum, ChumStruct)]
+pub enum AlphaLevel {
+    // alpha is always 255
+    Opaque,
+    // alpha is always either 0 or 255
+    Bit,
+    // alpha can be any value
+    Blend,
 }
 
 impl AlphaLevel {
@@ -517,12 +516,13 @@ impl Default for Bitmap {
     }
 }
 
-chum_struct! {
-    pub struct BitmapStruct {
-        pub alpha: [enum [u8] AlphaLevel],
-        pub flags: [flags [u8] {a, b, c}],
-        pub unknown: [int_custom [u8] 1, 5],
-    }
+#[derive(ChumStruct)]
+pub struct BitmapStruct {
+    pub alpha: AlphaLevel,
+    #[chum(flags(a, b, c))]
+    pub flags: u8,
+    #[chum(min = 1, max = 5)]
+    pub unknown: u8,
 }
 
 /// Convert a chunk index to an index into Bitmap's data array.
@@ -1,16 +1,17 @@
 //! See https://github.com/Jellonator/chum-world/wiki/CAMERA for more information
+use crate::binary::ChumBinary;
 use crate::common::*;
+use crate::structure::ChumStruct;
 
-// Material data
-chum_struct_binary! {
-    #[derive(Default, Clone)]
-    /// Camera object. Note that fov is in radians.
-    pub struct Camera {
-        pub header: [struct THeader],
-        pub item_type: [ignore [u16] ITEM_TYPE_CAMERA],
-        pub item_flags: [u16],
-        pub fov: [f32],
-        pub unk: [u32],
-        pub target: [reference NODE]
-    }
+/// Camera object. Note that fov is in radians.
+#[derive(Default, Clone, ChumStruct, ChumBinary)]
+pub struct Camera {
+    pub header: THeader,
+    #[chum(ignore = ITEM_TYPE_CAMERA)]
+    pub item_type: (),
+    pub item_flags: u16,
+    pub fov: f32,
+    pub unk: u32,
+    #[chum(reference = "NODE")]
+    pub target: i32,
 }
@@ -1,31 +1,46 @@
+use crate::binary::ChumBinary;
 use crate::common::*;
+use crate::error::DestructureError;
+use crate::structure::{destructure, ChumStruct, ChumStructVariant};
 
-chum_struct_binary! {
-    #[derive(Default)]
-    pub struct CollisionVol {
-        pub header: [struct THeader],
-        pub item_type: [ignore [u16] ITEM_TYPE_COLLISIONVOL],
-        pub item_flags: [u16],
-        pub unk1: [u32],
-        pub local_transform: [Transform3D],
-        pub local_transform_inv: [custom_structure [Transform3D]
-            // Inverse is calculated based on transform
-            structure: |_lod: &CollisionVol| {
-                None
-            };
-            // The value of `item_subtype` depends on the presence of the `sounds` value
-            destructure: |data: &crate::structure::ChumStructVariant| {
-                let tx: &Transform3D = data.get_struct_item("local_transform").unwrap().get_transform3d().unwrap();
-                tx.inverse().unwrap()
-            };
-        ],
-        pub unk2: [u32],
-        pub unk3: [u32],
-        pub node_ids: [fixed array [reference NODE] 10],
-        pub unk4: [fixed array [f32] 10],
-        pub unk5: [dynamic array [u32] [reference] 0],
-        pub bitmaps: [dynamic array [u32] [reference BITMAP] 0],
-        pub volume_type: [reference],
-        pub unk6: [u32],
-    }
+#[derive(Default, ChumStruct, ChumBinary)]
+pub struct CollisionVol {
+    pub header: THeader,
+    #[chum(ignore = ITEM_TYPE_COLLISIONVOL)]
+    pub item_type: (),
+    pub item_flags: u16,
+    pub unk1: u32,
+    pub local_transform: Transform3D,
+    #[chum(
+        structure = structure_local_transform_inv,
+        destructure = destructure_local_transform_inv
+    )]
+    pub local_transform_inv: Transform3D,
+    pub unk2: u32,
+    pub unk3: u32,
+    #[chum(reference = "NODE")]
+    pub node_ids: [i32; 10],
+    pub unk4: [f32; 10],
+    #[chum(len = u32, reference)]
+    pub unk5: Vec<i32>,
+    #[chum(len = u32, reference = "BITMAP")]
+    pub bitmaps: Vec<i32>,
+    #[chum(reference)]
+    pub volume_type: i32,
+    pub unk6: u32,
+}
+
+// Inverse is calculated based on transform
+fn structure_local_transform_inv(_vol: &CollisionVol) -> Option<ChumStructVariant> {
+    None
+}
+
+fn destructure_local_transform_inv(
+    data: &ChumStructVariant,
+) -> Result<Transform3D, DestructureError> {
+    let tx: Transform3D =
+        destructure::get_member_as(data, "local_transform", destructure::get_transform3d)?;
+    tx.inverse().ok_or_else(|| DestructureError::NotInvertible {
+        path: "local_transform".to_owned(),
+    })
 }
@@ -1,31 +1,34 @@
+use crate::binary::ChumBinary;
+use crate::error::DestructureError;
 use crate::reader::node;
+use crate::structure::{destructure, ChumStruct, ChumStructVariant};
 
 const T_NODE: i32 = -1276508687;
 
-chum_struct_binary! {
-    #[derive(Clone, Default)]
-    pub struct GameObj {
-        pub prefabs: [dynamic array [u32] [struct Prefab] Prefab::default()]
-    }
+#[derive(Clone, Default, ChumStruct, ChumBinary)]
+pub struct GameObj {
+    #[chum(len = u32)]
+    pub prefabs: Vec<Prefab>,
 }
 
-chum_struct_binary! {
-    #[derive(Clone)]
-    pub struct Prefab {
-        pub asset_type: [ignore [i32] T_NODE],
-        pub subtype1: [reference],
-        pub subtype2: [custom_structure [reference]
-            // Always same as subtype1 so value does not matter
-            structure: |_prefab: &Prefab| {
-                None
-            };
-            // The value of `item_subtype` depends on the presence of the `sounds` value
-            destructure: |data: &crate::structure::ChumStructVariant| {
-                data.get_struct_item("subtype1").unwrap().get_reference_id().unwrap()
-            };
-        ],
-        pub node: [struct node::Node]
-    }
+#[derive(Clone, ChumStruct, ChumBinary)]
+pub struct Prefab {
+    #[chum(ignore = T_NODE)]
+    pub asset_type: (),
+    #[chum(reference)]
+    pub subtype1: i32,
+    #[chum(structure = structure_subtype2, destructure = destructure_subtype2)]
+    pub subtype2: i32,
+    pub node: node::Node,
+}
+
+// Always same as subtype1 so value does not matter
+fn structure_subtype2(_prefab: &Prefab) -> Option<ChumStructVariant> {
+    None
+}
+
+fn destructure_subtype2(data: &ChumStructVariant) -> Result<i32, DestructureError> {
+    destructure::get_member_as(data, "subtype1", destructure::get_reference)
 }
 
 impl Default for Prefab {
@@ -1,89 +1,99 @@
-// use crate::binary::ChumBinary;
+use crate::binary::ChumBinary;
 use crate::common::*;
-// use crate::format::TotemFormat;
+use crate::format::TotemFormat;
 // use crate::util::error;
 use crate::error::*;
-use std::io;
+use crate::structure::{destructure, ChumStruct, ChumStructVariant};
+use std::io::{self, Read, Write};
 
-chum_struct_binary! {
-    #[derive(Default)]
-    pub struct Lod {
-        pub header: [struct THeader],
-        pub item_type: [ignore [u16] ITEM_TYPE_LOD],
-        pub item_flags: [custom_structure [u16]
-            // Do not present the subtype
-            structure: |_lod: &Lod| {
-                None
-            };
-            // The value of `item_subtype` depends on the presence of the `sounds` value
-            destructure: |data: &crate::structure::ChumStructVariant| {
-                if let Some(_) = data.get_struct_item("sounds").unwrap().get_optional_value().unwrap() {
-                    2
-                } else {
-                    0
-                }
-            };
-        ],
-        pub unk1: [option [fixed array [f32] 4] [0f32; 4]],
-        pub unk2: [option [fixed array [f32] 4] [0f32; 4]],
-        pub unk3: [option [fixed array [f32] 9] [0f32; 9]],
-        pub unk4: [option [struct LodTransform] LodTransform::default()],
-        pub unk5: [option [fixed array [f32] 4] [0f32; 4]],
-        pub unk6: [ignore [u8] 0u8],
-        pub unk7: [option [struct LodTransform] LodTransform::default()],
-        pub unk8: [option [fixed array [f32] 4] [0f32; 4]],
-        pub unk9: [ignore [u8] 0u8],
-        pub unk10: [option [struct LodTransform] LodTransform::default()],
-        pub unk11: [option [fixed array [f32] 4] [0f32; 4]],
-        pub unk12: [ignore [u8] 0u8],
-        pub unk13: [ignore [u8] 0u8],
-        pub unk14: [option [fixed array [f32] 4] [0f32; 4]],
-        pub unk15: [option [struct LodUnkStruct] LodUnkStruct::default()],
-        pub unk16: [option [fixed array [f32] 4] [0f32; 4]],
-        pub unk17: [option [fixed array [f32] 4] [0f32; 4]],
-        pub unk18: [fixed array [f32] 2],
-        pub unk19: [u16],
-        pub skin_ids: [dynamic array [u32] [reference SKIN] 0i32],
-        pub anims: [dynamic array [u32] [struct LodAnimEntry] LodAnimEntry::default()],
-        pub sounds: [custom_binary
-            [option [struct LodSoundData] LodSoundData::default()]
-            // Only read `sounds` if the subtype is 2
-            read: |lod: &Inner, file, fmt| -> StructUnpackResult<Option<LodSoundData>> {
-                match lod.item_flags.unwrap() {
-                    2 => match LodSoundData::read_from(file, fmt) {
-                        Ok(value) => Ok(Some(value)),
-                        Err(e) => Err(e.structuralize("Lod", "sounds"))
-                    },
-                    0 => Ok(None),
-                    o => Err(StructUnpackError {
-                        structname: "Lod".to_owned(),
-                        structpath: "sounds".to_owned(),
-                        error: UnpackError::InvalidEnumeration {
-                            enum_name: "item_subtype".to_owned(),
-                            value: o as i64
-                        }
-                    })
-                }
-            };
-            // Only write to file if not None
-            write: |value: &Option<LodSoundData>, file, fmt| -> io::Result<()> {
-                if let Some(ref inner) = value {
-                    inner.write_to(file, fmt)
-                } else {
-                    Ok(())
-                }
-            };
-        ]
+#[derive(Default, ChumStruct, ChumBinary)]
+pub struct Lod {
+    pub header: THeader,
+    #[chum(ignore = ITEM_TYPE_LOD)]
+    pub item_type: (),
+    #[chum(structure = structure_item_flags, destructure = destructure_item_flags)]
+    pub item_flags: u16,
+    pub unk1: Option<[f32; 4]>,
+    pub unk2: Option<[f32; 4]>,
+    pub unk3: Option<[f32; 9]>,
+    pub unk4: Option<LodTransform>,
+    pub unk5: Option<[f32; 4]>,
+    #[chum(ignore = 0u8)]
+    pub unk6: (),
+    pub unk7: Option<LodTransform>,
+    pub unk8: Option<[f32; 4]>,
+    #[chum(ignore = 0u8)]
+    pub unk9: (),
+    pub unk10: Option<LodTransform>,
+    pub unk11: Option<[f32; 4]>,
+    #[chum(ignore = 0u8)]
+    pub unk12: (),
+    #[chum(ignore = 0u8)]
+    pub unk13: (),
+    pub unk14: Option<[f32; 4]>,
+    pub unk15: Option<LodUnkStruct>,
+    pub unk16: Option<[f32; 4]>,
+    pub unk17: Option<[f32; 4]>,
+    pub unk18: [f32; 2],
+    pub unk19: u16,
+    #[chum(len = u32, reference = "SKIN")]
+    pub skin_ids: Vec<i32>,
+    #[chum(len = u32)]
+    pub anims: Vec<LodAnimEntry>,
+    #[chum(
+        read = read_sounds(item_flags, file, fmt),
+        write = write_sounds(sounds, file, fmt)
+    )]
+    pub sounds: Option<LodSoundData>,
+}
+
+// Do not present the subtype
+fn structure_item_flags(_lod: &Lod) -> Option<ChumStructVariant> {
+    None
+}
+
+// The value of `item_subtype` depends on the presence of the `sounds` value
+fn destructure_item_flags(data: &ChumStructVariant) -> Result<u16, DestructureError> {
+    let sounds = destructure::get_member_as(data, "sounds", destructure::get_optional)?;
+    Ok(if sounds.is_some() { 2 } else { 0 })
+}
+
+// Only read `sounds` if the subtype is 2
+fn read_sounds(
+    item_flags: u16,
+    file: &mut dyn Read,
+    fmt: TotemFormat,
+) -> StructUnpackResult<Option<LodSoundData>> {
+    match item_flags {
+        2 => LodSoundData::read_from(file, fmt).map(Some),
+        0 => Ok(None),
+        o => Err(StructUnpackError::new(UnpackError::InvalidEnumeration {
+            enum_name: "item_subtype".to_owned(),
+            value: o as i64,
+        })),
     }
 }
 
-chum_struct_binary! {
-    pub struct LodTransform {
-        pub transform: [Transform3D],
-        pub junk: [ignore [fixed array [u8] 16] [0u8; 16]]
+// Only write to file if not None
+fn write_sounds(
+    value: &Option<LodSoundData>,
+    file: &mut dyn Write,
+    fmt: TotemFormat,
+) -> io::Result<()> {
+    if let Some(ref inner) = value {
+        inner.write_to(file, fmt)
+    } else {
+        Ok(())
     }
 }
 
+#[derive(ChumStruct, ChumBinary)]
+pub struct LodTransform {
+    pub transform: Transform3D,
+    #[chum(ignore = [0u8; 16])]
+    pub junk: (),
+}
+
 impl Default for LodTransform {
     fn default() -> Self {
         LodTransform {
@@ -93,11 +103,10 @@ impl Default for LodTransform {
     }
 }
 
-chum_struct_binary! {
-    pub struct LodUnkStruct {
-        pub unk1: [fixed array[f32] 4],
-        pub unk2: [u32],
-    }
+#[derive(ChumStruct, ChumBinary)]
+pub struct LodUnkStruct {
+    pub unk1: [f32; 4],
+    pub unk2: u32,
 }
 
 impl Default for LodUnkStruct {
@@ -109,12 +118,12 @@ impl Default for LodUnkStruct {
     }
 }
 
-chum_struct_binary! {
-    pub struct LodAnimEntry {
-        // pub symbol: AnimSymbol, Jimmy files differ on animsymbols, so this is disabled for now
-        pub symbol: [u32],
-        pub animation_id: [reference ANIMATION],
-    }
+#[derive(ChumStruct, ChumBinary)]
+pub struct LodAnimEntry {
+    // pub symbol: AnimSymbol, Jimmy files differ on animsymbols, so this is disabled for now
+    pub symbol: u32,
+    #[chum(reference = "ANIMATION")]
+    pub animation_id: i32,
 }
 
 impl Default for LodAnimEntry {
@@ -126,13 +135,16 @@ impl Default for LodAnimEntry {
     }
 }
 
-chum_struct_binary! {
-    pub struct LodSoundData {
-        pub data: [
-            dynamic array [u32] [struct LodSoundEntry]
-            LodSoundEntry {symbol: 0u32, sound_id: 0i32}
-        ],
-    }
+#[derive(ChumStruct, ChumBinary)]
+pub struct LodSoundData {
+    #[chum(
+        len = u32,
+        default = LodSoundEntry {
+            symbol: 0u32,
+            sound_id: 0i32
+        }
+    )]
+    pub data: Vec<LodSoundEntry>,
 }
 
 impl Default for LodSoundData {
@@ -141,9 +153,9 @@ impl Default for LodSoundData {
     }
 }
 
-chum_struct_binary! {
-    pub struct LodSoundEntry {
-        pub symbol: [u32],
-        pub sound_id: [reference SOUND],
-    }
+#[derive(ChumStruct, ChumBinary)]
+pub struct LodSoundEntry {
+    pub symbol: u32,
+    #[chum(reference = "SOUND")]
+    pub sound_id: i32,
 }
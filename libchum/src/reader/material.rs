@@ -1,20 +1,25 @@
 //! See https://github.com/Jellonator/chum-world/wiki/MATERIAL for more information
 
+use crate::binary::ChumBinary;
+use crate::common::*;
+use crate::structure::ChumStruct;
+
 // Material data
-chum_struct_binary! {
-    #[derive(Default, Clone)]
-    pub struct Material {
-        pub color: [Color],
-        pub emission: [Vector3 rgb],
-        pub unk2: [f32],
-        pub transform: [Transform2D],
-        pub rotation: [f32],
-        pub offset: [Vector2],
-        pub scale: [Vector2],
-        pub unk4: [fixed array [u8] 13],
-        pub texture: [reference BITMAP],
-        pub texture_reflection: [reference BITMAP],
-    }
+#[derive(Default, Clone, ChumStruct, ChumBinary)]
+pub struct Material {
+    pub color: ColorRGBA,
+    #[chum(rgb)]
+    pub emission: Vector3,
+    pub unk2: f32,
+    pub transform: Transform2D,
+    pub rotation: f32,
+    pub offset: Vector2,
+    pub scale: Vector2,
+    pub unk4: [u8; 13],
+    #[chum(reference = "BITMAP")]
+    pub texture: i32,
+    #[chum(reference = "BITMAP")]
+    pub texture_reflection: i32,
 }
 
 impl Material {
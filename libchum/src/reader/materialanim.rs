@@ -1,6 +1,11 @@
 //! See https://github.com/Jellonator/chum-world/wiki/MATERIALANIM for more information
 
+use crate::binary::ChumBinary;
 use crate::common::*;
+use crate::error::{DestructureError, StructUnpackResult};
+use crate::format::TotemFormat;
+use crate::structure::{destructure, ChumEnum, ChumStruct, ChumStructVariant, ColorInfo};
+use std::io::{self, Read, Write};
 
 // /// Interpolation method
 // #[derive(Clone, Copy)]
@@ -15,14 +20,13 @@ use crate::common::*;
 //     Invalid,
 // }
 
-chum_enum! {
-    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
-    pub enum Interpolation {
-        Invalid,
-        Discrete,
-        Linear,
-        Unknown
-    }
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ChumEnum, ChumStruct, ChumBinary)]
+#[chum(repr = u16)]
+pub enum Interpolation {
+    Invalid,
+    Discrete,
+    Linear,
+    Unknown,
 }
 
 impl Default for Interpolation {
@@ -31,63 +35,113 @@ impl Default for Interpolation {
     }
 }
 
+/// Track data that is stored after the frame and junk data, and presented as-is.
+/// `TrackFrame<i32>` and `TrackFrame<Vector3>` are implemented separately.
+pub trait PlainTrackData: ChumBinary + ChumStruct + Clone + Default {}
+
+impl PlainTrackData for Vector2 {}
+impl PlainTrackData for f32 {}
+impl PlainTrackData for [u8; 4] {}
+
 /// A single frame in a track
-#[derive(Clone, Default)]
+#[derive(Clone, Default, ChumStruct, ChumBinary)]
+#[chum(bound = "T: PlainTrackData")]
 pub struct TrackFrame<T>
 where
     T: Clone + Default,
 {
     pub frame: u16,
+    #[chum(ignore = 0u16)]
     pub junk: (),
     pub data: T,
 }
 
-// Implement ChumBinary for all TrackFrame types
-chum_struct_binary_impl! {
-    impl ChumBinary for TrackFrame<i32> {
+impl ChumBinary for TrackFrame<i32> {
+    fn read_from(file: &mut dyn Read, fmt: TotemFormat) -> StructUnpackResult<Self> {
         // IMPORTANT: bitmap_id comes BEFORE frame for TextureFrame.
-        data: [i32],
         // TextureFrame is also the only Track with no junk data.
-        junk: [ignore [void] ()],
-        frame: [u16],
+        let data = i32::read_from(file, fmt).map_err(|e| e.in_field("TrackFrame", "data"))?;
+        let frame = u16::read_from(file, fmt).map_err(|e| e.in_field("TrackFrame", "frame"))?;
+        Ok(TrackFrame {
+            frame,
+            junk: (),
+            data,
+        })
     }
-}
-chum_struct_binary_impl! {
-    impl ChumBinary for TrackFrame<Vector2> {
-        frame: [u16],
-        junk: [ignore [u16] 0u16],
-        data: [Vector2]
+    fn write_to(&self, writer: &mut dyn Write, fmt: TotemFormat) -> io::Result<()> {
+        self.data.write_to(writer, fmt)?;
+        self.frame.write_to(writer, fmt)
     }
 }
-chum_struct_binary_impl! {
-    impl ChumBinary for TrackFrame<f32> {
-        frame: [u16],
-        junk: [ignore [u16] 0u16],
-        data: [f32]
+
+impl ChumStruct for TrackFrame<i32> {
+    fn structure(&self) -> ChumStructVariant {
+        ChumStructVariant::Struct(vec![
+            ("data".to_owned(), self.data.structure()),
+            ("frame".to_owned(), self.frame.structure()),
+        ])
+    }
+    fn destructure(data: &ChumStructVariant) -> Result<Self, DestructureError> {
+        Ok(TrackFrame {
+            data: destructure::get_member_as(data, "data", i32::destructure)?,
+            junk: (),
+            frame: destructure::get_member_as(data, "frame", u16::destructure)?,
+        })
     }
 }
-chum_struct_binary_impl! {
-    impl ChumBinary for TrackFrame<Vector3> {
-        frame: [u16],
-        junk: [ignore [u16] 0u16],
-        data: [Vector3 rgb]
+
+impl ChumBinary for TrackFrame<Vector3> {
+    fn read_from(file: &mut dyn Read, fmt: TotemFormat) -> StructUnpackResult<Self> {
+        let frame = u16::read_from(file, fmt).map_err(|e| e.in_field("TrackFrame", "frame"))?;
+        u16::read_from(file, fmt).map_err(|e| e.in_field("TrackFrame", "junk"))?;
+        let data = Vector3::read_from(file, fmt).map_err(|e| e.in_field("TrackFrame", "data"))?;
+        Ok(TrackFrame {
+            frame,
+            junk: (),
+            data,
+        })
+    }
+    fn write_to(&self, writer: &mut dyn Write, fmt: TotemFormat) -> io::Result<()> {
+        self.frame.write_to(writer, fmt)?;
+        0u16.write_to(writer, fmt)?;
+        self.data.write_to(writer, fmt)
     }
 }
-chum_struct_binary_impl! {
-    impl ChumBinary for TrackFrame<[u8; 4]> {
-        frame: [u16],
-        junk: [ignore [u16] 0u16],
-        data: [fixed array [u8] 4]
+
+/// Colors are presented without alpha
+impl ChumStruct for TrackFrame<Vector3> {
+    fn structure(&self) -> ChumStructVariant {
+        ChumStructVariant::Struct(vec![
+            ("frame".to_owned(), self.frame.structure()),
+            (
+                "data".to_owned(),
+                ChumStructVariant::Color(
+                    ColorRGBA::new(self.data.x, self.data.y, self.data.z, 1.0),
+                    ColorInfo { has_alpha: false },
+                ),
+            ),
+        ])
+    }
+    fn destructure(data: &ChumStructVariant) -> Result<Self, DestructureError> {
+        Ok(TrackFrame {
+            frame: destructure::get_member_as(data, "frame", u16::destructure)?,
+            junk: (),
+            data: destructure::get_member_as(data, "data", |x| {
+                destructure::get_color(x).map(|color| Vector3::new(color.r, color.g, color.b))
+            })?,
+        })
     }
 }
 
 /// A full track, including a list of frames and interpolation method
-#[derive(Clone, Default)]
+#[derive(Clone, Default, ChumStruct, ChumBinary)]
+#[chum(bound = "TrackFrame<T>: ChumBinary + ChumStruct")]
 pub struct Track<T>
 where
     T: Clone + Default,
 {
     pub interp: Interpolation,
+    #[chum(len = u32)]
     pub frames: Vec<TrackFrame<T>>,
 }
 
@@ -147,56 +201,23 @@ where
     }
 }
 
-// Implement ChumBinary for all Track types
-chum_struct_binary_impl! {
-    impl ChumBinary for Track<i32> {
-        interp: [enum [u16] Interpolation],
-        frames: [dynamic array [u32] [struct TrackFrame<i32>] TrackFrame::<i32>::default()]
-    }
-}
-chum_struct_binary_impl! {
-    impl ChumBinary for Track<Vector2> {
-        interp: [enum [u16] Interpolation],
-        frames: [dynamic array [u32] [struct TrackFrame<Vector2>] TrackFrame::<Vector2>::default()]
-    }
-}
-chum_struct_binary_impl! {
-    impl ChumBinary for Track<f32> {
-        interp: [enum [u16] Interpolation],
-        frames: [dynamic array [u32] [struct TrackFrame<f32>] TrackFrame::<f32>::default()]
-    }
-}
-chum_struct_binary_impl! {
-    impl ChumBinary for Track<Vector3> {
-        interp: [enum [u16] Interpolation],
-        frames: [dynamic array [u32] [struct TrackFrame<Vector3>] TrackFrame::<Vector3>::default()]
-    }
-}
-chum_struct_binary_impl! {
-    impl ChumBinary for Track<[u8; 4]> {
-        interp: [enum [u16] Interpolation],
-        frames: [dynamic array [u32] [struct TrackFrame<[u8; 4]>] TrackFrame::<[u8; 4]>::default()]
-    }
-}
-
-chum_struct_binary! {
-    /// Material animation file
-    #[derive(Clone, Default)]
-    pub struct MaterialAnimation {
-        pub unk1: [u8],
-        pub length: [f32],
-        pub track_texture: [struct Track<i32>],
-        pub track_scroll: [struct Track<Vector2>],
-        pub track_stretch: [struct Track<Vector2>],
-        pub track_rotation: [struct Track<f32>],
-        pub track_color: [struct Track<Vector3>],
-        pub track_emission: [struct Track<Vector3>],
-        pub track_alpha: [struct Track<f32>],
-        pub track_unk1: [struct Track<[u8; 4]>],
-        pub track_unk2: [struct Track<[u8; 4]>],
-        pub track_unk3: [struct Track<[u8; 4]>],
-        pub material_id: [reference MATERIAL],
-    }
+/// Material animation file
+#[derive(Clone, Default, ChumStruct, ChumBinary)]
+pub struct MaterialAnimation {
+    pub unk1: u8,
+    pub length: f32,
+    pub track_texture: Track<i32>,
+    pub track_scroll: Track<Vector2>,
+    pub track_stretch: Track<Vector2>,
+    pub track_rotation: Track<f32>,
+    pub track_color: Track<Vector3>,
+    pub track_emission: Track<Vector3>,
+    pub track_alpha: Track<f32>,
+    pub track_unk1: Track<[u8; 4]>,
+    pub track_unk2: Track<[u8; 4]>,
+    pub track_unk3: Track<[u8; 4]>,
+    #[chum(reference = "MATERIAL")]
+    pub material_id: i32,
 }
 
 pub const TRACK_TEXTURE: usize = 0;
@@ -1,6 +1,8 @@
-chum_struct_binary! {
-    #[derive(Clone, Default)]
-    pub struct MaterialObj {
-        pub material_anims: [dynamic array [u32] [reference MATERIALANIM] 0i32]
-    }
+use crate::binary::ChumBinary;
+use crate::structure::ChumStruct;
+
+#[derive(Clone, Default, ChumStruct, ChumBinary)]
+pub struct MaterialObj {
+    #[chum(len = u32, reference = "MATERIALANIM")]
+    pub material_anims: Vec<i32>,
 }
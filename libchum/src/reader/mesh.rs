@@ -4,6 +4,7 @@ use crate::error;
 use crate::format::TotemFormat;
 use crate::reader::skin;
 use crate::scene;
+use crate::structure::ChumStruct;
 use std::collections::HashMap;
 use std::io::{self, Read, Write};
 
@@ -24,31 +25,27 @@ pub struct MeshTri {
     pub points: [MeshPoint; 3],
 }
 
-chum_binary! {
-    /// A triangle strip
-    #[derive(Clone, Debug, Default)]
-    pub struct Strip {
-        pub vertex_ids: [dynamic array [u32] [u16] 0u16],
-        pub material: [u32],
-        pub tri_order: [u32],
-    }
+/// A triangle strip
+#[derive(Clone, Debug, Default, ChumBinary)]
+pub struct Strip {
+    #[chum(len = u32)]
+    pub vertex_ids: Vec<u16>,
+    pub material: u32,
+    pub tri_order: u32,
 }
 
-chum_binary! {
-    /// A combination of a normal index and a texture coordinate index
-    #[derive(Clone, Debug, Default)]
-    pub struct ElementData {
-        pub texcoord_id: [u16],
-        pub normal_id: [u16],
-    }
+/// A combination of a normal index and a texture coordinate index
+#[derive(Clone, Debug, Default, ChumBinary)]
+pub struct ElementData {
+    pub texcoord_id: u16,
+    pub normal_id: u16,
 }
 
-chum_binary! {
-    /// A triangle strip's extra data
-    #[derive(Clone, Debug)]
-    pub struct StripExt {
-        pub elements: [dynamic array [u32] [struct ElementData] ElementData::default()],
-    }
+/// A triangle strip's extra data
+#[derive(Clone, Debug, ChumBinary)]
+pub struct StripExt {
+    #[chum(len = u32)]
+    pub elements: Vec<ElementData>,
 }
 
 #[derive(Clone, Debug)]
@@ -75,60 +72,72 @@ pub struct Mesh {
     pub strip_order: Vec<u32>,
 }
 
-chum_binary! {
-    /// temporary data structure used for reading/writing to binary files
-    #[derive(Clone, Debug)]
-    pub struct MeshTemp {
-        pub header: [struct THeader],
-        pub item_type: [ignore [u16] ITEM_TYPE_MESH],
-        pub item_flags: [u16],
-        pub vertices: [dynamic array [u32] [Vector3] Vector3::zero()],
-        pub texcoords: [dynamic array [u32] [Vector2] Vector2::zero()],
-        pub normals: [dynamic array [u32] [Vector3] Vector3::zero()],
-        pub strip_data: [dynamic array [u32] [struct Strip] Strip::default()],
-        pub strip_groups: [custom_binary
-            [dynamic array [u32] [i32] 0i32]
-            read: |mesh: &Inner, file: &mut dyn Read, fmt: TotemFormat| -> error::StructUnpackResult<Vec<i32>> {
-                if mesh.item_flags.unwrap() & 4 != 0 {
-                    let len = mesh.strip_data.as_ref().unwrap().len();
-                    let mut v = Vec::with_capacity(len);
-                    for i in 0..len {
-                        v.push(fmt.read_i32(file).map_err(|e| {
-                            error::StructUnpackError {
-                                structname: "MeshTemp".to_owned(),
-                                structpath: format!("strip_groups[{}]", i),
-                                error: e.into()
-                            }
-                        })?);
-                    }
-                    Ok(v)
-                } else {
-                    Ok(Vec::new())
-                }
-            };
-            write: |value: &Vec<i32>, file: &mut dyn Write, fmt: TotemFormat| -> io::Result<()> {
-                for value in value.iter() {
-                    fmt.write_i32(file, *value)?;
-                }
-                Ok(())
-            };
-        ],
-        pub strip_exts: [dynamic array [u32] [struct StripExt] Strip::default()],
-        pub materials: [dynamic array [u32] [reference MATERIAL] 0i32],
-        pub sphere_shapes: [dynamic array [u32] [struct SphereShape] SphereShape::default()],
-        pub cuboid_shapes: [dynamic array [u32] [struct CuboidShape] CuboidShape::default()],
-        pub cylinder_shapes: [dynamic array [u32] [struct CylinderShape] CylinderShape::default()],
-        pub unk_shapes: [ignore [u32] 0u32],
-        pub strip_order: [dynamic array [u32] [u32] 0u32],
+/// temporary data structure used for reading/writing to binary files
+#[derive(Clone, Debug, ChumBinary)]
+pub struct MeshTemp {
+    pub header: THeader,
+    #[chum(ignore = ITEM_TYPE_MESH)]
+    pub item_type: (),
+    pub item_flags: u16,
+    #[chum(len = u32)]
+    pub vertices: Vec<Vector3>,
+    #[chum(len = u32)]
+    pub texcoords: Vec<Vector2>,
+    #[chum(len = u32)]
+    pub normals: Vec<Vector3>,
+    #[chum(len = u32)]
+    pub strip_data: Vec<Strip>,
+    #[chum(
+        read = read_strip_groups(item_flags, &strip_data, file, fmt),
+        write = write_strip_groups(strip_groups, file, fmt)
+    )]
+    pub strip_groups: Vec<i32>,
+    #[chum(len = u32)]
+    pub strip_exts: Vec<StripExt>,
+    #[chum(len = u32, reference = "MATERIAL")]
+    pub materials: Vec<i32>,
+    #[chum(len = u32)]
+    pub sphere_shapes: Vec<SphereShape>,
+    #[chum(len = u32)]
+    pub cuboid_shapes: Vec<CuboidShape>,
+    #[chum(len = u32)]
+    pub cylinder_shapes: Vec<CylinderShape>,
+    #[chum(ignore = 0u32)]
+    pub unk_shapes: (),
+    #[chum(len = u32)]
+    pub strip_order: Vec<u32>,
+}
+
+/// Strip groups are only present if `item_flags & 4` is set,
+/// and there is one for each strip
+fn read_strip_groups(
+    item_flags: u16,
+    strip_data: &[Strip],
+    file: &mut dyn Read,
+    fmt: TotemFormat,
+) -> error::StructUnpackResult<Vec<i32>> {
+    if item_flags & 4 != 0 {
+        let mut v = Vec::with_capacity(strip_data.len());
+        for i in 0..strip_data.len() {
+            v.push(i32::read_from(file, fmt).map_err(|e| e.in_index(i))?);
+        }
+        Ok(v)
+    } else {
+        Ok(Vec::new())
     }
 }
 
-chum_struct_binary! {
-    #[derive(Clone, Debug)]
-    pub struct SphereShape {
-        pub position: [Vector3],
-        pub radius: [f32],
+fn write_strip_groups(value: &[i32], file: &mut dyn Write, fmt: TotemFormat) -> io::Result<()> {
+    for value in value.iter() {
+        fmt.write_i32(file, *value)?;
     }
+    Ok(())
+}
+
+#[derive(Clone, Debug, ChumStruct, ChumBinary)]
+pub struct SphereShape {
+    pub position: Vector3,
+    pub radius: f32,
 }
 
 impl Default for SphereShape {
@@ -140,12 +149,11 @@ impl Default for SphereShape {
     }
 }
 
-chum_struct_binary! {
-    #[derive(Clone, Debug)]
-    pub struct CuboidShape {
-        pub transform: [Transform3D],
-        pub junk: [ignore [fixed array [u8] 16] [0u8; 16]],
-    }
+#[derive(Clone, Debug, ChumStruct, ChumBinary)]
+pub struct CuboidShape {
+    pub transform: Transform3D,
+    #[chum(ignore = [0u8; 16])]
+    pub junk: (),
 }
 
 impl Default for CuboidShape {
@@ -157,15 +165,14 @@ impl Default for CuboidShape {
     }
 }
 
-chum_struct_binary! {
-    #[derive(Clone, Debug)]
-    pub struct CylinderShape {
-        pub position: [Vector3],
-        pub height: [f32],
-        pub normal: [Vector3],
-        pub junk: [ignore [fixed array [u8] 4] [0u8; 4]],
-        pub radius: [f32],
-    }
+#[derive(Clone, Debug, ChumStruct, ChumBinary)]
+pub struct CylinderShape {
+    pub position: Vector3,
+    pub height: f32,
+    pub normal: Vector3,
+    #[chum(ignore = [0u8; 4])]
+    pub junk: (),
+    pub radius: f32,
 }
 
 impl Default for CylinderShape {
@@ -180,15 +187,14 @@ impl Default for CylinderShape {
     }
 }
 
-chum_struct! {
-    /// Structured data for Mesh
-    #[derive(Clone)]
-    pub struct MeshStruct {
-        pub materials: [dynamic array [u32] [reference MATERIAL] 0i32],
-        pub sphere_shapes: [dynamic array [u32] [struct SphereShape] SphereShape::default()],
-        pub cuboid_shapes: [dynamic array [u32] [struct CuboidShape] CuboidShape::default()],
-        pub cylinder_shapes: [dynamic array [u32] [struct CylinderShape] CylinderShape::default()],
-    }
+/// Structured data for Mesh
+#[derive(Clone, ChumStruct)]
+pub struct MeshStruct {
+    #[chum(reference = "MATERIAL")]
+    pub materials: Vec<i32>,
+    pub sphere_shapes: Vec<SphereShape>,
+    pub cuboid_shapes: Vec<CuboidShape>,
+    pub cylinder_shapes: Vec<CylinderShape>,
 }
 
 /// Get a vector of triangle indices in (vertex, texcoord, normal) order.
@@ -20,3 +20,236 @@ pub mod sound;
 pub mod spline;
 pub mod surface;
 pub mod warp;
+
+use crate::binary::ChumBinary;
+use crate::error;
+use crate::format::TotemFormat;
+use crate::structure::{ChumStruct, ChumStructVariant, ValidationError};
+
+/// Error that can occur while reading the structure of a file.
+#[derive(Debug, thiserror::Error)]
+pub enum ReadStructureError {
+    #[error("Files of type {0} do not have a structure")]
+    UnsupportedType(String),
+    #[error(transparent)]
+    Unpack(#[from] error::UnpackError),
+    #[error(transparent)]
+    StructUnpack(#[from] error::StructUnpackError),
+}
+
+/// Error that can occur while writing a structure as file data.
+#[derive(Debug, thiserror::Error)]
+pub enum WriteStructureError {
+    #[error("Files of type {0} can not be written from a structure")]
+    UnsupportedType(String),
+    #[error("Invalid structure: {}", join_errors(.0))]
+    Invalid(Vec<ValidationError>),
+    #[error(transparent)]
+    Destructure(#[from] error::DestructureError),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+fn join_errors(errors: &[ValidationError]) -> String {
+    errors
+        .iter()
+        .map(|x| x.to_string())
+        .collect::<Vec<String>>()
+        .join("; ")
+}
+
+/// Read the given file data as the structure for the given file type.
+pub fn read_structure(
+    type_id: &str,
+    data: &[u8],
+    fmt: TotemFormat,
+) -> Result<ChumStructVariant, ReadStructureError> {
+    let mut data = data;
+    Ok(match type_id {
+        "BITMAP" => bitmap::Bitmap::read_data(data, fmt)?
+            .get_struct()
+            .structure(),
+        "CAMERA" => camera::Camera::read_from(&mut data, fmt)?.structure(),
+        "COLLISIONVOL" => collisionvol::CollisionVol::read_from(&mut data, fmt)?.structure(),
+        "GAMEOBJ" => gameobj::GameObj::read_from(&mut data, fmt)?.structure(),
+        "HFOG" => hfog::HFog::read_from(&mut data, fmt)?.structure(),
+        "LIGHT" => light::Light::read_from(&mut data, fmt)?.structure(),
+        "LOD" => lod::Lod::read_from(&mut data, fmt)?.structure(),
+        "MATERIAL" => material::Material::read_from(&mut data, fmt)?.structure(),
+        "MATERIALANIM" => materialanim::MaterialAnimation::read_from(&mut data, fmt)?.structure(),
+        "MATERIALOBJ" => materialobj::MaterialObj::read_from(&mut data, fmt)?.structure(),
+        "MESH" => mesh::Mesh::read_from(&mut data, fmt)?
+            .get_struct()
+            .structure(),
+        "NODE" => node::Node::read_from(&mut data, fmt)?.structure(),
+        "OMNI" => omni::Omni::read_from(&mut data, fmt)?.structure(),
+        "ROTSHAPE" => rotshape::RotShape::read_from(&mut data, fmt)?.structure(),
+        "WARP" => warp::Warp::read_from(&mut data, fmt)?.structure(),
+        _ => return Err(ReadStructureError::UnsupportedType(type_id.to_string())),
+    })
+}
+
+/// Get the structure of a new file of the given type.
+/// Returns None if files of the given type can not be written from a structure.
+pub fn get_default_structure(type_id: &str) -> Option<ChumStructVariant> {
+    Some(match type_id {
+        "CAMERA" => camera::Camera::default().structure(),
+        "COLLISIONVOL" => collisionvol::CollisionVol::default().structure(),
+        "GAMEOBJ" => gameobj::GameObj::default().structure(),
+        "HFOG" => hfog::HFog::default().structure(),
+        "LIGHT" => light::Light::default().structure(),
+        "LOD" => lod::Lod::default().structure(),
+        "MATERIAL" => material::Material::default().structure(),
+        "MATERIALANIM" => materialanim::MaterialAnimation::default().structure(),
+        "MATERIALOBJ" => materialobj::MaterialObj::default().structure(),
+        "NODE" => node::Node::default().structure(),
+        "OMNI" => omni::Omni::default().structure(),
+        "ROTSHAPE" => rotshape::RotShape::default().structure(),
+        "WARP" => warp::Warp::default().structure(),
+        _ => return None,
+    })
+}
+
+fn write_as<T>(data: &ChumStructVariant, fmt: TotemFormat) -> Result<Vec<u8>, WriteStructureError>
+where
+    T: ChumStruct + ChumBinary + Default,
+{
+    let mut errors = data.validate_against(&T::default().structure());
+    errors.retain(|x| !x.is_warning());
+    if !errors.is_empty() {
+        return Err(WriteStructureError::Invalid(errors));
+    }
+    let mut output = Vec::new();
+    T::destructure(data)?.write_to(&mut output, fmt)?;
+    Ok(output)
+}
+
+/// Write the given structure as the file data for the given file type.
+/// The structure must have the same shape as the type's default structure,
+/// and must pass validation against it.
+pub fn write_structure(
+    type_id: &str,
+    data: &ChumStructVariant,
+    fmt: TotemFormat,
+) -> Result<Vec<u8>, WriteStructureError> {
+    match type_id {
+        "CAMERA" => write_as::<camera::Camera>(data, fmt),
+        "COLLISIONVOL" => write_as::<collisionvol::CollisionVol>(data, fmt),
+        "GAMEOBJ" => write_as::<gameobj::GameObj>(data, fmt),
+        "HFOG" => write_as::<hfog::HFog>(data, fmt),
+        "LIGHT" => write_as::<light::Light>(data, fmt),
+        "LOD" => write_as::<lod::Lod>(data, fmt),
+        "MATERIAL" => write_as::<material::Material>(data, fmt),
+        "MATERIALANIM" => write_as::<materialanim::MaterialAnimation>(data, fmt),
+        "MATERIALOBJ" => write_as::<materialobj::MaterialObj>(data, fmt),
+        "NODE" => write_as::<node::Node>(data, fmt),
+        "OMNI" => write_as::<omni::Omni>(data, fmt),
+        "ROTSHAPE" => write_as::<rotshape::RotShape>(data, fmt),
+        "WARP" => write_as::<warp::Warp>(data, fmt),
+        _ => Err(WriteStructureError::UnsupportedType(type_id.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::common::{Vector2, Vector3};
+    use crate::reader::*;
+
+    // Files written by the readers before they were derived, in both byte orders
+    const GOLDEN: &[(&str, &[u8], &[u8])] = &[
+        (
+            "COLLISIONVOL",
+            include_bytes!("../../res/golden/COLLISIONVOL.NGC"),
+            include_bytes!("../../res/golden/COLLISIONVOL.PS2"),
+        ),
+        (
+            "MATERIAL",
+            include_bytes!("../../res/golden/MATERIAL.NGC"),
+            include_bytes!("../../res/golden/MATERIAL.PS2"),
+        ),
+        (
+            "MESH",
+            include_bytes!("../../res/golden/MESH.NGC"),
+            include_bytes!("../../res/golden/MESH.PS2"),
+        ),
+        (
+            "NODE",
+            include_bytes!("../../res/golden/NODE.NGC"),
+            include_bytes!("../../res/golden/NODE.PS2"),
+        ),
+        (
+            "SKIN",
+            include_bytes!("../../res/golden/SKIN.NGC"),
+            include_bytes!("../../res/golden/SKIN.PS2"),
+        ),
+        (
+            "SURFACE",
+            include_bytes!("../../res/golden/SURFACE.NGC"),
+            include_bytes!("../../res/golden/SURFACE.PS2"),
+        ),
+    ];
+
+    fn rewrite<T: ChumBinary>(data: &[u8], fmt: TotemFormat) -> Vec<u8> {
+        let mut data = data;
+        let value = T::read_from(&mut data, fmt).unwrap();
+        assert!(data.is_empty());
+        let mut output = Vec::new();
+        value.write_to(&mut output, fmt).unwrap();
+        output
+    }
+
+    #[test]
+    fn golden_round_trip() {
+        for (type_id, ngc, ps2) in GOLDEN {
+            for (data, fmt) in [(*ngc, TotemFormat::NGC), (*ps2, TotemFormat::PS2)].iter() {
+                let output = match *type_id {
+                    "COLLISIONVOL" => rewrite::<collisionvol::CollisionVol>(data, *fmt),
+                    "MATERIAL" => rewrite::<material::Material>(data, *fmt),
+                    "MESH" => rewrite::<mesh::Mesh>(data, *fmt),
+                    "NODE" => rewrite::<node::Node>(data, *fmt),
+                    "SKIN" => rewrite::<skin::Skin>(data, *fmt),
+                    // Surfaces can only be read
+                    _ => continue,
+                };
+                assert_eq!(&output[..], *data, "{} {:?}", type_id, fmt);
+            }
+        }
+    }
+
+    #[test]
+    fn golden_structure_round_trip() {
+        for (type_id, ngc, ps2) in GOLDEN {
+            if get_default_structure(type_id).is_none() {
+                continue;
+            }
+            for (data, fmt) in [(*ngc, TotemFormat::NGC), (*ps2, TotemFormat::PS2)].iter() {
+                let structure = read_structure(type_id, data, *fmt).unwrap();
+                let output = write_structure(type_id, &structure, *fmt).unwrap();
+                assert_eq!(&output[..], *data, "{} {:?}", type_id, fmt);
+            }
+        }
+    }
+
+    #[test]
+    fn golden_surface() {
+        for (data, fmt) in [
+            (GOLDEN[5].1, TotemFormat::NGC),
+            (GOLDEN[5].2, TotemFormat::PS2),
+        ]
+        .iter()
+        {
+            let surface = surface::SurfaceObject::read_data(data, *fmt).unwrap();
+            assert_eq!(surface.item_flags, 3);
+            assert_eq!(surface.vertices.len(), 4);
+            assert_eq!(surface.vertices[2], Vector3::new(1.0, 1.0, 0.0));
+            assert_eq!(surface.surfaces.len(), 1);
+            assert_eq!(surface.surfaces[0].texcoords[3], Vector2::new(0.0, 1.0));
+            assert_eq!(surface.surfaces[0].curve_ids, [0, 1, 2, 3]);
+            assert_eq!(surface.surfaces[0].curve_order, 1);
+            assert_eq!(surface.surfaces[0].material_id, 0x0bad_f00d);
+            assert_eq!(surface.curves.len(), 4);
+            assert_eq!((surface.curves[3].p1, surface.curves[3].p2), (3, 0));
+            assert_eq!(surface.normals, vec![Vector3::new(0.0, 0.0, 1.0)]);
+        }
+    }
+}
@@ -1,6 +1,9 @@
+use crate::binary::{self, ChumBinary};
+use crate::common::*;
 use crate::error::*;
 use crate::format::TotemFormat;
 use crate::reader::material;
+use crate::structure::ChumStruct;
 use std::error::Error;
 use std::fmt;
 use std::io::{self, Read, Write};
@@ -33,31 +36,37 @@ impl fmt::Display for NodeReadError {
 
 impl Error for NodeReadError {}
 
-chum_struct_binary! {
-    #[derive(Clone, Default)]
-    pub struct Node {
-        pub node_parent_id: [reference NODE],
-        pub node_unk_ids: [fixed array [reference NODE] 3],
-        pub resource_id: [reference],
-        pub node_data: [struct NodeDataUnion],
-        pub light_id: [reference LIGHT],
-        pub hfog_id: [reference HFOG],
-        pub userdefine_id: [reference USERDEFINE],
-        pub floatv1: [fixed array [f32] 9],
-        pub floatv2: [fixed array [f32] 9],
-        pub local_transform: [Transform3D],
-        pub local_translation: [Vector3],
-        pub junk1: [ignore [fixed array [u8] 4] [0u8; 4]],
-        pub local_rotation: [Quaternion],
-        pub local_scale: [Vector3],
-        pub junk2: [ignore [fixed array [u8] 4] [0u8; 4]],
-        pub unk1: [fixed array [f32] 2],
-        pub unk2: [fixed array [u32] 8],
-        pub unk3: [fixed array [f32] 4],
-        pub unk4: [fixed array [u16] 2],
-        pub global_transform: [Transform3D],
-        pub global_transform_inverse: [Transform3D],
-    }
+#[derive(Clone, Default, ChumStruct, ChumBinary)]
+pub struct Node {
+    #[chum(reference = "NODE")]
+    pub node_parent_id: i32,
+    #[chum(reference = "NODE")]
+    pub node_unk_ids: [i32; 3],
+    #[chum(reference)]
+    pub resource_id: i32,
+    pub node_data: NodeDataUnion,
+    #[chum(reference = "LIGHT")]
+    pub light_id: i32,
+    #[chum(reference = "HFOG")]
+    pub hfog_id: i32,
+    #[chum(reference = "USERDEFINE")]
+    pub userdefine_id: i32,
+    pub floatv1: [f32; 9],
+    pub floatv2: [f32; 9],
+    pub local_transform: Transform3D,
+    pub local_translation: Vector3,
+    #[chum(ignore = [0u8; 4])]
+    pub junk1: (),
+    pub local_rotation: Quaternion,
+    pub local_scale: Vector3,
+    #[chum(ignore = [0u8; 4])]
+    pub junk2: (),
+    pub unk1: [f32; 2],
+    pub unk2: [u32; 8],
+    pub unk3: [f32; 4],
+    pub unk4: [u16; 2],
+    pub global_transform: Transform3D,
+    pub global_transform_inverse: Transform3D,
 }
 
 // String                Hash | Resource Type
@@ -69,110 +78,119 @@ chum_struct_binary! {
 // SURFACEDATAS     413080818 | SURFACE
 // LODDATA         -141015160 | LOD
 // PARTICLESDATA   -241612565 | PARTICLES
-chum_struct_binary_enum! {
-    #[derive(Clone)]
-    pub enum NodeDataUnion [i32] {
-        Empty: 0 => {},
-        NodeDataLod: T_LODDATA => {
-            path_id: [i32] = 0i32,
-            subtype_id: [i32] = 0i32,
-            unk1: [fixed array [f32] 5] = [0f32;5],
-            data: [dynamic array [u32] [struct NodeDataUnion] NodeDataUnion::Empty{}] = Vec::new(),
-            unk2: [fixed array [u8] 100] = [0u8;100],
-            node_id: [reference NODE] = 0i32,
-            light1_id: [reference LIGHT] = 0i32,
-            light2_id: [reference LIGHT] = 0i32,
-            nodes: [dynamic array [u32] [reference NODE] 0i32] = Vec::new(),
-            unk3: [dynamic array [u32] [u32] 0u32] = Vec::new(),
-        },
-        NodeDataSkin: T_SKEL => {
-            path_id: [i32] = 0i32,
-            subtype_id: [i32] = 0i32,
-            unk1: [fixed array [f32] 5] = [0f32; 5],
-            unk2: [dynamic array [u32] [struct NodeSkinUnk2] NodeSkinUnk2::default()] = Vec::new(),
-            unk3_id: [i32] = 0i32,
-            materials: [dynamic array [u32] [struct NodeSkinMaterial] NodeSkinMaterial::default()] = Vec::new(),
-            unk4: [dynamic array [u32] [struct NodeSkinUnk] NodeSkinUnk::default()] = Vec::new(),
-            unk5: [dynamic array [u32] [struct NodeSkinUnk] NodeSkinUnk::default()] = Vec::new(),
-            unk6: [dynamic array [u32] [struct NodeSkinUnk] NodeSkinUnk::default()] = Vec::new(),
-            unk7: [custom_binary
-                [dynamic array [u32] [struct NodeSkinUnk7] NodeSkinUnk7::default()]
-                read: |_skel: &Inner, file: &mut dyn Read, fmt: TotemFormat| -> StructUnpackResult<Vec<NodeSkinUnk7>> {
-                    // Another one of Joker's tricks
-                    // also while you're here, look at the amount of code that
-                    // is required to read a few integers with proper error handling.
-                    // THIS is why I am using macros.
-                    let len = fmt.read_u32(file).map_err(|e| StructUnpackError {
-                        structname: "NodeDataUnion::NodeDataSkin".to_owned(),
-                        structpath: "unk7".to_owned(),
-                        error: e.into()
-                    })? as usize;
-                    let mut value = vec![NodeSkinUnk7::default(); len];
-                    for i in 0..len {
-                        value[i].data = NodeDataUnion::read_from(file, fmt).map_err(|e|
-                            e.structuralize("NodeDataUnion::NodeDataSkin", &format!("unk7[{}].data", i))
-                        )?;
-                    }
-                    for i in 0..len {
-                        let inner_len = fmt.read_u32(file).map_err(|e| StructUnpackError {
-                            structname: "NodeDataUnion::NodeDataSkin".to_owned(),
-                            structpath: format!("unk7[{}].ids", i),
-                            error: e.into()
-                        })? as usize;
-                        let mut ids = Vec::with_capacity(inner_len.min(crate::common::SAFE_CAPACITY_SMALL));
-                        for j in 0..inner_len {
-                            ids.push(fmt.read_i32(file).map_err(|e| StructUnpackError {
-                                structname: "NodeDataUnion::NodeDataSkin".to_owned(),
-                                structpath: format!("unk7[{}].ids[{}]", i, j),
-                                error: e.into()
-                            })?);
-                        }
-                        value[i].ids = ids;
-                    }
-                    Ok(value)
-                };
-                write: |value: &Vec<NodeSkinUnk7>, file: &mut dyn Write, fmt: TotemFormat| -> io::Result<()> {
-                    fmt.write_u32(file, value.len() as u32)?;
-                    for inner in value.iter() {
-                        inner.data.write_to(file, fmt)?;
-                    }
-                    for inner in value.iter() {
-                        fmt.write_u32(file, inner.ids.len() as u32)?;
-                        for id in inner.ids.iter() {
-                            fmt.write_i32(file, *id)?;
-                        }
-                    }
-                    Ok(())
-                };
-            ] = Vec::new(),
-        },
-        NodeDataSurface: T_SURFACEDATAS => {
-            data_id: [i32] = 0i32,
-            subtype_id: [i32] = 0i32,
-            data: [fixed array [f32] 5] = [0f32; 5],
-            unk1: [dynamic array [u32] [struct NodeDataSurfaceUnk] NodeDataSurfaceUnk::default()] = Vec::new(),
-            unk2: [u32] = 0u32,
-            unk3: [u32] = 0u32,
-        },
-        NodeDataRotshape: T_ROTSHAPEDATA => {
-            data_id: [i32] = 0i32,
-            subtype_id: [i32] = 0i32,
-            unk1: [fixed array [u32] 6] = [0u32; 6],
-            unk2: [u16] = 0u16,
-            junk: [fixed array [u8] 28] = [0u8;28],
-        },
-        NodeDataMesh: T_MESHDATA => {
-            data_id: [i32] = 0i32,
-            subtype_id: [i32] = 0i32,
-            data: [fixed array [f32] 5] = [0f32; 5],
-        },
-        NodeDataParticles: T_PARTICLESDATA => {
-            data_id: [i32] = 0i32,
-            subtype_id: [i32] = 0i32,
-            unk1: [fixed array [f32] 5] = [0f32; 5],
-            unk2: [u16] = 0u16,
-        },
+#[derive(Clone, ChumStruct, ChumBinary)]
+#[chum(tag = i32)]
+pub enum NodeDataUnion {
+    #[chum(tag = 0)]
+    Empty {},
+    #[chum(tag = T_LODDATA)]
+    NodeDataLod {
+        path_id: i32,
+        subtype_id: i32,
+        unk1: [f32; 5],
+        #[chum(len = u32)]
+        data: Vec<NodeDataUnion>,
+        #[chum(default = [0u8; 100])]
+        unk2: [u8; 100],
+        #[chum(reference = "NODE")]
+        node_id: i32,
+        #[chum(reference = "LIGHT")]
+        light1_id: i32,
+        #[chum(reference = "LIGHT")]
+        light2_id: i32,
+        #[chum(len = u32, reference = "NODE")]
+        nodes: Vec<i32>,
+        #[chum(len = u32)]
+        unk3: Vec<u32>,
+    },
+    #[chum(tag = T_SKEL)]
+    NodeDataSkin {
+        path_id: i32,
+        subtype_id: i32,
+        unk1: [f32; 5],
+        #[chum(len = u32)]
+        unk2: Vec<NodeSkinUnk2>,
+        unk3_id: i32,
+        #[chum(len = u32)]
+        materials: Vec<NodeSkinMaterial>,
+        #[chum(len = u32)]
+        unk4: Vec<NodeSkinUnk>,
+        #[chum(len = u32)]
+        unk5: Vec<NodeSkinUnk>,
+        #[chum(len = u32)]
+        unk6: Vec<NodeSkinUnk>,
+        #[chum(
+            read = read_skin_unk7(file, fmt),
+            write = write_skin_unk7(unk7, file, fmt)
+        )]
+        unk7: Vec<NodeSkinUnk7>,
+    },
+    #[chum(tag = T_SURFACEDATAS)]
+    NodeDataSurface {
+        data_id: i32,
+        subtype_id: i32,
+        data: [f32; 5],
+        #[chum(len = u32)]
+        unk1: Vec<NodeDataSurfaceUnk>,
+        unk2: u32,
+        unk3: u32,
+    },
+    #[chum(tag = T_ROTSHAPEDATA)]
+    NodeDataRotshape {
+        data_id: i32,
+        subtype_id: i32,
+        unk1: [u32; 6],
+        unk2: u16,
+        junk: [u8; 28],
+    },
+    #[chum(tag = T_MESHDATA)]
+    NodeDataMesh {
+        data_id: i32,
+        subtype_id: i32,
+        data: [f32; 5],
+    },
+    #[chum(tag = T_PARTICLESDATA)]
+    NodeDataParticles {
+        data_id: i32,
+        subtype_id: i32,
+        unk1: [f32; 5],
+        unk2: u16,
+    },
+}
+
+// Another one of Joker's tricks:
+// the data of every element comes before the ids of every element.
+fn read_skin_unk7(file: &mut dyn Read, fmt: TotemFormat) -> StructUnpackResult<Vec<NodeSkinUnk7>> {
+    let len = u32::read_from(file, fmt)? as usize;
+    let mut value = Vec::with_capacity(len.min(SAFE_CAPACITY_BIG));
+    for i in 0..len {
+        let data = NodeDataUnion::read_from(file, fmt)
+            .map_err(|e| e.in_field("NodeSkinUnk7", "data").in_index(i))?;
+        value.push(NodeSkinUnk7 {
+            data,
+            ids: Vec::new(),
+        });
+    }
+    for (i, inner) in value.iter_mut().enumerate() {
+        inner.ids = binary::read_vec::<u32, i32>(file, fmt)
+            .map_err(|e| e.in_field("NodeSkinUnk7", "ids").in_index(i))?;
+    }
+    Ok(value)
+}
+
+fn write_skin_unk7(
+    value: &[NodeSkinUnk7],
+    file: &mut dyn Write,
+    fmt: TotemFormat,
+) -> io::Result<()> {
+    fmt.write_u32(file, value.len() as u32)?;
+    for inner in value.iter() {
+        inner.data.write_to(file, fmt)?;
     }
+    for inner in value.iter() {
+        binary::write_vec::<u32, _>(&inner.ids, file, fmt)?;
+    }
+    Ok(())
 }
 
 impl Default for NodeDataUnion {
@@ -181,11 +199,9 @@ impl Default for NodeDataUnion {
     }
 }
 
-chum_struct_binary! {
-    #[derive(Clone)]
-    pub struct NodeDataSurfaceUnk {
-        pub data: [fixed array [u8] 104],
-    }
+#[derive(Clone, ChumStruct, ChumBinary)]
+pub struct NodeDataSurfaceUnk {
+    pub data: [u8; 104],
 }
 
 impl Default for NodeDataSurfaceUnk {
@@ -194,36 +210,37 @@ impl Default for NodeDataSurfaceUnk {
     }
 }
 
-chum_struct_binary! {
-    #[derive(Default, Clone)]
-    pub struct NodeSkinUnk2 {
-        pub unk_ids: [fixed array [i32] 5],
-        pub extra_data: [struct NodeSkinUnk2ExtraDataUnion],
-        pub local_translation: [Vector3],
-        pub junk1: [ignore [fixed array [u8] 4] [0u8; 4]],
-        pub local_rotation: [Quaternion],
-        pub local_scale: [Vector3],
-        pub floatv1: [fixed array [f32] 9],
-        pub floatv2: [fixed array [f32] 9],
-        pub tx1: [Transform3D],
-        pub tx2: [Transform3D],
-    }
+#[derive(Default, Clone, ChumStruct, ChumBinary)]
+pub struct NodeSkinUnk2 {
+    pub unk_ids: [i32; 5],
+    pub extra_data: NodeSkinUnk2ExtraDataUnion,
+    pub local_translation: Vector3,
+    #[chum(ignore = [0u8; 4])]
+    pub junk1: (),
+    pub local_rotation: Quaternion,
+    pub local_scale: Vector3,
+    pub floatv1: [f32; 9],
+    pub floatv2: [f32; 9],
+    pub tx1: Transform3D,
+    pub tx2: Transform3D,
 }
 
-chum_struct_binary_enum! {
-    /// | String              Hash | Resource Type
-    /// |-------------------------------------------------
-    /// |                        0 | (empty)
-    /// | USERDEFINE   -1879206489 | USERDEFINE (embedded)
-    #[derive(Clone)]
-    pub enum NodeSkinUnk2ExtraDataUnion [i32] {
-        Empty: 0 => {},
-        UserDefine: E_USERDATA => {
-            type1: [i32] = 0i32,
-            type2: [i32] = 0i32,
-            data: [dynamic array [u32] [u8] 0u8] = Vec::new(),
-        },
-    }
+/// | String              Hash | Resource Type
+/// |-------------------------------------------------
+/// |                        0 | (empty)
+/// | USERDEFINE   -1879206489 | USERDEFINE (embedded)
+#[derive(Clone, ChumStruct, ChumBinary)]
+#[chum(tag = i32)]
+pub enum NodeSkinUnk2ExtraDataUnion {
+    #[chum(tag = 0)]
+    Empty {},
+    #[chum(tag = E_USERDATA)]
+    UserDefine {
+        type1: i32,
+        type2: i32,
+        #[chum(len = u32)]
+        data: Vec<u8>,
+    },
 }
 
 impl Default for NodeSkinUnk2ExtraDataUnion {
@@ -232,29 +249,24 @@ impl Default for NodeSkinUnk2ExtraDataUnion {
     }
 }
 
-chum_struct_binary! {
-    #[derive(Default, Clone)]
-    pub struct NodeSkinMaterial {
-        pub filetype_id: [i32],
-        pub filename_id: [i32],
-        pub subtype_id: [i32],
-        pub material: [struct material::Material],
-    }
+#[derive(Default, Clone, ChumStruct, ChumBinary)]
+pub struct NodeSkinMaterial {
+    pub filetype_id: i32,
+    pub filename_id: i32,
+    pub subtype_id: i32,
+    pub material: material::Material,
 }
 
-chum_struct_binary! {
-    #[derive(Default, Clone)]
-    pub struct NodeSkinUnk {
-        pub unk1: [fixed array [f32] 4],
-        pub unk2_id: [i32],
-        pub unk3_id: [i32],
-    }
+#[derive(Default, Clone, ChumStruct, ChumBinary)]
+pub struct NodeSkinUnk {
+    pub unk1: [f32; 4],
+    pub unk2_id: i32,
+    pub unk3_id: i32,
 }
 
-chum_struct_binary! {
-    #[derive(Default, Clone)]
-    pub struct NodeSkinUnk7 {
-        pub data: [struct NodeDataUnion],
-        pub ids: [dynamic array [u32] [i32] 0i32],
-    }
+#[derive(Default, Clone, ChumStruct, ChumBinary)]
+pub struct NodeSkinUnk7 {
+    pub data: NodeDataUnion,
+    #[chum(len = u32)]
+    pub ids: Vec<i32>,
 }
@@ -1,14 +1,16 @@
+use crate::binary::ChumBinary;
 use crate::common::*;
+use crate::structure::ChumStruct;
 
-chum_struct_binary! {
-    #[derive(Clone, Default)]
-    pub struct Omni {
-        pub header: [struct THeader],
-        pub item_type: [ignore [u16] ITEM_TYPE_OMNI],
-        pub item_flags: [u16],
-        pub color: [Vector3 rgb],
-        pub unknown1: [u8],
-        pub junk: [fixed array [u8] 3],
-        pub unknown2: [Vector2]
-    }
+#[derive(Clone, Default, ChumStruct, ChumBinary)]
+pub struct Omni {
+    pub header: THeader,
+    #[chum(ignore = ITEM_TYPE_OMNI)]
+    pub item_type: (),
+    pub item_flags: u16,
+    #[chum(rgb)]
+    pub color: Vector3,
+    pub unknown1: u8,
+    pub junk: [u8; 3],
+    pub unknown2: Vector2,
 }
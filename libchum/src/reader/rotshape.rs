@@ -1,32 +1,39 @@
+use crate::binary::ChumBinary;
 use crate::common::*;
+use crate::structure::{ChumEnum, ChumStruct};
 
-chum_struct_binary! {
-    /// Rotation shape
-    #[derive(Clone, Default)]
-    pub struct RotShape {
-        pub header: [struct THeader],
-        pub item_type: [ignore [u16] ITEM_TYPE_ROTSHAPE],
-        pub item_flags: [u16],
-        pub junk1: [ignore [u32] 1],
-        pub offset: [Vector3],
-        pub junk2: [ignore [u32] 1],
-        pub unk7: [reference],
-        pub junk3: [ignore [u32] 2],
-        pub size: [fixed array [Vector3] 2],
-        pub junk4: [ignore [u32] 4],
-        pub texcoords: [fixed array [Vector2] 4],
-        pub junk5: [ignore [u32] 1],
-        pub materialanim_id: [reference MATERIALANIM],
-        pub billboard_mode: [enum [u16] BillBoardMode],
-    }
+/// Rotation shape
+#[derive(Clone, Default, ChumStruct, ChumBinary)]
+pub struct RotShape {
+    pub header: THeader,
+    #[chum(ignore = ITEM_TYPE_ROTSHAPE)]
+    pub item_type: (),
+    pub item_flags: u16,
+    #[chum(ignore = 1u32)]
+    pub junk1: (),
+    pub offset: Vector3,
+    #[chum(ignore = 1u32)]
+    pub junk2: (),
+    #[chum(reference)]
+    pub unk7: i32,
+    #[chum(ignore = 2u32)]
+    pub junk3: (),
+    pub size: [Vector3; 2],
+    #[chum(ignore = 4u32)]
+    pub junk4: (),
+    pub texcoords: [Vector2; 4],
+    #[chum(ignore = 1u32)]
+    pub junk5: (),
+    #[chum(reference = "MATERIALANIM")]
+    pub materialanim_id: i32,
+    pub billboard_mode: BillBoardMode,
 }
 
-chum_enum! {
-    #[derive(Copy, Clone, Debug)]
-    pub enum BillBoardMode {
-        YAxis,
-        Full,
-    }
+#[derive(Copy, Clone, Debug, ChumEnum, ChumStruct, ChumBinary)]
+#[chum(repr = u16)]
+pub enum BillBoardMode {
+    YAxis,
+    Full,
 }
 
 impl Default for BillBoardMode {
@@ -1,76 +1,74 @@
+use crate::binary::ChumBinary;
 use crate::common::*;
 // use std::error::Error;
 use crate::scene;
 use std::collections::HashMap;
 
-chum_binary! {
-    #[derive(Clone, Debug, Default)]
-    pub struct Skin {
-        pub header: [struct THeader],
-        pub item_type: [ignore [u16] ITEM_TYPE_SKIN],
-        pub item_flags: [u16],
-        pub meshes: [dynamic array [u32] [reference MESH] 0i32],
-        pub unk_zero: [ignore [u32] 0u32],
-        pub vertex_groups: [dynamic array [u32] [struct VertexGroup] VertexGroup::default()],
-        pub anims: [option [struct AnimSection] AnimSection::default()],
-        pub unknown: [dynamic array [u32] [struct UnknownEntry] UnknownEntry::default()],
-    }
+#[derive(Clone, Debug, Default, ChumBinary)]
+pub struct Skin {
+    pub header: THeader,
+    #[chum(ignore = ITEM_TYPE_SKIN)]
+    pub item_type: (),
+    pub item_flags: u16,
+    #[chum(len = u32, reference = "MESH")]
+    pub meshes: Vec<i32>,
+    #[chum(ignore = 0u32)]
+    pub unk_zero: (),
+    #[chum(len = u32)]
+    pub vertex_groups: Vec<VertexGroup>,
+    pub anims: Option<AnimSection>,
+    #[chum(len = u32)]
+    pub unknown: Vec<UnknownEntry>,
 }
 
-chum_binary! {
-    #[derive(Clone, Debug, Default)]
-    pub struct VertexGroup {
-        pub group_id: [reference],
-        pub sections: [dynamic array [u32] [struct VertexGroupSection] VertexGroupSection::default()],
-    }
+#[derive(Clone, Debug, Default, ChumBinary)]
+pub struct VertexGroup {
+    /// The hash of the name of the bone that this group is attached to
+    pub group_id: i32,
+    #[chum(len = u32)]
+    pub sections: Vec<VertexGroupSection>,
 }
 
-chum_binary! {
-    #[derive(Clone, Debug, Default)]
-    pub struct VertexGroupSection {
-        pub mesh_index: [u16],
-        pub vertices: [dynamic array [u32] [struct VertexGroupVertex] VertexGroupVertex::default()],
-        pub normals: [dynamic array [u32] [struct VertexGroupNormal] VertexGroupNormal::default()],
-    }
+#[derive(Clone, Debug, Default, ChumBinary)]
+pub struct VertexGroupSection {
+    pub mesh_index: u16,
+    #[chum(len = u32)]
+    pub vertices: Vec<VertexGroupVertex>,
+    #[chum(len = u32)]
+    pub normals: Vec<VertexGroupNormal>,
 }
 
-chum_binary! {
-    #[derive(Clone, Debug, Default)]
-    pub struct VertexGroupVertex {
-        pub vertex_id: [u32],
-        pub weight: [f32],
-    }
+#[derive(Clone, Debug, Default, ChumBinary)]
+pub struct VertexGroupVertex {
+    pub vertex_id: u32,
+    pub weight: f32,
 }
 
-chum_binary! {
-    #[derive(Clone, Debug, Default)]
-    pub struct VertexGroupNormal {
-        pub normal_id: [u32],
-        pub weight: [f32],
-    }
+#[derive(Clone, Debug, Default, ChumBinary)]
+pub struct VertexGroupNormal {
+    pub normal_id: u32,
+    pub weight: f32,
 }
 
-chum_binary! {
-    #[derive(Clone, Debug, Default)]
-    pub struct AnimSection {
-        pub entries: [dynamic array [u32] [struct AnimEntry] AnimEntry::default()],
-    }
+#[derive(Clone, Debug, Default, ChumBinary)]
+pub struct AnimSection {
+    #[chum(len = u32)]
+    pub entries: Vec<AnimEntry>,
 }
 
-chum_binary! {
-    #[derive(Clone, Debug, Default)]
-    pub struct AnimEntry {
-        pub symbol: [u32],
-        pub anim_id: [reference ANIMATION],
-    }
+#[derive(Clone, Debug, Default, ChumBinary)]
+pub struct AnimEntry {
+    pub symbol: u32,
+    #[chum(reference = "ANIMATION")]
+    pub anim_id: i32,
 }
 
-chum_binary! {
-    #[derive(Clone, Debug, Default)]
-    pub struct UnknownEntry {
-        pub vertices: [dynamic array [u32] [u32] 0u32],
-        pub normals: [dynamic array [u32] [u32] 0u32],
-    }
+#[derive(Clone, Debug, Default, ChumBinary)]
+pub struct UnknownEntry {
+    #[chum(len = u32)]
+    pub vertices: Vec<u32>,
+    #[chum(len = u32)]
+    pub normals: Vec<u32>,
 }
 
 /// specialized stucture used for skin export
@@ -1,59 +1,67 @@
 // use crate::common::*;
+use crate::binary::ChumBinary;
 use crate::error::*;
 use crate::format;
+use crate::structure::ChumStruct;
 use crate::util::dsp;
 use std::io;
 
-chum_binary! {
-    pub struct SoundGcn {
-        pub unk0: [ignore [u8] 0u8],
-        pub unk0_junk: [ignore [fixed array [u8] 3] [0; 3]],
-        pub sample_rate: [u32],
-        pub junk: [ignore [fixed array [u8] 4] [0; 4]],
-        pub data_length: [u32],
-        pub unk1: [ignore [u32] 0u32],
-        pub unk2: [ignore [u32] 2u32],
-        pub num_adpcm_nibbles: [u32],
-        pub unk3: [ignore [u32] 2u32],
-        pub unk4: [u32],
-        pub unk5: [ignore [u32] 0u32],
-        pub unk6: [ignore [u32] 0u32],
-        pub unk7: [ignore [i16] 0i16],
-        pub coefficients: [fixed array [i16] 16],
-        pub unk8: [ignore [i16] 0i16],
-        pub first_header: [i16],
-        pub unk9: [ignore [i16] 0i16],
-        pub unk10: [u32],
-        pub unk11: [u32],
-        pub data: [custom_binary
-            [dynamic array [u32] [u8] 0u8]
-            // read number of bytes defined by data_length
-            read: |snd: &Inner, file, fmt: format::TotemFormat| -> StructUnpackResult<Vec<u8>> {
-                let num_bytes = snd.data_length.unwrap();
-                let mut v = vec![0u8; num_bytes as usize];
-                match fmt.read_exact(file, v.as_mut_slice()) {
-                    Ok(_) => Ok(v),
-                    Err(e) => Err(StructUnpackError {
-                        structname: "Sound".to_owned(),
-                        structpath: "data".to_owned(),
-                        error: e.into()
-                    })
-                }
-            };
-            // write number of bytes defined by data_length
-            write: |value: &Vec<u8>, file, fmt: format::TotemFormat| -> io::Result<()> {
-                fmt.write_bytes(file, value.as_slice())
-            };
-        ]
-    }
+#[derive(ChumBinary)]
+pub struct SoundGcn {
+    #[chum(ignore = 0u8)]
+    pub unk0: (),
+    #[chum(ignore = [0u8; 3])]
+    pub unk0_junk: (),
+    pub sample_rate: u32,
+    #[chum(ignore = [0u8; 4])]
+    pub junk: (),
+    pub data_length: u32,
+    #[chum(ignore = 0u32)]
+    pub unk1: (),
+    #[chum(ignore = 2u32)]
+    pub unk2: (),
+    pub num_adpcm_nibbles: u32,
+    #[chum(ignore = 2u32)]
+    pub unk3: (),
+    pub unk4: u32,
+    #[chum(ignore = 0u32)]
+    pub unk5: (),
+    #[chum(ignore = 0u32)]
+    pub unk6: (),
+    #[chum(ignore = 0i16)]
+    pub unk7: (),
+    pub coefficients: [i16; 16],
+    #[chum(ignore = 0i16)]
+    pub unk8: (),
+    pub first_header: i16,
+    #[chum(ignore = 0i16)]
+    pub unk9: (),
+    pub unk10: u32,
+    pub unk11: u32,
+    #[chum(
+        read = read_data(data_length, file, fmt),
+        write = fmt.write_bytes(file, data)
+    )]
+    pub data: Vec<u8>,
 }
 
-chum_struct! {
-    pub struct SoundGcnStruct {
-        pub sample_rate: [u32],
-        pub unk10: [u32],
-        pub unk11: [u32],
-    }
+/// Read the number of bytes defined by data_length
+fn read_data(
+    data_length: u32,
+    file: &mut dyn io::Read,
+    fmt: format::TotemFormat,
+) -> StructUnpackResult<Vec<u8>> {
+    let mut v = vec![0u8; data_length as usize];
+    fmt.read_exact(file, v.as_mut_slice())
+        .map_err(StructUnpackError::new)?;
+    Ok(v)
+}
+
+#[derive(ChumStruct)]
+pub struct SoundGcnStruct {
+    pub sample_rate: u32,
+    pub unk10: u32,
+    pub unk11: u32,
 }
 
 impl Default for SoundGcn {
@@ -1,16 +1,18 @@
+use crate::binary::ChumBinary;
 use crate::common::*;
 
-chum_binary! {
-    #[derive(Clone, Default)]
-    pub struct Spline {
-        pub header: [struct THeader],
-        pub item_type: [ignore [u16] ITEM_TYPE_SPLINE],
-        pub item_flags: [u16],
-        pub vertices: [dynamic array [u32] [Vector3] Vector3::default()],
-        pub sections: [dynamic array [u32] [struct SplineSection] SplineSection::default()],
-        pub unk4: [fixed array [f32] 4],
-        pub length: [f32],
-    }
+#[derive(Clone, Default, ChumBinary)]
+pub struct Spline {
+    pub header: THeader,
+    #[chum(ignore = ITEM_TYPE_SPLINE)]
+    pub item_type: (),
+    pub item_flags: u16,
+    #[chum(len = u32)]
+    pub vertices: Vec<Vector3>,
+    #[chum(len = u32)]
+    pub sections: Vec<SplineSection>,
+    pub unk4: [f32; 4],
+    pub length: f32,
 }
 
 impl Spline {
@@ -39,24 +41,20 @@ impl Spline {
     }
 }
 
-chum_binary! {
-    #[derive(Clone, Default)]
-    pub struct SplineSection {
-        pub p1: [u32],
-        pub p2: [u32],
-        pub p1_t: [u32],
-        pub p2_t: [u32],
-        pub unk: [u32],
-        pub section_length: [f32],
-        pub subsections: [fixed array [struct SplineSubsection] 8],
-    }
+#[derive(Clone, Default, ChumBinary)]
+pub struct SplineSection {
+    pub p1: u32,
+    pub p2: u32,
+    pub p1_t: u32,
+    pub p2_t: u32,
+    pub unk: u32,
+    pub section_length: f32,
+    pub subsections: [SplineSubsection; 8],
 }
 
-chum_binary! {
-    #[derive(Clone, Default)]
-    pub struct SplineSubsection {
-        pub point1: [Vector3],
-        pub point2: [Vector3],
-        pub subsection_length: [f32],
-    }
+#[derive(Clone, Default, ChumBinary)]
+pub struct SplineSubsection {
+    pub point1: Vector3,
+    pub point2: Vector3,
+    pub subsection_length: f32,
 }
@@ -340,7 +340,8 @@ impl SurfaceObject {
         // fmt.skip_n_bytes(file, 96)?;
         // let _unknown2 = fmt.read_u16(file)?;
         // let _unknown3 = fmt.read_u16(file)?;
-        let header = THeader::read_from(file, fmt).unwrap();
+        let header = THeader::read_from(file, fmt)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
         let _item_type = fmt.read_u16(file)?;
         let item_flags = fmt.read_u16(file)?;
         let num_vertices = fmt.read_u32(file)?;
@@ -1,9 +1,12 @@
-chum_struct_binary! {
-    #[derive(Clone, Default)]
-    pub struct Warp {
-        pub size: [f32],
-        pub material_ids: [fixed array [reference MATERIAL] 6],
-        pub vertices: [fixed array [Vector3] 8],
-        pub texcoords: [fixed array [Vector2] 4],
-    }
+use crate::binary::ChumBinary;
+use crate::common::*;
+use crate::structure::ChumStruct;
+
+#[derive(Clone, Default, ChumStruct, ChumBinary)]
+pub struct Warp {
+    pub size: f32,
+    #[chum(reference = "MATERIAL")]
+    pub material_ids: [i32; 6],
+    pub vertices: [Vector3; 8],
+    pub texcoords: [Vector2; 4],
 }
@@ -2,6 +2,8 @@
 //! Ideally, this will be a generic interface for export (and eventually import)
 //! of 3D scenes.
 
+use crate::archive::ChumArchive;
+use crate::binary::ChumBinary;
 use crate::common;
 use crate::gltf as gltf_rs;
 use crate::reader;
@@ -9,6 +11,7 @@ use crate::util::idmap::IdMap;
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::fs;
+use std::io::Write;
 
 pub mod gltf;
 
@@ -287,8 +290,7 @@ impl Scene {
         use std::borrow::Cow;
         let writer = fs::File::create(name)?;
         if name.to_lowercase().ends_with("gltf") {
-            let (gltfroot, _buffer) = gltf::export_scene(&self, false);
-            gltf_json::serialize::to_writer_pretty(writer, &gltfroot)?;
+            self.write_gltf(writer)?;
         } else {
             let (gltfroot, buffer) = gltf::export_scene(&self, true);
             let json_string = gltf_json::serialize::to_string(&gltfroot)?;
@@ -308,6 +310,61 @@ impl Scene {
         Ok(())
     }
 
+    /// Write this scene as a .gltf file, with its buffer embedded in the file
+    pub fn write_gltf<W: Write>(&self, writer: W) -> Result<(), Box<dyn std::error::Error>> {
+        let (gltfroot, _buffer) = gltf::export_scene(&self, false);
+        gltf_json::serialize::to_writer_pretty(writer, &gltfroot)?;
+        Ok(())
+    }
+
+    /// Add the materials that this scene's meshes use, and the textures that those
+    /// materials use, from the given archive.
+    /// MATERIALANIM files are added as the material that they animate.
+    /// Files that are missing or can not be read are skipped.
+    pub fn add_archive_materials(&mut self, archive: &ChumArchive) {
+        let fmt = archive.get_format();
+        let read_material = |id: i32| {
+            let file = archive.get_file_from_hash(id)?;
+            match file.get_type_id() {
+                "MATERIAL" => reader::material::Material::read_from(&mut file.get_data(), fmt).ok(),
+                "MATERIALANIM" => {
+                    let anim = reader::materialanim::MaterialAnimation::read_from(
+                        &mut file.get_data(),
+                        fmt,
+                    )
+                    .ok()?;
+                    let material = archive.get_file_from_hash(anim.material_id)?;
+                    reader::material::Material::read_from(&mut material.get_data(), fmt).ok()
+                }
+                _ => None,
+            }
+        };
+        for id in self.get_required_materials() {
+            if self.materials.contains_key(id) {
+                continue;
+            }
+            if let (Some(name), Some(material)) = (archive.get_name_from_id(id), read_material(id))
+            {
+                // Materials are named after the file that the mesh refers to
+                self.materials
+                    .insert(name.to_string(), SMaterial::from_material(&material));
+            }
+        }
+        for id in self.get_required_textures() {
+            if self.textures.contains_key(id) {
+                continue;
+            }
+            let file = match archive.get_file_from_hash(id) {
+                Some(file) => file,
+                None => continue,
+            };
+            if let Ok(data) = reader::bitmap::Bitmap::read_data(file.get_data(), fmt) {
+                self.textures
+                    .insert(file.get_name_id().to_string(), STexture { data });
+            }
+        }
+    }
+
     pub fn get_required_materials(&self) -> HashSet<i32> {
         let mut v = HashSet::new();
         for (_i, value) in self.meshes.iter() {
@@ -0,0 +1,259 @@
+//! Functions that get values out of a structure while destructuring it.
+//! These are used by the `ChumStruct` derive, and by custom `destructure` functions.
+//! Errors have an empty path, unless noted otherwise; callers prepend the path to the value.
+
+use crate::common;
+use crate::error::DestructureError;
+use crate::structure::{ChumEnum, ChumStructVariant};
+use std::convert::TryInto;
+
+/// Describe the kind of the given value, for error messages
+pub fn describe(value: &ChumStructVariant) -> &'static str {
+    use ChumStructVariant::*;
+    match value {
+        Integer(..) => "an integer",
+        Float(_) => "a number",
+        Transform3D(_) => "a 3D transform",
+        Transform2D(_) => "a 2D transform",
+        Vec2(_) => "a 2D vector",
+        Vec3(_) => "a 3D vector",
+        Color(..) => "a color",
+        Reference(..) => "a reference",
+        Array(_) => "an array",
+        Struct(_) => "a struct",
+        Optional { .. } => "an optional value",
+        Variant { .. } => "a variant",
+    }
+}
+
+fn expect<'a, T, F>(
+    value: &'a ChumStructVariant,
+    expected: &'static str,
+    get: F,
+) -> Result<T, DestructureError>
+where
+    F: FnOnce(&'a ChumStructVariant) -> Option<T>,
+{
+    get(value).ok_or_else(|| DestructureError::TypeMismatch {
+        path: String::new(),
+        expected,
+        found: describe(value),
+    })
+}
+
+/// Get an integer in the range [min, max]
+pub fn get_integer(value: &ChumStructVariant, min: i64, max: i64) -> Result<i64, DestructureError> {
+    let x = expect(value, "an integer", ChumStructVariant::get_i64)?;
+    if x < min || x > max {
+        Err(DestructureError::OutOfRange {
+            path: String::new(),
+            value: x,
+            min,
+            max,
+        })
+    } else {
+        Ok(x)
+    }
+}
+
+/// Get a value of an enumeration that implements `ChumEnum`
+pub fn get_enum<T: ChumEnum>(
+    value: &ChumStructVariant,
+    enum_name: &str,
+) -> Result<T, DestructureError> {
+    let x = expect(value, "an integer", ChumStructVariant::get_i64)?;
+    if x < 0 || x > u32::MAX as i64 {
+        None
+    } else {
+        T::from_u32(x as u32)
+    }
+    .ok_or_else(|| DestructureError::InvalidEnumeration {
+        path: String::new(),
+        enum_name: enum_name.to_owned(),
+        value: x,
+    })
+}
+
+pub fn get_float(value: &ChumStructVariant) -> Result<f32, DestructureError> {
+    expect(value, "a number", ChumStructVariant::get_f32)
+}
+
+pub fn get_transform3d(value: &ChumStructVariant) -> Result<common::Transform3D, DestructureError> {
+    expect(value, "a 3D transform", ChumStructVariant::get_transform3d).copied()
+}
+
+pub fn get_transform2d(value: &ChumStructVariant) -> Result<common::Transform2D, DestructureError> {
+    expect(value, "a 2D transform", ChumStructVariant::get_transform2d).copied()
+}
+
+pub fn get_vec2(value: &ChumStructVariant) -> Result<common::Vector2, DestructureError> {
+    expect(value, "a 2D vector", ChumStructVariant::get_vec2).copied()
+}
+
+pub fn get_vec3(value: &ChumStructVariant) -> Result<common::Vector3, DestructureError> {
+    expect(value, "a 3D vector", ChumStructVariant::get_vec3).copied()
+}
+
+pub fn get_color(value: &ChumStructVariant) -> Result<common::ColorRGBA, DestructureError> {
+    expect(value, "a color", ChumStructVariant::get_color).copied()
+}
+
+pub fn get_reference(value: &ChumStructVariant) -> Result<i32, DestructureError> {
+    expect(value, "a reference", ChumStructVariant::get_reference_id)
+}
+
+pub fn get_array(value: &ChumStructVariant) -> Result<&[ChumStructVariant], DestructureError> {
+    expect(value, "an array", ChumStructVariant::get_array)
+}
+
+/// Get an array that has exactly `len` elements
+pub fn get_fixed_array(
+    value: &ChumStructVariant,
+    len: usize,
+) -> Result<&[ChumStructVariant], DestructureError> {
+    let data = get_array(value)?;
+    if data.len() != len {
+        return Err(DestructureError::WrongLength {
+            path: String::new(),
+            expected: len,
+            found: data.len(),
+        });
+    }
+    Ok(data)
+}
+
+/// Get every element of an array using the given function.
+/// Errors have the path to the element.
+pub fn get_elements<'a, T, F>(
+    value: &'a ChumStructVariant,
+    get: F,
+) -> Result<Vec<T>, DestructureError>
+where
+    F: Fn(&'a ChumStructVariant) -> Result<T, DestructureError>,
+{
+    get_array(value)?
+        .iter()
+        .enumerate()
+        .map(|(i, x)| get(x).map_err(|e| e.prepend_index(i)))
+        .collect()
+}
+
+/// Get every element of an array that has exactly `N` elements using the given function.
+/// Errors have the path to the element.
+pub fn get_fixed_elements<'a, T, F, const N: usize>(
+    value: &'a ChumStructVariant,
+    get: F,
+) -> Result<[T; N], DestructureError>
+where
+    F: Fn(&'a ChumStructVariant) -> Result<T, DestructureError>,
+{
+    let data = get_fixed_array(value, N)?
+        .iter()
+        .enumerate()
+        .map(|(i, x)| get(x).map_err(|e| e.prepend_index(i)))
+        .collect::<Result<Vec<T>, DestructureError>>()?;
+    // The length was checked above, so this never fails
+    Ok(data.try_into().ok().unwrap())
+}
+
+pub fn get_optional(
+    value: &ChumStructVariant,
+) -> Result<Option<&ChumStructVariant>, DestructureError> {
+    expect(
+        value,
+        "an optional value",
+        ChumStructVariant::get_optional_value,
+    )
+}
+
+/// Get the name and the value of a variant
+pub fn get_variant(
+    value: &ChumStructVariant,
+) -> Result<(&str, &ChumStructVariant), DestructureError> {
+    let name = expect(value, "a variant", ChumStructVariant::get_variant_name)?;
+    Ok((name, value.get_variant_data().unwrap()))
+}
+
+/// Get a struct member.
+/// If the member is missing, the error's path is the member's name.
+pub fn get_member<'a>(
+    value: &'a ChumStructVariant,
+    name: &str,
+) -> Result<&'a ChumStructVariant, DestructureError> {
+    expect(value, "a struct", ChumStructVariant::get_struct)?;
+    value
+        .get_struct_item(name)
+        .ok_or_else(|| DestructureError::MissingMember {
+            path: name.to_owned(),
+        })
+}
+
+/// Get a struct member using one of the functions in this module.
+/// Errors have the path to the member.
+pub fn get_member_as<'a, T, F>(
+    value: &'a ChumStructVariant,
+    name: &str,
+    get: F,
+) -> Result<T, DestructureError>
+where
+    F: FnOnce(&'a ChumStructVariant) -> Result<T, DestructureError>,
+{
+    get(get_member(value, name)?).map_err(|e| e.prepend_member(name))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::reader::material::Material;
+    use crate::reader::node::Node;
+    use crate::reader::rotshape::RotShape;
+    use crate::structure::destructure::*;
+    use crate::structure::{ChumStruct, IntType};
+
+    #[test]
+    fn destructure_errors() {
+        let node = Node::default().structure();
+        assert!(Node::destructure(&node).is_ok());
+        let mut data = node.clone();
+        *data.get_struct_item_mut("local_transform").unwrap() = ChumStructVariant::Float(1.0);
+        let error = Node::destructure(&data).err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "local_transform: expected a 3D transform, found a number"
+        );
+        let mut data = node.clone();
+        if let ChumStructVariant::Struct(ref mut members) = data {
+            members.retain(|x| x.0 != "resource_id");
+        }
+        let error = Node::destructure(&data).err().unwrap();
+        assert_eq!(error.to_string(), "resource_id: missing member");
+
+        let mut data = Material::default().structure();
+        if let ChumStructVariant::Array(ref mut array) = data.get_struct_item_mut("unk4").unwrap() {
+            array.data[2] = ChumStructVariant::Integer(256, IntType::U8);
+        }
+        match Material::destructure(&data) {
+            Err(DestructureError::OutOfRange {
+                path, value: 256, ..
+            }) => assert_eq!(path, "unk4[2]"),
+            _ => panic!("Expected an out of range error"),
+        }
+
+        let mut data = RotShape::default().structure();
+        *data.get_struct_item_mut("billboard_mode").unwrap() =
+            ChumStructVariant::Integer(2, IntType::Enum(vec![]));
+        match RotShape::destructure(&data) {
+            Err(DestructureError::InvalidEnumeration { path, value: 2, .. }) => {
+                assert_eq!(path, "billboard_mode")
+            }
+            _ => panic!("Expected an invalid enumeration error"),
+        }
+
+        let error = Material::destructure(&ChumStructVariant::Float(1.0))
+            .err()
+            .unwrap();
+        assert_eq!(
+            error.to_string(),
+            "(root): expected a struct, found a number"
+        );
+    }
+}
@@ -0,0 +1,253 @@
+use crate::structure::{join_member, ChumStruct, ChumStructVariant, IntType};
+use std::fmt;
+
+const TRANSFORM3D_FIELDS: [&str; 16] = [
+    "m11", "m12", "m13", "m14", "m21", "m22", "m23", "m24", "m31", "m32", "m33", "m34", "m41",
+    "m42", "m43", "m44",
+];
+const TRANSFORM2D_FIELDS: [&str; 6] = ["m11", "m12", "m21", "m22", "m31", "m32"];
+
+/// A single field that differs between two structures.
+/// `old` is None if the field was added, and `new` is None if it was removed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FieldChange {
+    pub path: String,
+    pub old: Option<String>,
+    pub new: Option<String>,
+}
+
+impl fmt::Display for FieldChange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (&self.old, &self.new) {
+            (Some(old), Some(new)) => write!(f, "{}: {} -> {}", self.path, old, new),
+            (None, Some(new)) => write!(f, "{}: added {}", self.path, new),
+            (Some(old), None) => write!(f, "{}: removed {}", self.path, old),
+            (None, None) => write!(f, "{}: unchanged", self.path),
+        }
+    }
+}
+
+/// Format a structure value on a single line.
+pub fn format_value(value: &ChumStructVariant) -> String {
+    use ChumStructVariant::*;
+    match value {
+        Integer(value, IntType::Enum(ref names)) => match names.get(*value as usize) {
+            Some(name) => name.clone(),
+            None => value.to_string(),
+        },
+        Integer(value, _) => value.to_string(),
+        Float(value) => format!("{:?}", value),
+        Transform3D(ref t) => format!("{:?}", t.to_array()),
+        Transform2D(ref t) => format!("{:?}", t.to_array()),
+        Vec2(ref v) => format!("({:?}, {:?})", v.x, v.y),
+        Vec3(ref v) => format!("({:?}, {:?}, {:?})", v.x, v.y, v.z),
+        Color(ref c, _) => format!("rgba({:?}, {:?}, {:?}, {:?})", c.r, c.g, c.b, c.a),
+        Reference(id, _) => format!("#{}", id),
+        Array(ref data) => format!(
+            "[{}]",
+            data.data
+                .iter()
+                .map(format_value)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        Struct(ref members) => format!(
+            "{{{}}}",
+            members
+                .iter()
+                .map(|(name, value)| format!("{}: {}", name, format_value(value)))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        Optional { value: None, .. } => "none".to_string(),
+        Optional {
+            value: Some(ref value),
+            ..
+        } => format_value(value),
+        Variant {
+            ref current,
+            ref value,
+            ..
+        } => format!("{} {}", current, format_value(value)),
+    }
+}
+
+fn push_change(changes: &mut Vec<FieldChange>, path: &str, old: String, new: String) {
+    if old != new {
+        changes.push(FieldChange {
+            path: path.to_string(),
+            old: Some(old),
+            new: Some(new),
+        });
+    }
+}
+
+fn diff_floats(
+    changes: &mut Vec<FieldChange>,
+    path: &str,
+    names: &[&str],
+    old: &[f32],
+    new: &[f32],
+) {
+    for ((name, a), b) in names.iter().zip(old.iter()).zip(new.iter()) {
+        // Compare bits so that NaN values do not always show up as changed
+        if a.to_bits() != b.to_bits() {
+            push_change(
+                changes,
+                &join_member(path, name),
+                format!("{:?}", a),
+                format!("{:?}", b),
+            );
+        }
+    }
+}
+
+fn diff_into(
+    changes: &mut Vec<FieldChange>,
+    path: &str,
+    old: &ChumStructVariant,
+    new: &ChumStructVariant,
+) {
+    use ChumStructVariant::*;
+    match (old, new) {
+        (Integer(a, _), Integer(b, _)) => {
+            if a != b {
+                push_change(changes, path, format_value(old), format_value(new));
+            }
+        }
+        (Float(a), Float(b)) => {
+            if a.to_bits() != b.to_bits() {
+                push_change(changes, path, format!("{:?}", a), format!("{:?}", b));
+            }
+        }
+        (Transform3D(a), Transform3D(b)) => diff_floats(
+            changes,
+            path,
+            &TRANSFORM3D_FIELDS,
+            &a.to_array(),
+            &b.to_array(),
+        ),
+        (Transform2D(a), Transform2D(b)) => diff_floats(
+            changes,
+            path,
+            &TRANSFORM2D_FIELDS,
+            &a.to_array(),
+            &b.to_array(),
+        ),
+        (Vec2(a), Vec2(b)) => diff_floats(changes, path, &["x", "y"], &[a.x, a.y], &[b.x, b.y]),
+        (Vec3(a), Vec3(b)) => diff_floats(
+            changes,
+            path,
+            &["x", "y", "z"],
+            &[a.x, a.y, a.z],
+            &[b.x, b.y, b.z],
+        ),
+        (Color(a, _), Color(b, _)) => diff_floats(
+            changes,
+            path,
+            &["r", "g", "b", "a"],
+            &[a.r, a.g, a.b, a.a],
+            &[b.r, b.g, b.b, b.a],
+        ),
+        (Reference(a, _), Reference(b, _)) => {
+            if a != b {
+                push_change(changes, path, format_value(old), format_value(new));
+            }
+        }
+        (Array(a), Array(b)) => {
+            for (i, (x, y)) in a.data.iter().zip(b.data.iter()).enumerate() {
+                diff_into(changes, &format!("{}[{}]", path, i), x, y);
+            }
+            for (i, x) in a.data.iter().enumerate().skip(b.data.len()) {
+                changes.push(FieldChange {
+                    path: format!("{}[{}]", path, i),
+                    old: Some(format_value(x)),
+                    new: None,
+                });
+            }
+            for (i, y) in b.data.iter().enumerate().skip(a.data.len()) {
+                changes.push(FieldChange {
+                    path: format!("{}[{}]", path, i),
+                    old: None,
+                    new: Some(format_value(y)),
+                });
+            }
+        }
+        (Struct(a), Struct(b)) => {
+            for (name, x) in a.iter() {
+                let member_path = join_member(path, name);
+                match new.get_struct_item(name) {
+                    Some(y) => diff_into(changes, &member_path, x, y),
+                    None => changes.push(FieldChange {
+                        path: member_path,
+                        old: Some(format_value(x)),
+                        new: None,
+                    }),
+                }
+            }
+            for (name, y) in b.iter() {
+                if old.get_struct_item(name).is_none() {
+                    changes.push(FieldChange {
+                        path: join_member(path, name),
+                        old: None,
+                        new: Some(format_value(y)),
+                    });
+                }
+            }
+        }
+        (Optional { value: Some(a), .. }, Optional { value: Some(b), .. }) => {
+            diff_into(changes, path, a, b)
+        }
+        (Optional { value: None, .. }, Optional { value: None, .. }) => {}
+        (
+            Variant {
+                current: a_name,
+                value: a,
+                ..
+            },
+            Variant {
+                current: b_name,
+                value: b,
+                ..
+            },
+        ) if a_name == b_name => diff_into(changes, &join_member(path, a_name), a, b),
+        (_, _) => push_change(changes, path, format_value(old), format_value(new)),
+    }
+}
+
+/// Find every field that differs between two structures.
+/// Fields are identified by their path, e.g. `material.color.a` or `nodes[3]`.
+/// Variant values add the name of the current variant to the path.
+pub fn diff(old: &ChumStructVariant, new: &ChumStructVariant) -> Vec<FieldChange> {
+    let mut changes = Vec::new();
+    diff_into(&mut changes, "", old, new);
+    changes
+}
+
+/// Find every field that differs between two values of the same structure.
+pub fn diff_structs<T: ChumStruct>(old: &T, new: &T) -> Vec<FieldChange> {
+    diff(&old.structure(), &new.structure())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::reader::material::Material;
+    use crate::structure::diff::*;
+
+    #[test]
+    fn diff_material() {
+        let old = Material::default();
+        let mut new = old.clone();
+        assert_eq!(diff_structs(&old, &new), vec![]);
+        new.color.a = 0.5;
+        new.emission.y = 2.0;
+        new.unk4[3] = 7;
+        new.texture = 42;
+        let changes = diff_structs(&old, &new);
+        let paths: Vec<&str> = changes.iter().map(|c| c.path.as_str()).collect();
+        assert_eq!(paths, vec!["color.a", "emission.g", "unk4[3]", "texture"]);
+        assert_eq!(changes[0].old.as_deref(), Some("1.0"));
+        assert_eq!(changes[0].new.as_deref(), Some("0.5"));
+        assert_eq!(changes[3].to_string(), "texture: #0 -> #42");
+    }
+}
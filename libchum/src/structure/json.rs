@@ -0,0 +1,568 @@
+//! Conversion between structures and JSON values.
+//!
+//! Values are written without their types, so reading a value back requires a
+//! template structure of the same type, such as the structure of a default value.
+//! `serde_json::Value` implements Serialize and Deserialize, so the values can
+//! also be written to any other format that serde supports.
+//!
+//! | Structure    | JSON                                                   |
+//! |--------------|--------------------------------------------------------|
+//! | Integer      | number; enums as a name, flags as a list of names      |
+//! |              | unless bits without a name are set                     |
+//! | Float        | number, or `"NaN"`, `"inf"` or `"-inf"`                |
+//! | Transform    | list of 16 (3D) or 6 (2D) numbers, row by row          |
+//! | Vec2, Vec3   | list of 2 or 3 numbers                                 |
+//! | Quaternion   | list of 4 numbers, `[i, j, k, w]`                      |
+//! | Color        | `[r, g, b, a]`, or `[r, g, b]` if it has no alpha      |
+//! | Reference    | name if it is known, otherwise the ID                  |
+//! | Array        | list                                                   |
+//! | Struct       | object, in field order                                 |
+//! | Optional     | `null` or the value                                    |
+//! | Variant      | object with a single member, e.g. `{"Mesh": {...}}`    |
+
+use crate::common::{self, ColorRGBA, Vector2, Vector3};
+use crate::error::DestructureError;
+use crate::structure::{
+    error_path, join_member, ArrayData, ChumStruct, ChumStructVariant, IntType, VariantOption,
+};
+use crate::util;
+use serde_json::{Map, Value};
+
+/// Error that can occur while reading a structure from a JSON value.
+#[derive(Debug, thiserror::Error)]
+pub enum JsonError {
+    #[error("{path}: expected {expected}, found {found}")]
+    TypeMismatch {
+        path: String,
+        expected: &'static str,
+        found: &'static str,
+    },
+    #[error("{path}: {value} is out of range; expected {min} to {max}")]
+    OutOfRange {
+        path: String,
+        value: i64,
+        min: i64,
+        max: i64,
+    },
+    #[error("{path}: unknown name {name}; expected one of {expected:?}")]
+    UnknownName {
+        path: String,
+        name: String,
+        expected: Vec<String>,
+    },
+    #[error("{path}: missing member")]
+    MissingMember { path: String },
+    #[error("{path}: unknown member")]
+    UnknownMember { path: String },
+    #[error("{path}: expected {expected} elements, found {found}")]
+    WrongLength {
+        path: String,
+        expected: usize,
+        found: usize,
+    },
+    #[error(transparent)]
+    Destructure(#[from] DestructureError),
+}
+
+fn describe(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "a list",
+        Value::Object(_) => "an object",
+    }
+}
+
+fn float_to_json(value: f32) -> Value {
+    if value.is_nan() {
+        return Value::from("NaN");
+    } else if value.is_infinite() {
+        return Value::from(if value > 0.0 { "inf" } else { "-inf" });
+    }
+    // Prefer the shortest decimal form, e.g. 0.1 instead of 0.10000000149011612
+    match format!("{}", value).parse::<f64>() {
+        Ok(short) if short as f32 == value => Value::from(short),
+        _ => Value::from(value as f64),
+    }
+}
+
+fn floats_to_json(values: &[f32]) -> Value {
+    Value::Array(values.iter().copied().map(float_to_json).collect())
+}
+
+fn to_json_inner<'a>(value: &ChumStructVariant, names: &dyn Fn(i32) -> Option<&'a str>) -> Value {
+    use ChumStructVariant::*;
+    match value {
+        Integer(x, IntType::Enum(ref options)) => match options.get(*x as usize) {
+            Some(name) if *x >= 0 => Value::from(name.as_str()),
+            _ => Value::from(*x),
+        },
+        Integer(x, IntType::Flags(ref options)) => {
+            if *x >= 0 && *x <= IntType::Flags(options.clone()).get_range().1 {
+                Value::Array(
+                    options
+                        .iter()
+                        .enumerate()
+                        .filter(|(i, _)| x & (1 << i) != 0)
+                        .map(|(_, name)| Value::from(name.as_str()))
+                        .collect(),
+                )
+            } else {
+                Value::from(*x)
+            }
+        }
+        Integer(x, _) => Value::from(*x),
+        Float(x) => float_to_json(*x),
+        Transform3D(ref t) => floats_to_json(&t.to_array()),
+        Transform2D(ref t) => floats_to_json(&t.to_array()),
+        Vec2(ref v) => floats_to_json(&[v.x, v.y]),
+        Vec3(ref v) => floats_to_json(&[v.x, v.y, v.z]),
+        Color(ref c, ref info) => {
+            if info.has_alpha {
+                floats_to_json(&[c.r, c.g, c.b, c.a])
+            } else {
+                floats_to_json(&[c.r, c.g, c.b])
+            }
+        }
+        // Names are only used if they hash back to the same ID
+        Reference(id, _) => match names(*id) {
+            Some(name) if util::hash_name_i32(name) == *id => Value::from(name),
+            _ => Value::from(*id),
+        },
+        Array(ref data) => {
+            Value::Array(data.data.iter().map(|x| to_json_inner(x, names)).collect())
+        }
+        Struct(ref members) => Value::Object(
+            members
+                .iter()
+                .map(|(name, x)| (name.clone(), to_json_inner(x, names)))
+                .collect(),
+        ),
+        Optional { value: None, .. } => Value::Null,
+        Optional {
+            value: Some(ref x), ..
+        } => to_json_inner(x, names),
+        Variant {
+            ref current,
+            ref value,
+            ..
+        } => {
+            let mut map = Map::new();
+            map.insert(current.clone(), to_json_inner(value, names));
+            Value::Object(map)
+        }
+    }
+}
+
+/// Convert a structure to a JSON value.
+/// References are written as names if `names` can resolve them.
+pub fn to_json<'a, F>(value: &ChumStructVariant, names: F) -> Value
+where
+    F: Fn(i32) -> Option<&'a str>,
+{
+    to_json_inner(value, &names)
+}
+
+/// Convert a value to a JSON value.
+pub fn struct_to_json<'a, T, F>(value: &T, names: F) -> Value
+where
+    T: ChumStruct,
+    F: Fn(i32) -> Option<&'a str>,
+{
+    to_json(&value.structure(), names)
+}
+
+fn float_from_json(value: &Value, path: &str) -> Result<f32, JsonError> {
+    match value {
+        Value::Number(ref x) => Ok(x.as_f64().unwrap_or_default() as f32),
+        Value::String(ref x) if x == "NaN" => Ok(f32::NAN),
+        Value::String(ref x) if x == "inf" => Ok(f32::INFINITY),
+        Value::String(ref x) if x == "-inf" => Ok(f32::NEG_INFINITY),
+        _ => Err(JsonError::TypeMismatch {
+            path: error_path(path),
+            expected: "a number",
+            found: describe(value),
+        }),
+    }
+}
+
+fn floats_from_json(value: &Value, path: &str, lengths: &[usize]) -> Result<Vec<f32>, JsonError> {
+    let items = match value {
+        Value::Array(ref items) => items,
+        _ => {
+            return Err(JsonError::TypeMismatch {
+                path: error_path(path),
+                expected: "a list of numbers",
+                found: describe(value),
+            })
+        }
+    };
+    if !lengths.contains(&items.len()) {
+        return Err(JsonError::WrongLength {
+            path: error_path(path),
+            expected: lengths[0],
+            found: items.len(),
+        });
+    }
+    items
+        .iter()
+        .enumerate()
+        .map(|(i, x)| float_from_json(x, &format!("{}[{}]", path, i)))
+        .collect()
+}
+
+fn find_name(options: &[String], name: &str, path: &str) -> Result<usize, JsonError> {
+    options
+        .iter()
+        .position(|x| x == name)
+        .ok_or_else(|| JsonError::UnknownName {
+            path: error_path(path),
+            name: name.to_string(),
+            expected: options.to_vec(),
+        })
+}
+
+fn int_from_json(value: &Value, inttype: &IntType, path: &str) -> Result<i64, JsonError> {
+    let x = match (value, inttype) {
+        (Value::String(ref name), IntType::Enum(ref options)) => {
+            return Ok(find_name(options, name, path)? as i64)
+        }
+        (Value::Array(ref items), IntType::Flags(ref options)) => {
+            let mut x = 0i64;
+            for (i, item) in items.iter().enumerate() {
+                let item_path = format!("{}[{}]", path, i);
+                match item {
+                    Value::String(ref name) => x |= 1 << find_name(options, name, &item_path)?,
+                    _ => {
+                        return Err(JsonError::TypeMismatch {
+                            path: item_path,
+                            expected: "a flag name",
+                            found: describe(item),
+                        })
+                    }
+                }
+            }
+            return Ok(x);
+        }
+        (Value::Number(ref x), _) if x.is_i64() => x.as_i64().unwrap(),
+        (Value::Number(ref x), _) if x.is_u64() => {
+            let (min, max) = inttype.get_range();
+            return Err(JsonError::OutOfRange {
+                path: error_path(path),
+                value: i64::MAX,
+                min,
+                max,
+            });
+        }
+        _ => {
+            return Err(JsonError::TypeMismatch {
+                path: error_path(path),
+                expected: match inttype {
+                    IntType::Enum(_) => "a name",
+                    IntType::Flags(_) => "a list of flag names",
+                    _ => "an integer",
+                },
+                found: describe(value),
+            })
+        }
+    };
+    let (min, max) = match inttype {
+        // Enumerations are indices into their list of names
+        IntType::Enum(ref options) => (0, options.len() as i64 - 1),
+        // Flags may have bits set that do not have a name;
+        // the range of the field's integer type is checked when destructuring
+        IntType::Flags(_) => return Ok(x),
+        _ => inttype.get_range(),
+    };
+    if x < min || x > max {
+        return Err(JsonError::OutOfRange {
+            path: error_path(path),
+            value: x,
+            min,
+            max,
+        });
+    }
+    Ok(x)
+}
+
+fn variant_from_json(
+    current: &str,
+    template: &ChumStructVariant,
+    options: &[VariantOption],
+    value: &Value,
+    path: &str,
+) -> Result<ChumStructVariant, JsonError> {
+    let (name, inner) = match value {
+        Value::Object(ref map) if map.len() == 1 => map.iter().next().unwrap(),
+        _ => {
+            return Err(JsonError::TypeMismatch {
+                path: error_path(path),
+                expected: "an object with a single member",
+                found: describe(value),
+            })
+        }
+    };
+    let names: Vec<String> = options.iter().map(|x| x.name.clone()).collect();
+    let index = find_name(&names, name, path)?;
+    let inner_path = join_member(path, name);
+    let value = if name == current {
+        from_json_inner(template, inner, &inner_path)?
+    } else {
+        from_json_inner(&(options[index].default_value)(), inner, &inner_path)?
+    };
+    Ok(ChumStructVariant::Variant {
+        current: name.clone(),
+        value: Box::new(value),
+        options: options.to_vec(),
+    })
+}
+
+fn from_json_inner(
+    template: &ChumStructVariant,
+    value: &Value,
+    path: &str,
+) -> Result<ChumStructVariant, JsonError> {
+    use ChumStructVariant::*;
+    Ok(match template {
+        Integer(_, ref inttype) => Integer(int_from_json(value, inttype, path)?, inttype.clone()),
+        Float(_) => Float(float_from_json(value, path)?),
+        Transform3D(_) => {
+            let v = floats_from_json(value, path, &[16])?;
+            let mut array = [0.0f32; 16];
+            array.copy_from_slice(&v);
+            Transform3D(common::Transform3D::from_array(array))
+        }
+        Transform2D(_) => {
+            let v = floats_from_json(value, path, &[6])?;
+            let mut array = [0.0f32; 6];
+            array.copy_from_slice(&v);
+            Transform2D(common::Transform2D::from_array(array))
+        }
+        Vec2(_) => {
+            let v = floats_from_json(value, path, &[2])?;
+            Vec2(Vector2::new(v[0], v[1]))
+        }
+        Vec3(_) => {
+            let v = floats_from_json(value, path, &[3])?;
+            Vec3(Vector3::new(v[0], v[1], v[2]))
+        }
+        Color(_, ref info) => {
+            let lengths: &[usize] = if info.has_alpha { &[4] } else { &[3, 4] };
+            let v = floats_from_json(value, path, lengths)?;
+            let a = v.get(3).copied().unwrap_or(1.0);
+            Color(ColorRGBA::new(v[0], v[1], v[2], a), *info)
+        }
+        Reference(_, ref type_id) => match value {
+            Value::String(ref name) => Reference(util::hash_name_i32(name), type_id.clone()),
+            Value::Number(_) => Reference(
+                int_from_json(value, &IntType::I32, path)? as i32,
+                type_id.clone(),
+            ),
+            _ => {
+                return Err(JsonError::TypeMismatch {
+                    path: error_path(path),
+                    expected: "a name or an ID",
+                    found: describe(value),
+                })
+            }
+        },
+        Array(ref data) => {
+            let items = match value {
+                Value::Array(ref items) => items,
+                _ => {
+                    return Err(JsonError::TypeMismatch {
+                        path: error_path(path),
+                        expected: "a list",
+                        found: describe(value),
+                    })
+                }
+            };
+            if !data.can_resize && items.len() != data.data.len() {
+                return Err(JsonError::WrongLength {
+                    path: error_path(path),
+                    expected: data.data.len(),
+                    found: items.len(),
+                });
+            }
+            let mut values = Vec::with_capacity(items.len());
+            for (i, item) in items.iter().enumerate() {
+                let item_path = format!("{}[{}]", path, i);
+                let value = match data.data.get(i) {
+                    Some(x) => from_json_inner(x, item, &item_path)?,
+                    None => from_json_inner(&(data.default_value)(), item, &item_path)?,
+                };
+                values.push(value);
+            }
+            Array(ArrayData {
+                data: values,
+                default_value: data.default_value,
+                can_resize: data.can_resize,
+            })
+        }
+        Struct(ref members) => {
+            let map = match value {
+                Value::Object(ref map) => map,
+                _ => {
+                    return Err(JsonError::TypeMismatch {
+                        path: error_path(path),
+                        expected: "an object",
+                        found: describe(value),
+                    })
+                }
+            };
+            if let Some(name) = map
+                .keys()
+                .find(|name| !members.iter().any(|(x, _)| x == *name))
+            {
+                return Err(JsonError::UnknownMember {
+                    path: join_member(path, name),
+                });
+            }
+            let mut values = Vec::with_capacity(members.len());
+            for (name, member) in members.iter() {
+                let member_path = join_member(path, name);
+                let item = map.get(name).ok_or_else(|| JsonError::MissingMember {
+                    path: member_path.clone(),
+                })?;
+                values.push((name.clone(), from_json_inner(member, item, &member_path)?));
+            }
+            Struct(values)
+        }
+        Optional {
+            value: ref inner,
+            default_value,
+        } => Optional {
+            value: match (value, inner) {
+                (Value::Null, _) => None,
+                (_, Some(ref x)) => Some(Box::new(from_json_inner(x, value, path)?)),
+                (_, None) => Some(Box::new(from_json_inner(&default_value(), value, path)?)),
+            },
+            default_value: *default_value,
+        },
+        Variant {
+            ref current,
+            value: ref inner,
+            ref options,
+        } => variant_from_json(current, inner, options, value, path)?,
+    })
+}
+
+/// Read a structure from a JSON value, using `template` for the type of each field.
+/// Arrays may only change length if the template's arrays can be resized.
+pub fn from_json(
+    template: &ChumStructVariant,
+    value: &Value,
+) -> Result<ChumStructVariant, JsonError> {
+    from_json_inner(template, value, "")
+}
+
+/// Read a value from a JSON value, using the structure of the default value as the template.
+pub fn struct_from_json<T>(value: &Value) -> Result<T, JsonError>
+where
+    T: ChumStruct + Default,
+{
+    let structure = from_json(&T::default().structure(), value)?;
+    Ok(T::destructure(&structure)?)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::reader::bitmap::{AlphaLevel, BitmapStruct};
+    use crate::reader::material::Material;
+    use crate::reader::node::{Node, NodeDataUnion};
+    use crate::structure::json::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn json_round_trip() {
+        let mut node = Node {
+            node_parent_id: util::hash_name_i32("ROOT"),
+            resource_id: -5,
+            ..Default::default()
+        };
+        node.floatv1[0] = 0.1;
+        node.floatv1[1] = f32::NAN;
+        node.floatv1[2] = f32::NEG_INFINITY;
+        node.unk2[0] = u32::MAX;
+        node.local_transform = common::Transform3D::from_array([
+            1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0, 11.0, 12.0, 13.0, 14.0, 15.0, 16.0,
+        ]);
+        node.local_rotation = common::Quaternion::new(0.1, 0.7, -0.3, 0.64);
+        let names: HashMap<i32, &str> = vec![(util::hash_name_i32("ROOT"), "ROOT")]
+            .into_iter()
+            .collect();
+        let value = struct_to_json(&node, |id| names.get(&id).copied());
+        assert_eq!(value["node_parent_id"], "ROOT");
+        assert_eq!(value["resource_id"], -5);
+        assert_eq!(value["floatv1"][0], 0.1);
+        assert_eq!(value["floatv1"][1], "NaN");
+        assert_eq!(value["unk2"][0], u32::MAX as i64);
+        assert_eq!(value["local_transform"][4], 5.0);
+        assert_eq!(value["local_rotation"][1], 0.7);
+        assert_eq!(value["local_rotation"][3], 0.64);
+        assert!(value["node_data"].get("Empty").is_some());
+        let keys: Vec<&String> = value.as_object().unwrap().keys().collect();
+        assert_eq!(keys[0], "node_parent_id");
+
+        let text = serde_json::to_string(&value).unwrap();
+        let value: Value = serde_json::from_str(&text).unwrap();
+        let read: Node = struct_from_json(&value).unwrap();
+        assert_eq!(read.node_parent_id, node.node_parent_id);
+        assert_eq!(read.resource_id, -5);
+        assert_eq!(read.floatv1[0], 0.1);
+        assert!(read.floatv1[1].is_nan());
+        assert_eq!(read.floatv1[2], f32::NEG_INFINITY);
+        assert_eq!(read.unk2[0], u32::MAX);
+        assert_eq!(read.local_transform, node.local_transform);
+        assert_eq!(read.local_rotation, node.local_rotation);
+        assert!(matches!(read.node_data, NodeDataUnion::Empty {}));
+
+        let material = Material::default();
+        let mut value = struct_to_json(&material, |_| None);
+        value["texture"] = Value::from("DB:>TEX.TBITMAP");
+        let read: Material = struct_from_json(&value).unwrap();
+        assert_eq!(read.texture, util::hash_name_i32("DB:>TEX.TBITMAP"));
+
+        value["texture"] = Value::from(1.5);
+        match struct_from_json::<Material>(&value) {
+            Err(JsonError::TypeMismatch { path, .. }) => assert_eq!(path, "texture"),
+            _ => panic!("Expected a type mismatch"),
+        }
+        value["texture"] = Value::from(0);
+        value.as_object_mut().unwrap().remove("color");
+        match struct_from_json::<Material>(&value) {
+            Err(JsonError::MissingMember { path }) => assert_eq!(path, "color"),
+            _ => panic!("Expected a missing member"),
+        }
+    }
+
+    #[test]
+    fn json_unnamed_flags() {
+        let bitmap = BitmapStruct {
+            alpha: AlphaLevel::Blend,
+            flags: 0b1010_0101,
+            unknown: 1,
+        };
+        let template = bitmap.structure();
+        let read = |value: &Value| {
+            from_json(&template, value).and_then(|x| Ok(BitmapStruct::destructure(&x)?))
+        };
+        // Bits without a name are written as a number instead of a list of names
+        let mut value = struct_to_json(&bitmap, |_| None);
+        assert_eq!(value["flags"], 0b1010_0101);
+        assert_eq!(read(&value).unwrap().flags, bitmap.flags);
+
+        value["flags"] = Value::from(vec!["a", "c"]);
+        assert_eq!(read(&value).unwrap().flags, 0b101);
+
+        value["flags"] = Value::from(0x100);
+        match read(&value) {
+            Err(JsonError::Destructure(DestructureError::OutOfRange { path, .. })) => {
+                assert_eq!(path, "flags")
+            }
+            _ => panic!("Expected an out of range value"),
+        }
+    }
+}
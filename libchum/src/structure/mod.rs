@@ -1,3 +1,14 @@
+pub mod destructure;
+pub mod diff;
+pub mod json;
+mod primitive;
+pub mod validate;
+
+pub use diff::FieldChange;
+pub use json::JsonError;
+pub use libchum_derive::{ChumEnum, ChumStruct};
+pub use validate::ValidationError;
+
 use crate::common;
 use crate::error;
 
@@ -26,6 +37,7 @@ pub enum IntType {
 }
 
 impl IntType {
+    /// Get the smallest and largest legal values of this type
     pub fn get_range(&self) -> (i64, i64) {
         match self {
             IntType::I8 => (i8::MIN as i64, i8::MAX as i64),
@@ -35,7 +47,7 @@ impl IntType {
             IntType::I32 => (i32::MIN as i64, i32::MAX as i64),
             IntType::U32 => (u32::MIN as i64, u32::MAX as i64),
             IntType::Custom(a, b) => (*a, *b),
-            IntType::Enum(ref v) => (0, v.len() as i64),
+            IntType::Enum(ref v) => (0, v.len() as i64 - 1),
             IntType::Flags(ref v) => (0, 2i64.pow(v.len() as u32) - 1),
         }
     }
@@ -92,7 +104,76 @@ pub enum ChumPathElement<'a> {
 
 pub type ChumPath<'a> = [ChumPathElement<'a>];
 
+/// A reference to another file, found in a structure.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StructReference<'a> {
+    /// Path to the reference, e.g. `texture` or `nodes[3].node`
+    pub path: String,
+    pub id: i32,
+    /// The type of file that the reference should point to
+    pub type_id: Option<&'a str>,
+}
+
+/// Get the path to the member `name` of the value at `path`, e.g. `nodes[3].node`
+pub(crate) fn join_member(path: &str, name: &str) -> String {
+    if path.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", path, name)
+    }
+}
+
+/// Get the path to show in an error message; the structure itself has an empty path
+pub(crate) fn error_path(path: &str) -> String {
+    if path.is_empty() {
+        "(root)".to_string()
+    } else {
+        path.to_string()
+    }
+}
+
+fn collect_references<'a>(
+    value: &'a ChumStructVariant,
+    path: &str,
+    references: &mut Vec<StructReference<'a>>,
+) {
+    use ChumStructVariant::*;
+    match value {
+        Reference(id, ref type_id) => references.push(StructReference {
+            path: path.to_string(),
+            id: *id,
+            type_id: type_id.as_deref(),
+        }),
+        Array(ref data) => {
+            for (i, x) in data.data.iter().enumerate() {
+                collect_references(x, &format!("{}[{}]", path, i), references);
+            }
+        }
+        Struct(ref members) => {
+            for (name, x) in members.iter() {
+                collect_references(x, &join_member(path, name), references);
+            }
+        }
+        Optional {
+            value: Some(ref x), ..
+        } => collect_references(x, path, references),
+        Variant {
+            ref current,
+            ref value,
+            ..
+        } => collect_references(value, &join_member(path, current), references),
+        _ => {}
+    }
+}
+
 impl ChumStructVariant {
+    /// Get every reference in this structure, in the order they appear.
+    pub fn get_references(&self) -> Vec<StructReference<'_>> {
+        let mut references = Vec::new();
+        collect_references(self, "", &mut references);
+        references
+    }
+
     pub fn get<'a>(&self, path: &ChumPath<'a>) -> Option<&ChumStructVariant> {
         use ChumPathElement::*;
         use ChumStructVariant::*;
@@ -151,17 +232,7 @@ impl ChumStructVariant {
     pub fn get_i64_range(&self) -> Option<(i64, i64)> {
         use ChumStructVariant::*;
         match *self {
-            Integer(_, ref x) => match x {
-                IntType::I8 => Some((i8::MIN as i64, i8::MAX as i64)),
-                IntType::U8 => Some((u8::MIN as i64, u8::MAX as i64)),
-                IntType::I16 => Some((i16::MIN as i64, i16::MAX as i64)),
-                IntType::U16 => Some((u16::MIN as i64, u16::MAX as i64)),
-                IntType::I32 => Some((i32::MIN as i64, i32::MAX as i64)),
-                IntType::U32 => Some((u32::MIN as i64, u32::MAX as i64)),
-                IntType::Custom(a, b) => Some((*a, *b)),
-                IntType::Enum(ref v) => Some((0, v.len() as i64)),
-                IntType::Flags(ref v) => Some((0, 2i64.pow(v.len() as u32) - 1)),
-            },
+            Integer(_, ref x) => Some(x.get_range()),
             _ => None,
         }
     }
@@ -249,6 +320,15 @@ impl ChumStructVariant {
             _ => None,
         }
     }
+    pub fn get_struct_item_mut(&mut self, name: &str) -> Option<&mut ChumStructVariant> {
+        match self {
+            ChumStructVariant::Struct(ref mut data) => data
+                .iter_mut()
+                .find(|(id, _)| id == name)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
     pub fn get_optional_value(&self) -> Option<Option<&ChumStructVariant>> {
         use std::borrow::Borrow;
         use ChumStructVariant::*;
@@ -0,0 +1,90 @@
+//! `ChumStruct` implementations for the types that structures are built from.
+
+use crate::common;
+use crate::error::DestructureError;
+use crate::structure::destructure;
+use crate::structure::{ArrayData, ChumStruct, ChumStructVariant, ColorInfo, IntType};
+
+macro_rules! impl_struct_integer {
+    ($type:ty, $inttype:ident) => {
+        impl ChumStruct for $type {
+            fn structure(&self) -> ChumStructVariant {
+                ChumStructVariant::Integer(*self as i64, IntType::$inttype)
+            }
+            fn destructure(data: &ChumStructVariant) -> Result<Self, DestructureError> {
+                destructure::get_integer(data, <$type>::MIN as i64, <$type>::MAX as i64)
+                    .map(|x| x as $type)
+            }
+        }
+    };
+}
+
+impl_struct_integer!(u8, U8);
+impl_struct_integer!(i8, I8);
+impl_struct_integer!(u16, U16);
+impl_struct_integer!(i16, I16);
+impl_struct_integer!(u32, U32);
+impl_struct_integer!(i32, I32);
+
+macro_rules! impl_struct_copy {
+    ($type:ty, $variant:ident, $get:path) => {
+        impl ChumStruct for $type {
+            fn structure(&self) -> ChumStructVariant {
+                ChumStructVariant::$variant(*self)
+            }
+            fn destructure(data: &ChumStructVariant) -> Result<Self, DestructureError> {
+                $get(data)
+            }
+        }
+    };
+}
+
+impl_struct_copy!(f32, Float, destructure::get_float);
+impl_struct_copy!(
+    common::Transform3D,
+    Transform3D,
+    destructure::get_transform3d
+);
+impl_struct_copy!(
+    common::Transform2D,
+    Transform2D,
+    destructure::get_transform2d
+);
+impl_struct_copy!(common::Vector2, Vec2, destructure::get_vec2);
+impl_struct_copy!(common::Vector3, Vec3, destructure::get_vec3);
+
+impl ChumStruct for common::ColorRGBA {
+    fn structure(&self) -> ChumStructVariant {
+        ChumStructVariant::Color(*self, ColorInfo { has_alpha: true })
+    }
+    fn destructure(data: &ChumStructVariant) -> Result<Self, DestructureError> {
+        destructure::get_color(data)
+    }
+}
+
+/// Quaternions are presented as their components, `[i, j, k, w]`,
+/// so that they are not changed when they are written back
+impl ChumStruct for common::Quaternion {
+    fn structure(&self) -> ChumStructVariant {
+        let q = &self.inner;
+        [q.i, q.j, q.k, q.r].structure()
+    }
+    fn destructure(data: &ChumStructVariant) -> Result<Self, DestructureError> {
+        let [i, j, k, w] = <[f32; 4]>::destructure(data)?;
+        Ok(common::Quaternion::new(i, j, k, w))
+    }
+}
+
+impl<T: ChumStruct, const N: usize> ChumStruct for [T; N] {
+    fn structure(&self) -> ChumStructVariant {
+        ChumStructVariant::Array(ArrayData {
+            can_resize: false,
+            data: self.iter().map(T::structure).collect(),
+            // some random default value that won't be used anyways
+            default_value: || ChumStructVariant::Integer(0, IntType::U8),
+        })
+    }
+    fn destructure(data: &ChumStructVariant) -> Result<Self, DestructureError> {
+        destructure::get_fixed_elements(data, T::destructure)
+    }
+}
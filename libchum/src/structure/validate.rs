@@ -0,0 +1,234 @@
+use crate::structure::{join_member, ChumStructVariant, IntType};
+
+/// A value that would not be written correctly, found while validating a structure.
+#[derive(Clone, Debug, PartialEq, thiserror::Error)]
+pub enum ValidationError {
+    #[error("{path}: {value} is out of range; expected {min} to {max}")]
+    OutOfRange {
+        path: String,
+        value: i64,
+        min: i64,
+        max: i64,
+    },
+    /// Numbers that are not finite can be written, but are usually a mistake
+    #[error("{path}: {value} is not a finite number")]
+    NotFinite { path: String, value: f32 },
+    #[error("{path}: expected {expected} elements, found {found}")]
+    WrongLength {
+        path: String,
+        expected: usize,
+        found: usize,
+    },
+}
+
+impl ValidationError {
+    /// Returns true if the value can still be written, but is probably a mistake
+    pub fn is_warning(&self) -> bool {
+        matches!(self, ValidationError::NotFinite { .. })
+    }
+}
+
+fn check_finite(values: &[f32], path: &str, errors: &mut Vec<ValidationError>) {
+    if let Some(value) = values.iter().find(|x| !x.is_finite()) {
+        errors.push(ValidationError::NotFinite {
+            path: path.to_string(),
+            value: *value,
+        });
+    }
+}
+
+fn validate_value(
+    value: &ChumStructVariant,
+    template: Option<&ChumStructVariant>,
+    path: &str,
+    errors: &mut Vec<ValidationError>,
+) {
+    use ChumStructVariant::*;
+    match value {
+        // Flags may have bits set that do not have a name,
+        // so their range depends on the type of the field they are stored in
+        Integer(_, IntType::Flags(_)) => {}
+        Integer(x, ref inttype) => {
+            let (min, max) = inttype.get_range();
+            if *x < min || *x > max {
+                errors.push(ValidationError::OutOfRange {
+                    path: path.to_string(),
+                    value: *x,
+                    min,
+                    max,
+                });
+            }
+        }
+        Float(x) => check_finite(&[*x], path, errors),
+        Transform3D(ref tx) => check_finite(&tx.to_array(), path, errors),
+        Transform2D(ref tx) => check_finite(&tx.to_array(), path, errors),
+        Vec2(ref v) => check_finite(&v.to_array(), path, errors),
+        Vec3(ref v) => check_finite(&v.to_array(), path, errors),
+        Color(ref c, _) => check_finite(&[c.r, c.g, c.b, c.a], path, errors),
+        Reference(..) => {}
+        Array(ref array) => {
+            let template = match template {
+                Some(Array(ref x)) => Some(x),
+                _ => None,
+            };
+            if let Some(t) = template {
+                if !t.can_resize && t.data.len() != array.data.len() {
+                    errors.push(ValidationError::WrongLength {
+                        path: path.to_string(),
+                        expected: t.data.len(),
+                        found: array.data.len(),
+                    });
+                }
+            }
+            let default_element = template.map(|t| (t.default_value)());
+            for (i, element) in array.data.iter().enumerate() {
+                let element_template = template
+                    .and_then(|t| t.data.get(i))
+                    .or(default_element.as_ref());
+                validate_value(
+                    element,
+                    element_template,
+                    &format!("{}[{}]", path, i),
+                    errors,
+                );
+            }
+        }
+        Struct(ref members) => {
+            for (name, member) in members.iter() {
+                let member_template = template.and_then(|t| t.get_struct_item(name));
+                validate_value(member, member_template, &join_member(path, name), errors);
+            }
+        }
+        Optional { value: None, .. } => {}
+        Optional {
+            value: Some(ref x), ..
+        } => {
+            let default_value = match template {
+                Some(Optional {
+                    value: None,
+                    default_value,
+                }) => Some(default_value()),
+                _ => None,
+            };
+            let inner_template = match template {
+                Some(Optional {
+                    value: Some(ref t), ..
+                }) => Some(t.as_ref()),
+                _ => default_value.as_ref(),
+            };
+            validate_value(x, inner_template, path, errors);
+        }
+        Variant {
+            ref current,
+            value: ref x,
+            ..
+        } => {
+            let inner_template = match template {
+                Some(Variant { ref options, .. }) => options
+                    .iter()
+                    .find(|option| &option.name == current)
+                    .map(|option| (option.default_value)()),
+                _ => None,
+            };
+            validate_value(
+                x,
+                inner_template.as_ref(),
+                &join_member(path, current),
+                errors,
+            );
+        }
+    }
+}
+
+impl ChumStructVariant {
+    /// Check that every value in this structure can be written as it is:
+    /// integers must be in the range of their type.
+    /// Numbers that are not finite are reported as warnings.
+    /// Returns every problem that was found, in order.
+    pub fn validate(&self) -> Vec<ValidationError> {
+        let mut errors = Vec::new();
+        validate_value(self, None, "", &mut errors);
+        errors
+    }
+
+    /// Same as `validate`, but also checks that fixed arrays
+    /// have as many elements as they do in `template`.
+    /// `template` is usually the structure of the type's default value.
+    pub fn validate_against(&self, template: &ChumStructVariant) -> Vec<ValidationError> {
+        let mut errors = Vec::new();
+        validate_value(self, Some(template), "", &mut errors);
+        errors
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::common::{Transform3D, Vector3};
+    use crate::error::DestructureError;
+    use crate::format::TotemFormat;
+    use crate::reader::collisionvol::CollisionVol;
+    use crate::reader::node::Node;
+    use crate::structure::validate::*;
+    use crate::structure::{ChumStruct, IntType};
+
+    #[test]
+    fn validate_structure() {
+        let template = Node::default().structure();
+        assert_eq!(template.validate_against(&template), vec![]);
+
+        let mut data = template.clone();
+        *data.get_struct_item_mut("local_translation").unwrap() =
+            ChumStructVariant::Vec3(Vector3::new(0.0, f32::NAN, 0.0));
+        if let ChumStructVariant::Array(ref mut array) = data.get_struct_item_mut("unk2").unwrap() {
+            array.data[1] = ChumStructVariant::Integer(-1, IntType::U32);
+        }
+        if let ChumStructVariant::Array(ref mut array) =
+            data.get_struct_item_mut("floatv1").unwrap()
+        {
+            array.data.pop();
+        }
+        let errors = data.validate_against(&template);
+        assert_eq!(errors.len(), 3);
+        assert_eq!(
+            errors[0].to_string(),
+            "floatv1: expected 9 elements, found 8"
+        );
+        assert!(
+            matches!(errors[1], ValidationError::NotFinite { ref path, .. } if path == "local_translation")
+        );
+        assert!(errors[1].is_warning() && !errors[2].is_warning());
+        assert_eq!(
+            errors[2],
+            ValidationError::OutOfRange {
+                path: "unk2[1]".to_string(),
+                value: -1,
+                min: 0,
+                max: u32::MAX as i64,
+            }
+        );
+        // Lengths can only be checked against a template
+        assert_eq!(data.validate().len(), 2);
+
+        // Numbers that are not finite can still be written
+        let mut data = template.clone();
+        *data.get_struct_item_mut("local_translation").unwrap() =
+            ChumStructVariant::Vec3(Vector3::new(0.0, f32::NAN, 0.0));
+        assert!(crate::reader::write_structure("NODE", &data, TotemFormat::NGC).is_ok());
+
+        // Only transforms that are inverted when they are written must be invertible
+        let mut data = CollisionVol::default().structure();
+        *data.get_struct_item_mut("local_transform").unwrap() =
+            ChumStructVariant::Transform3D(Transform3D::scale(1.0, 0.0, 1.0));
+        assert_eq!(data.validate(), vec![]);
+        match CollisionVol::destructure(&data) {
+            Err(DestructureError::NotInvertible { path }) => assert_eq!(path, "local_transform"),
+            _ => panic!("Expected a transform that can not be inverted"),
+        }
+
+        // Not every bit of a flags value has a name
+        let data = ChumStructVariant::Integer(0xF0, IntType::Flags(vec!["A".into(), "B".into()]));
+        assert_eq!(data.validate(), vec![]);
+        let data = ChumStructVariant::Integer(2, IntType::Enum(vec!["A".into(), "B".into()]));
+        assert_eq!(data.validate().len(), 1);
+    }
+}
@@ -0,0 +1,221 @@
+//! A workspace is a group of archives that are used together,
+//! e.g. a level's archive along with the game's global archives.
+//! References in one archive may point to files that are defined in another.
+
+use crate::archive::{ChumArchive, ChumFile};
+use crate::names::NameDictionary;
+use std::collections::HashMap;
+
+/// A file found by resolving an ID in a workspace.
+#[derive(Clone, Copy)]
+pub struct ResolvedFile<'a> {
+    /// Index of the archive that defines the file
+    pub archive: usize,
+    /// Label of the archive that defines the file
+    pub label: &'a str,
+    pub file: &'a ChumFile,
+}
+
+/// An ID that is defined by more than one archive in a workspace.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Shadowing {
+    pub id: i32,
+    pub name: Option<String>,
+    /// Index of the archive whose file is used
+    pub used: usize,
+    /// Indices of the archives whose files are hidden
+    pub shadowed: Vec<usize>,
+}
+
+/// A group of archives whose files and names are looked up together.
+/// Archives that were added first take priority over archives added later,
+/// so a level's archive should be added before the global archives it uses.
+#[derive(Default)]
+pub struct Workspace {
+    archives: Vec<(String, ChumArchive)>,
+}
+
+impl Workspace {
+    /// Create a new, empty workspace
+    pub fn new() -> Workspace {
+        Workspace {
+            archives: Vec::new(),
+        }
+    }
+
+    /// Add an archive with the given label, returning its index
+    pub fn add_archive<S: Into<String>>(&mut self, label: S, archive: ChumArchive) -> usize {
+        self.archives.push((label.into(), archive));
+        self.archives.len() - 1
+    }
+
+    /// Remove the archive at the given index.
+    /// Archives after it will have their index decreased by one.
+    pub fn remove_archive(&mut self, index: usize) -> Option<(String, ChumArchive)> {
+        if index < self.archives.len() {
+            Some(self.archives.remove(index))
+        } else {
+            None
+        }
+    }
+
+    /// Get the number of archives in this workspace
+    pub fn len(&self) -> usize {
+        self.archives.len()
+    }
+
+    /// Returns true if this workspace has no archives
+    pub fn is_empty(&self) -> bool {
+        self.archives.is_empty()
+    }
+
+    /// Get the archive at the given index
+    pub fn get_archive(&self, index: usize) -> Option<&ChumArchive> {
+        self.archives.get(index).map(|(_, archive)| archive)
+    }
+
+    /// Get the archive at the given index mutably
+    pub fn get_archive_mut(&mut self, index: usize) -> Option<&mut ChumArchive> {
+        self.archives.get_mut(index).map(|(_, archive)| archive)
+    }
+
+    /// Get the label of the archive at the given index
+    pub fn get_label(&self, index: usize) -> Option<&str> {
+        self.archives.get(index).map(|(label, _)| label.as_str())
+    }
+
+    /// Get the index of the archive with the given label
+    pub fn find_archive(&self, label: &str) -> Option<usize> {
+        self.archives.iter().position(|(x, _)| x == label)
+    }
+
+    /// Get all archives in this workspace along with their labels, in priority order
+    pub fn get_archives(&self) -> impl Iterator<Item = (&str, &ChumArchive)> {
+        self.archives
+            .iter()
+            .map(|(label, archive)| (label.as_str(), archive))
+    }
+
+    /// Get a string from the given ID, searching every archive's name table
+    pub fn get_name_from_id(&self, id: i32) -> Option<&str> {
+        self.archives
+            .iter()
+            .find_map(|(_, archive)| archive.get_name_from_id(id))
+    }
+
+    /// Get a string from the given ID, looking in the given dictionary
+    /// if it is not in any archive's name table.
+    pub fn resolve_name<'a>(&'a self, id: i32, dictionary: &'a NameDictionary) -> Option<&'a str> {
+        self.get_name_from_id(id)
+            .or_else(|| dictionary.get_name(id))
+    }
+
+    /// Get the merged name table of every archive
+    pub fn get_name_map(&self) -> HashMap<i32, &str> {
+        let mut names = HashMap::new();
+        for (_, archive) in self.archives.iter().rev() {
+            for (id, name) in archive.get_name_map() {
+                names.insert(*id, name.as_str());
+            }
+        }
+        names
+    }
+
+    /// Find the file that the given ID refers to
+    pub fn resolve(&self, id: i32) -> Option<ResolvedFile<'_>> {
+        self.resolve_all(id).next()
+    }
+
+    /// Find the file that the given ID refers to, only if it has the given type
+    pub fn resolve_typed(&self, id: i32, type_id: &str) -> Option<ResolvedFile<'_>> {
+        self.resolve(id)
+            .filter(|resolved| resolved.file.get_type_id() == type_id)
+    }
+
+    /// Find every file with the given ID, in priority order.
+    /// Only the first file is used; the others are shadowed.
+    pub fn resolve_all(&self, id: i32) -> impl Iterator<Item = ResolvedFile<'_>> {
+        self.archives
+            .iter()
+            .enumerate()
+            .filter_map(move |(i, (label, archive))| {
+                archive.get_file_from_hash(id).map(|file| ResolvedFile {
+                    archive: i,
+                    label: label.as_str(),
+                    file,
+                })
+            })
+    }
+
+    /// Find every ID that is defined by more than one archive, sorted by ID
+    pub fn find_shadowed(&self) -> Vec<Shadowing> {
+        let mut defined: HashMap<i32, Vec<usize>> = HashMap::new();
+        for (i, (_, archive)) in self.archives.iter().enumerate() {
+            for (id, _) in archive.get_files_hash() {
+                defined.entry(*id).or_default().push(i);
+            }
+        }
+        let mut shadowed: Vec<Shadowing> = defined
+            .into_iter()
+            .filter(|(_, archives)| archives.len() > 1)
+            .map(|(id, archives)| Shadowing {
+                id,
+                name: self.get_name_from_id(id).map(|x| x.to_owned()),
+                used: archives[0],
+                shadowed: archives[1..].to_vec(),
+            })
+            .collect();
+        shadowed.sort_by_key(|x| x.id);
+        shadowed
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::archive::fixture::ArchiveBuilder;
+    use crate::format::TotemFormat;
+    use crate::util;
+    use crate::workspace::*;
+
+    #[test]
+    fn workspace_resolve() {
+        let mut workspace = Workspace::new();
+        workspace.add_archive(
+            "level",
+            ArchiveBuilder::new(TotemFormat::NGC)
+                .add("LEVEL.NODE", "NODE", vec![1; 4])
+                .add("SHARED.MAT", "MATERIAL", vec![1; 4])
+                .build(),
+        );
+        workspace.add_archive(
+            "global",
+            ArchiveBuilder::new(TotemFormat::NGC)
+                .add("GLOBAL.MAT", "MATERIAL", vec![2; 4])
+                .add("SHARED.MAT", "MATERIAL", vec![2; 4])
+                .build(),
+        );
+
+        let global = util::hash_name_i32("GLOBAL.MAT");
+        let resolved = workspace.resolve(global).unwrap();
+        assert_eq!(resolved.label, "global");
+        assert_eq!(resolved.file.get_name_id(), "GLOBAL.MAT");
+        assert!(workspace.resolve_typed(global, "MATERIAL").is_some());
+        assert!(workspace.resolve_typed(global, "NODE").is_none());
+        assert_eq!(workspace.get_name_from_id(global), Some("GLOBAL.MAT"));
+        assert!(workspace.resolve(util::hash_name_i32("MISSING")).is_none());
+
+        let shared = util::hash_name_i32("SHARED.MAT");
+        assert_eq!(workspace.resolve(shared).unwrap().archive, 0);
+        assert_eq!(workspace.resolve(shared).unwrap().file.get_data(), &[1; 4]);
+        assert_eq!(
+            workspace.find_shadowed(),
+            vec![Shadowing {
+                id: shared,
+                name: Some("SHARED.MAT".to_string()),
+                used: 0,
+                shadowed: vec![1],
+            }]
+        );
+        assert_eq!(workspace.get_name_map().len(), 5);
+    }
+}
@@ -0,0 +1,391 @@
+use quote::ToTokens;
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::{Attribute, Expr, Ident, Lit, LitStr, Token, Type, WherePredicate};
+
+/// A single argument of a `#[chum(...)]` attribute:
+/// `key`, `key = value` or `key(a, b, c)`
+struct Arg {
+    key: Ident,
+    value: ArgValue,
+}
+
+enum ArgValue {
+    None,
+    Expr(Box<Expr>),
+    List(Vec<Ident>),
+}
+
+impl Parse for Arg {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let key: Ident = input.parse()?;
+        let value = if input.peek(Token![=]) {
+            input.parse::<Token![=]>()?;
+            ArgValue::Expr(input.parse()?)
+        } else if input.peek(syn::token::Paren) {
+            let content;
+            syn::parenthesized!(content in input);
+            let names = Punctuated::<Ident, Token![,]>::parse_terminated(&content)?;
+            ArgValue::List(names.into_iter().collect())
+        } else {
+            ArgValue::None
+        };
+        Ok(Arg { key, value })
+    }
+}
+
+impl Arg {
+    fn name(&self) -> String {
+        self.key.to_string()
+    }
+
+    fn expr(self) -> syn::Result<Expr> {
+        match self.value {
+            ArgValue::Expr(expr) => Ok(*expr),
+            _ => Err(syn::Error::new(
+                self.key.span(),
+                format!("expected `{} = ...`", self.key),
+            )),
+        }
+    }
+
+    fn flag(self) -> syn::Result<()> {
+        match self.value {
+            ArgValue::None => Ok(()),
+            _ => Err(syn::Error::new(
+                self.key.span(),
+                format!("`{}` does not take a value", self.key),
+            )),
+        }
+    }
+
+    fn list(self) -> syn::Result<Vec<Ident>> {
+        match self.value {
+            ArgValue::List(names) => Ok(names),
+            _ => Err(syn::Error::new(
+                self.key.span(),
+                format!("expected `{}(a, b, ...)`", self.key),
+            )),
+        }
+    }
+
+    fn string(self) -> syn::Result<LitStr> {
+        let name = self.name();
+        match self.expr()? {
+            Expr::Lit(syn::ExprLit {
+                lit: Lit::Str(s), ..
+            }) => Ok(s),
+            other => Err(syn::Error::new(
+                other.span(),
+                format!("expected a string, e.g. `{} = \"...\"`", name),
+            )),
+        }
+    }
+
+    fn ty(self) -> syn::Result<Type> {
+        let expr = self.expr()?;
+        syn::parse2(expr.to_token_stream())
+            .map_err(|_| syn::Error::new(expr.span(), "expected a type, e.g. `u32`"))
+    }
+}
+
+/// Get every argument of every `#[chum(...)]` attribute
+fn parse_args(attrs: &[Attribute]) -> syn::Result<Vec<Arg>> {
+    let mut args = Vec::new();
+    for attr in attrs.iter().filter(|a| a.path.is_ident("chum")) {
+        let list = attr.parse_args_with(Punctuated::<Arg, Token![,]>::parse_terminated)?;
+        args.extend(list);
+    }
+    Ok(args)
+}
+
+fn set<T>(slot: &mut Option<T>, key: &Ident, value: T) -> syn::Result<()> {
+    if slot.is_some() {
+        return Err(syn::Error::new(
+            key.span(),
+            format!("duplicate attribute `{}`", key),
+        ));
+    }
+    *slot = Some(value);
+    Ok(())
+}
+
+fn unknown(key: &Ident, expected: &str) -> syn::Error {
+    syn::Error::new(
+        key.span(),
+        format!("unknown attribute `{}`; expected one of {}", key, expected),
+    )
+}
+
+/// Attributes of a struct or enum
+#[derive(Default)]
+pub struct ContainerAttrs {
+    /// Replaces the default `T: Trait` bounds on type parameters
+    pub bound: Option<Vec<WherePredicate>>,
+    /// Type of the tag that comes before an enum's variant
+    pub tag: Option<Type>,
+    /// Integer type that a C-like enum is stored as
+    pub repr: Option<Type>,
+}
+
+impl ContainerAttrs {
+    pub fn parse(attrs: &[Attribute]) -> syn::Result<ContainerAttrs> {
+        let mut result = ContainerAttrs::default();
+        for arg in parse_args(attrs)? {
+            let key = arg.key.clone();
+            match arg.name().as_str() {
+                "bound" => {
+                    let s = arg.string()?;
+                    let predicates =
+                        s.parse_with(Punctuated::<WherePredicate, Token![,]>::parse_terminated)?;
+                    set(&mut result.bound, &key, predicates.into_iter().collect())?;
+                }
+                "tag" => set(&mut result.tag, &key, arg.ty()?)?,
+                "repr" => set(&mut result.repr, &key, arg.ty()?)?,
+                _ => return Err(unknown(&key, "`bound`, `tag`, `repr`")),
+            }
+        }
+        Ok(result)
+    }
+}
+
+/// Attributes of an enum variant
+#[derive(Default)]
+pub struct VariantAttrs {
+    /// Value of the enum's tag for this variant
+    pub tag: Option<Expr>,
+}
+
+impl VariantAttrs {
+    pub fn parse(attrs: &[Attribute]) -> syn::Result<VariantAttrs> {
+        let mut result = VariantAttrs::default();
+        for arg in parse_args(attrs)? {
+            let key = arg.key.clone();
+            match arg.name().as_str() {
+                "tag" => set(&mut result.tag, &key, arg.expr()?)?,
+                _ => return Err(unknown(&key, "`tag`")),
+            }
+        }
+        Ok(result)
+    }
+}
+
+/// How an integer field is presented in a structure
+pub enum IntKind {
+    Flags(Vec<Ident>),
+    Range(Box<Expr>, Box<Expr>),
+}
+
+/// Attributes of a field
+#[derive(Default)]
+pub struct FieldAttrs {
+    /// `Some(None)` for `reference`, `Some(Some(type))` for `reference = "TYPE"`
+    pub reference: Option<Option<LitStr>>,
+    pub len: Option<Type>,
+    pub ignore: Option<Expr>,
+    pub int: Option<IntKind>,
+    pub rgb: bool,
+    pub default: Option<Expr>,
+    pub read: Option<Expr>,
+    pub write: Option<Expr>,
+    pub structure: Option<Expr>,
+    pub destructure: Option<Expr>,
+}
+
+const FIELD_ATTRS: &str = "`reference`, `len`, `ignore`, `flags`, `min`, `max`, `rgb`, \
+                           `default`, `read`, `write`, `structure`, `destructure`";
+
+impl FieldAttrs {
+    pub fn parse(attrs: &[Attribute], span: proc_macro2::Span) -> syn::Result<FieldAttrs> {
+        let mut result = FieldAttrs::default();
+        let mut flags = None;
+        let mut min = None;
+        let mut max = None;
+        let mut rgb = None;
+        for arg in parse_args(attrs)? {
+            let key = arg.key.clone();
+            match arg.name().as_str() {
+                "reference" => {
+                    let value = match arg.value {
+                        ArgValue::None => None,
+                        _ => Some(arg.string()?),
+                    };
+                    set(&mut result.reference, &key, value)?;
+                }
+                "len" => set(&mut result.len, &key, arg.ty()?)?,
+                "ignore" => set(&mut result.ignore, &key, arg.expr()?)?,
+                "flags" => set(&mut flags, &key, arg.list()?)?,
+                "min" => set(&mut min, &key, arg.expr()?)?,
+                "max" => set(&mut max, &key, arg.expr()?)?,
+                "rgb" => {
+                    arg.flag()?;
+                    set(&mut rgb, &key, ())?;
+                }
+                "default" => set(&mut result.default, &key, arg.expr()?)?,
+                "read" => set(&mut result.read, &key, arg.expr()?)?,
+                "write" => set(&mut result.write, &key, arg.expr()?)?,
+                "structure" => set(&mut result.structure, &key, arg.expr()?)?,
+                "destructure" => set(&mut result.destructure, &key, arg.expr()?)?,
+                _ => return Err(unknown(&key, FIELD_ATTRS)),
+            }
+        }
+        result.rgb = rgb.is_some();
+        result.int = match (flags, min, max) {
+            (None, None, None) => None,
+            (Some(names), None, None) => Some(IntKind::Flags(names)),
+            (None, Some(min), Some(max)) => Some(IntKind::Range(Box::new(min), Box::new(max))),
+            (None, Some(min), None) => {
+                return Err(syn::Error::new(min.span(), "`min` needs a `max`"))
+            }
+            (None, None, Some(max)) => {
+                return Err(syn::Error::new(max.span(), "`max` needs a `min`"))
+            }
+            (Some(_), _, _) => {
+                return Err(syn::Error::new(
+                    span,
+                    "`flags` can not be combined with `min` and `max`",
+                ))
+            }
+        };
+        let kinds = [result.reference.is_some(), result.int.is_some(), result.rgb];
+        if kinds.iter().filter(|x| **x).count() > 1 {
+            return Err(syn::Error::new(
+                span,
+                "only one of `reference`, `flags`, `min`/`max` and `rgb` can be used",
+            ));
+        }
+        if let (Some(read), None) = (&result.read, &result.write) {
+            return Err(syn::Error::new(read.span(), "`read` needs a `write`"));
+        }
+        if let (None, Some(write)) = (&result.read, &result.write) {
+            return Err(syn::Error::new(write.span(), "`write` needs a `read`"));
+        }
+        if let (Some(structure), None) = (&result.structure, &result.destructure) {
+            return Err(syn::Error::new(
+                structure.span(),
+                "`structure` needs a `destructure`",
+            ));
+        }
+        if let (None, Some(destructure)) = (&result.structure, &result.destructure) {
+            return Err(syn::Error::new(
+                destructure.span(),
+                "`destructure` needs a `structure`",
+            ));
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::attr::*;
+    use proc_macro2::Span;
+
+    fn parse_field(attrs: &[Attribute]) -> syn::Result<FieldAttrs> {
+        FieldAttrs::parse(attrs, Span::call_site())
+    }
+
+    fn field_error(attrs: &[Attribute]) -> String {
+        match parse_field(attrs) {
+            Ok(_) => panic!("expected an error"),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    #[test]
+    fn field_attrs() {
+        let attrs = parse_field(&[
+            syn::parse_quote!(#[chum(reference = "BITMAP")]),
+            syn::parse_quote!(#[chum(len = u32, default = 5)]),
+        ])
+        .unwrap();
+        assert_eq!(attrs.reference.unwrap().unwrap().value(), "BITMAP");
+        assert!(attrs.len.is_some());
+        assert!(attrs.default.is_some());
+
+        let attrs = parse_field(&[syn::parse_quote!(#[chum(reference)])]).unwrap();
+        assert!(matches!(attrs.reference, Some(None)));
+
+        let attrs = parse_field(&[syn::parse_quote!(#[chum(flags(a, b, c))])]).unwrap();
+        match attrs.int {
+            Some(IntKind::Flags(names)) => assert_eq!(names.len(), 3),
+            _ => panic!("expected flags"),
+        }
+
+        let attrs = parse_field(&[syn::parse_quote!(#[chum(min = 1, max = 5)])]).unwrap();
+        assert!(matches!(attrs.int, Some(IntKind::Range(_, _))));
+
+        let attrs = parse_field(&[
+            syn::parse_quote!(#[doc = "not a chum attribute"]),
+            syn::parse_quote!(#[chum(rgb)]),
+        ])
+        .unwrap();
+        assert!(attrs.rgb);
+    }
+
+    #[test]
+    fn field_attr_errors() {
+        assert_eq!(
+            field_error(&[syn::parse_quote!(#[chum(len = u32, len = u16)])]),
+            "duplicate attribute `len`"
+        );
+        assert!(field_error(&[syn::parse_quote!(#[chum(size = 4)])])
+            .starts_with("unknown attribute `size`"));
+        assert_eq!(
+            field_error(&[syn::parse_quote!(#[chum(rgb = true)])]),
+            "`rgb` does not take a value"
+        );
+        assert_eq!(
+            field_error(&[syn::parse_quote!(#[chum(flags = 4)])]),
+            "expected `flags(a, b, ...)`"
+        );
+        assert_eq!(
+            field_error(&[syn::parse_quote!(#[chum(reference = BITMAP)])]),
+            "expected a string, e.g. `reference = \"...\"`"
+        );
+        assert_eq!(
+            field_error(&[syn::parse_quote!(#[chum(len = 4)])]),
+            "expected a type, e.g. `u32`"
+        );
+        assert_eq!(
+            field_error(&[syn::parse_quote!(#[chum(min = 1)])]),
+            "`min` needs a `max`"
+        );
+        assert_eq!(
+            field_error(&[syn::parse_quote!(#[chum(flags(a), max = 1)])]),
+            "`flags` can not be combined with `min` and `max`"
+        );
+        assert_eq!(
+            field_error(&[syn::parse_quote!(#[chum(reference, rgb)])]),
+            "only one of `reference`, `flags`, `min`/`max` and `rgb` can be used"
+        );
+        assert_eq!(
+            field_error(&[syn::parse_quote!(#[chum(read = x)])]),
+            "`read` needs a `write`"
+        );
+        assert_eq!(
+            field_error(&[syn::parse_quote!(#[chum(destructure = x)])]),
+            "`destructure` needs a `structure`"
+        );
+    }
+
+    #[test]
+    fn container_attrs() {
+        let attrs = ContainerAttrs::parse(&[syn::parse_quote!(
+            #[chum(tag = i32, bound = "T: Clone, U: Default")]
+        )])
+        .unwrap();
+        assert!(attrs.tag.is_some());
+        assert_eq!(attrs.bound.unwrap().len(), 2);
+        assert!(attrs.repr.is_none());
+
+        let error = ContainerAttrs::parse(&[syn::parse_quote!(#[chum(len = u32)])])
+            .err()
+            .unwrap();
+        assert_eq!(
+            error.to_string(),
+            "unknown attribute `len`; expected one of `bound`, `tag`, `repr`"
+        );
+    }
+}
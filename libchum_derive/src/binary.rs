@@ -0,0 +1,290 @@
+use crate::data::{shape, Body, Container, Field, Shape, Variant};
+use proc_macro2::TokenStream;
+use quote::{quote, quote_spanned};
+use syn::spanned::Spanned;
+
+/// Check that a `Vec` is only used where its length type is known
+fn check_vecs(field: &Field) -> syn::Result<()> {
+    if field.attrs.read.is_some() {
+        return Ok(());
+    }
+    fn check_nested(ty: &syn::Type) -> syn::Result<()> {
+        match shape(ty) {
+            Shape::Vec(_) => Err(syn::Error::new(
+                ty.span(),
+                "`Vec` is only supported as the outermost type of a field",
+            )),
+            Shape::Array(inner) | Shape::Option(inner) => check_nested(inner),
+            Shape::Unit | Shape::Leaf => Ok(()),
+        }
+    }
+    match shape(field.ty) {
+        Shape::Vec(_) if field.attrs.len.is_none() => Err(syn::Error::new(
+            field.ty.span(),
+            "`Vec` fields need `#[chum(len = u32)]`, the type of the length that comes before the elements",
+        )),
+        Shape::Vec(inner) => check_nested(inner),
+        _ => check_nested(field.ty),
+    }
+}
+
+/// Statement that reads the given field into a local with the same name.
+/// `path` is the name of the struct or variant, for errors.
+fn read_field(field: &Field, path: &str) -> syn::Result<TokenStream> {
+    check_vecs(field)?;
+    let name = field.ident;
+    let ty = field.ty;
+    let field_name = field.name();
+    let value = if let Some(ref ignore) = field.attrs.ignore {
+        quote_spanned!(ignore.span()=> ::libchum::binary::read_ignored(&(#ignore), file, fmt))
+    } else if let Some(ref read) = field.attrs.read {
+        quote_spanned!(read.span()=> (#read))
+    } else if let Some(ref len) = field.attrs.len {
+        quote_spanned!(ty.span()=> ::libchum::binary::read_vec::<#len, _>(file, fmt))
+    } else {
+        quote_spanned!(ty.span()=> <#ty as ::libchum::binary::ChumBinary>::read_from(file, fmt))
+    };
+    Ok(quote! {
+        let #name: #ty = #value.map_err(|e| e.in_field(#path, #field_name))?;
+    })
+}
+
+/// Statement that writes the given field from a local reference with the same name
+fn write_field(field: &Field) -> TokenStream {
+    let name = field.ident;
+    let ty = field.ty;
+    if let Some(ref ignore) = field.attrs.ignore {
+        quote_spanned!(ignore.span()=> ::libchum::binary::ChumBinary::write_to(&(#ignore), file, fmt)?;)
+    } else if let Some(ref write) = field.attrs.write {
+        quote_spanned!(write.span()=> (#write)?;)
+    } else if let Some(ref len) = field.attrs.len {
+        quote_spanned!(ty.span()=> ::libchum::binary::write_vec::<#len, _>(#name, file, fmt)?;)
+    } else {
+        quote_spanned!(ty.span()=> <#ty as ::libchum::binary::ChumBinary>::write_to(#name, file, fmt)?;)
+    }
+}
+
+fn read_fields(fields: &[Field], path: &str) -> syn::Result<Vec<TokenStream>> {
+    fields.iter().map(|x| read_field(x, path)).collect()
+}
+
+fn struct_body(container: &Container, fields: &[Field]) -> syn::Result<(TokenStream, TokenStream)> {
+    let reads = read_fields(fields, &container.name())?;
+    let writes = fields.iter().map(write_field);
+    let names = fields.iter().map(|x| x.ident);
+    let patterns = fields.iter().map(|field| {
+        let name = field.ident;
+        if field.is_ignored() {
+            quote!(#name: _)
+        } else {
+            quote!(#name)
+        }
+    });
+    let read = quote! {
+        #(#reads)*
+        ::std::result::Result::Ok(Self { #(#names),* })
+    };
+    let write = quote! {
+        #[allow(unused_variables)]
+        let Self { #(#patterns),* } = self;
+        #(#writes)*
+        ::std::result::Result::Ok(())
+    };
+    Ok((read, write))
+}
+
+fn enum_body(
+    container: &Container,
+    variants: &[Variant],
+) -> syn::Result<(TokenStream, TokenStream)> {
+    container.check_enum_tags(variants)?;
+    let tag_ty = container.attrs.tag.as_ref().unwrap();
+    let enum_name = container.name();
+    let mut read_arms = Vec::new();
+    let mut write_arms = Vec::new();
+    for variant in variants.iter() {
+        let path = format!("{}::{}", enum_name, variant.ident);
+        let tag = variant.attrs.tag.as_ref().unwrap();
+        let reads = read_fields(&variant.fields, &path)?;
+        let construct = variant.construct();
+        read_arms.push(quote! {
+            if tag == (#tag) {
+                #(#reads)*
+                ::std::result::Result::Ok(#construct)
+            }
+        });
+        let pattern = variant.pattern();
+        let writes = variant.fields.iter().map(write_field);
+        write_arms.push(quote! {
+            #[allow(unused_variables)]
+            #pattern => {
+                let tag: #tag_ty = #tag;
+                ::libchum::binary::ChumBinary::write_to(&tag, file, fmt)?;
+                #(#writes)*
+            }
+        });
+    }
+    let read = quote! {
+        let tag = <#tag_ty as ::libchum::binary::ChumBinary>::read_from(file, fmt)
+            .map_err(|e| e.in_field(#enum_name, ""))?;
+        #(#read_arms else)* {
+            ::std::result::Result::Err(::libchum::error::StructUnpackError {
+                structname: #enum_name.to_owned(),
+                structpath: ::std::string::String::new(),
+                error: ::libchum::error::UnpackError::InvalidEnumeration {
+                    enum_name: #enum_name.to_owned(),
+                    value: tag as i64,
+                },
+            })
+        }
+    };
+    let write = quote! {
+        match self {
+            #(#write_arms)*
+        }
+        ::std::result::Result::Ok(())
+    };
+    Ok((read, write))
+}
+
+fn c_like_body(container: &Container) -> syn::Result<(TokenStream, TokenStream)> {
+    let repr = match container.attrs.repr {
+        Some(ref repr) => repr,
+        None => {
+            return Err(syn::Error::new(
+                container.ident.span(),
+                "enums without fields need `#[chum(repr = u8)]`, the integer type that they are stored as",
+            ))
+        }
+    };
+    let enum_name = container.name();
+    let read = quote! {
+        let value = <#repr as ::libchum::binary::ChumBinary>::read_from(file, fmt)
+            .map_err(|e| e.in_field(#enum_name, ""))?;
+        <Self as ::libchum::structure::ChumEnum>::from_u32(value as u32).ok_or_else(|| {
+            ::libchum::error::StructUnpackError {
+                structname: #enum_name.to_owned(),
+                structpath: ::std::string::String::new(),
+                error: ::libchum::error::UnpackError::InvalidEnumeration {
+                    enum_name: #enum_name.to_owned(),
+                    value: value as i64,
+                },
+            }
+        })
+    };
+    let write = quote! {
+        let value = ::libchum::structure::ChumEnum::to_u32(self) as #repr;
+        ::libchum::binary::ChumBinary::write_to(&value, file, fmt)
+    };
+    Ok((read, write))
+}
+
+pub fn derive(container: &Container) -> syn::Result<TokenStream> {
+    let (read, write) = if container.is_c_like() {
+        c_like_body(container)?
+    } else {
+        match container.body {
+            Body::Struct(ref fields) => struct_body(container, fields)?,
+            Body::Enum(ref variants) => enum_body(container, variants)?,
+        }
+    };
+    let ident = container.ident;
+    let generics = container.impl_generics(&quote!(::libchum::binary::ChumBinary));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::libchum::binary::ChumBinary for #ident #ty_generics #where_clause {
+            fn read_from(
+                file: &mut dyn ::std::io::Read,
+                fmt: ::libchum::format::TotemFormat,
+            ) -> ::libchum::error::StructUnpackResult<Self> {
+                #read
+            }
+
+            fn write_to(
+                &self,
+                file: &mut dyn ::std::io::Write,
+                fmt: ::libchum::format::TotemFormat,
+            ) -> ::std::io::Result<()> {
+                #write
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::binary::*;
+    use syn::DeriveInput;
+
+    fn derive_error(input: DeriveInput) -> String {
+        let container = Container::from_ast(&input).unwrap();
+        match derive(&container) {
+            Ok(_) => panic!("expected an error"),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    #[test]
+    fn derive_errors() {
+        assert!(derive_error(syn::parse_quote!(
+            struct A {
+                values: Vec<u8>,
+            }
+        ))
+        .starts_with("`Vec` fields need `#[chum(len = u32)]`"));
+        assert_eq!(
+            derive_error(syn::parse_quote!(
+                struct A {
+                    values: [Vec<u8>; 2],
+                }
+            )),
+            "`Vec` is only supported as the outermost type of a field"
+        );
+        assert!(derive_error(syn::parse_quote!(
+            enum A {
+                B { value: u8 },
+            }
+        ))
+        .starts_with("enums with fields need `#[chum(tag = i32)]`"));
+        assert!(derive_error(syn::parse_quote!(
+            #[chum(tag = i32)]
+            enum A {
+                B { value: u8 },
+            }
+        ))
+        .starts_with("every variant needs `#[chum(tag = value)]`"));
+        assert!(derive_error(syn::parse_quote!(
+            enum A {
+                B,
+                C,
+            }
+        ))
+        .starts_with("enums without fields need `#[chum(repr = u8)]`"));
+    }
+
+    #[test]
+    fn derive_bounds() {
+        let input: DeriveInput = syn::parse_quote! {
+            struct A<T> {
+                #[chum(len = u32)]
+                values: Vec<T>,
+                #[chum(read = Ok(0), write = Ok(()))]
+                custom: u8,
+            }
+        };
+        let container = Container::from_ast(&input).unwrap();
+        let output = derive(&container).unwrap().to_string();
+        assert!(output.contains("where T : :: libchum :: binary :: ChumBinary"));
+        assert!(output.contains("read_vec :: < u32 , _ >"));
+
+        let input: DeriveInput = syn::parse_quote! {
+            #[chum(bound = "T: Clone")]
+            struct A<T> {
+                value: Wrapper<T>,
+            }
+        };
+        let container = Container::from_ast(&input).unwrap();
+        let output = derive(&container).unwrap().to_string();
+        assert!(output.contains("where T : Clone"));
+    }
+}
@@ -0,0 +1,440 @@
+use crate::attr::{ContainerAttrs, FieldAttrs, VariantAttrs};
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::spanned::Spanned;
+use syn::{Data, DeriveInput, Fields, Generics, Ident, Type};
+
+/// The shape of a field's type
+pub enum Shape<'a> {
+    /// `()`, only used for ignored fields
+    Unit,
+    /// `[T; N]`
+    Array(&'a Type),
+    /// `Vec<T>`
+    Vec(&'a Type),
+    /// `Option<T>`
+    Option(&'a Type),
+    /// Any other type, which implements the traits itself
+    Leaf,
+}
+
+/// Get the type argument of `Name<T>`
+fn generic_argument<'a>(ty: &'a Type, name: &str) -> Option<&'a Type> {
+    let path = match ty {
+        Type::Path(ref path) if path.qself.is_none() => &path.path,
+        _ => return None,
+    };
+    let segment = path.segments.last()?;
+    if segment.ident != name {
+        return None;
+    }
+    match segment.arguments {
+        syn::PathArguments::AngleBracketed(ref args) if args.args.len() == 1 => {
+            match args.args.first() {
+                Some(syn::GenericArgument::Type(ref inner)) => Some(inner),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+pub fn shape(ty: &Type) -> Shape<'_> {
+    match ty {
+        Type::Paren(ref inner) => shape(&inner.elem),
+        Type::Group(ref inner) => shape(&inner.elem),
+        Type::Tuple(ref tuple) if tuple.elems.is_empty() => Shape::Unit,
+        Type::Array(ref array) => Shape::Array(&array.elem),
+        _ => {
+            if let Some(inner) = generic_argument(ty, "Vec") {
+                Shape::Vec(inner)
+            } else if let Some(inner) = generic_argument(ty, "Option") {
+                Shape::Option(inner)
+            } else {
+                Shape::Leaf
+            }
+        }
+    }
+}
+
+/// Get the type inside of any arrays, `Vec`s and `Option`s
+pub fn leaf(ty: &Type) -> &Type {
+    match shape(ty) {
+        Shape::Array(inner) | Shape::Vec(inner) | Shape::Option(inner) => leaf(inner),
+        Shape::Unit | Shape::Leaf => ty,
+    }
+}
+
+fn is_named(ty: &Type, names: &[&str]) -> bool {
+    match ty {
+        Type::Path(ref path) if path.qself.is_none() => path
+            .path
+            .segments
+            .last()
+            .map(|x| x.arguments.is_empty() && names.iter().any(|name| x.ident == name))
+            .unwrap_or(false),
+        _ => false,
+    }
+}
+
+pub const INTEGER_TYPES: &[&str] = &["u8", "i8", "u16", "i16", "u32", "i32"];
+
+pub struct Field<'a> {
+    pub ident: &'a Ident,
+    pub ty: &'a Type,
+    pub attrs: FieldAttrs,
+}
+
+impl<'a> Field<'a> {
+    fn from_ast(field: &'a syn::Field) -> syn::Result<Field<'a>> {
+        let ident = field.ident.as_ref().unwrap();
+        let ty = &field.ty;
+        let attrs = FieldAttrs::parse(&field.attrs, field.span())?;
+        if ident == "file" || ident == "fmt" {
+            return Err(syn::Error::new(
+                ident.span(),
+                format!(
+                    "`{}` is used by the generated code; rename this field",
+                    ident
+                ),
+            ));
+        }
+        let error = |message: &str| Err(syn::Error::new(ty.span(), message));
+        let is_unit = matches!(shape(ty), Shape::Unit);
+        if attrs.ignore.is_some() {
+            if !is_unit {
+                return error("fields with `ignore` must have the type `()`");
+            }
+            if attrs.reference.is_some()
+                || attrs.len.is_some()
+                || attrs.int.is_some()
+                || attrs.rgb
+                || attrs.default.is_some()
+                || attrs.read.is_some()
+                || attrs.structure.is_some()
+            {
+                return Err(syn::Error::new(
+                    field.span(),
+                    "`ignore` can not be combined with other attributes",
+                ));
+            }
+        } else if is_unit {
+            return error("fields with the type `()` need `#[chum(ignore = value)]`, where the value is written in place of the field");
+        }
+        if let Some(ref len) = attrs.len {
+            if !matches!(shape(ty), Shape::Vec(_)) {
+                return Err(syn::Error::new(
+                    len.span(),
+                    "`len` can only be used on `Vec` fields",
+                ));
+            }
+        }
+        let leaf = leaf(ty);
+        if attrs.reference.is_some() && !is_named(leaf, &["i32"]) {
+            return error("`reference` can only be used on `i32` values");
+        }
+        if attrs.rgb && !is_named(leaf, &["Vector3"]) {
+            return error("`rgb` can only be used on `Vector3` values");
+        }
+        if attrs.int.is_some() && !is_named(leaf, INTEGER_TYPES) {
+            return error("`flags`, `min` and `max` can only be used on integer values");
+        }
+        Ok(Field { ident, ty, attrs })
+    }
+
+    pub fn name(&self) -> String {
+        self.ident.to_string()
+    }
+
+    pub fn is_ignored(&self) -> bool {
+        self.attrs.ignore.is_some()
+    }
+}
+
+pub struct Variant<'a> {
+    pub ident: &'a Ident,
+    pub fields: Vec<Field<'a>>,
+    /// True if the variant is written without braces, e.g. `Empty` instead of `Empty {}`
+    pub is_unit: bool,
+    pub attrs: VariantAttrs,
+}
+
+impl<'a> Variant<'a> {
+    /// Pattern that matches this variant and binds every field that is not ignored
+    pub fn pattern(&self) -> TokenStream {
+        let ident = self.ident;
+        if self.is_unit {
+            return quote!(Self::#ident);
+        }
+        let fields = self.fields.iter().map(|field| {
+            let name = field.ident;
+            if field.is_ignored() {
+                quote!(#name: _)
+            } else {
+                quote!(#name)
+            }
+        });
+        quote!(Self::#ident { #(#fields),* })
+    }
+
+    /// Expression that builds this variant from locals with the same names as its fields
+    pub fn construct(&self) -> TokenStream {
+        let ident = self.ident;
+        if self.is_unit {
+            return quote!(Self::#ident);
+        }
+        let fields = self.fields.iter().map(|field| field.ident);
+        quote!(Self::#ident { #(#fields),* })
+    }
+}
+
+pub enum Body<'a> {
+    Struct(Vec<Field<'a>>),
+    Enum(Vec<Variant<'a>>),
+}
+
+pub struct Container<'a> {
+    pub ident: &'a Ident,
+    pub generics: &'a Generics,
+    pub attrs: ContainerAttrs,
+    pub body: Body<'a>,
+}
+
+fn named_fields(fields: &Fields, span: proc_macro2::Span) -> syn::Result<Vec<Field<'_>>> {
+    match fields {
+        Fields::Named(ref named) => named.named.iter().map(Field::from_ast).collect(),
+        Fields::Unit => Ok(Vec::new()),
+        Fields::Unnamed(_) => Err(syn::Error::new(
+            span,
+            "tuple structs and tuple variants are not supported; use named fields",
+        )),
+    }
+}
+
+impl<'a> Container<'a> {
+    pub fn from_ast(input: &'a DeriveInput) -> syn::Result<Container<'a>> {
+        let attrs = ContainerAttrs::parse(&input.attrs)?;
+        let body = match input.data {
+            Data::Struct(ref data) => Body::Struct(named_fields(&data.fields, input.ident.span())?),
+            Data::Enum(ref data) => {
+                let mut variants = Vec::new();
+                for variant in data.variants.iter() {
+                    if let Some((_, ref discriminant)) = variant.discriminant {
+                        return Err(syn::Error::new(
+                            discriminant.span(),
+                            "explicit discriminants are not supported; use `#[chum(tag = ...)]`",
+                        ));
+                    }
+                    variants.push(Variant {
+                        ident: &variant.ident,
+                        fields: named_fields(&variant.fields, variant.ident.span())?,
+                        is_unit: matches!(variant.fields, Fields::Unit),
+                        attrs: VariantAttrs::parse(&variant.attrs)?,
+                    });
+                }
+                Body::Enum(variants)
+            }
+            Data::Union(_) => {
+                return Err(syn::Error::new(
+                    input.ident.span(),
+                    "unions are not supported",
+                ))
+            }
+        };
+        Ok(Container {
+            ident: &input.ident,
+            generics: &input.generics,
+            attrs,
+            body,
+        })
+    }
+
+    pub fn name(&self) -> String {
+        self.ident.to_string()
+    }
+
+    /// Returns true if this is an enum without a tag whose variants have no fields.
+    /// Such enums are stored as integers, counting up from 0.
+    pub fn is_c_like(&self) -> bool {
+        match self.body {
+            Body::Enum(ref variants) => {
+                self.attrs.tag.is_none() && variants.iter().all(|x| x.is_unit)
+            }
+            Body::Struct(_) => false,
+        }
+    }
+
+    /// Get the generics for an implementation of the given trait.
+    /// Every type parameter must implement the trait, unless `bound` is given.
+    pub fn impl_generics(&self, trait_path: &TokenStream) -> Generics {
+        let mut generics = self.generics.clone();
+        let predicates = match self.attrs.bound {
+            Some(ref bound) => bound.clone(),
+            None => self
+                .generics
+                .type_params()
+                .map(|param| {
+                    let ident = &param.ident;
+                    syn::parse_quote!(#ident: #trait_path)
+                })
+                .collect(),
+        };
+        generics.make_where_clause().predicates.extend(predicates);
+        generics
+    }
+
+    /// Check that this enum can be read from and written to binary data
+    pub fn check_enum_tags(&self, variants: &[Variant]) -> syn::Result<()> {
+        if self.attrs.tag.is_none() {
+            return Err(syn::Error::new(
+                self.ident.span(),
+                "enums with fields need `#[chum(tag = i32)]`, the type of the tag that comes before the variant",
+            ));
+        }
+        for variant in variants.iter() {
+            if variant.attrs.tag.is_none() {
+                return Err(syn::Error::new(
+                    variant.ident.span(),
+                    "every variant needs `#[chum(tag = value)]`, the value of the tag for this variant",
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::data::*;
+
+    fn container_error(input: DeriveInput) -> String {
+        match Container::from_ast(&input) {
+            Ok(_) => panic!("expected an error"),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    #[test]
+    fn shapes() {
+        let ty: Type = syn::parse_quote!(Vec<[Option<i32>; 4]>);
+        assert!(matches!(shape(&ty), Shape::Vec(_)));
+        assert!(is_named(leaf(&ty), &["i32"]));
+        let ty: Type = syn::parse_quote!(());
+        assert!(matches!(shape(&ty), Shape::Unit));
+        let ty: Type = syn::parse_quote!(std::vec::Vec<u8>);
+        assert!(matches!(shape(&ty), Shape::Vec(_)));
+        let ty: Type = syn::parse_quote!(HashMap<u8, u8>);
+        assert!(matches!(shape(&ty), Shape::Leaf));
+    }
+
+    #[test]
+    fn containers() {
+        let input: DeriveInput = syn::parse_quote! {
+            #[chum(tag = i32)]
+            enum Union {
+                #[chum(tag = 0)]
+                Empty,
+                #[chum(tag = 1)]
+                Value {
+                    #[chum(ignore = 0u16)]
+                    junk: (),
+                    value: f32,
+                },
+            }
+        };
+        let container = Container::from_ast(&input).unwrap();
+        assert_eq!(container.name(), "Union");
+        assert!(!container.is_c_like());
+        match container.body {
+            Body::Enum(ref variants) => {
+                assert!(variants[0].is_unit);
+                assert_eq!(variants[1].fields.len(), 2);
+                assert!(variants[1].fields[0].is_ignored());
+                assert_eq!(
+                    variants[1].pattern().to_string(),
+                    "Self :: Value { junk : _ , value }"
+                );
+            }
+            Body::Struct(_) => panic!("expected an enum"),
+        }
+
+        let input: DeriveInput = syn::parse_quote! {
+            enum Kind { A, B, C }
+        };
+        assert!(Container::from_ast(&input).unwrap().is_c_like());
+    }
+
+    #[test]
+    fn container_errors() {
+        assert_eq!(
+            container_error(syn::parse_quote!(
+                struct A(u32);
+            )),
+            "tuple structs and tuple variants are not supported; use named fields"
+        );
+        assert_eq!(
+            container_error(syn::parse_quote!(
+                union A {
+                    a: u32,
+                }
+            )),
+            "unions are not supported"
+        );
+        assert!(container_error(syn::parse_quote!(
+            enum A {
+                B = 1,
+            }
+        ))
+        .starts_with("explicit discriminants are not supported"));
+        assert_eq!(
+            container_error(syn::parse_quote!(
+                struct A {
+                    fmt: u32,
+                }
+            )),
+            "`fmt` is used by the generated code; rename this field"
+        );
+        assert_eq!(
+            container_error(syn::parse_quote!(
+                struct A {
+                    #[chum(ignore = 0u32)]
+                    junk: u32,
+                }
+            )),
+            "fields with `ignore` must have the type `()`"
+        );
+        assert!(container_error(syn::parse_quote!(
+            struct A {
+                junk: (),
+            }
+        ))
+        .starts_with("fields with the type `()` need `#[chum(ignore = value)]`"));
+        assert_eq!(
+            container_error(syn::parse_quote!(
+                struct A {
+                    #[chum(len = u32)]
+                    values: [u8; 4],
+                }
+            )),
+            "`len` can only be used on `Vec` fields"
+        );
+        assert_eq!(
+            container_error(syn::parse_quote!(
+                struct A {
+                    #[chum(reference)]
+                    ids: Vec<u32>,
+                }
+            )),
+            "`reference` can only be used on `i32` values"
+        );
+        assert_eq!(
+            container_error(syn::parse_quote!(
+                struct A {
+                    #[chum(min = 0, max = 1)]
+                    value: f32,
+                }
+            )),
+            "`flags`, `min` and `max` can only be used on integer values"
+        );
+    }
+}
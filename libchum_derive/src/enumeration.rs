@@ -0,0 +1,42 @@
+use crate::data::{Body, Container};
+use proc_macro2::{Literal, TokenStream};
+use quote::quote;
+
+pub fn derive(container: &Container) -> syn::Result<TokenStream> {
+    let variants = match container.body {
+        Body::Enum(ref variants) if container.is_c_like() => variants,
+        _ => {
+            return Err(syn::Error::new(
+                container.ident.span(),
+                "ChumEnum can only be derived for enums whose variants have no fields",
+            ))
+        }
+    };
+    let idents: Vec<_> = variants.iter().map(|x| x.ident).collect();
+    let values: Vec<_> = (0..variants.len())
+        .map(|i| Literal::u32_unsuffixed(i as u32))
+        .collect();
+    let names = idents.iter().map(|x| x.to_string());
+    let ident = container.ident;
+    let (impl_generics, ty_generics, where_clause) = container.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::libchum::structure::ChumEnum for #ident #ty_generics #where_clause {
+            fn from_u32(value: u32) -> ::std::option::Option<Self> {
+                match value {
+                    #(#values => ::std::option::Option::Some(Self::#idents),)*
+                    _ => ::std::option::Option::None,
+                }
+            }
+
+            fn to_u32(&self) -> u32 {
+                match self {
+                    #(Self::#idents => #values,)*
+                }
+            }
+
+            fn get_names(&self) -> ::std::vec::Vec<::std::string::String> {
+                vec![#(#names.to_owned()),*]
+            }
+        }
+    })
+}
@@ -0,0 +1,88 @@
+//! Derive macros for libchum's `ChumBinary`, `ChumStruct` and `ChumEnum` traits.
+//! Use them through libchum, e.g. `use libchum::binary::ChumBinary;`.
+
+extern crate proc_macro;
+
+mod attr;
+mod binary;
+mod data;
+mod enumeration;
+mod structure;
+
+use data::Container;
+use proc_macro::TokenStream;
+use syn::{parse_macro_input, DeriveInput};
+
+fn expand<F>(input: TokenStream, derive: F) -> TokenStream
+where
+    F: FnOnce(&Container) -> syn::Result<proc_macro2::TokenStream>,
+{
+    let input = parse_macro_input!(input as DeriveInput);
+    Container::from_ast(&input)
+        .and_then(|container| derive(&container))
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+/// Implement `ChumBinary` by reading and writing each field in order.
+/// Every field's type must implement `ChumBinary`, except for these types:
+/// * `[T; N]`: N values of T.
+/// * `Vec<T>`: a length followed by that many values of T.
+///   Needs `#[chum(len = u32)]`, the type of the length.
+/// * `Option<T>`: a u8 that is 1 if there is a value, followed by the value.
+/// * `()`: a value that is not kept. Needs `#[chum(ignore = value)]`;
+///   the value is written in its place, and its type decides what is read.
+///   Literals need a suffix, e.g. `#[chum(ignore = 0u16)]` or `#[chum(ignore = [0u8; 4])]`.
+///
+/// Other field attributes:
+/// * `#[chum(read = expr, write = expr)]`: custom read and write expressions.
+///   `read` results in `StructUnpackResult<T>`; the fields before it are in scope as values.
+///   `write` results in `io::Result<()>`; every field is in scope as a reference.
+///   `file` and `fmt` are the reader or writer and the format.
+///
+/// Enums with fields need `#[chum(tag = i32)]`, the type of the tag that comes before
+/// the variant, and each variant needs `#[chum(tag = value)]`.
+/// Enums without fields need `#[chum(repr = u8)]`, the integer type that they are stored as,
+/// and must also derive `ChumEnum`.
+///
+/// Type parameters must implement `ChumBinary`,
+/// unless `#[chum(bound = "T: Trait, ...")]` is given.
+#[proc_macro_derive(ChumBinary, attributes(chum))]
+pub fn derive_chum_binary(input: TokenStream) -> TokenStream {
+    expand(input, binary::derive)
+}
+
+/// Implement `ChumStruct`, presenting each field as a member of a struct.
+/// Every field's type must implement `ChumStruct`, except for `[T; N]`, `Vec<T>`,
+/// `Option<T>` and `()` (see `ChumBinary`). Ignored fields are not presented.
+///
+/// Field attributes, which apply to the values inside of any arrays or options:
+/// * `#[chum(reference)]`, `#[chum(reference = "BITMAP")]`: an `i32` that refers to
+///   another file, optionally of the given type.
+/// * `#[chum(flags(a, b, c))]`: an integer made of named flags.
+/// * `#[chum(min = 1, max = 5)]`: an integer in the given range.
+/// * `#[chum(rgb)]`: a `Vector3` that is presented as a color without alpha.
+/// * `#[chum(default = expr)]`: the value of new elements of a `Vec`, or of an `Option`
+///   when it is given a value. In enum variants, it is also the value of other fields
+///   when the variant is selected. Defaults to `Default::default()`.
+/// * `#[chum(structure = f, destructure = g)]`: custom structure functions.
+///   `f: fn(&Self) -> Option<ChumStructVariant>`; return `None` to not present the field.
+///   `g: fn(&ChumStructVariant) -> Result<T, DestructureError>` is given the whole
+///   structure; the functions in `structure::destructure` can read from it.
+///
+/// Enums with fields are presented as variants. Enums without fields are presented as
+/// enumerated integers, and must also derive `ChumEnum`.
+///
+/// Type parameters must implement `ChumStruct`,
+/// unless `#[chum(bound = "T: Trait, ...")]` is given.
+#[proc_macro_derive(ChumStruct, attributes(chum))]
+pub fn derive_chum_struct(input: TokenStream) -> TokenStream {
+    expand(input, structure::derive)
+}
+
+/// Implement `ChumEnum` for an enum without fields.
+/// Variants count up from 0, in the order that they are declared.
+#[proc_macro_derive(ChumEnum, attributes(chum))]
+pub fn derive_chum_enum(input: TokenStream) -> TokenStream {
+    expand(input, enumeration::derive)
+}
@@ -0,0 +1,368 @@
+use crate::attr::{FieldAttrs, IntKind};
+use crate::data::{shape, Body, Container, Field, Shape, Variant};
+use proc_macro2::TokenStream;
+use quote::{quote, quote_spanned};
+use syn::spanned::Spanned;
+use syn::{Expr, Type};
+
+/// Structure a value that does not contain arrays or options.
+/// `value` is an expression of type `&T`.
+fn structure_leaf(ty: &Type, attrs: &FieldAttrs, value: TokenStream) -> TokenStream {
+    if let Some(ref reference) = attrs.reference {
+        let type_name = match reference {
+            Some(name) => quote!(::std::option::Option::Some(#name.to_owned())),
+            None => quote!(::std::option::Option::None),
+        };
+        quote!(::libchum::structure::ChumStructVariant::Reference(*(#value), #type_name))
+    } else if attrs.rgb {
+        quote! {
+            ::libchum::structure::ChumStructVariant::Color(
+                ::libchum::common::ColorRGBA::new((#value).x, (#value).y, (#value).z, 1.0),
+                ::libchum::structure::ColorInfo { has_alpha: false },
+            )
+        }
+    } else {
+        match attrs.int {
+            Some(IntKind::Flags(ref names)) => {
+                let names = names.iter().map(|x| x.to_string());
+                quote! {
+                    ::libchum::structure::ChumStructVariant::Integer(
+                        *(#value) as i64,
+                        ::libchum::structure::IntType::Flags(vec![#(#names.to_owned()),*]),
+                    )
+                }
+            }
+            Some(IntKind::Range(ref min, ref max)) => quote! {
+                ::libchum::structure::ChumStructVariant::Integer(
+                    *(#value) as i64,
+                    ::libchum::structure::IntType::Custom(#min, #max),
+                )
+            },
+            None => quote_spanned! {ty.span()=>
+                <#ty as ::libchum::structure::ChumStruct>::structure(#value)
+            },
+        }
+    }
+}
+
+/// Function that creates a new element of a `Vec` or `Option`
+fn default_element(inner: &Type, attrs: &FieldAttrs, default: Option<&Expr>) -> TokenStream {
+    let default = match default {
+        Some(expr) => quote_spanned!(expr.span()=> #expr),
+        None => quote!(::std::default::Default::default()),
+    };
+    let element = structure_value(inner, attrs, quote!(x), None);
+    quote! {
+        || {
+            let x: &#inner = &(#default);
+            #element
+        }
+    }
+}
+
+/// Structure a value. `value` is an expression of type `&T`.
+/// `default` is the default element of a `Vec` or `Option`.
+fn structure_value(
+    ty: &Type,
+    attrs: &FieldAttrs,
+    value: TokenStream,
+    default: Option<&Expr>,
+) -> TokenStream {
+    match shape(ty) {
+        Shape::Array(inner) => {
+            let element = structure_value(inner, attrs, quote!(x), None);
+            quote! {
+                ::libchum::structure::ChumStructVariant::Array(::libchum::structure::ArrayData {
+                    can_resize: false,
+                    data: (#value).iter().map(|x| #element).collect(),
+                    // Fixed arrays can not be resized, so this is never used
+                    default_value: || ::libchum::structure::ChumStructVariant::Integer(
+                        0,
+                        ::libchum::structure::IntType::U8,
+                    ),
+                })
+            }
+        }
+        Shape::Vec(inner) => {
+            let element = structure_value(inner, attrs, quote!(x), None);
+            let default_value = default_element(inner, attrs, default);
+            quote! {
+                ::libchum::structure::ChumStructVariant::Array(::libchum::structure::ArrayData {
+                    can_resize: true,
+                    data: (#value).iter().map(|x| #element).collect(),
+                    default_value: #default_value,
+                })
+            }
+        }
+        Shape::Option(inner) => {
+            let element = structure_value(inner, attrs, quote!(x), None);
+            let default_value = default_element(inner, attrs, default);
+            quote! {
+                ::libchum::structure::ChumStructVariant::Optional {
+                    value: (#value).as_ref().map(|x| ::std::boxed::Box::new(#element)),
+                    default_value: #default_value,
+                }
+            }
+        }
+        Shape::Unit | Shape::Leaf => structure_leaf(ty, attrs, value),
+    }
+}
+
+/// Destructure a value. `value` is an expression of type `&ChumStructVariant`.
+/// Results in `Result<T, DestructureError>`.
+fn destructure_value(ty: &Type, attrs: &FieldAttrs, value: TokenStream) -> TokenStream {
+    match shape(ty) {
+        Shape::Array(inner) => {
+            let element = destructure_value(inner, attrs, quote!(x));
+            quote!(::libchum::structure::destructure::get_fixed_elements(#value, |x| #element))
+        }
+        Shape::Vec(inner) => {
+            let element = destructure_value(inner, attrs, quote!(x));
+            quote!(::libchum::structure::destructure::get_elements(#value, |x| #element))
+        }
+        Shape::Option(inner) => {
+            let element = destructure_value(inner, attrs, quote!(x));
+            quote! {
+                ::libchum::structure::destructure::get_optional(#value)
+                    .and_then(|x| x.map(|x| #element).transpose())
+            }
+        }
+        Shape::Unit | Shape::Leaf => {
+            if attrs.reference.is_some() {
+                quote!(::libchum::structure::destructure::get_reference(#value))
+            } else if attrs.rgb {
+                quote! {
+                    ::libchum::structure::destructure::get_color(#value)
+                        .map(|color| ::libchum::common::Vector3::new(color.r, color.g, color.b))
+                }
+            } else {
+                match attrs.int {
+                    // Flags may have bits set that do not have a name
+                    Some(IntKind::Flags(_)) => quote! {
+                        ::libchum::structure::destructure::get_integer(
+                            #value,
+                            <#ty>::MIN as i64,
+                            <#ty>::MAX as i64,
+                        ).map(|x| x as #ty)
+                    },
+                    Some(IntKind::Range(ref min, ref max)) => quote! {
+                        ::libchum::structure::destructure::get_integer(#value, #min, #max)
+                            .map(|x| x as #ty)
+                    },
+                    None => quote_spanned! {ty.span()=>
+                        <#ty as ::libchum::structure::ChumStruct>::destructure(#value)
+                    },
+                }
+            }
+        }
+    }
+}
+
+/// Statement that adds the given field to `members`.
+/// `value` is an expression of type `&T`.
+fn structure_field(field: &Field, value: TokenStream) -> TokenStream {
+    let name = field.name();
+    if field.is_ignored() {
+        quote!()
+    } else if let Some(ref structure) = field.attrs.structure {
+        quote! {
+            if let ::std::option::Option::Some(value) = (#structure)(self) {
+                members.push((#name.to_owned(), value));
+            }
+        }
+    } else {
+        let value = structure_value(field.ty, &field.attrs, value, field.attrs.default.as_ref());
+        quote!(members.push((#name.to_owned(), #value));)
+    }
+}
+
+/// Expression that destructures the given field from `data`.
+/// If this is in an enum variant, `variant` is the name of the variant.
+fn destructure_field(field: &Field, variant: Option<&str>) -> TokenStream {
+    let name = field.name();
+    let ty = field.ty;
+    let value = if field.is_ignored() {
+        return quote!(());
+    } else if let Some(ref destructure) = field.attrs.destructure {
+        quote! {{
+            let destructure: fn(
+                &::libchum::structure::ChumStructVariant,
+            ) -> ::std::result::Result<#ty, ::libchum::error::DestructureError> = #destructure;
+            destructure(data)
+        }}
+    } else {
+        let value = destructure_value(ty, &field.attrs, quote!(x));
+        quote!(::libchum::structure::destructure::get_member_as(data, #name, |x| #value))
+    };
+    match variant {
+        Some(variant) => quote!(#value.map_err(|e| e.prepend_member(#variant))?),
+        None => quote!(#value?),
+    }
+}
+
+fn struct_body(fields: &[Field]) -> (TokenStream, TokenStream) {
+    let members = fields.iter().map(|field| {
+        let name = field.ident;
+        structure_field(field, quote!(&self.#name))
+    });
+    let names = fields.iter().map(|x| x.ident);
+    let values = fields.iter().map(|x| destructure_field(x, None));
+    let structure = quote! {
+        #[allow(unused_mut)]
+        let mut members = ::std::vec::Vec::new();
+        #(#members)*
+        ::libchum::structure::ChumStructVariant::Struct(members)
+    };
+    let destructure = quote! {
+        ::std::result::Result::Ok(Self {
+            #(#names: #values),*
+        })
+    };
+    (structure, destructure)
+}
+
+/// Function that creates the structure of a variant when it is selected in the editor
+fn variant_default(variant: &Variant) -> syn::Result<TokenStream> {
+    let mut members = Vec::new();
+    for field in variant.fields.iter().filter(|x| !x.is_ignored()) {
+        if let Some(ref structure) = field.attrs.structure {
+            return Err(syn::Error::new(
+                structure.span(),
+                "`structure` can not be used in enum variants",
+            ));
+        }
+        let ty = field.ty;
+        let default = match shape(ty) {
+            Shape::Vec(_) | Shape::Option(_) => quote!(::std::default::Default::default()),
+            _ => match field.attrs.default {
+                Some(ref expr) => quote_spanned!(expr.span()=> #expr),
+                None => quote!(::std::default::Default::default()),
+            },
+        };
+        let name = field.ident;
+        let member = structure_field(field, quote!(#name));
+        members.push(quote! {
+            let #name: &#ty = &(#default);
+            #member
+        });
+    }
+    Ok(quote! {
+        || {
+            #[allow(unused_mut)]
+            let mut members = ::std::vec::Vec::new();
+            #(#members)*
+            ::libchum::structure::ChumStructVariant::Struct(members)
+        }
+    })
+}
+
+fn enum_body(variants: &[Variant]) -> syn::Result<(TokenStream, TokenStream)> {
+    let mut options = Vec::new();
+    let mut structure_arms = Vec::new();
+    let mut destructure_arms = Vec::new();
+    for variant in variants.iter() {
+        let name = variant.ident.to_string();
+        let default_value = variant_default(variant)?;
+        options.push(quote! {
+            ::libchum::structure::VariantOption {
+                name: #name.to_owned(),
+                default_value: #default_value,
+            }
+        });
+        let pattern = variant.pattern();
+        let members = variant.fields.iter().map(|field| {
+            let name = field.ident;
+            structure_field(field, quote!(#name))
+        });
+        structure_arms.push(quote! {
+            #pattern => {
+                #[allow(unused_mut)]
+                let mut members = ::std::vec::Vec::new();
+                #(#members)*
+                ::libchum::structure::ChumStructVariant::Variant {
+                    current: #name.to_owned(),
+                    options,
+                    value: ::std::boxed::Box::new(
+                        ::libchum::structure::ChumStructVariant::Struct(members),
+                    ),
+                }
+            }
+        });
+        let ident = variant.ident;
+        let value = if variant.is_unit {
+            quote!(Self::#ident)
+        } else {
+            let names = variant.fields.iter().map(|x| x.ident);
+            let values = variant
+                .fields
+                .iter()
+                .map(|x| destructure_field(x, Some(&name)));
+            quote!(Self::#ident { #(#names: #values),* })
+        };
+        destructure_arms.push(quote! {
+            #name => ::std::result::Result::Ok(#value),
+        });
+    }
+    let names = variants.iter().map(|x| x.ident.to_string());
+    let structure = quote! {
+        let options = vec![#(#options),*];
+        match self {
+            #(#structure_arms)*
+        }
+    };
+    let destructure = quote! {
+        let (name, data) = ::libchum::structure::destructure::get_variant(data)?;
+        match name {
+            #(#destructure_arms)*
+            other => ::std::result::Result::Err(
+                ::libchum::error::DestructureError::InvalidVariant {
+                    path: ::std::string::String::new(),
+                    expected: vec![#(#names.to_owned()),*],
+                    value: other.to_owned(),
+                },
+            ),
+        }
+    };
+    Ok((structure, destructure))
+}
+
+fn c_like_body(container: &Container) -> (TokenStream, TokenStream) {
+    let name = container.name();
+    let structure = quote! {
+        ::libchum::structure::ChumStructVariant::Integer(
+            ::libchum::structure::ChumEnum::to_u32(self) as i64,
+            ::libchum::structure::IntType::Enum(::libchum::structure::ChumEnum::get_names(self)),
+        )
+    };
+    let destructure = quote! {
+        ::libchum::structure::destructure::get_enum::<Self>(data, #name)
+    };
+    (structure, destructure)
+}
+
+pub fn derive(container: &Container) -> syn::Result<TokenStream> {
+    let (structure, destructure) = if container.is_c_like() {
+        c_like_body(container)
+    } else {
+        match container.body {
+            Body::Struct(ref fields) => struct_body(fields),
+            Body::Enum(ref variants) => enum_body(variants)?,
+        }
+    };
+    let ident = container.ident;
+    let generics = container.impl_generics(&quote!(::libchum::structure::ChumStruct));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::libchum::structure::ChumStruct for #ident #ty_generics #where_clause {
+            fn structure(&self) -> ::libchum::structure::ChumStructVariant {
+                #structure
+            }
+
+            fn destructure(
+                data: &::libchum::structure::ChumStructVariant,
+            ) -> ::std::result::Result<Self, ::libchum::error::DestructureError> {
+                #destructure
+            }
+        }
+    })
+}
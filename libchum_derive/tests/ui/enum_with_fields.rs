@@ -0,0 +1,9 @@
+use libchum::structure::ChumEnum;
+
+#[derive(ChumEnum)]
+enum Data {
+    Empty,
+    Value { value: f32 },
+}
+
+fn main() {}
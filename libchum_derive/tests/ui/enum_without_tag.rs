@@ -0,0 +1,9 @@
+use libchum::binary::ChumBinary;
+
+#[derive(ChumBinary)]
+enum Data {
+    Empty,
+    Value { value: f32 },
+}
+
+fn main() {}
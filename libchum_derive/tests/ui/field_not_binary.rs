@@ -0,0 +1,11 @@
+use libchum::binary::ChumBinary;
+
+struct Unknown;
+
+#[derive(ChumBinary)]
+struct Container {
+    value: u32,
+    unknown: Unknown,
+}
+
+fn main() {}
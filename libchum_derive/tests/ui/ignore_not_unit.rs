@@ -0,0 +1,9 @@
+use libchum::binary::ChumBinary;
+
+#[derive(ChumBinary)]
+struct Header {
+    #[chum(ignore = 0u16)]
+    junk: u16,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use libchum::structure::ChumStruct;
+
+#[derive(ChumStruct)]
+struct Texture {
+    #[chum(reference = "BITMAP")]
+    texture: u32,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use libchum::binary::ChumBinary;
+
+#[derive(ChumBinary)]
+struct Value {
+    #[chum(size = 4)]
+    value: u32,
+}
+
+fn main() {}
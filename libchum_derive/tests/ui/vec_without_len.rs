@@ -0,0 +1,8 @@
+use libchum::binary::ChumBinary;
+
+#[derive(ChumBinary)]
+struct Values {
+    values: Vec<u32>,
+}
+
+fn main() {}